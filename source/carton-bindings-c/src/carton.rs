@@ -107,6 +107,27 @@ impl Carton {
         });
     }
 
+    /// Get the sha256 of the MANIFEST of the loaded model. This does not make any network requests.
+    /// `string_out` is set to null if the hash isn't available (e.g. for unpacked models).
+    /// Note: any returned pointers are only valid until the carton is destroyed.
+    #[no_mangle]
+    pub extern "C" fn carton_get_manifest_sha256(
+        &self,
+        string_out: *mut *const c_char,
+        strlen_out: *mut u64,
+    ) {
+        match &self.inner.get_info().manifest_sha256 {
+            Some(item) => unsafe {
+                *string_out = item.as_ptr() as *const _;
+                *strlen_out = item.len() as _;
+            },
+            None => unsafe {
+                *string_out = std::ptr::null();
+                *strlen_out = 0;
+            },
+        }
+    }
+
     /// Destroy a Carton
     #[no_mangle]
     pub extern "C" fn carton_destroy(carton: *mut Carton) {
@@ -12,11 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use carton_core::types::{for_each_carton_type, for_each_numeric_carton_type, TypedStorage};
+use carton_core::types::{
+    for_each_carton_type, for_each_numeric_or_complex_carton_type, TypedStorage,
+};
 use ndarray::ShapeBuilder;
 use std::ffi::{c_char, c_void, CStr};
 
-use crate::types::{CartonStatus, DataType};
+use crate::{
+    types::{CartonStatus, DataType},
+    utils::slice_or_empty,
+};
 
 pub struct CartonTensor {
     inner: carton_core::types::Tensor,
@@ -31,7 +36,7 @@ impl From<carton_core::types::Tensor> for CartonTensor {
         for_each_carton_type! {
             return match &value {
                 $(carton_core::types::Tensor::$CartonType(v) => {let v = v.view(); Self {shape: v.shape().iter().map(|v| (*v) as _).collect(), strides: v.strides().iter().map(|v| (*v) as _).collect(), inner: value}},)*
-                carton_core::types::Tensor::NestedTensor(_) => panic!("Nested tensors are not yet supported in the C bindings!")
+                carton_core::types::Tensor::NestedTensor(_) | carton_core::types::Tensor::LabeledNestedTensor { .. } => panic!("Nested tensors are not yet supported in the C bindings!")
             }
         }
     }
@@ -70,6 +75,7 @@ impl<T> Drop for ExternalBlobWrapper<T> {
 impl CartonTensor {
     /// Create a numeric tensor by wrapping user-owned data.
     /// `deleter` will be called with `deleter_arg` when Carton no longer has references to `data`
+    /// For scalars, `num_dims` should be 0 and `data` should point to a single element
     #[no_mangle]
     pub extern "C" fn carton_tensor_numeric_from_blob(
         data: *const c_void,
@@ -81,10 +87,11 @@ impl CartonTensor {
         deleter_arg: *const c_void,
         tensor_out: *mut *mut CartonTensor,
     ) -> CartonStatus {
-        let shape = unsafe { std::slice::from_raw_parts(shape, num_dims as _) };
-        let strides = unsafe { std::slice::from_raw_parts(strides, num_dims as _) };
+        // `shape` and `strides` can be NULL for scalars
+        let shape = unsafe { slice_or_empty(shape, num_dims) };
+        let strides = unsafe { slice_or_empty(strides, num_dims) };
 
-        for_each_numeric_carton_type! {
+        for_each_numeric_or_complex_carton_type! {
             match dtype {
                 $(DataType::$CartonType => {
                     let view = unsafe {
@@ -111,6 +118,7 @@ impl CartonTensor {
     }
 
     /// Create a tensor with a provided shape and data type
+    /// For scalars, `num_dims` should be 0. The tensor has a single element
     #[no_mangle]
     pub extern "C" fn carton_tensor_create(
         dtype: DataType,
@@ -118,7 +126,8 @@ impl CartonTensor {
         num_dims: u64,
         tensor_out: *mut *mut CartonTensor,
     ) {
-        let shape = unsafe { std::slice::from_raw_parts(shape, num_dims as _) };
+        // `shape` can be NULL for scalars
+        let shape = unsafe { slice_or_empty(shape, num_dims) };
 
         for_each_carton_type! {
             match dtype {
@@ -138,7 +147,7 @@ impl CartonTensor {
     /// Note: the returned data pointer is only valid until this CartonTensor is destroyed.
     #[no_mangle]
     pub extern "C" fn carton_tensor_data(&self, data_out: *mut *mut c_void) {
-        for_each_numeric_carton_type! {
+        for_each_numeric_or_complex_carton_type! {
             match &self.inner {
                 $(carton_core::types::Tensor::$CartonType(v) => unsafe { *data_out = v.view().as_ptr() as _ },)*
                 _ => unsafe {*data_out = std::ptr::null_mut()}
@@ -152,7 +161,7 @@ impl CartonTensor {
         for_each_carton_type! {
             return match &self.inner {
                 $(carton_core::types::Tensor::$CartonType(_) => unsafe { *dtype_out = DataType::$CartonType },)*
-                carton_core::types::Tensor::NestedTensor(_) => panic!("Nested tensors are not yet supported in the C bindings!")
+                carton_core::types::Tensor::NestedTensor(_) | carton_core::types::Tensor::LabeledNestedTensor { .. } => panic!("Nested tensors are not yet supported in the C bindings!")
             }
         }
     }
@@ -207,36 +216,44 @@ impl CartonTensor {
     }
 
     /// For a string tensor, set a string at a particular (flattened) index.
+    /// Returns `CARTON_STATUS_INVALID_UTF8` (and leaves the tensor unchanged) if `string` isn't valid UTF-8
     #[no_mangle]
-    pub extern "C" fn carton_tensor_set_string(&mut self, index: u64, string: *const c_char) {
-        let new = unsafe { CStr::from_ptr(string).to_str().unwrap().to_owned() };
-        self.carton_tensor_set_string_inner(index, new);
+    pub extern "C" fn carton_tensor_set_string(
+        &mut self,
+        index: u64,
+        string: *const c_char,
+    ) -> CartonStatus {
+        let new = unsafe { CStr::from_ptr(string).to_bytes() };
+        self.carton_tensor_set_string_inner(index, new)
     }
 
+    /// Same as `carton_tensor_set_string`, but `string` doesn't need to be null terminated
     #[no_mangle]
     pub extern "C" fn carton_tensor_set_string_with_strlen(
         &mut self,
         index: u64,
         string: *const c_char,
         strlen: u64,
-    ) {
-        let new = unsafe {
-            std::str::from_utf8(std::slice::from_raw_parts(string as *const _, strlen as _))
-                .unwrap()
-                .to_owned()
-        };
-
-        self.carton_tensor_set_string_inner(index, new);
+    ) -> CartonStatus {
+        let new = unsafe { std::slice::from_raw_parts(string as *const u8, strlen as _) };
+        self.carton_tensor_set_string_inner(index, new)
     }
 
-    fn carton_tensor_set_string_inner(&mut self, index: u64, string: String) {
+    fn carton_tensor_set_string_inner(&mut self, index: u64, string: &[u8]) -> CartonStatus {
+        let string = match std::str::from_utf8(string) {
+            Ok(v) => v,
+            Err(_) => return CartonStatus::InvalidUtf8,
+        };
+
         if let carton_core::types::Tensor::String(v) = &mut self.inner {
             let mut view = v.view_mut();
             let item = view.iter_mut().nth(index as _).unwrap();
-            *item = string;
+            *item = string.to_owned();
         } else {
             panic!("Tried to call `set_string` on a non-string tensor")
         }
+
+        CartonStatus::Success
     }
 
     /// Destroy a CartonTensor
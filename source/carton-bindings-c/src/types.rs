@@ -46,6 +46,8 @@ pub enum DataType {
     U16,
     U32,
     U64,
+    Complex64,
+    Complex128,
 }
 
 /// cbindgen:rename-all=QualifiedScreamingSnakeCase
@@ -57,6 +59,9 @@ pub enum CartonStatus {
 
     /// There were no async tasks ready
     NoAsyncTasksReady,
+
+    /// A string passed to Carton was not valid UTF-8. Nothing was modified
+    InvalidUtf8,
 }
 
 #[derive(Debug)]
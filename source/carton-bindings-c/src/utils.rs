@@ -22,6 +22,17 @@ pub(crate) fn runtime() -> &'static Runtime {
     CELL.get_or_init(|| Runtime::new().unwrap())
 }
 
+/// Like `std::slice::from_raw_parts`, but allows `ptr` to be NULL if `len` is 0 (e.g. the shape of a scalar)
+///
+/// SAFETY: if `len` is not 0, `ptr` must point to `len` valid items that outlive `'a`
+pub(crate) unsafe fn slice_or_empty<'a, T>(ptr: *const T, len: u64) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len as _)
+    }
+}
+
 /// A macro that helps ensure that we (somewhat safely) convert between C and Rust types
 macro_rules! ffi_conversions {
     ($t:ident) => {
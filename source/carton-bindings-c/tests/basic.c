@@ -55,11 +55,21 @@ void load_callback(Carton *model, CartonStatus status, void *callback_arg)
     assert(callback_arg == (void *)885);
     assert(status == CARTON_STATUS_SUCCESS);
 
+    // Packed models should have a manifest hash
+    const char *manifest_sha256;
+    uint64_t manifest_sha256_len;
+    carton_get_manifest_sha256(model, &manifest_sha256, &manifest_sha256_len);
+    assert(manifest_sha256 != NULL);
+    assert(manifest_sha256_len == 64);
+
     // Create a tensor
     CartonTensor *tensor;
     uint64_t dims[] = {1};
     carton_tensor_create(DATA_TYPE_STRING, dims, 1, &tensor);
-    carton_tensor_set_string(tensor, 0, "Today is a good [MASK].");
+    assert(carton_tensor_set_string(tensor, 0, "Today is a good [MASK].") == CARTON_STATUS_SUCCESS);
+
+    // Invalid UTF-8 is rejected
+    assert(carton_tensor_set_string_with_strlen(tensor, 0, "\xff\xfe", 2) == CARTON_STATUS_INVALID_UTF8);
 
     // Create an input map
     CartonTensorMap *tensors;
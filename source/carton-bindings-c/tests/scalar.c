@@ -0,0 +1,59 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#include <assert.h>
+#include <stdio.h>
+#include "../carton.h"
+
+void noop_deleter(const void *arg)
+{
+}
+
+int main()
+{
+    // Create a scalar (0-d) tensor
+    CartonTensor *tensor;
+    carton_tensor_create(DATA_TYPE_FLOAT, NULL, 0, &tensor);
+
+    const uint64_t *shape;
+    uint64_t num_dims;
+    carton_tensor_shape(tensor, &shape, &num_dims);
+    assert(num_dims == 0);
+
+    // Scalars have exactly one element
+    float *data;
+    carton_tensor_data(tensor, (void **)&data);
+    assert(data != NULL);
+    assert(data[0] == 0.0f);
+    data[0] = 1.5f;
+
+    carton_tensor_data(tensor, (void **)&data);
+    assert(data[0] == 1.5f);
+    carton_tensor_destroy(tensor);
+
+    // Wrap a scalar from a blob
+    float value = 2.5f;
+    carton_tensor_numeric_from_blob(&value, DATA_TYPE_FLOAT, NULL, NULL, 0, noop_deleter, NULL, &tensor);
+
+    const int64_t *strides;
+    carton_tensor_strides(tensor, &strides, &num_dims);
+    assert(num_dims == 0);
+
+    carton_tensor_data(tensor, (void **)&data);
+    assert(data[0] == 2.5f);
+    carton_tensor_destroy(tensor);
+
+    printf("Scalar tests passed\n");
+    return 0;
+}
@@ -116,13 +116,15 @@ namespace carton
         return {ptr, len};
     }
 
-    void Tensor::set_string(uint64_t index, std::string_view string)
+    Status Tensor::set_string(uint64_t index, std::string_view string)
     {
-        carton_tensor_set_string_with_strlen(
+        auto status = carton_tensor_set_string_with_strlen(
             static_cast<CartonTensor *>(handle_),
             index,
             string.data(),
             string.size());
+
+        return static_cast<Status>(status);
     }
 
     std::string_view Tensor::get_string(uint64_t index) const
@@ -379,6 +381,23 @@ namespace carton
         return promise->get_future();
     }
 
+    std::optional<std::string_view> Carton::manifest_sha256() const
+    {
+        const char *out;
+        uint64_t len;
+        carton_get_manifest_sha256(
+            static_cast<::Carton *>(handle_),
+            &out,
+            &len);
+
+        if (out == nullptr)
+        {
+            return std::nullopt;
+        }
+
+        return std::string_view{out, len};
+    }
+
     void Carton::load(std::string_view url_or_path,
                       void (*callback)(Result<Carton>, void *callback_arg),
                       void *callback_arg)
@@ -32,6 +32,9 @@ namespace carton
 
         // There were no async tasks ready
         kNoAsyncTasksReady,
+
+        // A string passed to Carton was not valid UTF-8. Nothing was modified
+        kInvalidUtf8,
     };
 
     // Data types of tensors
@@ -48,6 +51,8 @@ namespace carton
         kU16,
         kU32,
         kU64,
+        kComplex64,
+        kComplex128,
     };
 
     // A result type that used as the return value of some functions that can fail
@@ -133,8 +138,9 @@ namespace carton
 
         // For a string tensor, set a string at a particular (flattened) index
         // This will copy data from the provided string_view.
+        // Returns kInvalidUtf8 (and leaves the tensor unchanged) if `string` isn't valid UTF-8
         // TODO: do some template magic to make this easy to use
-        void set_string(uint64_t index, std::string_view string);
+        Status set_string(uint64_t index, std::string_view string);
 
         // For a string tensor, get a string at a particular (flattened) index
         // Note: the returned view is only valid until the tensor is modified.
@@ -285,6 +291,11 @@ namespace carton
         // Run inference
         std::future<Result<TensorMap>> infer(TensorMap tensors);
 
+        // Get the sha256 of the MANIFEST of the loaded model (if available)
+        // This should always be available unless we're running an unpacked model
+        // Note: the returned view is only valid while this Carton is in scope
+        std::optional<std::string_view> manifest_sha256() const;
+
         // Load with a callback
         //
         // IMPORTANT: these callbacks should not block or do CPU-intensive work.
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+const { Readable } = require("stream")
+
 const native = require("./carton_native.node")
 
 const load = async (path, opts = {}) => {
@@ -62,4 +64,29 @@ class Carton {
         // Run the model
         return await native.infer.call(this.inner.handle, nativeTensors)
     }
+
+    // Returns a `Readable` stream of the contents of the misc file at `path` (e.g. an image
+    // referenced by the model description). The file is read in chunks of `chunkSize` bytes as
+    // the stream is consumed so large files don't need to fit in memory
+    miscFileStream(path, { chunkSize = 64 * 1024 } = {}) {
+        const handle = this.inner.handle
+        let reader = null
+
+        return new Readable({
+            async read() {
+                try {
+                    // Open the file on the first read
+                    if (reader === null) {
+                        reader = await native.open_misc_file.call(handle, path)
+                    }
+
+                    // An empty chunk means we've reached the end of the file
+                    const chunk = await native.read_misc_file_chunk.call(reader, chunkSize)
+                    this.push(chunk.length === 0 ? null : chunk)
+                } catch (err) {
+                    this.destroy(err)
+                }
+            }
+        })
+    }
 }
\ No newline at end of file
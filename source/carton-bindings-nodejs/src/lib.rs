@@ -15,18 +15,24 @@
 use std::{collections::HashMap, sync::Arc};
 
 use carton::{
+    info::MiscFile,
     types::{for_each_carton_type, Device, LoadOpts, Tensor},
     Carton,
 };
 use ndarray::ShapeBuilder;
 use neon::{prelude::*, types::buffer::TypedArray};
 use once_cell::sync::OnceCell;
-use tokio::runtime::Runtime;
+use tokio::{io::AsyncReadExt, runtime::Runtime, sync::Mutex};
 
 struct CartonWrapper(pub Arc<Carton>);
 
 impl Finalize for CartonWrapper {}
 
+/// An open misc file that JS reads in chunks (see `Carton.miscFileStream` in `js/index.js`)
+struct MiscFileReaderWrapper(Arc<Mutex<MiscFile>>);
+
+impl Finalize for MiscFileReaderWrapper {}
+
 // Return a global tokio runtime or create one if it doesn't exist.
 // Throws a JavaScript exception if the `Runtime` fails to create.
 // Based on https://github.com/neon-bindings/examples/blob/main/examples/tokio-fetch/src/lib.rs
@@ -66,6 +72,16 @@ fn load(mut cx: FunctionContext) -> JsResult<JsPromise> {
         override_runner_opts: None,
         visible_device: Device::maybe_from_str(&visible_device)
             .or_else(|err| cx.throw_error(err.to_string()))?,
+        streaming_buffer_size: None,
+        auto_install_runner: true,
+        max_archive_size: None,
+        max_archive_file_size: None,
+        expected_manifest_sha256: None,
+        runner_path_override: None,
+        signature_public_key: None,
+        runner_env: HashMap::new(),
+        prefetch_linked_files: false,
+        seed: None,
     };
 
     let rt = runtime(&mut cx)?;
@@ -152,13 +168,39 @@ impl CartonWrapper {
                 })
                 .collect();
 
+            // Scalars have an empty shape, but some libraries (e.g. `@stdlib/ndarray`) give them a stride of `[0]`
+            let stride = if shape.is_empty() { Vec::new() } else { stride };
+
             let dtype = val.get::<JsString, _, _>(&mut cx, "dtype")?.value(&mut cx);
 
+            // The number of elements we need to read from the buffer (scalars have one)
+            let required_len = if shape.contains(&0) {
+                0
+            } else {
+                1 + shape
+                    .iter()
+                    .zip(&stride)
+                    .map(|(dim, stride)| (dim - 1) * stride)
+                    .sum::<usize>()
+            };
+
             // TODO this makes another copy (the `to_owned`)
             // TODO: we should ignore strings here
             for_each_carton_type! {
                 let t: Tensor = match dtype.as_str() {
                     $(
+                        // Zero-element tensors may come with an empty (and unaligned) buffer so we don't view it
+                        $TypeStr if shape.iter().product::<usize>() == 0 => {
+                            Tensor::$CartonType(ndarray::ArrayD::<$RustType>::default(shape).into())
+                        },
+                        $TypeStr if buffer.len() < required_len * std::mem::size_of::<$RustType>() => {
+                            return cx.throw_error(format!(
+                                "The buffer for tensor `{}` has {} bytes, but its shape ({shape:?}) and stride ({stride:?}) need at least {} bytes",
+                                prop.downcast_or_throw::<JsString, _>(&mut cx)?.value(&mut cx),
+                                buffer.len(),
+                                required_len * std::mem::size_of::<$RustType>(),
+                            ))
+                        },
                         $TypeStr => unsafe {
                             Tensor::$CartonType(ndarray::ArrayView::from_shape_ptr(
                                 shape.strides(stride),
@@ -218,7 +260,11 @@ impl CartonWrapper {
                                         )
                                     };
 
-                                    let buf = JsArrayBuffer::external(&mut cx, data);
+                                    let buf = if data.is_empty() {
+                                        JsArrayBuffer::new(&mut cx, 0)?
+                                    } else {
+                                        JsArrayBuffer::external(&mut cx, data)
+                                    };
 
                                     // Get the shape
                                     let shape = vec_to_array(&mut cx, view.shape())?;
@@ -234,7 +280,7 @@ impl CartonWrapper {
                                     out.set(&mut cx, keystr, info)?;
                                 },
                             )*
-                            Tensor::NestedTensor(_) => panic!("Nested tensor output not implemented yet"),
+                            Tensor::NestedTensor(_) | Tensor::LabeledNestedTensor { .. } => panic!("Nested tensor output not implemented yet"),
                         }
 
                     }
@@ -247,12 +293,103 @@ impl CartonWrapper {
         // Return the promise to node
         Ok(promise)
     }
+
+    /// Open the misc file at the path in the first arg. Resolves to a handle that can be passed
+    /// to `read_misc_file_chunk`
+    fn open_misc_file(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let path = cx.argument::<JsString>(0)?.value(&mut cx);
+
+        let this = cx
+            .this()
+            .downcast_or_throw::<JsBox<CartonWrapper>, _>(&mut cx)?
+            .0
+            .clone();
+
+        let loader = match this
+            .get_info()
+            .info
+            .misc_files
+            .as_ref()
+            .and_then(|files| files.get(&path))
+        {
+            Some(loader) => loader.clone(),
+            None => {
+                return cx.throw_error(format!("This model doesn't have a misc file at `{path}`"))
+            }
+        };
+
+        // Get the tokio runtime
+        let rt = runtime(&mut cx)?;
+        let channel = cx.channel();
+
+        // Create a promise
+        let (deferred, promise) = cx.promise();
+
+        // Spawn a task
+        rt.spawn(async move {
+            let reader = loader.get().await;
+
+            // This runs on the JS main thread
+            deferred.settle_with(&channel, move |mut cx| {
+                Ok(cx.boxed(MiscFileReaderWrapper(Arc::new(Mutex::new(reader)))))
+            });
+        });
+
+        // Return the promise to node
+        Ok(promise)
+    }
+}
+
+impl MiscFileReaderWrapper {
+    /// Read up to `size` (the first arg) bytes from the file. Resolves to a `Buffer` that is
+    /// empty once we've reached the end of the file
+    fn read_chunk(mut cx: FunctionContext) -> JsResult<JsPromise> {
+        let size = cx.argument::<JsNumber>(0)?.value(&mut cx) as usize;
+
+        let reader = cx
+            .this()
+            .downcast_or_throw::<JsBox<MiscFileReaderWrapper>, _>(&mut cx)?
+            .0
+            .clone();
+
+        // Get the tokio runtime
+        let rt = runtime(&mut cx)?;
+        let channel = cx.channel();
+
+        // Create a promise
+        let (deferred, promise) = cx.promise();
+
+        // Spawn a task
+        rt.spawn(async move {
+            let mut data = vec![0; size];
+            let res = reader.lock().await.read(&mut data).await.map(|n| {
+                data.truncate(n);
+                data
+            });
+
+            // This runs on the JS main thread
+            deferred.settle_with(&channel, move |mut cx| {
+                let data = res.or_else(|err| cx.throw_error(err.to_string()))?;
+
+                if data.is_empty() {
+                    JsBuffer::new(&mut cx, 0)
+                } else {
+                    Ok(JsBuffer::external(&mut cx, data))
+                }
+            });
+        });
+
+        // Return the promise to node
+        Ok(promise)
+    }
 }
 
 #[neon::main]
 fn main(mut cx: ModuleContext) -> NeonResult<()> {
     cx.export_function("load", load)?;
     cx.export_function("infer", CartonWrapper::infer)?;
+    cx.export_function("open_misc_file", CartonWrapper::open_misc_file)?;
+    cx.export_function("read_misc_file_chunk", MiscFileReaderWrapper::read_chunk)?;
     Ok(())
 }
 
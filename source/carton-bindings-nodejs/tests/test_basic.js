@@ -63,4 +63,31 @@ async function testndarray() {
     await test(input)
 }
 
-testndarray()
\ No newline at end of file
+testndarray()
+
+async function testScalar() {
+    const ndarray = require('ndarray');
+    const assert = require('assert');
+
+    // A 0-d tensor (i.e. a scalar) has an empty shape and a single element
+    const input = {
+        "a": ndarray(new Float32Array([1.5]), [])
+    }
+
+    const model = await carton.load("/tmp/somepath", {
+        runner: "torchscript",
+        visible_device: "CPU",
+    })
+
+    let out = await model.infer(input)
+    console.log("Out: ", out)
+
+    // Every output buffer should match its shape (scalar outputs have an empty shape and one element)
+    const itemSize = { "float32": 4, "float64": 8, "int8": 1, "int16": 2, "int32": 4, "int64": 8, "uint8": 1, "uint16": 2, "uint32": 4, "uint64": 8 }
+    for (const key in out) {
+        const numel = out[key].shape.reduce((a, b) => a * b, 1)
+        assert.strictEqual(out[key].buffer.byteLength, numel * itemSize[out[key].dtype])
+    }
+}
+
+testScalar()
@@ -18,10 +18,12 @@ use std::sync::Arc;
 use std::{collections::HashMap, str::FromStr};
 
 use async_trait::async_trait;
-use carton_core::conversion_utils::{convert_map, convert_opt_map, convert_opt_vec, convert_vec};
+use carton_core::conversion_utils::{
+    convert_map, convert_opt_map, convert_opt_vec, convert_vec, try_convert_map,
+};
 use carton_core::info::LinkedFile;
 use carton_core::types::{DataType, RunnerOpt, Tensor};
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict};
 use pyo3::{exceptions::PyValueError, prelude::*, PyDowncastError};
 use semver::VersionReq;
 use target_lexicon::Triple;
@@ -35,6 +37,16 @@ pub(crate) fn create_load_opts(
     override_runner_name: Option<String>,
     override_required_framework_version: Option<String>,
     override_runner_opts: Option<HashMap<String, PyRunnerOpt>>,
+    streaming_buffer_size: Option<usize>,
+    auto_install_runner: Option<bool>,
+    max_archive_size: Option<u64>,
+    max_archive_file_size: Option<u64>,
+    expected_manifest_sha256: Option<String>,
+    runner_path_override: Option<String>,
+    signature_public_key: Option<String>,
+    runner_env: Option<HashMap<String, String>>,
+    prefetch_linked_files: Option<bool>,
+    seed: Option<u64>,
 ) -> PyResult<carton_core::types::LoadOpts> {
     Ok(carton_core::types::LoadOpts {
         override_runner_name,
@@ -49,6 +61,16 @@ pub(crate) fn create_load_opts(
                     .map_err(|e| PyValueError::new_err(e.to_string()))?,
             },
         },
+        streaming_buffer_size,
+        auto_install_runner: auto_install_runner.unwrap_or(true),
+        max_archive_size,
+        max_archive_file_size,
+        expected_manifest_sha256,
+        runner_path_override: runner_path_override.map(|v| v.into()),
+        signature_public_key,
+        runner_env: runner_env.unwrap_or_default(),
+        prefetch_linked_files: prefetch_linked_files.unwrap_or(false),
+        seed,
     })
 }
 
@@ -76,6 +98,10 @@ pub(crate) fn create_pack_opts(
     examples: Option<Vec<Example>>,
     misc_files: Option<HashMap<String, Vec<u8>>>,
     linked_files: Option<HashMap<String, Vec<String>>>,
+    allow_invalid_examples: bool,
+    signing_key: Option<String>,
+    store_uncompressed: bool,
+    compress_manifest: bool,
 ) -> PyResult<carton_core::types::PackOpts> {
     let misc_files: Option<HashMap<String, LazyLoadedMiscFile>> = convert_opt_map(misc_files);
 
@@ -103,11 +129,12 @@ pub(crate) fn create_pack_opts(
             },
             misc_files: convert_opt_map(misc_files),
         },
-        linked_files: linked_files.map(|v| {
-            v.into_iter()
-                .map(|(k, v)| LinkedFile { sha256: k, urls: v })
-                .collect()
-        }),
+        linked_files: linked_files
+            .map(|v| v.into_iter().map(|(k, v)| LinkedFile::new(v, k)).collect()),
+        allow_invalid_examples,
+        signing_key,
+        store_uncompressed,
+        compress_manifest,
     })
 }
 
@@ -173,6 +200,12 @@ pub(crate) struct CartonInfo {
     /// (i.e one that does not reference parent directories, etc)
     #[pyo3(get)]
     pub misc_files: Option<HashMap<String, LazyLoadedMiscFile>>,
+
+    /// The sha256 of the MANIFEST file (if available)
+    /// This should always be available unless we're running an unpacked model
+    // Note: this field is not directly in CartonInfo in the rust library
+    #[pyo3(get)]
+    pub manifest_sha256: Option<String>,
 }
 
 #[pymethods]
@@ -198,8 +231,9 @@ fn convert_required_platforms(r: Option<Vec<String>>) -> PyResult<Option<Vec<Tri
     })
 }
 
-impl From<carton_core::types::CartonInfo> for CartonInfo {
-    fn from(value: carton_core::types::CartonInfo) -> Self {
+impl From<carton_core::info::CartonInfoWithExtras> for CartonInfo {
+    fn from(info: carton_core::info::CartonInfoWithExtras) -> Self {
+        let value = info.info;
         Self {
             model_name: value.model_name,
             short_description: value.short_description,
@@ -224,6 +258,7 @@ impl From<carton_core::types::CartonInfo> for CartonInfo {
                 opts: convert_opt_map(value.runner.opts),
             },
             misc_files: convert_opt_map(value.misc_files),
+            manifest_sha256: info.manifest_sha256,
         }
     }
 }
@@ -249,6 +284,14 @@ pub(crate) struct TensorSpec {
     /// Optional internal name
     #[pyo3(get, set)]
     pub internal_name: Option<String>,
+
+    /// Whether this input can be omitted when running inference
+    #[pyo3(get, set)]
+    pub optional: bool,
+
+    /// The value to use for an optional input if it isn't provided
+    #[pyo3(get, set)]
+    pub default: Option<LazyLoadedTensor>,
 }
 
 #[pymethods]
@@ -266,11 +309,14 @@ impl TensorSpec {
     }
 
     #[new]
+    #[pyo3(signature = (name, dtype, shape, description=None, optional=false, default=None))]
     fn new<'py>(
         name: String,
         dtype: &str,
         shape: Shape,
         description: Option<String>,
+        optional: bool,
+        default: Option<SupportedTensorType<'py>>,
     ) -> PyResult<Self> {
         Ok(Self {
             name,
@@ -278,6 +324,8 @@ impl TensorSpec {
             shape,
             description,
             internal_name: None,
+            optional,
+            default: default.map(|v| v.try_into()).transpose()?,
         })
     }
 }
@@ -290,6 +338,8 @@ impl From<TensorSpec> for carton_core::info::TensorSpec {
             shape: value.shape.into(),
             description: value.description,
             internal_name: value.internal_name,
+            optional: value.optional,
+            default: value.default.map(|v| v.into()),
         }
     }
 }
@@ -302,6 +352,8 @@ impl From<carton_core::info::TensorSpec> for TensorSpec {
             shape: value.shape.into(),
             description: value.description,
             internal_name: value.internal_name,
+            optional: value.optional,
+            default: value.default.map(|v| v.into()),
         }
     }
 }
@@ -357,12 +409,35 @@ fn handle_none(item: &PyAny) -> PyResult<()> {
     }
 }
 
-/// A dimension can be either a fixed value, a symbol, or any value
+/// A dimension can be either a fixed value, a symbol, a bounded range, or any value
+/// Ranges are represented as a dict with optional `min` and `max` keys (e.g. `{"min": 1, "max": 64}`)
 #[derive(Clone, FromPyObject, Debug)]
 pub enum Dimension {
     Value(u64),
     Symbol(String),
     Any(#[pyo3(from_py_with = "handle_none")] ()),
+    Range(#[pyo3(from_py_with = "handle_range")] (Option<u64>, Option<u64>)),
+}
+
+fn handle_range(item: &PyAny) -> PyResult<(Option<u64>, Option<u64>)> {
+    let dict: &PyDict = item.downcast()?;
+    let get_bound = |key: &str| -> PyResult<Option<u64>> {
+        match dict.get_item(key) {
+            Some(v) if !v.is_none() => Ok(Some(v.extract()?)),
+            _ => Ok(None),
+        }
+    };
+
+    let (min, max) = (get_bound("min")?, get_bound("max")?);
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(PyValueError::new_err(format!(
+                "The `min` of a dimension range must not be larger than the `max`, but got {min} > {max}"
+            )));
+        }
+    }
+
+    Ok((min, max))
 }
 
 impl From<Dimension> for carton_core::info::Dimension {
@@ -371,6 +446,7 @@ impl From<Dimension> for carton_core::info::Dimension {
             Dimension::Value(v) => Self::Value(v),
             Dimension::Symbol(v) => Self::Symbol(v),
             Dimension::Any(_) => Self::Any,
+            Dimension::Range((min, max)) => Self::Range { min, max },
         }
     }
 }
@@ -381,6 +457,7 @@ impl From<carton_core::info::Dimension> for Dimension {
             carton_core::info::Dimension::Value(v) => Self::Value(v),
             carton_core::info::Dimension::Symbol(v) => Self::Symbol(v),
             carton_core::info::Dimension::Any => Self::Any(()),
+            carton_core::info::Dimension::Range { min, max } => Self::Range((min, max)),
         }
     }
 }
@@ -391,6 +468,12 @@ impl IntoPy<PyObject> for Dimension {
             Dimension::Value(item) => item.into_py(py),
             Dimension::Symbol(item) => item.into_py(py),
             Dimension::Any(_) => Python::None(py),
+            Dimension::Range((min, max)) => {
+                let out = PyDict::new(py);
+                out.set_item("min", min).unwrap();
+                out.set_item("max", max).unwrap();
+                out.into_py(py)
+            }
         }
     }
 }
@@ -424,8 +507,8 @@ impl SelfTest {
         Ok(Self {
             name,
             description,
-            inputs: convert_map(inputs),
-            expected_out: convert_opt_map(expected_out),
+            inputs: try_convert_map(inputs)?,
+            expected_out: expected_out.map(try_convert_map).transpose()?,
         })
     }
 }
@@ -458,12 +541,14 @@ pub(crate) enum PyArrayOrMisc<'py> {
     Misc(Vec<u8>),
 }
 
-impl<'py> From<PyArrayOrMisc<'py>> for TensorOrMisc {
-    fn from(value: PyArrayOrMisc<'py>) -> Self {
-        match value {
-            PyArrayOrMisc::Tensor(v) => Self::Tensor(v.into()),
+impl<'py> TryFrom<PyArrayOrMisc<'py>> for TensorOrMisc {
+    type Error = PyErr;
+
+    fn try_from(value: PyArrayOrMisc<'py>) -> PyResult<Self> {
+        Ok(match value {
+            PyArrayOrMisc::Tensor(v) => Self::Tensor(v.try_into()?),
             PyArrayOrMisc::Misc(v) => Self::Misc(v.into()),
-        }
+        })
     }
 }
 
@@ -495,8 +580,8 @@ impl Example {
         Ok(Self {
             name,
             description,
-            inputs: convert_map(inputs),
-            sample_out: convert_map(sample_out),
+            inputs: try_convert_map(inputs)?,
+            sample_out: try_convert_map(sample_out)?,
         })
     }
 }
@@ -535,11 +620,13 @@ impl std::fmt::Debug for LazyLoadedTensor {
     }
 }
 
-impl<'py> From<SupportedTensorType<'py>> for LazyLoadedTensor {
-    fn from(value: SupportedTensorType<'py>) -> Self {
-        LazyLoadedTensor {
-            inner: carton_core::info::PossiblyLoaded::from_value(value.into()),
-        }
+impl<'py> TryFrom<SupportedTensorType<'py>> for LazyLoadedTensor {
+    type Error = PyErr;
+
+    fn try_from(value: SupportedTensorType<'py>) -> PyResult<Self> {
+        Ok(LazyLoadedTensor {
+            inner: carton_core::info::PossiblyLoaded::from_value(value.try_into()?),
+        })
     }
 }
 
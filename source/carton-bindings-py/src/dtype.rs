@@ -0,0 +1,130 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mapping between carton datatypes and numpy dtypes
+use std::str::FromStr;
+
+use carton_core::types::DataType;
+use pyo3::{basic::CompareOp, exceptions::PyValueError, prelude::*, types::PyString};
+
+/// A carton datatype (e.g. the `dtype` of a `TensorSpec`)
+#[pyclass(name = "DataType")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PyDataType {
+    inner: DataType,
+}
+
+#[pymethods]
+impl PyDataType {
+    /// Create a datatype from its name (e.g. "float32" or "string")
+    #[new]
+    fn new(name: &str) -> PyResult<Self> {
+        Ok(Self {
+            inner: DataType::from_str(name).map_err(|e| PyValueError::new_err(e))?,
+        })
+    }
+
+    /// Get the carton datatype for a numpy dtype. This accepts anything `np.dtype` does
+    /// (e.g. `np.float32`, `'<f4'`, `'int64'`, `np.dtype('U5')`).
+    ///
+    /// Raises a `ValueError` if carton doesn't support the dtype or if it has a non-native
+    /// byte order
+    #[staticmethod]
+    fn from_numpy(py: Python, dtype: &PyAny) -> PyResult<Self> {
+        // Normalize aliases and byte order markers (e.g. `'<f4'` on little endian platforms
+        // has a byte order of `'='`)
+        let dtype = py.import("numpy")?.call_method1("dtype", (dtype,))?;
+        let kind: char = dtype.getattr("kind")?.extract()?;
+        let itemsize: usize = dtype.getattr("itemsize")?.extract()?;
+        let byteorder: char = dtype.getattr("byteorder")?.extract()?;
+
+        if byteorder == '<' || byteorder == '>' {
+            return Err(PyValueError::new_err(format!(
+                "numpy dtype `{}` has a non-native byte order. Consider converting your array with `arr.astype(arr.dtype.newbyteorder('='))`",
+                dtype.str()?
+            )));
+        }
+
+        let inner = match (kind, itemsize) {
+            ('f', 4) => DataType::Float,
+            ('f', 8) => DataType::Double,
+            ('i', 1) => DataType::I8,
+            ('i', 2) => DataType::I16,
+            ('i', 4) => DataType::I32,
+            ('i', 8) => DataType::I64,
+            ('u', 1) => DataType::U8,
+            ('u', 2) => DataType::U16,
+            ('u', 4) => DataType::U32,
+            ('u', 8) => DataType::U64,
+            ('c', 8) => DataType::Complex64,
+            ('c', 16) => DataType::Complex128,
+            // Unicode strings of any length
+            ('U', _) => DataType::String,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "numpy dtype `{}` is not supported by carton",
+                    dtype.str()?
+                )))
+            }
+        };
+
+        Ok(Self { inner })
+    }
+
+    /// Get the numpy dtype for this datatype. Strings map to numpy unicode strings (`np.dtype('U')`)
+    fn to_numpy(&self, py: Python) -> PyResult<PyObject> {
+        let name = match self.inner {
+            DataType::String => "U",
+            other => other.to_str(),
+        };
+
+        Ok(py.import("numpy")?.call_method1("dtype", (name,))?.into())
+    }
+
+    /// The name of this datatype (e.g. "float32")
+    #[getter]
+    fn name(&self) -> &'static str {
+        self.inner.to_str()
+    }
+
+    fn __str__(&self) -> &'static str {
+        self.inner.to_str()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DataType('{}')", self.inner.to_str())
+    }
+
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp, py: Python) -> PyObject {
+        // Allow comparisons with other datatypes and with datatype names (e.g. `TensorSpec.dtype`)
+        let other = match other.extract::<PyDataType>() {
+            Ok(other) => Some(other.inner),
+            Err(_) => other
+                .extract::<&str>()
+                .ok()
+                .and_then(|name| DataType::from_str(name).ok()),
+        };
+
+        match (other, op) {
+            (Some(other), CompareOp::Eq) => (self.inner == other).into_py(py),
+            (Some(other), CompareOp::Ne) => (self.inner != other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __hash__(&self, py: Python) -> PyResult<isize> {
+        // Hash the same way as the name since we compare equal to it
+        PyString::new(py, self.inner.to_str()).hash()
+    }
+}
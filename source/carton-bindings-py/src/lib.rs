@@ -17,14 +17,17 @@ use std::{
     sync::{Arc, OnceLock},
 };
 
+use carton_core::conversion_utils::try_convert_map;
 use conversions::{
     create_load_opts, create_pack_opts, CartonInfo, Device, Example, LazyLoadedMiscFile,
     LazyLoadedTensor, PyRunnerOpt, RunnerInfo, SelfTest, TensorSpec,
 };
+use dtype::PyDataType;
 use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
 use tensor::{tensor_to_py, SupportedTensorType};
 
 mod conversions;
+mod dtype;
 mod tensor;
 
 #[pyclass]
@@ -48,8 +51,8 @@ fn maybe_init_logging() -> &'static pyo3_log::ResetHandle {
 #[pymethods]
 impl Carton {
     fn infer<'a>(&self, py: Python<'a>, tensors: &PyDict) -> PyResult<&'a PyAny> {
-        let tensors: HashMap<String, SupportedTensorType> = tensors.extract().unwrap();
-        let transformed: HashMap<_, _> = tensors.into_iter().map(|(k, v)| (k, v.into())).collect();
+        let tensors: HashMap<String, SupportedTensorType> = tensors.extract()?;
+        let transformed: HashMap<String, carton_core::types::Tensor> = try_convert_map(tensors)?;
 
         let inner = self.inner.clone();
         pyo3_asyncio::tokio::future_into_py(py, async move {
@@ -66,8 +69,8 @@ impl Carton {
     }
 
     fn seal<'a>(&self, py: Python<'a>, tensors: &PyDict) -> PyResult<&'a PyAny> {
-        let tensors: HashMap<String, SupportedTensorType> = tensors.extract().unwrap();
-        let transformed = tensors.into_iter().map(|(k, v)| (k, v.into())).collect();
+        let tensors: HashMap<String, SupportedTensorType> = tensors.extract()?;
+        let transformed: HashMap<String, carton_core::types::Tensor> = try_convert_map(tensors)?;
 
         let inner = self.inner.clone();
         pyo3_asyncio::tokio::future_into_py(py, async move {
@@ -94,7 +97,7 @@ impl Carton {
     #[getter]
     fn info(&self) -> CartonInfo {
         // TODO: maybe cache this conversion?
-        (*self.inner.get_info()).info.clone().into()
+        self.inner.get_info().clone().into()
     }
 }
 
@@ -107,6 +110,16 @@ fn load(
     override_runner_name: Option<String>,
     override_required_framework_version: Option<String>,
     override_runner_opts: Option<HashMap<String, PyRunnerOpt>>,
+    streaming_buffer_size: Option<usize>,
+    auto_install_runner: Option<bool>,
+    max_archive_size: Option<u64>,
+    max_archive_file_size: Option<u64>,
+    expected_manifest_sha256: Option<String>,
+    runner_path_override: Option<String>,
+    signature_public_key: Option<String>,
+    runner_env: Option<HashMap<String, String>>,
+    prefetch_linked_files: Option<bool>,
+    seed: Option<u64>,
 ) -> PyResult<&PyAny> {
     maybe_init_logging();
     pyo3_asyncio::tokio::future_into_py(py, async move {
@@ -115,6 +128,16 @@ fn load(
             override_runner_name,
             override_required_framework_version,
             override_runner_opts,
+            streaming_buffer_size,
+            auto_install_runner,
+            max_archive_size,
+            max_archive_file_size,
+            expected_manifest_sha256,
+            runner_path_override,
+            signature_public_key,
+            runner_env,
+            prefetch_linked_files,
+            seed,
         )?;
 
         // TODO: use something more specific than ValueError
@@ -172,10 +195,29 @@ fn load_unpacked(
             examples,
             misc_files,
             linked_files,
+            false,
+            None,
+            false,
+            false,
         )?;
 
         // No need for overrides here
-        let load_opts = create_load_opts(visible_device, None, None, None)?;
+        let load_opts = create_load_opts(
+            visible_device,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )?;
 
         let inner = carton_core::Carton::load_unpacked(path, pack_opts, load_opts)
             .await
@@ -209,6 +251,10 @@ fn pack(
     examples: Option<Vec<Example>>,
     misc_files: Option<HashMap<String, Vec<u8>>>,
     linked_files: Option<HashMap<String, Vec<String>>>,
+    allow_invalid_examples: Option<bool>,
+    signing_key: Option<String>,
+    store_uncompressed: Option<bool>,
+    compress_manifest: Option<bool>,
 ) -> PyResult<&PyAny> {
     maybe_init_logging();
     pyo3_asyncio::tokio::future_into_py(py, async move {
@@ -230,6 +276,10 @@ fn pack(
             examples,
             misc_files,
             linked_files,
+            allow_invalid_examples.unwrap_or(false),
+            signing_key,
+            store_uncompressed.unwrap_or(false),
+            compress_manifest.unwrap_or(false),
         )?;
 
         let out = carton_core::Carton::pack(path, opts)
@@ -247,7 +297,6 @@ fn get_model_info(py: Python, url_or_path: String) -> PyResult<&PyAny> {
     pyo3_asyncio::tokio::future_into_py(py, async move {
         let out: CartonInfo = carton_core::Carton::get_model_info(url_or_path)
             .await
-            .map(|v| v.info)
             .map_err(|e| PyValueError::new_err(e.to_string()))?
             .into();
 
@@ -290,5 +339,6 @@ fn cartonml(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<LazyLoadedTensor>()?;
     m.add_class::<LazyLoadedMiscFile>()?;
     m.add_class::<RunnerInfo>()?;
+    m.add_class::<PyDataType>()?;
     Ok(())
 }
@@ -32,6 +32,9 @@ pub(crate) enum SupportedTensorType<'py> {
     U32(&'py PyArrayDyn<u32>),
     U64(&'py PyArrayDyn<u64>),
 
+    Complex64(&'py PyArrayDyn<num_complex::Complex<f32>>),
+    Complex128(&'py PyArrayDyn<num_complex::Complex<f64>>),
+
     String(PyStringArrayType<'py>),
 }
 
@@ -126,9 +129,11 @@ impl TypedStorage<String> for StringPyTensorStorage {
     }
 }
 
-impl From<SupportedTensorType<'_>> for Tensor {
-    fn from(value: SupportedTensorType<'_>) -> Self {
-        match value {
+impl TryFrom<SupportedTensorType<'_>> for Tensor {
+    type Error = pyo3::PyErr;
+
+    fn try_from(value: SupportedTensorType<'_>) -> Result<Self, Self::Error> {
+        Ok(match value {
             SupportedTensorType::Float(item) => {
                 Tensor::Float(TypedPyTensorStorage::from(item).into())
             }
@@ -136,7 +141,7 @@ impl From<SupportedTensorType<'_>> for Tensor {
                 Tensor::Double(TypedPyTensorStorage::from(item).into())
             }
             SupportedTensorType::String(item) => {
-                let arr = item.to_ndarray();
+                let arr = item.to_ndarray()?;
                 let out = StringPyTensorStorage::new(arr);
                 Tensor::String(out.into())
             }
@@ -150,7 +155,14 @@ impl From<SupportedTensorType<'_>> for Tensor {
             SupportedTensorType::U16(item) => Tensor::U16(TypedPyTensorStorage::from(item).into()),
             SupportedTensorType::U32(item) => Tensor::U32(TypedPyTensorStorage::from(item).into()),
             SupportedTensorType::U64(item) => Tensor::U64(TypedPyTensorStorage::from(item).into()),
-        }
+
+            SupportedTensorType::Complex64(item) => {
+                Tensor::Complex64(TypedPyTensorStorage::from(item).into())
+            }
+            SupportedTensorType::Complex128(item) => {
+                Tensor::Complex128(TypedPyTensorStorage::from(item).into())
+            }
+        })
     }
 }
 
@@ -174,7 +186,9 @@ pub(crate) fn tensor_to_py(item: &Tensor) -> PyObject {
             Tensor::U16(item) => item.view().to_pyarray(py).to_object(py),
             Tensor::U32(item) => item.view().to_pyarray(py).to_object(py),
             Tensor::U64(item) => item.view().to_pyarray(py).to_object(py),
-            Tensor::NestedTensor(_) => {
+            Tensor::Complex64(item) => item.view().to_pyarray(py).to_object(py),
+            Tensor::Complex128(item) => item.view().to_pyarray(py).to_object(py),
+            Tensor::NestedTensor(_) | Tensor::LabeledNestedTensor { .. } => {
                 panic!("Nested tensor output not implemented yet")
             }
         }
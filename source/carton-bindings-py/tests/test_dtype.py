@@ -0,0 +1,43 @@
+# Copyright 2023 Vivek Panyam
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import sys
+
+import cartonml as carton
+import numpy as np
+
+# Aliases and byte order markers
+assert carton.DataType.from_numpy(np.float32) == carton.DataType("float32")
+assert carton.DataType.from_numpy("=f4") == "float32"
+assert carton.DataType.from_numpy("int64").name == "int64"
+assert carton.DataType.from_numpy(np.dtype("U5")) == "string"
+assert carton.DataType.from_numpy(np.arange(4).dtype) == carton.DataType.from_numpy(np.int_)
+
+native = "<" if sys.byteorder == "little" else ">"
+swapped = ">" if sys.byteorder == "little" else "<"
+assert carton.DataType.from_numpy(native + "u2") == "uint16"
+
+# Non-native byte orders and unsupported dtypes are errors
+for dtype in [swapped + "f4", np.float16, np.bool_, object]:
+    try:
+        carton.DataType.from_numpy(dtype)
+        assert False, f"Expected an error for {dtype}"
+    except ValueError:
+        pass
+
+# Round trips
+for name in ["float32", "float64", "int8", "int16", "int32", "int64", "uint8", "uint16", "uint32", "uint64", "complex64", "complex128", "string"]:
+    dtype = carton.DataType(name)
+    assert carton.DataType.from_numpy(dtype.to_numpy()) == dtype
+    assert np.zeros(2, dtype=dtype.to_numpy()).dtype.kind in "fiucU"
@@ -59,6 +59,7 @@ class Test(unittest.IsolatedAsyncioTestCase):
             dict(name = "i", dtype = "uint16", shape = ["multiple", "symbols"]),
             dict(name = "j", dtype = "uint32", shape = [2, None, "symbol"]),
             dict(name = "long", dtype = "uint64", shape = None, description = "A description"),
+            dict(name = "opt", dtype = "float32", shape = [], optional = True),
         ]
         
         for item in items:
@@ -70,6 +71,15 @@ class Test(unittest.IsolatedAsyncioTestCase):
             if "description" in item:
                 self.assertEqual(ts.description, item["description"])
 
+            self.assertEqual(ts.optional, item.get("optional", False))
+
+    def test_invalid_unicode_string_tensor(self):
+        """
+        Ensure that string arrays containing invalid unicode (e.g. lone surrogates) raise an error instead of panicking
+        """
+        with self.assertRaisesRegex(ValueError, "Element 2 of the string array"):
+            SelfTest(inputs = dict(a = np.array(["Hello", "World", "\ud800"])))
+
     async def test_roundtrip_selftest(self):
         """
         Ensure that python -> rust -> python doesn't change anything for SelfTests
@@ -305,5 +315,8 @@ def get_model():
         model_arch_readback = await model.info.misc_files["model_architecture.png"].read()
         self.assertEqual(self.model_architecture, model_arch_readback)
 
+        # Unpacked models don't have a manifest
+        self.assertIsNone(model.info.manifest_sha256)
+
 if __name__ == "__main__":
     unittest.main()
\ No newline at end of file
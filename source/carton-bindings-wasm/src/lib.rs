@@ -12,14 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use wasm_bindgen::prelude::*;
+use wasm_bindgen::{prelude::*, JsCast};
 
 use carton_core::{
     conversion_utils::convert_vec,
     info::{ArcMiscFileLoader, PossiblyLoaded},
-    types::{for_each_numeric_carton_type, Tensor},
+    types::{for_each_numeric_or_complex_carton_type, Tensor},
 };
 use serde::ser::Serialize;
 use tokio_util::compat::TokioAsyncReadCompatExt;
@@ -99,6 +99,10 @@ pub struct CartonInfo {
     /// (i.e one that does not reference parent directories, etc)
     pub misc_files: Option<js_sys::Map>, // HashMap<String, MiscFileLoaderWrapper>
 
+    /// The misc files referenced by `model_description`
+    // Note: this field is not directly in CartonInfo in the rust library
+    pub misc_file_references: MiscFileReferences,
+
     /// The sha256 of the MANIFEST file (if available)
     /// This should always be available unless we're running an unpacked model
     // Note: this field is not directly in CartonInfo in the rust library
@@ -108,6 +112,7 @@ pub struct CartonInfo {
 impl From<carton_core::info::CartonInfoWithExtras> for CartonInfo {
     fn from(info: carton_core::info::CartonInfoWithExtras) -> Self {
         let value = info.info;
+        let misc_file_references = value.resolve_misc_file_references().into();
         let serializer = serde_wasm_bindgen::Serializer::json_compatible();
         Self {
             model_name: value.model_name,
@@ -139,11 +144,45 @@ impl From<carton_core::info::CartonInfoWithExtras> for CartonInfo {
                         .collect(),
                 )
             }),
+            misc_file_references,
             manifest_sha256: info.manifest_sha256,
         }
     }
 }
 
+/// The misc file references in a model description
+#[derive(Clone)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct MiscFileReferences {
+    /// References that match a misc file
+    pub resolved: js_sys::Map, // HashMap<String, MiscFileLoaderWrapper>
+
+    /// References to misc files that don't exist
+    pub missing: js_sys::Array, // Vec<String>
+
+    /// Misc files that aren't referenced by the description
+    pub unreferenced: js_sys::Array, // Vec<String>
+}
+
+impl From<carton_core::info::MiscFileReferences> for MiscFileReferences {
+    fn from(value: carton_core::info::MiscFileReferences) -> Self {
+        // Note: `Map` keeps insertion order so `resolved` stays in the order of the description
+        let resolved = js_sys::Map::new();
+        for item in value.resolved {
+            resolved.set(
+                &item.reference.into(),
+                &MiscFileLoaderWrapper(item.loader).into(),
+            );
+        }
+
+        Self {
+            resolved,
+            missing: value.missing.into_iter().map(JsValue::from).collect(),
+            unreferenced: value.unreferenced.into_iter().map(JsValue::from).collect(),
+        }
+    }
+}
+
 fn to_map<K: Into<JsValue>, V: Into<JsValue>>(value: HashMap<K, V>) -> js_sys::Map {
     let out = js_sys::Map::new();
     for (k, v) in value {
@@ -248,10 +287,53 @@ pub struct TensorWrapper {
     pub dtype: String,
     pub stride: Vec<usize>,
 
+    // The location and length (in bytes) of the data of a numeric tensor in WASM memory.
+    // This stays valid as long as `_keepalive` is alive
+    data: Option<(*const u8, usize)>,
+
+    // A view of `data` along with the WASM memory buffer it was created from
+    view_cache: Rc<RefCell<Option<(JsValue, js_sys::Uint8Array)>>>,
+
     // Keep the tensor alive
     _keepalive: PossiblyLoaded<Tensor>,
 }
 
+#[wasm_bindgen]
+impl TensorWrapper {
+    /// Get the data of a numeric tensor as a `Uint8Array` that points directly into WASM memory (instead
+    /// of the copy in `buffer`). The view is cached and reused until the WASM memory grows, which
+    /// invalidates existing views. Callers should not hold on to the returned view across calls into WASM.
+    ///
+    /// For string tensors, this returns `buffer`
+    pub fn view(&self) -> JsValue {
+        let (ptr, len) = match self.data {
+            Some(data) => data,
+            None => return self.buffer.clone(),
+        };
+
+        let memory_buffer = wasm_bindgen::memory()
+            .unchecked_into::<js_sys::WebAssembly::Memory>()
+            .buffer();
+
+        let mut cache = self.view_cache.borrow_mut();
+        if let Some((cached_buffer, view)) = cache.as_ref() {
+            // The memory buffer is replaced when WASM memory grows
+            if cached_buffer == &memory_buffer {
+                return view.clone().into();
+            }
+        }
+
+        let view = js_sys::Uint8Array::new_with_byte_offset_and_length(
+            &memory_buffer,
+            ptr as u32,
+            len as u32,
+        );
+        *cache = Some((memory_buffer, view.clone()));
+
+        view.into()
+    }
+}
+
 #[wasm_bindgen]
 pub struct PossiblyLoadedWrapper(PossiblyLoaded<TensorWrapper>);
 
@@ -260,17 +342,14 @@ impl From<PossiblyLoaded<Tensor>> for PossiblyLoadedWrapper {
         Self(PossiblyLoaded::from_loader(Box::pin(async move {
             let t = value.get().await;
 
-            for_each_numeric_carton_type! {
+            for_each_numeric_or_complex_carton_type! {
                 return match t {
                     $(
                         carton_core::types::Tensor::$CartonType(item) => {
                             // TODO: handle things not in standard layout
                             // view.as_standard_layout() can create a copy so we need to ensure that stays alive if we use it
                             let view = item.view();
-                            let data = view.as_slice().unwrap();
-
-                            // Convert to a u8 slice
-                            let u8slice = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * std::mem::size_of::<$RustType>()) };
+                            let u8slice = t.as_bytes().unwrap();
 
                             // Avoiding a copy here is hard because views can be invalidated by new memory allocations
                             // that cause the WASM buffer size to change
@@ -282,12 +361,14 @@ impl From<PossiblyLoaded<Tensor>> for PossiblyLoadedWrapper {
                                 shape: view.shape().iter().map(|v| *v as _).collect(),
                                 dtype: $TypeStr.to_owned(),
                                 stride: view.strides().iter().map(|v| *v as _).collect(),
+                                data: Some((u8slice.as_ptr(), u8slice.len())),
+                                view_cache: Default::default(),
 
                                 _keepalive: value
                             }
                         },
                     )*
-                    carton_core::types::Tensor::NestedTensor(_) => panic!("Nested tensor output not implemented yet"),
+                    carton_core::types::Tensor::NestedTensor(_) | carton_core::types::Tensor::LabeledNestedTensor { .. } => panic!("Nested tensor output not implemented yet"),
                     carton_core::types::Tensor::String(item) => {
                         let view = item.view();
                         let data: Vec<_> = view.as_standard_layout().into_iter().collect();
@@ -297,6 +378,8 @@ impl From<PossiblyLoaded<Tensor>> for PossiblyLoadedWrapper {
                             shape: view.shape().iter().map(|v| *v as _).collect(),
                             dtype: "string".into(),
                             stride: view.strides().iter().map(|v| *v as _).collect(),
+                            data: None,
+                            view_cache: Default::default(),
 
                             // TODO: do we need this keepalive for string tensors?
                             _keepalive: value
@@ -13,18 +13,30 @@
 // limitations under the License.
 
 const carton = require("../dist/node")
+const assert = require("assert")
 
 const express = require('express')
 const app = express()
 
 app.use(express.static('data'))
 
+const ITEM_SIZE = { "float32": 4, "float64": 8, "int8": 1, "int16": 2, "int32": 4, "int64": 8, "uint8": 1, "uint16": 2, "uint32": 4, "uint64": 8, "complex64": 8, "complex128": 16 }
+
 const loadTensorOrMisc = async (item) => {
     if (item.constructor.name === "MiscFileLoaderWrapper") {
         const stream = await item.get()
         return stream
     } else {
         const tensor = await item.get()
+        if (tensor.dtype !== "string") {
+            // The zero-copy view should have the same contents as the buffer
+            assert.deepStrictEqual(new Uint8Array(tensor.view()), tensor.buffer)
+
+            // The buffer should match the shape. Scalars (0-d tensors) have an empty shape and one element
+            const numel = tensor.shape.reduce((a, b) => a * b, 1)
+            assert.strictEqual(tensor.buffer.byteLength, numel * ITEM_SIZE[tensor.dtype])
+        }
+
         return {
             buffer: tensor.buffer,
             shape: tensor.shape,
@@ -15,8 +15,9 @@
 //! This benchmark measures tensor allocation overhead
 use carton_macros::for_each_numeric_carton_type;
 use carton_runner_interface::_only_public_for_benchmarks_do_not_use::{
-    alloc_tensor_inline, alloc_tensor_no_pool_inline, alloc_tensor_no_pool_shm, alloc_tensor_shm,
-    InlineAllocator, InlineTensorStorage, SHMAllocator, SHMTensorStorage, TypedAlloc,
+    alloc_tensor_filled_inline, alloc_tensor_filled_shm, alloc_tensor_inline,
+    alloc_tensor_no_pool_inline, alloc_tensor_no_pool_shm, alloc_tensor_shm, InlineAllocator,
+    InlineTensorStorage, SHMAllocator, SHMTensorStorage, TypedAlloc,
 };
 use criterion::{
     criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup, BenchmarkId,
@@ -45,7 +46,7 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         shape,
         |b, shape| {
             b.iter(|| {
-                let mut t = alloc_tensor_inline::<T>(shape.clone());
+                let mut t = alloc_tensor_inline::<T>(shape.clone()).unwrap();
                 t.view_mut()
                     .as_slice_mut()
                     .unwrap()
@@ -62,7 +63,7 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         shape,
         |b, shape| {
             b.iter(|| {
-                let mut t = alloc_tensor_no_pool_inline::<T>(shape.clone());
+                let mut t = alloc_tensor_no_pool_inline::<T>(shape.clone()).unwrap();
                 t.view_mut()
                     .as_slice_mut()
                     .unwrap()
@@ -78,7 +79,7 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         ),
         shape,
         |b, shape| {
-            let mut t = alloc_tensor_inline::<T>(shape.clone());
+            let mut t = alloc_tensor_inline::<T>(shape.clone()).unwrap();
             b.iter(|| {
                 t.view_mut()
                     .as_slice_mut()
@@ -88,6 +89,17 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         },
     );
 
+    group.bench_with_input(
+        BenchmarkId::new(
+            "inline_storage_filled",
+            name.to_owned() + "_" + std::any::type_name::<T>(),
+        ),
+        shape,
+        |b, shape| {
+            b.iter(|| alloc_tensor_filled_inline::<T>(shape.clone(), fill_value.clone()).unwrap())
+        },
+    );
+
     group.bench_with_input(
         BenchmarkId::new(
             "shm_storage_with_pool",
@@ -96,7 +108,7 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         shape,
         |b, shape| {
             b.iter(|| {
-                let mut t = alloc_tensor_shm::<T>(shape.clone());
+                let mut t = alloc_tensor_shm::<T>(shape.clone()).unwrap();
                 t.view_mut()
                     .as_slice_mut()
                     .unwrap()
@@ -105,6 +117,17 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         },
     );
 
+    group.bench_with_input(
+        BenchmarkId::new(
+            "shm_storage_filled",
+            name.to_owned() + "_" + std::any::type_name::<T>(),
+        ),
+        shape,
+        |b, shape| {
+            b.iter(|| alloc_tensor_filled_shm::<T>(shape.clone(), fill_value.clone()).unwrap())
+        },
+    );
+
     group.bench_with_input(
         BenchmarkId::new(
             "shm_storage_without_pool",
@@ -113,7 +136,7 @@ fn typed_alloc_benchmark<T: Clone + Default, U: Measurement>(
         shape,
         |b, shape| {
             b.iter(|| {
-                let mut t = alloc_tensor_no_pool_shm::<T>(shape.clone());
+                let mut t = alloc_tensor_no_pool_shm::<T>(shape.clone()).unwrap();
                 t.view_mut()
                     .as_slice_mut()
                     .unwrap()
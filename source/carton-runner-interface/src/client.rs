@@ -12,7 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::{atomic::AtomicU64, Arc};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
 
 use anywhere::{transport::serde::SerdeTransport, Servable};
 use dashmap::DashMap;
@@ -20,6 +26,7 @@ use lunchbox::types::{MaybeSend, MaybeSync};
 use tokio::sync::{mpsc, oneshot};
 
 use crate::{
+    capabilities,
     do_not_modify::comms::OwnedComms,
     do_not_modify::{
         comms::Comms,
@@ -28,12 +35,18 @@ use crate::{
         },
     },
     do_spawn,
+    error::RunnerError,
     multiplexer::Multiplexer,
 };
 
+/// The default max number of responses to buffer for each streaming RPC
+pub(crate) const DEFAULT_STREAMING_BUFFER_SIZE: usize = 16;
+
+type ResponseResult = Result<RPCResponseData, RunnerError>;
+
 enum ResponseQueue {
-    OneShot(oneshot::Sender<RPCResponseData>),
-    Streaming(mpsc::Sender<RPCResponseData>),
+    OneShot(oneshot::Sender<ResponseResult>),
+    Streaming(mpsc::Sender<ResponseResult>),
 }
 
 pub(crate) struct Client {
@@ -42,9 +55,18 @@ pub(crate) struct Client {
 
     // RPC handling
     inflight: Arc<DashMap<RpcId, ResponseQueue>>,
-    rpc_id_gen: AtomicU64,
+    rpc_id_gen: Arc<AtomicU64>,
     rpc_sender: mpsc::Sender<RPCRequest>,
 
+    // Set once the runner exits or disconnects
+    disconnected: Arc<AtomicBool>,
+
+    // The optional features the runner told us it supports (see `crate::capabilities`)
+    capabilities: Arc<RwLock<HashSet<String>>>,
+
+    // The max number of responses to buffer for each streaming RPC
+    streaming_buffer_size: usize,
+
     // Filesystem handling
     fs_multiplexer: Multiplexer<
         anywhere::transport::serde::ResponseMessageType,
@@ -64,30 +86,70 @@ impl Client {
         // Hold inflight requests
         let inflight: Arc<DashMap<RpcId, ResponseQueue>> = Arc::new(DashMap::new());
         let inflight_clone = inflight.clone();
+        let disconnected = Arc::new(AtomicBool::new(false));
+        let disconnected_clone = disconnected.clone();
+        let capabilities = Arc::new(RwLock::new(HashSet::new()));
+        let capabilities_clone = capabilities.clone();
 
         // Handle rpc responses
+        // Note: if a streaming response queue is full, we wait for the consumer to catch up before
+        // handling any other responses. This applies backpressure all the way to the runner so
+        // it pauses instead of buffering an unbounded number of responses.
+        //
+        // Responses for requests that were cancelled (i.e. their entries in `inflight` were removed
+        // or their receivers were dropped) are ignored
         tokio::spawn(async move {
             while let Some(response) = recv.recv().await {
-                // Handle logging
-                if let RPCResponseData::LogMessage { record } = response.data {
-                    record.do_log();
-                } else {
-                    // Send the response to the callback
-                    if response.complete {
-                        match inflight_clone.remove(&response.id).unwrap().1 {
-                            ResponseQueue::OneShot(v) => v.send(response.data).unwrap(),
-                            ResponseQueue::Streaming(v) => v.send(response.data).await.unwrap(),
-                        }
-                    } else {
-                        match inflight_clone.get(&response.id).unwrap().value() {
-                            ResponseQueue::OneShot(_) => {
-                                panic!("Got a streaming response for a non-streaming RPC")
+                match response.data {
+                    // Handle logging
+                    RPCResponseData::LogMessage { record } => record.do_log(),
+                    RPCResponseData::Capabilities { features } => {
+                        capabilities_clone.write().unwrap().extend(features);
+                    }
+                    data => {
+                        // Send the response to the callback
+                        if response.complete {
+                            match inflight_clone.remove(&response.id).map(|v| v.1) {
+                                Some(ResponseQueue::OneShot(v)) => {
+                                    let _ = v.send(Ok(data));
+                                }
+                                Some(ResponseQueue::Streaming(v)) => {
+                                    let _ = v.send(Ok(data)).await;
+                                }
+                                None => {}
+                            }
+                        } else {
+                            // Clone the sender so we don't hold a reference into the map while waiting
+                            let queue = inflight_clone.get(&response.id).map(|v| match v.value() {
+                                ResponseQueue::OneShot(_) => {
+                                    panic!("Got a streaming response for a non-streaming RPC")
+                                }
+                                ResponseQueue::Streaming(v) => v.clone(),
+                            });
+
+                            if let Some(v) = queue {
+                                let _ = v.send(Ok(data)).await;
                             }
-                            ResponseQueue::Streaming(v) => v.send(response.data).await.unwrap(),
                         }
                     }
                 }
             }
+
+            // The runner exited or disconnected so fail every request that's still waiting for a response.
+            // Requests made after this point see `disconnected` and fail immediately
+            disconnected_clone.store(true, Ordering::SeqCst);
+            let ids: Vec<RpcId> = inflight_clone.iter().map(|v| *v.key()).collect();
+            for id in ids {
+                match inflight_clone.remove(&id).map(|v| v.1) {
+                    Some(ResponseQueue::OneShot(v)) => {
+                        let _ = v.send(Err(RunnerError::Disconnected));
+                    }
+                    Some(ResponseQueue::Streaming(v)) => {
+                        let _ = v.send(Err(RunnerError::Disconnected)).await;
+                    }
+                    None => {}
+                }
+            }
         });
 
         // Set up filesystem handling
@@ -103,6 +165,9 @@ impl Client {
             inflight,
             rpc_id_gen: Default::default(),
             rpc_sender: send,
+            disconnected,
+            capabilities,
+            streaming_buffer_size: DEFAULT_STREAMING_BUFFER_SIZE,
             fs_multiplexer: mp,
         };
 
@@ -155,52 +220,164 @@ impl Client {
         FsToken(id)
     }
 
-    /// Make an RPC request and get the response
-    pub(crate) async fn do_rpc(&self, data: RPCRequestData) -> RPCResponseData {
+    /// Make an RPC request and get the response.
+    /// If this future is dropped before the response arrives, the runner is asked to cancel the request.
+    /// Returns `RunnerError::Disconnected` if the runner exits or disconnects before it responds
+    pub(crate) async fn do_rpc(
+        &self,
+        data: RPCRequestData,
+    ) -> Result<RPCResponseData, RunnerError> {
+        Ok(self.do_rpc_with_id(data).await?.1)
+    }
+
+    /// Same as `do_rpc`, but also returns the ID of the request
+    pub(crate) async fn do_rpc_with_id(
+        &self,
+        data: RPCRequestData,
+    ) -> Result<(RpcId, RPCResponseData), RunnerError> {
         // Set the RPC ID
-        let id = self
-            .rpc_id_gen
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let id = self.rpc_id_gen.fetch_add(1, Ordering::Relaxed);
 
         let req = RPCRequest { id, data };
 
         // Setup our response handler
         let (tx, rx) = oneshot::channel();
         self.inflight.insert(req.id, ResponseQueue::OneShot(tx));
+        let _guard = self.cancel_on_drop(id);
 
         // Send the request
-        self.rpc_sender.send(req).await.unwrap();
+        self.send(req).await?;
 
         // Wait for the response
         match rx.await {
-            Ok(v) => v,
-            Err(_) => panic!("The sender dropped!"),
+            Ok(v) => Ok((id, v?)),
+            Err(_) => Err(RunnerError::Disconnected),
         }
     }
 
-    /// Make an RPC request and get the response
+    /// Make a streaming RPC request and get the responses.
+    /// If the returned value is dropped before the last response arrives, the runner is asked to
+    /// cancel the request
     pub(crate) async fn do_streaming_rpc(
         &self,
         data: RPCRequestData,
-    ) -> mpsc::Receiver<RPCResponseData> {
+    ) -> Result<StreamingResponse, RunnerError> {
         // Set the RPC ID
-        let id = self
-            .rpc_id_gen
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let id = self.rpc_id_gen.fetch_add(1, Ordering::Relaxed);
 
         let req = RPCRequest { id, data };
 
         // Setup our response handler
-        let (tx, rx) = mpsc::channel(16);
+        let (tx, rx) = mpsc::channel(self.streaming_buffer_size);
         self.inflight.insert(req.id, ResponseQueue::Streaming(tx));
+        let guard = self.cancel_on_drop(id);
 
         // Send the request
-        self.rpc_sender.send(req).await.unwrap();
+        self.send(req).await?;
 
-        rx
+        Ok(StreamingResponse { rx, _guard: guard })
+    }
+
+    /// Send a request whose response queue is already in `inflight`. Fails if the runner has disconnected
+    async fn send(&self, req: RPCRequest) -> Result<(), RunnerError> {
+        // This is checked after the response queue was added to `inflight` so if the runner disconnected
+        // without failing the request, we see it here
+        if self.disconnected.load(Ordering::SeqCst) {
+            return Err(RunnerError::Disconnected);
+        }
+
+        self.rpc_sender
+            .send(req)
+            .await
+            .map_err(|_| RunnerError::Disconnected)
+    }
+
+    /// Send a request that doesn't have a response without waiting (e.g. from `drop`)
+    pub(crate) fn send_in_background(&self, data: RPCRequestData) {
+        let req = RPCRequest {
+            id: self.rpc_id_gen.fetch_add(1, Ordering::Relaxed),
+            data,
+        };
+
+        let sender = self.rpc_sender.clone();
+        do_spawn(async move {
+            // This fails if the runner has shut down in which case there's nothing to do
+            let _ = sender.send(req).await;
+        });
+    }
+
+    /// Whether the runner told us it supports `capability` (one of the names in `crate::capabilities`)
+    pub(crate) fn supports(&self, capability: &str) -> bool {
+        self.capabilities.read().unwrap().contains(capability)
+    }
+
+    fn cancel_on_drop(&self, id: RpcId) -> CancelOnDrop {
+        CancelOnDrop {
+            id,
+            inflight: self.inflight.clone(),
+            rpc_id_gen: self.rpc_id_gen.clone(),
+            rpc_sender: self.rpc_sender.clone(),
+            capabilities: self.capabilities.clone(),
+        }
     }
 
     pub(crate) fn get_comms(&self) -> &Comms {
         &self.comms
     }
+
+    pub(crate) fn set_streaming_buffer_size(&mut self, size: usize) {
+        // `mpsc::channel` requires a capacity of at least 1
+        self.streaming_buffer_size = size.max(1);
+    }
+}
+
+/// The responses to a streaming RPC
+pub(crate) struct StreamingResponse {
+    rx: mpsc::Receiver<ResponseResult>,
+    _guard: CancelOnDrop,
+}
+
+impl StreamingResponse {
+    /// Get the next response. Returns `None` after the last one. If the runner disconnects before the last
+    /// response, the final item is `RunnerError::Disconnected`
+    pub(crate) async fn recv(&mut self) -> Option<ResponseResult> {
+        self.rx.recv().await
+    }
+}
+
+/// Cancels a request if it's dropped before the final response for the request is received
+struct CancelOnDrop {
+    id: RpcId,
+    inflight: Arc<DashMap<RpcId, ResponseQueue>>,
+    rpc_id_gen: Arc<AtomicU64>,
+    rpc_sender: mpsc::Sender<RPCRequest>,
+    capabilities: Arc<RwLock<HashSet<String>>>,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        // The response handler removes the entry when the final response arrives so if it's still
+        // here, the request is still in flight
+        if self.inflight.remove(&self.id).is_none()
+            || !self
+                .capabilities
+                .read()
+                .unwrap()
+                .contains(capabilities::CANCELLATION)
+        {
+            return;
+        }
+
+        let req = RPCRequest {
+            id: self.rpc_id_gen.fetch_add(1, Ordering::Relaxed),
+            data: RPCRequestData::Cancel { req_id: self.id },
+        };
+
+        // We can't wait in `drop` so send the request in the background
+        let sender = self.rpc_sender.clone();
+        do_spawn(async move {
+            // This fails if the runner has shut down in which case there's nothing to cancel
+            let _ = sender.send(req).await;
+        });
+    }
 }
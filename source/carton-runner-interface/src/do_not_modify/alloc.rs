@@ -12,12 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use carton_macros::for_each_numeric_carton_type;
+use carton_macros::for_each_numeric_or_complex_carton_type;
 
 /// Numeric tensor types supported by this version of the runner interface
 pub(crate) trait NumericTensorType: Default + Copy {}
 
-for_each_numeric_carton_type! {
+for_each_numeric_or_complex_carton_type! {
     $(
         impl NumericTensorType for $RustType {}
     )*
@@ -45,3 +45,27 @@ where
 pub trait AllocatableBy<A: Allocator>: Sized {
     fn alloc(allocator: &A, numel: usize) -> A::Output;
 }
+
+/// Get the number of elements in a tensor with shape `shape`. Returns `None` if this overflows.
+/// Shapes can come from untrusted input so use this instead of `shape.iter().product()`
+pub fn shape_numel(shape: &[u64]) -> Option<u64> {
+    shape.iter().try_fold(1u64, |acc, v| acc.checked_mul(*v))
+}
+
+/// Get the number of elements to allocate for a tensor of `T` with shape `shape`.
+/// Returns an error if the number of elements or the size in bytes doesn't fit in memory
+pub(crate) fn alloc_numel<T>(shape: &[u64]) -> Result<usize, String> {
+    let too_large =
+        || format!("Cannot allocate a tensor with shape {shape:?} because it is too large");
+
+    // We always allocate at least one element
+    let numel = shape_numel(shape).ok_or_else(too_large)?.max(1);
+    let size_bytes = numel
+        .checked_mul(std::mem::size_of::<T>() as u64)
+        .ok_or_else(too_large)?;
+    if size_bytes > isize::MAX as u64 {
+        return Err(too_large());
+    }
+
+    numel.try_into().map_err(|_| too_large())
+}
@@ -18,12 +18,12 @@ use std::{
     sync::Arc,
 };
 
-use carton_macros::for_each_numeric_carton_type;
+use carton_macros::for_each_numeric_or_complex_carton_type;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    alloc::{AllocatableBy, Allocator, AsPtr, NumericTensorType, TypedAlloc},
+    alloc::{alloc_numel, AllocatableBy, Allocator, AsPtr, NumericTensorType, TypedAlloc},
     alloc_pool::{PoolAllocator, PoolItem},
     storage::TensorStorage,
 };
@@ -83,7 +83,7 @@ impl Allocator for InlineAllocator {
     type Output = InlineTensorStorage;
 }
 
-for_each_numeric_carton_type! {
+for_each_numeric_or_complex_carton_type! {
     $(
         /// We're using a macro here instead of a generic impl because rust gives misleading error messages otherwise.
         impl TypedAlloc<$RustType> for InlineAllocator {
@@ -120,7 +120,7 @@ impl<T: NumericTensorType + Default + Copy + AllocatableBy<InlineAllocator>>
 {
     fn from(view: ndarray::ArrayViewD<'_, T>) -> Self {
         // Alloc a tensor
-        let mut out = alloc_tensor(view.shape().iter().map(|v| (*v) as _).collect());
+        let mut out = alloc_tensor_like(&view);
 
         if view.is_standard_layout() {
             // We can just memcpy the data
@@ -139,7 +139,7 @@ impl<T: NumericTensorType + Default + Copy + AllocatableBy<InlineAllocator>>
 impl From<ndarray::ArrayViewD<'_, String>> for TensorStorage<String, InlineTensorStorage> {
     fn from(view: ndarray::ArrayViewD<'_, String>) -> Self {
         // Alloc a tensor
-        let mut out = alloc_tensor(view.shape().iter().map(|v| (*v) as _).collect());
+        let mut out = alloc_tensor_like(&view);
 
         // Can't memcpy
         out.view_mut().assign(&view);
@@ -148,46 +148,103 @@ impl From<ndarray::ArrayViewD<'_, String>> for TensorStorage<String, InlineTenso
     }
 }
 
-// Allocates a contiguous tensor with a shape and type
+// Allocates a contiguous tensor with a shape and type without using a pool
+// Returns an error if the shape is too large to allocate
 #[cfg(feature = "benchmark")]
 pub fn alloc_tensor_no_pool<T: Default + Clone + AllocatableBy<InlineAllocator>>(
     shape: Vec<u64>,
-) -> TensorStorage<T, InlineTensorStorage> {
+) -> Result<TensorStorage<T, InlineTensorStorage>, String> {
     static POOL_ALLOCATOR: Lazy<InlineAllocator> = Lazy::new(|| InlineAllocator::without_pool());
 
-    let numel = shape.iter().product::<u64>().max(1) as usize;
+    let numel = alloc_numel::<T>(&shape)?;
 
     let data = T::alloc(&POOL_ALLOCATOR, numel);
 
-    TensorStorage {
+    Ok(TensorStorage {
         data,
         shape,
         strides: None,
         pd: PhantomData,
-    }
+    })
 }
 
+static ALLOCATOR: Lazy<InlineAllocator> = Lazy::new(|| InlineAllocator::new());
+
+/// Allocates a contiguous tensor with a shape and type.
+/// Returns an error if the shape is too large to allocate
 pub fn alloc_tensor<T: Default + Clone + AllocatableBy<InlineAllocator>>(
     shape: Vec<u64>,
-) -> TensorStorage<T, InlineTensorStorage> {
-    static POOL_ALLOCATOR: Lazy<InlineAllocator> = Lazy::new(|| InlineAllocator::new());
+) -> Result<TensorStorage<T, InlineTensorStorage>, String> {
+    let numel = alloc_numel::<T>(&shape)?;
 
-    let numel = shape.iter().product::<u64>().max(1) as usize;
+    let data = T::alloc(&ALLOCATOR, numel);
 
-    let data = T::alloc(&POOL_ALLOCATOR, numel);
+    Ok(TensorStorage {
+        data,
+        shape,
+        strides: None,
+        pd: PhantomData,
+    })
+}
+
+/// Allocates a contiguous tensor with the same shape as `view`.
+/// This can't fail because the shape already describes data in memory
+fn alloc_tensor_like<T: Default + Clone + AllocatableBy<InlineAllocator>, U>(
+    view: &ndarray::ArrayViewD<'_, U>,
+) -> TensorStorage<T, InlineTensorStorage> {
+    // We always allocate at least one element
+    let data = T::alloc(&ALLOCATOR, view.len().max(1));
 
     TensorStorage {
         data,
-        shape,
+        shape: view.shape().iter().map(|v| (*v) as _).collect(),
         strides: None,
         pd: PhantomData,
     }
 }
 
+/// Same as `alloc_tensor`, but every element is set to `value` (e.g. a padding token or `-inf` for a mask)
+pub fn alloc_tensor_filled<T: Default + Clone + AllocatableBy<InlineAllocator>>(
+    shape: Vec<u64>,
+    value: T,
+) -> Result<TensorStorage<T, InlineTensorStorage>, String> {
+    let mut out = alloc_tensor(shape)?;
+
+    // Reused allocations from the pool aren't cleared so this always writes every element
+    out.view_mut().fill(value);
+    Ok(out)
+}
+
 impl<T: Default + Clone + AllocatableBy<InlineAllocator>> TensorStorage<T, InlineTensorStorage> {
-    pub fn new(shape: Vec<u64>) -> TensorStorage<T, InlineTensorStorage> {
+    /// Create a tensor with the default value of `T` in every element.
+    /// Returns an error if the shape is too large to allocate
+    pub fn new(shape: Vec<u64>) -> Result<TensorStorage<T, InlineTensorStorage>, String> {
         alloc_tensor(shape)
     }
+
+    /// Create a tensor with every element set to `value`.
+    /// Returns an error if the shape is too large to allocate
+    pub fn filled(
+        shape: Vec<u64>,
+        value: T,
+    ) -> Result<TensorStorage<T, InlineTensorStorage>, String> {
+        alloc_tensor_filled(shape, value)
+    }
+}
+
+impl<T, Storage: AsPtr<T>> TensorStorage<T, Storage> {
+    /// Apply `f` to each element of the tensor, returning a new tensor with the same shape
+    /// (e.g. to convert between types or to run a tokenizer on each string in a tensor)
+    pub fn map<U, F>(&self, mut f: F) -> TensorStorage<U, InlineTensorStorage>
+    where
+        U: Default + Clone + AllocatableBy<InlineAllocator>,
+        F: FnMut(&T) -> U,
+    {
+        let view = self.view();
+        let mut out = alloc_tensor_like(&view);
+        out.view_mut().zip_mut_with(&view, |o, i| *o = f(i));
+        out
+    }
 }
 
 impl<T> AllocatableBy<InlineAllocator> for T
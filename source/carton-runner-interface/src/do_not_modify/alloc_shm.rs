@@ -20,12 +20,12 @@ use std::{
     sync::{atomic::AtomicU64, Arc, Mutex, Weak},
 };
 
-use carton_macros::for_each_numeric_carton_type;
+use carton_macros::for_each_numeric_or_complex_carton_type;
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
 
 use super::{
-    alloc::{AsPtr, NumericTensorType, TypedAlloc, Allocator, AllocatableBy},
+    alloc::{alloc_numel, AsPtr, NumericTensorType, TypedAlloc, Allocator, AllocatableBy},
     alloc_pool::{AllocItem, PoolAllocator, PoolItem},
     storage::TensorStorage,
 };
@@ -264,7 +264,7 @@ impl Allocator for SHMAllocator {
     type Output = SHMTensorStorage;
 }
 
-for_each_numeric_carton_type! {
+for_each_numeric_or_complex_carton_type! {
     $(
         /// We're using a macro here instead of a generic impl because rust gives misleading error messages otherwise.
         impl TypedAlloc<$RustType> for SHMAllocator {
@@ -351,7 +351,7 @@ where
                 // TODO WARN
 
                 // We need to make a copy
-                let mut out = alloc_tensor(view.shape().iter().map(|v| (*v) as _).collect());
+                let mut out = alloc_tensor_like(&view);
 
                 if view.is_standard_layout() {
                     // We can just memcpy the data
@@ -373,7 +373,7 @@ where
 impl From<ndarray::ArrayViewD<'_, String>> for TensorStorage<String, SHMTensorStorage> {
     fn from(view: ndarray::ArrayViewD<'_, String>) -> Self {
         // Alloc a tensor
-        let mut out = alloc_tensor(view.shape().iter().map(|v| (*v) as _).collect());
+        let mut out = alloc_tensor_like(&view);
 
         // Can't memcpy
         out.view_mut().assign(&view);
@@ -382,42 +382,70 @@ impl From<ndarray::ArrayViewD<'_, String>> for TensorStorage<String, SHMTensorSt
     }
 }
 
-// Allocates a contiguous tensor with a shape and type
+// Allocates a contiguous tensor with a shape and type without using a pool
+// Returns an error if the shape is too large to allocate
 #[cfg(feature = "benchmark")]
 pub fn alloc_tensor_no_pool<T: Default + Clone + AllocatableBy<SHMAllocator>>(
     shape: Vec<u64>,
-) -> TensorStorage<T, SHMTensorStorage>
+) -> Result<TensorStorage<T, SHMTensorStorage>, String>
 {
     static POOL_ALLOCATOR: Lazy<SHMAllocator> = Lazy::new(|| SHMAllocator::without_pool());
 
-    let numel = shape.iter().product::<u64>().max(1) as usize;
+    let numel = alloc_numel::<T>(&shape)?;
 
     let data = T::alloc(&POOL_ALLOCATOR, numel);
 
-    TensorStorage {
+    Ok(TensorStorage {
         data,
         shape,
         strides: None,
         pd: PhantomData,
-    }
+    })
 }
 
-pub fn alloc_tensor<T: Default + Clone + AllocatableBy<SHMAllocator>>(shape: Vec<u64>) -> TensorStorage<T, SHMTensorStorage>
+static ALLOCATOR: Lazy<SHMAllocator> = Lazy::new(|| SHMAllocator::new());
+
+/// Allocates a contiguous tensor with a shape and type.
+/// Returns an error if the shape is too large to allocate
+pub fn alloc_tensor<T: Default + Clone + AllocatableBy<SHMAllocator>>(shape: Vec<u64>) -> Result<TensorStorage<T, SHMTensorStorage>, String>
 {
-    static POOL_ALLOCATOR: Lazy<SHMAllocator> = Lazy::new(|| SHMAllocator::new());
+    let numel = alloc_numel::<T>(&shape)?;
 
-    let numel = shape.iter().product::<u64>().max(1) as usize;
+    let data = T::alloc(&ALLOCATOR, numel);
 
-    let data = T::alloc(&POOL_ALLOCATOR, numel);
+    Ok(TensorStorage {
+        data,
+        shape,
+        strides: None,
+        pd: PhantomData,
+    })
+}
+
+/// Allocates a contiguous tensor with the same shape as `view`.
+/// This can't fail because the shape already describes data in memory
+fn alloc_tensor_like<T: Default + Clone + AllocatableBy<SHMAllocator>, U>(view: &ndarray::ArrayViewD<'_, U>) -> TensorStorage<T, SHMTensorStorage>
+{
+    // We always allocate at least one element
+    let data = T::alloc(&ALLOCATOR, view.len().max(1));
 
     TensorStorage {
         data,
-        shape,
+        shape: view.shape().iter().map(|v| (*v) as _).collect(),
         strides: None,
         pd: PhantomData,
     }
 }
 
+/// Same as `alloc_tensor`, but every element is set to `value`
+#[cfg(feature = "benchmark")]
+pub fn alloc_tensor_filled<T: Default + Clone + AllocatableBy<SHMAllocator>>(shape: Vec<u64>, value: T) -> Result<TensorStorage<T, SHMTensorStorage>, String>
+{
+    let mut out = alloc_tensor(shape)?;
+
+    // Reused allocations from the pool aren't cleared so this always writes every element
+    out.view_mut().fill(value);
+    Ok(out)
+}
 
 impl<T> AllocatableBy<SHMAllocator> for T
 where
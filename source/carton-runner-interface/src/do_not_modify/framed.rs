@@ -14,12 +14,13 @@
 
 //! A framed transport on top of an [`AsyncRead`] and [`AsyncWrite`] pair
 
-use std::{fmt::Debug, io::ErrorKind};
+use std::{fmt::Debug, io::ErrorKind, sync::Arc};
 
+use futures::future::Either;
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
-    sync::mpsc,
+    sync::{mpsc, Notify},
 };
 
 use crate::{do_spawn, MaybeSend};
@@ -36,23 +37,25 @@ pub(crate) async fn framed_transport<T, U, R, W>(
     T: Debug + Serialize + Send + 'static,
     U: Debug + DeserializeOwned + Send + 'static,
 {
+    // Used by the write task to stop the read task if writing fails. Requests that were already sent to the
+    // write task might not have been written so they'd never get a response. Ending the transport makes
+    // everything waiting on it fail instead of hang
+    let write_failed = Arc::new(Notify::new());
+    let write_failed_clone = write_failed.clone();
+
     // Spawn a task to handle reads
     do_spawn(async move {
         let mut br = BufReader::new(read_stream);
 
         loop {
-            // Read the size and then read the data
-            let size = match br.read_u64().await {
-                Ok(s) => s as usize,
-                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
-                Err(e) => panic!("Got unexpected error: {:#?}", e),
-            };
-
-            let mut data = vec![0u8; size];
-            br.read_exact(&mut data).await.unwrap();
+            let next = read_next::<U, _>(&mut br);
+            let stop = write_failed_clone.notified();
+            futures::pin_mut!(next, stop);
 
-            // TODO: offload this to a compute thread if it's too slow
-            let response: U = bincode::deserialize(&data).unwrap();
+            let response = match futures::future::select(next, stop).await {
+                Either::Left((Some(response), _)) => response,
+                Either::Left((None, _)) | Either::Right(_) => break,
+            };
 
             // Send the response
             res_tx.send(response).await.unwrap();
@@ -68,13 +71,14 @@ pub(crate) async fn framed_transport<T, U, R, W>(
                 Err(mpsc::error::TryRecvError::Empty) => {
                     // Nothing to recv
                     // Flush the writer
-                    match bw.flush().await {
-                        Ok(_) => {}
-                        Err(e) if e.kind() == ErrorKind::BrokenPipe => {
-                            // Disconnected
-                            break;
+                    if let Err(e) = bw.flush().await {
+                        // BrokenPipe means we're disconnected
+                        if e.kind() != ErrorKind::BrokenPipe {
+                            log::error!("Failed to write to the transport: {e}");
                         }
-                        e => e.unwrap(),
+
+                        write_failed.notify_one();
+                        break;
                     }
 
                     // Blocking wait for new things to send
@@ -91,12 +95,274 @@ pub(crate) async fn framed_transport<T, U, R, W>(
             };
 
             // Serialize and write size + data to the buffer
-            // TODO: offload this to a compute thread if it's too slow
-            let data = bincode::serialize(&item).unwrap();
-            bw.write_u64(data.len() as _).await.unwrap();
-            bw.write_all(&data).await.unwrap();
+            // If this fails, the message may have been partially written so the transport can't be used anymore
+            if let Err(e) = write_message(&mut bw, item).await {
+                log::error!("Failed to write a message: {e}");
+                write_failed.notify_one();
+                break;
+            }
+        }
+    });
+}
+
+/// Read the next size-prefixed message. Returns `None` if the stream ended or the message couldn't be read.
+///
+/// A message that can't be deserialized can't be routed back to whoever was waiting for it so the caller
+/// should end the transport in that case (which makes everything waiting on it fail)
+async fn read_next<U, R>(br: &mut R) -> Option<U>
+where
+    R: AsyncRead + Unpin,
+    U: DeserializeOwned + Send + 'static,
+{
+    // Read the size and then read the data
+    let size = match br.read_u64().await {
+        Ok(s) => s as usize,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return None,
+        Err(e) => {
+            log::error!("Failed to read from the transport: {e}");
+            return None;
+        }
+    };
+
+    match read_message(br, size).await {
+        Ok(v) => Some(v),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => None,
+        Err(e) => {
+            log::error!("Failed to read a message: {e}");
+            None
+        }
+    }
+}
+
+/// Messages at least this large (in bytes) are serialized and deserialized in bounded chunks on a blocking
+/// thread so neither side needs to buffer the whole serialized message (e.g. for multi-GB tensors).
+/// This doesn't change what's sent over the wire.
+#[cfg(not(target_family = "wasm"))]
+const STREAMING_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// The size of each chunk when streaming large messages
+#[cfg(not(target_family = "wasm"))]
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The max number of chunks buffered between the serialization thread and the transport
+#[cfg(not(target_family = "wasm"))]
+const MAX_BUFFERED_CHUNKS: usize = 4;
+
+/// Read and deserialize a message of `size` bytes. This always reads exactly `size` bytes from `br` (unless
+/// reading fails) so a message that fails to deserialize doesn't affect the ones after it.
+/// Deserialization errors are returned as `ErrorKind::InvalidData`
+async fn read_message<U, R>(br: &mut R, size: usize) -> std::io::Result<U>
+where
+    R: AsyncRead + Unpin,
+    U: DeserializeOwned + Send + 'static,
+{
+    #[cfg(not(target_family = "wasm"))]
+    {
+        if size as u64 >= STREAMING_THRESHOLD {
+            return read_message_chunked(br, size).await;
+        }
+    }
+
+    let mut data = vec![0u8; size];
+    br.read_exact(&mut data).await?;
+
+    // TODO: offload this to a compute thread if it's too slow
+    bincode::deserialize(&data).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Serialize and write a message (prefixed with its size).
+/// Serialization errors are returned as `ErrorKind::InvalidData`
+async fn write_message<T, W>(bw: &mut W, item: T) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize + Send + 'static,
+{
+    // Serialize into a buffer unless the message turns out to be large. In that case, it's streamed instead
+    #[cfg(not(target_family = "wasm"))]
+    let data = {
+        let mut writer = LimitedWriter {
+            buf: Vec::new(),
+            overflowed: false,
+        };
+
+        match bincode::serialize_into(&mut writer, &item) {
+            Ok(()) => writer.buf,
+            Err(_) if writer.overflowed => return write_message_chunked(bw, item).await,
+            Err(e) => return Err(std::io::Error::new(ErrorKind::InvalidData, e)),
         }
+    };
+
+    // TODO: offload this to a compute thread if it's too slow
+    #[cfg(target_family = "wasm")]
+    let data =
+        bincode::serialize(&item).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+
+    bw.write_u64(data.len() as _).await?;
+    bw.write_all(&data).await
+}
+
+/// Deserialize a message on a blocking thread as its data is read from the transport in chunks
+#[cfg(not(target_family = "wasm"))]
+async fn read_message_chunked<U, R>(br: &mut R, size: usize) -> std::io::Result<U>
+where
+    R: AsyncRead + Unpin,
+    U: DeserializeOwned + Send + 'static,
+{
+    let (chunk_tx, chunk_rx) = mpsc::channel(MAX_BUFFERED_CHUNKS);
+    let handle = tokio::task::spawn_blocking(move || {
+        let reader = ChunkReader {
+            rx: chunk_rx,
+            current: Vec::new(),
+            pos: 0,
+        };
+
+        bincode::deserialize_from(reader)
     });
+
+    let mut remaining = size;
+    let mut chunk_tx = Some(chunk_tx);
+    while remaining > 0 {
+        let mut chunk = vec![0u8; remaining.min(CHUNK_SIZE)];
+        br.read_exact(&mut chunk).await?;
+        remaining -= chunk.len();
+
+        // If deserialization stopped early (e.g. because it failed), keep reading (and dropping) the rest
+        // of the message. Any error is surfaced below
+        if let Some(tx) = &chunk_tx {
+            if tx.send(chunk).await.is_err() {
+                chunk_tx = None;
+            }
+        }
+    }
+
+    drop(chunk_tx);
+    match handle.await {
+        Ok(res) => res.map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e)),
+        Err(e) => Err(std::io::Error::new(ErrorKind::InvalidData, e)),
+    }
+}
+
+/// Serialize a message on a blocking thread and write it (prefixed with its size) to the transport in chunks
+/// as it's serialized.
+/// The size has to be written before the data so this needs a sizing pass before serializing. That's only
+/// done for large messages (see `write_message`)
+#[cfg(not(target_family = "wasm"))]
+async fn write_message_chunked<T, W>(bw: &mut W, item: T) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize + Send + 'static,
+{
+    let (chunk_tx, mut chunk_rx) = mpsc::channel(MAX_BUFFERED_CHUNKS);
+    let handle = tokio::task::spawn_blocking(move || -> bincode::Result<()> {
+        let mut writer = ChunkWriter {
+            tx: chunk_tx,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        };
+
+        // This is the same as `write_u64` (i.e. big endian)
+        let size = bincode::serialized_size(&item)?;
+        std::io::Write::write_all(&mut writer, &size.to_be_bytes())?;
+        bincode::serialize_into(&mut writer, &item)?;
+        std::io::Write::flush(&mut writer)?;
+        Ok(())
+    });
+
+    // If this fails, `chunk_rx` is dropped which stops serialization
+    while let Some(chunk) = chunk_rx.recv().await {
+        bw.write_all(&chunk).await?;
+    }
+
+    match handle.await {
+        Ok(res) => res.map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e)),
+        Err(e) => Err(std::io::Error::new(ErrorKind::Other, e)),
+    }
+}
+
+/// A [`std::io::Write`] into a buffer that fails once more than `STREAMING_THRESHOLD` bytes are written
+#[cfg(not(target_family = "wasm"))]
+struct LimitedWriter {
+    buf: Vec<u8>,
+    overflowed: bool,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl std::io::Write for LimitedWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if (self.buf.len() + data.len()) as u64 >= STREAMING_THRESHOLD {
+            self.overflowed = true;
+            return Err(std::io::Error::new(
+                ErrorKind::Other,
+                "The message is too large to buffer",
+            ));
+        }
+
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A blocking [`std::io::Read`] over chunks received from a channel
+#[cfg(not(target_family = "wasm"))]
+struct ChunkReader {
+    rx: mpsc::Receiver<Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl std::io::Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.current.len() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => {
+                    self.current = chunk;
+                    self.pos = 0;
+                }
+                // No more data
+                None => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A blocking [`std::io::Write`] that sends data to a channel in chunks of `CHUNK_SIZE` bytes
+#[cfg(not(target_family = "wasm"))]
+struct ChunkWriter {
+    tx: mpsc::Sender<Vec<u8>>,
+    buf: Vec<u8>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl std::io::Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let n = data.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == CHUNK_SIZE {
+            self.flush()?;
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        self.tx
+            .blocking_send(chunk)
+            .map_err(|_| std::io::Error::new(ErrorKind::BrokenPipe, "The transport was closed"))
+    }
 }
 
 pub(crate) async fn frame<T, U, R, W>(
@@ -117,3 +383,96 @@ where
 
     (send, recv)
 }
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use std::time::Duration;
+
+    use serde::{Serialize, Serializer};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::{frame, read_message, write_message, STREAMING_THRESHOLD};
+
+    /// A message that always fails to serialize
+    #[derive(Debug)]
+    struct Unserializable;
+
+    impl Serialize for Unserializable {
+        fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("not serializable"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_whole_messages() {
+        let large = vec![7u8; STREAMING_THRESHOLD as usize];
+
+        let mut buf = Vec::new();
+        write_message(&mut buf, large.clone()).await.unwrap();
+
+        // A large message that fails to deserialize as a `String` (it isn't valid UTF-8) after reading
+        // only a few bytes
+        let mut bad = 4u64.to_le_bytes().to_vec();
+        bad.resize(STREAMING_THRESHOLD as usize, 0xff);
+        buf.extend_from_slice(&(bad.len() as u64).to_be_bytes());
+        buf.extend_from_slice(&bad);
+
+        write_message(&mut buf, "after".to_owned()).await.unwrap();
+
+        let mut reader = buf.as_slice();
+        let size = reader.read_u64().await.unwrap() as usize;
+        assert_eq!(
+            read_message::<Vec<u8>, _>(&mut reader, size).await.unwrap(),
+            large
+        );
+
+        let size = reader.read_u64().await.unwrap() as usize;
+        let err = read_message::<String, _>(&mut reader, size)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        // The rest of the bad message was skipped so the next one reads normally
+        let size = reader.read_u64().await.unwrap() as usize;
+        assert_eq!(
+            read_message::<String, _>(&mut reader, size).await.unwrap(),
+            "after"
+        );
+        assert!(reader.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_message_ends_transport() {
+        let (local, mut remote) = tokio::io::duplex(1024);
+        let (read_stream, write_stream) = tokio::io::split(local);
+        let (_send, mut recv) = frame::<String, String, _, _>(read_stream, write_stream).await;
+
+        // A `String` that isn't valid UTF-8
+        let bad = [1u64.to_le_bytes().as_slice(), &[0xff]].concat();
+        remote.write_u64(bad.len() as _).await.unwrap();
+        remote.write_all(&bad).await.unwrap();
+
+        // The transport ends (even though `remote` is still open) instead of dropping the message and
+        // leaving whoever was waiting for it hanging
+        let res = tokio::time::timeout(Duration::from_secs(5), recv.recv())
+            .await
+            .expect("Expected the transport to end");
+        assert!(res.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_ends_transport() {
+        let (local, _remote) = tokio::io::duplex(1024);
+        let (read_stream, write_stream) = tokio::io::split(local);
+        let (send, mut recv) =
+            frame::<Unserializable, String, _, _>(read_stream, write_stream).await;
+
+        send.send(Unserializable).await.unwrap();
+
+        // The message was never written so nothing would respond to it. The transport ends instead
+        let res = tokio::time::timeout(Duration::from_secs(5), recv.recv())
+            .await
+            .expect("Expected the transport to end");
+        assert!(res.is_none());
+    }
+}
@@ -60,4 +60,90 @@ where
         let data = self.data.as_mut_ptr();
         unsafe { ndarray::ArrayViewMut::from_shape_ptr(self.get_shape(), data) }
     }
+
+    /// Iterate over the leading axis of the tensor, yielding a view of each sub-tensor (e.g. each item
+    /// in a batch or each row of a matrix). Each view has one less dimension than the tensor.
+    /// A 0-d tensor is treated as a batch with one item so this yields a (0-d) view of the scalar
+    pub fn outer_iter(&self) -> impl ExactSizeIterator<Item = ndarray::ArrayViewD<T>> {
+        let mut view = self.view();
+        if view.ndim() == 0 {
+            view = view.insert_axis(ndarray::Axis(0));
+        }
+
+        let len = view.len_of(ndarray::Axis(0));
+        (0..len).map(move |i| view.clone().index_axis_move(ndarray::Axis(0), i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::TensorStorage;
+
+    #[test]
+    fn test_outer_iter() {
+        // 0-d
+        let mut scalar = TensorStorage::<String>::new(vec![]).unwrap();
+        *scalar.view_mut().first_mut().unwrap() = "hello".into();
+        let items: Vec<_> = scalar.outer_iter().collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].ndim(), 0);
+        assert_eq!(items[0].first().unwrap(), "hello");
+
+        // 3-d
+        let mut t = TensorStorage::<i32>::new(vec![2, 3, 4]).unwrap();
+        t.view_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| *v = i as _);
+
+        let items: Vec<_> = t.outer_iter().collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].shape(), &[3, 4]);
+        assert_eq!(items[1][[0, 0]], 12);
+        assert_eq!(items[1][[2, 3]], 23);
+
+        // Empty leading axis
+        let empty = TensorStorage::<f32>::new(vec![0, 5]).unwrap();
+        assert_eq!(empty.outer_iter().len(), 0);
+    }
+
+    #[test]
+    fn test_map() {
+        let mut t = TensorStorage::<i64>::new(vec![2, 1, 3]).unwrap();
+        t.view_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| *v = i as _);
+
+        let out: TensorStorage<String> = t.map(|v| format!("token_{v}"));
+        assert_eq!(out.view().shape(), &[2, 1, 3]);
+        assert_eq!(out.view()[[1, 0, 2]], "token_5");
+
+        let scalar = TensorStorage::<u8>::new(vec![]).unwrap();
+        let out: TensorStorage<f32> = scalar.map(|v| *v as f32 + 0.5);
+        assert_eq!(out.view().ndim(), 0);
+        assert_eq!(*out.view().first().unwrap(), 0.5);
+    }
+
+    #[test]
+    fn test_filled() {
+        let t = TensorStorage::filled(vec![3, 4], f32::NEG_INFINITY).unwrap();
+        assert_eq!(t.view().shape(), &[3, 4]);
+        assert!(t.view().iter().all(|v| *v == f32::NEG_INFINITY));
+
+        // Reused allocations are filled too
+        drop(t);
+        let t = TensorStorage::filled(vec![3, 4], 1.0f32).unwrap();
+        assert!(t.view().iter().all(|v| *v == 1.0));
+
+        let t = TensorStorage::filled(vec![2], String::from("[PAD]")).unwrap();
+        assert!(t.view().iter().all(|v| v == "[PAD]"));
+    }
+
+    #[test]
+    fn test_alloc_too_large() {
+        assert!(TensorStorage::<f32>::new(vec![u64::MAX, 2]).is_err());
+        assert!(TensorStorage::<u64>::new(vec![1 << 62]).is_err());
+        assert!(TensorStorage::filled(vec![u64::MAX, u64::MAX], 0i8).is_err());
+    }
 }
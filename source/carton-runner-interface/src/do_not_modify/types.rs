@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-pub use carton_macros::{for_each_carton_type, for_each_numeric_carton_type};
+pub use carton_macros::{
+    for_each_carton_type, for_each_numeric_carton_type, for_each_numeric_or_complex_carton_type,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -125,6 +127,57 @@ pub(crate) enum RPCRequestData {
         // Do we support a streaming response
         streaming: bool,
     },
+
+    // Same as `InferWithTensors`, but with per-request runner options
+    // This is a separate variant (at the end of the enum) so older runners continue to work as long
+    // as no per-request options are passed.
+    // This is only sent to runners that advertise support for it (see `RPCResponseData::Capabilities`)
+    InferWithTensorsAndOpts {
+        tensors: HashMap<String, Handle<Tensor>>,
+
+        // Do we support a streaming response
+        streaming: bool,
+
+        runner_opts: HashMap<String, RunnerOpt>,
+    },
+
+    // Cancel an in-flight request (e.g. because the caller dropped the future for it). There is no
+    // response to this message.
+    // This is only sent to runners that advertise support for it (see `RPCResponseData::Capabilities`)
+    // because older runners don't know about it
+    Cancel {
+        req_id: RpcId,
+    },
+
+    // Same as `InferWithTensorsAndOpts` (without streaming), but the runner holds on to the outputs
+    // and responds with `LazyInfer`. Outputs are fetched individually with `FetchOutput` and freed
+    // with `ReleaseOutputs`.
+    // This is only sent to runners that advertise support for it (see `RPCResponseData::Capabilities`)
+    InferWithTensorsLazy {
+        tensors: HashMap<String, Handle<Tensor>>,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    },
+
+    // Get one output of an `InferWithTensorsLazy` request. The response is an `Infer` message
+    // containing only that output
+    FetchOutput {
+        req_id: RpcId,
+        name: String,
+    },
+
+    // Free the outputs of an `InferWithTensorsLazy` request that haven't been fetched. There is no
+    // response to this message
+    ReleaseOutputs {
+        req_id: RpcId,
+    },
+
+    // Check that the runner is alive. The response is `Pong`.
+    // This is only sent to runners that advertise support for it (see `RPCResponseData::Capabilities`)
+    Ping,
+
+    // Get the amount of device (e.g. GPU) memory the model is using. The response is `DeviceMemoryUsage`.
+    // This is only sent to runners that advertise support for it (see `RPCResponseData::Capabilities`)
+    DeviceMemoryUsage,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -159,6 +212,35 @@ pub(crate) enum RPCResponseData {
     },
 
     Empty,
+
+    /// Optional features this runner supports. This isn't a response to a request; runners send it
+    /// once on startup, but only if the core library indicates that it understands this message (see
+    /// `CAPABILITIES_ENV_VAR`). `features` contains the names of the supported features (see
+    /// `crate::capabilities`)
+    Capabilities {
+        features: Vec<String>,
+    },
+
+    /// The response to `InferWithTensorsLazy`. These are the names of the outputs the runner is holding
+    LazyInfer {
+        outputs: Vec<String>,
+    },
+
+    /// The response to `Ping`
+    Pong,
+
+    /// The response to `DeviceMemoryUsage`. This is `None` if the model isn't using a device other than
+    /// the CPU (or the runner can't tell)
+    DeviceMemoryUsage {
+        memory: Option<DeviceMemory>,
+    },
+
+    /// Like `Error`, but the request failed because the runner ran out of (e.g. GPU) memory. Runners only
+    /// send this if the core library indicates that it understands this message (see
+    /// `TYPED_ERRORS_ENV_VAR`). Otherwise, they send `Error`
+    OutOfMemory {
+        e: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -221,6 +303,39 @@ pub enum RunnerOpt {
     Boolean(bool),
 }
 
+/// Typed accessors for runner authors. Each of these returns `None` if the option is of a different type
+impl RunnerOpt {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Note: this also converts integers so options like `temperature = 1` work as expected
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Double(v) => Some(*v),
+            Self::Integer(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub(crate) struct SealHandle(pub(crate) u64);
 
@@ -235,18 +350,49 @@ pub enum Device {
         uuid: Option<String>,
     },
 }
-// TODO: pin the version of carton-macros so we don't accidentally change the types on the wire
-for_each_carton_type! {
-    /// TODO: We should to manually implement serialization and not depend on ndarray's serialization
-    /// staying the same. Or just pin to a specific ndarray version
-    #[derive(Debug, Serialize, Deserialize)]
-    pub enum Tensor {
-        $($CartonType(TensorStorage::<$RustType>),)*
-
-        /// A Nested Tensor / Ragged Tensor
-        /// See the docs in the core carton library for more details
-        NestedTensor(Vec<Tensor>)
-    }
+
+/// The amount of device (e.g. GPU) memory used by a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceMemory {
+    /// The number of bytes used by tensors (e.g. the weights of the model)
+    pub allocated_bytes: u64,
+
+    /// The number of bytes the framework is holding on to (e.g. in a caching allocator). This includes
+    /// `allocated_bytes`. `None` if the runner can't tell
+    pub reserved_bytes: Option<u64>,
+}
+
+// Note: the variants are listed explicitly (instead of using `for_each_carton_type!`) because
+// their order determines the discriminant on the wire. New types must be added at the end.
+/// TODO: We should to manually implement serialization and not depend on ndarray's serialization
+/// staying the same. Or just pin to a specific ndarray version
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Tensor {
+    Float(TensorStorage<f32>),
+    Double(TensorStorage<f64>),
+    String(TensorStorage<String>),
+    I8(TensorStorage<i8>),
+    I16(TensorStorage<i16>),
+    I32(TensorStorage<i32>),
+    I64(TensorStorage<i64>),
+    U8(TensorStorage<u8>),
+    U16(TensorStorage<u16>),
+    U32(TensorStorage<u32>),
+    U64(TensorStorage<u64>),
+
+    /// A Nested Tensor / Ragged Tensor
+    /// See the docs in the core carton library for more details
+    NestedTensor(Vec<Tensor>),
+
+    Complex64(TensorStorage<num_complex::Complex<f32>>),
+    Complex128(TensorStorage<num_complex::Complex<f64>>),
+
+    /// A Nested Tensor with a label for each contained tensor
+    /// See the docs in the core carton library for more details
+    LabeledNestedTensor {
+        tensors: Vec<Tensor>,
+        labels: Vec<String>,
+    },
 }
 
 pub type TensorStorage<T> = super::storage::TensorStorage<T, InlineTensorStorage>;
@@ -279,3 +425,26 @@ impl Handle<Tensor> {
         self.inner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RunnerOpt;
+
+    #[test]
+    fn test_runner_opt_accessors() {
+        assert_eq!(RunnerOpt::Integer(4).as_i64(), Some(4));
+        assert_eq!(RunnerOpt::Double(0.5).as_f64(), Some(0.5));
+        assert_eq!(RunnerOpt::String("cuda".into()).as_str(), Some("cuda"));
+        assert_eq!(RunnerOpt::Boolean(true).as_bool(), Some(true));
+
+        // Integers can be read as floats (e.g. `temperature = 1`), but not the other way around
+        assert_eq!(RunnerOpt::Integer(1).as_f64(), Some(1.0));
+        assert_eq!(RunnerOpt::Double(1.0).as_i64(), None);
+
+        // Other mismatched types return `None`
+        assert_eq!(RunnerOpt::String("4".into()).as_i64(), None);
+        assert_eq!(RunnerOpt::Integer(1).as_bool(), None);
+        assert_eq!(RunnerOpt::Boolean(true).as_str(), None);
+        assert_eq!(RunnerOpt::Boolean(false).as_f64(), None);
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Errors returned by `Runner`
+
+use std::fmt;
+
+/// An error from a request to a runner
+#[derive(Debug, Clone)]
+pub enum RunnerError {
+    /// The runner exited (e.g. because it crashed) or the connection to it was closed before it responded
+    Disconnected,
+
+    /// The runner (or the model) reported an error
+    Message(String),
+
+    /// The runner ran out of memory (e.g. GPU memory) while handling the request
+    OutOfMemory(String),
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disconnected => write!(f, "The runner exited or disconnected"),
+            Self::Message(e) | Self::OutOfMemory(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for RunnerError {}
+
+impl From<String> for RunnerError {
+    fn from(value: String) -> Self {
+        Self::Message(value)
+    }
+}
@@ -32,14 +32,20 @@ macro_rules! if_not_wasm {
     )*}
 }
 
+use std::collections::HashMap;
+
 mod client;
 mod do_not_modify;
+pub mod error;
 mod multiplexer;
 pub mod runner;
 
 if_not_wasm! {
     pub mod server;
     pub mod slowlog;
+
+    #[cfg(feature = "testing")]
+    pub mod testing;
 }
 
 if_not_wasm! {
@@ -57,6 +63,86 @@ if_wasm! {
     impl <T> MaybeSend for T {}
 }
 
+/// The env var that controls which log records a runner forwards to the core library (e.g. `info`
+/// or `debug`). If it isn't set, the core library passes its current max log level to the runner
+pub const LOG_LEVEL_ENV_VAR: &str = "CARTON_RUNNER_LOG_LEVEL";
+
+/// The core library sets this env var when it starts a runner to indicate that it understands
+/// `Capabilities` messages. Runners only send that message if this is set so they keep working with
+/// older versions of the core library
+pub(crate) const CAPABILITIES_ENV_VAR: &str = "CARTON_RUNNER_SEND_CAPABILITIES";
+
+/// The core library sets this env var when it starts a runner to indicate that it understands
+/// `OutOfMemory` responses. Runners send `Error` instead if this isn't set
+pub(crate) const TYPED_ERRORS_ENV_VAR: &str = "CARTON_RUNNER_SEND_TYPED_ERRORS";
+
+/// The names of the optional features a runner can list in its `Capabilities` message. The core library
+/// ignores names it doesn't know about so new features can be added without changing the message
+pub(crate) mod capabilities {
+    /// The runner handles `Cancel` requests
+    pub const CANCELLATION: &str = "cancellation";
+
+    /// The runner handles `InferWithTensorsLazy`, `FetchOutput` and `ReleaseOutputs` requests
+    pub const LAZY_OUTPUTS: &str = "lazy_outputs";
+
+    /// The runner handles `Ping` requests
+    pub const PING: &str = "ping";
+
+    /// The runner handles `DeviceMemoryUsage` requests
+    pub const DEVICE_MEMORY: &str = "device_memory";
+
+    /// The runner handles `InferWithTensorsAndOpts` requests
+    pub const INFER_OPTS: &str = "infer_opts";
+
+    /// Everything runners built with this version of the interface support
+    pub const ALL: &[&str] = &[CANCELLATION, LAZY_OUTPUTS, PING, DEVICE_MEMORY, INFER_OPTS];
+}
+
+/// The core library passes the names of a model's inputs (in the order they're declared in the carton)
+/// to runners in this load-time runner option so runners that take positional arguments don't need their
+/// own ordering file. It's only set if the carton declares its inputs. See `input_ordering`
+pub const INPUT_ORDERING_OPT: &str = "carton.input_ordering";
+
+/// The same as `INPUT_ORDERING_OPT`, but for outputs. See `output_ordering`
+pub const OUTPUT_ORDERING_OPT: &str = "carton.output_ordering";
+
+/// Get the canonical order of a model's inputs from the runner options passed to `Load`.
+/// Returns `None` if the carton doesn't declare its inputs
+pub fn input_ordering(runner_opts: &HashMap<String, types::RunnerOpt>) -> Option<Vec<String>> {
+    parse_ordering(runner_opts, INPUT_ORDERING_OPT)
+}
+
+/// Get the canonical order of a model's outputs from the runner options passed to `Load`.
+/// Returns `None` if the carton doesn't declare its outputs
+pub fn output_ordering(runner_opts: &HashMap<String, types::RunnerOpt>) -> Option<Vec<String>> {
+    parse_ordering(runner_opts, OUTPUT_ORDERING_OPT)
+}
+
+/// The core library passes `LoadOpts::seed` to runners in this load-time runner option. Runners should
+/// seed their framework's random number generators with it (if it's set) so results are reproducible.
+/// See `seed`
+pub const SEED_OPT: &str = "carton.seed";
+
+/// Get the random seed from the runner options passed to `Load` (if one was set)
+pub fn seed(runner_opts: &HashMap<String, types::RunnerOpt>) -> Option<u64> {
+    runner_opts.get(SEED_OPT)?.as_i64().map(|v| v as u64)
+}
+
+/// The names are separated by newlines
+fn parse_ordering(
+    runner_opts: &HashMap<String, types::RunnerOpt>,
+    key: &str,
+) -> Option<Vec<String>> {
+    let names = runner_opts.get(key)?.as_str()?;
+    if names.is_empty() {
+        // The carton declares that there are no inputs (or outputs)
+        return Some(Vec::new());
+    }
+
+    Some(names.split('\n').map(|v| v.to_owned()).collect())
+}
+
+pub use do_not_modify::alloc::shape_numel;
 pub use do_not_modify::types;
 pub use runner::Runner;
 
@@ -64,12 +150,12 @@ pub use runner::Runner;
 pub mod _only_public_for_benchmarks_do_not_use {
     pub use crate::do_not_modify::alloc::TypedAlloc;
     pub use crate::do_not_modify::alloc_inline::{
-        alloc_tensor as alloc_tensor_inline, alloc_tensor_no_pool as alloc_tensor_no_pool_inline,
-        InlineAllocator, InlineTensorStorage,
+        alloc_tensor as alloc_tensor_inline, alloc_tensor_filled as alloc_tensor_filled_inline,
+        alloc_tensor_no_pool as alloc_tensor_no_pool_inline, InlineAllocator, InlineTensorStorage,
     };
 
     pub use crate::do_not_modify::alloc_shm::{
-        alloc_tensor as alloc_tensor_shm, alloc_tensor_no_pool as alloc_tensor_no_pool_shm,
-        SHMAllocator, SHMTensorStorage,
+        alloc_tensor as alloc_tensor_shm, alloc_tensor_filled as alloc_tensor_filled_shm,
+        alloc_tensor_no_pool as alloc_tensor_no_pool_shm, SHMAllocator, SHMTensorStorage,
     };
 }
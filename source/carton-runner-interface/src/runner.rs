@@ -17,7 +17,10 @@ use std::{collections::HashMap, sync::Arc};
 use crate::{
     client::Client,
     do_not_modify::comms::OwnedComms,
-    do_not_modify::types::{Device, RPCRequestData, RPCResponseData, SealHandle, Tensor},
+    do_not_modify::types::{
+        Device, DeviceMemory, RPCRequestData, RPCResponseData, SealHandle, Tensor,
+    },
+    error::RunnerError,
     types::{Allocatable, Handle, RunnerOpt, TensorStorage},
 };
 
@@ -33,6 +36,18 @@ impl Runner {
     pub async fn new(
         runner_path: &std::path::Path,
         visible_device: Device,
+    ) -> Result<Runner, String> {
+        Self::new_with_env(runner_path, visible_device, &HashMap::new()).await
+    }
+
+    /// Same as `new`, but sets additional environment variables for the runner process. These are
+    /// merged with the environment of this process. `CUDA_VISIBLE_DEVICES` is always set based on
+    /// `visible_device` so it can't be overridden here
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn new_with_env(
+        runner_path: &std::path::Path,
+        visible_device: Device,
+        env: &HashMap<String, String>,
     ) -> Result<Runner, String> {
         use tokio::process::Command;
 
@@ -46,6 +61,7 @@ impl Runner {
 
         // Create a command to start the runner
         let mut command = Command::new(runner_path);
+        command.envs(env);
 
         // Check if we have a UUID for a GPU
         if let Device::GPU { uuid: Some(uuid) } = visible_device {
@@ -56,10 +72,24 @@ impl Runner {
             command.env("CUDA_VISIBLE_DEVICES", "");
         }
 
+        // Have the runner forward logs at the same level as this process unless the user
+        // explicitly set a level for runners
+        if std::env::var_os(crate::LOG_LEVEL_ENV_VAR).is_none()
+            && !env.contains_key(crate::LOG_LEVEL_ENV_VAR)
+        {
+            command.env(crate::LOG_LEVEL_ENV_VAR, log::max_level().to_string());
+        }
+
+        // Let the runner know it can tell us which optional features it supports (e.g. cancellation)
+        command.env(crate::CAPABILITIES_ENV_VAR, "1");
+
+        // And that it can send errors we handle differently (e.g. running out of memory)
+        command.env(crate::TYPED_ERRORS_ENV_VAR, "1");
+
         command
             .args(["--uds-path", uds_path.to_str().unwrap()])
             .spawn()
-            .expect("Runner failed to start");
+            .map_err(|e| format!("Runner failed to start: {e}"))?;
 
         // Create a client
         let client = Client::new(comms).await;
@@ -78,6 +108,12 @@ impl Runner {
         Ok(Self { client })
     }
 
+    /// Set the max number of responses to buffer for each streaming inference request.
+    /// Once the buffer is full, the runner is paused until the consumer catches up
+    pub fn set_streaming_buffer_size(&mut self, size: usize) {
+        self.client.set_streaming_buffer_size(size)
+    }
+
     pub async fn load<T>(
         &self,
         fs: &Arc<T>,
@@ -87,7 +123,7 @@ impl Runner {
         runner_opts: Option<HashMap<String, RunnerOpt>>,
         visible_device: Device,
         carton_manifest_hash: Option<String>,
-    ) -> Result<(), String>
+    ) -> Result<(), RunnerError>
     where
         T: lunchbox::ReadableFileSystem + MaybeSend + MaybeSync + 'static,
         T::FileType: lunchbox::types::ReadableFile + MaybeSend + MaybeSync + Unpin,
@@ -107,10 +143,11 @@ impl Runner {
                 visible_device,
                 carton_manifest_hash,
             })
-            .await
+            .await?
         {
             RPCResponseData::Load => Ok(()),
-            RPCResponseData::Error { e } => Err(e),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
             _ => panic!("Unexpected RPC response type!"),
         }
     }
@@ -126,7 +163,8 @@ impl Runner {
     pub async fn infer_with_inputs(
         &self,
         tensors_orig: HashMap<String, Tensor>,
-    ) -> Result<HashMap<String, Tensor>, String> {
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> Result<HashMap<String, Tensor>, RunnerError> {
         // Wrap each tensor in a handle (this possibly sends the fd for backing SHM chunks to the other process)
         let comms = self.client.get_comms();
         let mut tensors = HashMap::new();
@@ -136,11 +174,8 @@ impl Runner {
 
         match self
             .client
-            .do_rpc(RPCRequestData::InferWithTensors {
-                tensors,
-                streaming: false,
-            })
-            .await
+            .do_rpc(self.infer_request(tensors, false, runner_opts)?)
+            .await?
         {
             RPCResponseData::Infer { tensors } => {
                 let mut out = HashMap::new();
@@ -150,15 +185,55 @@ impl Runner {
 
                 Ok(out)
             }
-            RPCResponseData::Error { e } => Err(e),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
             _ => panic!("Unexpected RPC response type!"),
         }
     }
 
-    pub async fn streaming_infer_with_inputs(
+    /// Whether this runner supports per-request runner options in `infer_with_inputs` and
+    /// `streaming_infer_with_inputs`
+    pub fn supports_infer_opts(&self) -> bool {
+        self.client.supports(crate::capabilities::INFER_OPTS)
+    }
+
+    /// Build an infer request. We only use `InferWithTensorsAndOpts` if there are per-request options so that
+    /// older runners (which don't know about that message) continue to work otherwise.
+    /// Returns an error if there are per-request options, but the runner doesn't support them
+    fn infer_request(
+        &self,
+        tensors: HashMap<String, Handle<Tensor>>,
+        streaming: bool,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> Result<RPCRequestData, String> {
+        match runner_opts {
+            Some(runner_opts) if self.supports_infer_opts() => {
+                Ok(RPCRequestData::InferWithTensorsAndOpts {
+                    tensors,
+                    streaming,
+                    runner_opts,
+                })
+            }
+            Some(_) => Err("This runner doesn't support per-request runner options. It may have been built with an older version of Carton".to_owned()),
+            None => Ok(RPCRequestData::InferWithTensors { tensors, streaming }),
+        }
+    }
+
+    /// Whether this runner supports `infer_with_inputs_lazy`
+    pub fn supports_lazy_outputs(&self) -> bool {
+        self.client.supports(crate::capabilities::LAZY_OUTPUTS)
+    }
+
+    /// Run inference, but leave the outputs in the runner until they're fetched individually with
+    /// `fetch_lazy_output`. Returns an ID for the outputs along with their names.
+    /// `release_lazy_outputs` must be called once the outputs are no longer needed.
+    ///
+    /// Only call this if `supports_lazy_outputs` returns true
+    pub async fn infer_with_inputs_lazy(
         &self,
         tensors_orig: HashMap<String, Tensor>,
-    ) -> impl Stream<Item = Result<HashMap<String, Tensor>, String>> + '_ {
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> Result<(u64, Vec<String>), RunnerError> {
         // Wrap each tensor in a handle (this possibly sends the fd for backing SHM chunks to the other process)
         let comms = self.client.get_comms();
         let mut tensors = HashMap::new();
@@ -166,18 +241,119 @@ impl Runner {
             tensors.insert(k, Handle::new(v, comms).await);
         }
 
-        let mut res = self
+        match self
             .client
-            .do_streaming_rpc(RPCRequestData::InferWithTensors {
+            .do_rpc_with_id(RPCRequestData::InferWithTensorsLazy {
                 tensors,
-                streaming: true,
+                runner_opts,
             })
-            .await;
+            .await?
+        {
+            (id, RPCResponseData::LazyInfer { outputs }) => Ok((id, outputs)),
+            (_, RPCResponseData::Error { e }) => Err(RunnerError::Message(e)),
+            (_, RPCResponseData::OutOfMemory { e }) => Err(RunnerError::OutOfMemory(e)),
+            _ => panic!("Unexpected RPC response type!"),
+        }
+    }
+
+    /// Fetch one output of a request made with `infer_with_inputs_lazy`. Each output can only be fetched once
+    pub async fn fetch_lazy_output(&self, id: u64, name: String) -> Result<Tensor, RunnerError> {
+        let comms = self.client.get_comms();
+
+        match self
+            .client
+            .do_rpc(RPCRequestData::FetchOutput {
+                req_id: id,
+                name: name.clone(),
+            })
+            .await?
+        {
+            RPCResponseData::Infer { mut tensors } => match tensors.remove(&name) {
+                Some(v) => Ok(v.into_inner(comms).await),
+                None => Err(format!("The runner didn't return output `{name}`").into()),
+            },
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
+            _ => panic!("Unexpected RPC response type!"),
+        }
+    }
+
+    /// Free any outputs of a request made with `infer_with_inputs_lazy` that haven't been fetched.
+    /// This doesn't wait for the runner
+    pub fn release_lazy_outputs(&self, id: u64) {
+        self.client
+            .send_in_background(RPCRequestData::ReleaseOutputs { req_id: id })
+    }
+
+    /// Whether this runner supports `ping`
+    pub fn supports_ping(&self) -> bool {
+        self.client.supports(crate::capabilities::PING)
+    }
+
+    /// Check that the runner is alive and handling requests. The runner responds without waiting for
+    /// in-flight requests to finish.
+    ///
+    /// Only call this if `supports_ping` returns true
+    pub async fn ping(&self) -> Result<(), RunnerError> {
+        match self.client.do_rpc(RPCRequestData::Ping).await? {
+            RPCResponseData::Pong => Ok(()),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
+            _ => panic!("Unexpected RPC response type!"),
+        }
+    }
+
+    /// Whether this runner supports `device_memory_usage`
+    pub fn supports_device_memory(&self) -> bool {
+        self.client.supports(crate::capabilities::DEVICE_MEMORY)
+    }
+
+    /// Get the amount of device (e.g. GPU) memory used by the loaded model. Returns `None` if the model
+    /// isn't using a device other than the CPU.
+    ///
+    /// Only call this if `supports_device_memory` returns true
+    pub async fn device_memory_usage(&self) -> Result<Option<DeviceMemory>, RunnerError> {
+        match self
+            .client
+            .do_rpc(RPCRequestData::DeviceMemoryUsage)
+            .await?
+        {
+            RPCResponseData::DeviceMemoryUsage { memory } => Ok(memory),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
+            _ => panic!("Unexpected RPC response type!"),
+        }
+    }
+
+    pub async fn streaming_infer_with_inputs(
+        &self,
+        tensors_orig: HashMap<String, Tensor>,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> impl Stream<Item = Result<HashMap<String, Tensor>, RunnerError>> + '_ {
+        // Wrap each tensor in a handle (this possibly sends the fd for backing SHM chunks to the other process)
+        let comms = self.client.get_comms();
+        let mut tensors = HashMap::new();
+        for (k, v) in tensors_orig.into_iter() {
+            tensors.insert(k, Handle::new(v, comms).await);
+        }
+
+        let res = match self.infer_request(tensors, true, runner_opts) {
+            Ok(req) => self.client.do_streaming_rpc(req).await,
+            Err(e) => Err(e.into()),
+        };
 
         async_stream::stream! {
+            let mut res = match res {
+                Ok(res) => res,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
             while let Some(v) = res.recv().await {
                 match v {
-                    RPCResponseData::Infer { tensors } => {
+                    Ok(RPCResponseData::Infer { tensors }) => {
                         let mut out = HashMap::new();
                         for (k, v) in tensors.into_iter() {
                             out.insert(k, v.into_inner(comms).await);
@@ -185,15 +361,17 @@ impl Runner {
 
                         yield Ok(out)
                     }
-                    RPCResponseData::Error { e } => yield Err(e),
-                    RPCResponseData::Empty => { } // We can get this on the last message. Do nothing
-                    _ => panic!("Unexpected RPC response type!"),
+                    Ok(RPCResponseData::Error { e }) => yield Err(RunnerError::Message(e)),
+                    Ok(RPCResponseData::OutOfMemory { e }) => yield Err(RunnerError::OutOfMemory(e)),
+                    Ok(RPCResponseData::Empty) => { } // We can get this on the last message. Do nothing
+                    Ok(_) => panic!("Unexpected RPC response type!"),
+                    Err(e) => yield Err(e),
                 }
             }
         }
     }
 
-    pub async fn seal(&self, tensors_orig: HashMap<String, Tensor>) -> Result<u64, String> {
+    pub async fn seal(&self, tensors_orig: HashMap<String, Tensor>) -> Result<u64, RunnerError> {
         // Wrap each tensor in a handle (this possibly sends the fd for backing SHM chunks to the other process)
         let comms = self.client.get_comms();
         let mut tensors = HashMap::new();
@@ -201,14 +379,18 @@ impl Runner {
             tensors.insert(k, Handle::new(v, comms).await);
         }
 
-        match self.client.do_rpc(RPCRequestData::Seal { tensors }).await {
+        match self.client.do_rpc(RPCRequestData::Seal { tensors }).await? {
             RPCResponseData::Seal { handle } => Ok(handle.0),
-            RPCResponseData::Error { e } => Err(e),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
             _ => panic!("Unexpected RPC response type!"),
         }
     }
 
-    pub async fn infer_with_handle(&self, handle: u64) -> Result<HashMap<String, Tensor>, String> {
+    pub async fn infer_with_handle(
+        &self,
+        handle: u64,
+    ) -> Result<HashMap<String, Tensor>, RunnerError> {
         let comms = self.client.get_comms();
 
         match self
@@ -217,7 +399,7 @@ impl Runner {
                 handle: SealHandle(handle),
                 streaming: false,
             })
-            .await
+            .await?
         {
             RPCResponseData::Infer { tensors } => {
                 let mut out = HashMap::new();
@@ -227,7 +409,8 @@ impl Runner {
 
                 Ok(out)
             }
-            RPCResponseData::Error { e } => Err(e),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
             _ => panic!("Unexpected RPC response type!"),
         }
     }
@@ -235,10 +418,10 @@ impl Runner {
     pub async fn streaming_infer_with_handle(
         &self,
         handle: u64,
-    ) -> impl Stream<Item = Result<HashMap<String, Tensor>, String>> + '_ {
+    ) -> impl Stream<Item = Result<HashMap<String, Tensor>, RunnerError>> + '_ {
         let comms = self.client.get_comms();
 
-        let mut res = self
+        let res = self
             .client
             .do_streaming_rpc(RPCRequestData::InferWithHandle {
                 handle: SealHandle(handle),
@@ -247,9 +430,17 @@ impl Runner {
             .await;
 
         async_stream::stream! {
+            let mut res = match res {
+                Ok(res) => res,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
             while let Some(v) = res.recv().await {
                 match v {
-                    RPCResponseData::Infer { tensors } => {
+                    Ok(RPCResponseData::Infer { tensors }) => {
                         let mut out = HashMap::new();
                         for (k, v) in tensors.into_iter() {
                             out.insert(k, v.into_inner(comms).await);
@@ -257,9 +448,11 @@ impl Runner {
 
                         yield Ok(out)
                     }
-                    RPCResponseData::Error { e } => yield Err(e),
-                    RPCResponseData::Empty => { } // We can get this on the last message. Do nothing
-                    _ => panic!("Unexpected RPC response type!"),
+                    Ok(RPCResponseData::Error { e }) => yield Err(RunnerError::Message(e)),
+                    Ok(RPCResponseData::OutOfMemory { e }) => yield Err(RunnerError::OutOfMemory(e)),
+                    Ok(RPCResponseData::Empty) => { } // We can get this on the last message. Do nothing
+                    Ok(_) => panic!("Unexpected RPC response type!"),
+                    Err(e) => yield Err(e),
                 }
             }
         }
@@ -271,7 +464,7 @@ impl Runner {
         fs: &Arc<T>,
         input_path: &lunchbox::path::Path,
         temp_folder: &lunchbox::path::Path,
-    ) -> Result<lunchbox::path::PathBuf, String>
+    ) -> Result<lunchbox::path::PathBuf, RunnerError>
     where
         T: lunchbox::WritableFileSystem + MaybeSend + MaybeSync + 'static,
         T::FileType: lunchbox::types::WritableFile + MaybeSend + MaybeSync + Unpin,
@@ -287,10 +480,11 @@ impl Runner {
                 input_path: input_path.to_string(),
                 temp_folder: temp_folder.to_string(),
             })
-            .await
+            .await?
         {
             RPCResponseData::Pack { output_path } => Ok(output_path.into()),
-            RPCResponseData::Error { e } => Err(e),
+            RPCResponseData::Error { e } => Err(RunnerError::Message(e)),
+            RPCResponseData::OutOfMemory { e } => Err(RunnerError::OutOfMemory(e)),
             _ => panic!("Unexpected RPC response type!"),
         }
     }
@@ -302,7 +496,22 @@ impl Runner {
     where
         Tensor: From<TensorStorage<T>>,
     {
-        Ok(TensorStorage::new(shape).into())
+        // This fails if the shape is too large (e.g. if it overflows)
+        Ok(TensorStorage::new(shape)?.into())
+    }
+
+    /// Same as `alloc_tensor`, but every element is set to `value` (e.g. a padding token or `-inf` for
+    /// a mask) instead of the default value of `T`
+    pub fn alloc_tensor_filled<T: Clone + Default + Allocatable>(
+        &self,
+        shape: Vec<u64>,
+        value: T,
+    ) -> Result<Tensor, String>
+    where
+        Tensor: From<TensorStorage<T>>,
+    {
+        // This fails if the shape is too large (e.g. if it overflows)
+        Ok(TensorStorage::filled(shape, value)?.into())
     }
 
     // pub async fn infer_with_handle(
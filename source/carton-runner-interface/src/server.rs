@@ -14,9 +14,9 @@
 
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
-    sync::Mutex,
+    sync::{Arc, Mutex},
 };
 
 use anywhere::types::{AnywhereFS, ReadOnlyFS, ReadWriteFS};
@@ -26,26 +26,54 @@ use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::prelude::*;
 
 use crate::{
+    capabilities,
     do_not_modify::comms::Comms,
     do_not_modify::types::{ChannelId, FsToken, RPCRequest, RPCResponse},
     multiplexer::Multiplexer,
-    types::{Device, Handle, LogRecord, RPCRequestData, RPCResponseData, RpcId, RunnerOpt, Tensor},
+    types::{
+        Device, DeviceMemory, Handle, LogRecord, RPCRequestData, RPCResponseData, RpcId, RunnerOpt,
+        Tensor,
+    },
+    CAPABILITIES_ENV_VAR, LOG_LEVEL_ENV_VAR, TYPED_ERRORS_ENV_VAR,
 };
 
 pub struct Server {
-    comms: Comms,
+    comms: Arc<Comms>,
     fs_multiplexer: Multiplexer<
         anywhere::transport::serde::RequestMessageType,
         anywhere::transport::serde::ResponseMessageType,
     >,
 
     outgoing: mpsc::Sender<RPCResponse>,
-    incoming: mpsc::Receiver<RPCRequest>,
+    incoming: mpsc::UnboundedReceiver<RPCRequest>,
+
+    // Requests that haven't been completed yet and which of those have been cancelled
+    requests: Arc<Mutex<RequestTracker>>,
 
     // Keep this alive while the server is up
     _keepalive: Vec<Box<dyn Any + Send + Sync>>,
 }
 
+#[derive(Default)]
+struct RequestTracker {
+    active: HashSet<RpcId>,
+    cancelled: HashSet<RpcId>,
+
+    // Active requests that were sent as `InferWithTensorsLazy`
+    lazy: HashSet<RpcId>,
+
+    // The outputs of completed lazy requests that haven't been fetched or released yet
+    held_outputs: HashMap<RpcId, HashMap<String, Tensor>>,
+}
+
+impl RequestTracker {
+    fn complete(&mut self, req_id: RpcId) {
+        self.active.remove(&req_id);
+        self.cancelled.remove(&req_id);
+        self.lazy.remove(&req_id);
+    }
+}
+
 /// A handle that represents a map of sealed tensors
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct SealHandle(pub(crate) u64);
@@ -134,6 +162,9 @@ pub enum RequestData {
 
         // Do we support a streaming response
         streaming: bool,
+
+        // Per-request runner options. Runners should ignore (or warn about) options they don't recognize
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
     },
 
     InferWithHandle {
@@ -142,6 +173,10 @@ pub enum RequestData {
         // Do we support a streaming response
         streaming: bool,
     },
+
+    /// Report how much device (e.g. GPU) memory the model is using. Runners should respond with
+    /// `DeviceMemoryUsage` (with `memory` set to `None` if the model is running on the CPU)
+    DeviceMemoryUsage,
 }
 
 impl RequestData {
@@ -188,11 +223,36 @@ impl RequestData {
             RPCRequestData::InferWithTensors { tensors, streaming } => Self::InferWithTensors {
                 tensors: from_handles(tensors).await,
                 streaming,
+                runner_opts: None,
+            },
+            RPCRequestData::InferWithTensorsAndOpts {
+                tensors,
+                streaming,
+                runner_opts,
+            } => Self::InferWithTensors {
+                tensors: from_handles(tensors).await,
+                streaming,
+                runner_opts: Some(runner_opts),
             },
             RPCRequestData::InferWithHandle { handle, streaming } => Self::InferWithHandle {
                 handle: handle.into(),
                 streaming,
             },
+            RPCRequestData::InferWithTensorsLazy {
+                tensors,
+                runner_opts,
+            } => Self::InferWithTensors {
+                tensors: from_handles(tensors).await,
+                streaming: false,
+                runner_opts,
+            },
+            RPCRequestData::DeviceMemoryUsage => Self::DeviceMemoryUsage,
+            RPCRequestData::Cancel { .. }
+            | RPCRequestData::FetchOutput { .. }
+            | RPCRequestData::ReleaseOutputs { .. }
+            | RPCRequestData::Ping => {
+                unreachable!("These requests are handled by the server")
+            }
         }
     }
 }
@@ -219,6 +279,8 @@ pub enum ResponseData {
     },
 
     /// Something went wrong
+    /// Runners should send this (instead of panicking) if a `Pack` request fails so the
+    /// error can be surfaced to the user
     Error {
         e: String,
     },
@@ -229,6 +291,18 @@ pub enum ResponseData {
     },
 
     Empty,
+
+    /// The response to `DeviceMemoryUsage`
+    DeviceMemoryUsage {
+        memory: Option<DeviceMemory>,
+    },
+
+    /// The request failed because the runner ran out of memory (e.g. GPU memory). Runners should send this
+    /// instead of `Error` in that case so the core library can return a dedicated error to the user.
+    /// It's sent as `Error` to versions of the core library that don't support it
+    OutOfMemory {
+        e: String,
+    },
 }
 
 impl ResponseData {
@@ -254,19 +328,121 @@ impl ResponseData {
             ResponseData::Error { e } => RPCResponseData::Error { e },
             ResponseData::LogMessage { record } => RPCResponseData::LogMessage { record },
             ResponseData::Empty => RPCResponseData::Empty,
+            ResponseData::DeviceMemoryUsage { memory } => {
+                RPCResponseData::DeviceMemoryUsage { memory }
+            }
+            ResponseData::OutOfMemory { e } => {
+                if std::env::var_os(TYPED_ERRORS_ENV_VAR).is_some() {
+                    RPCResponseData::OutOfMemory { e }
+                } else {
+                    RPCResponseData::Error { e }
+                }
+            }
         }
     }
 }
 
 impl Server {
     async fn connect(path: &Path, logger: Option<&PassThroughLogger>) -> Self {
-        let comms = Comms::connect(path).await;
+        let comms = Arc::new(Comms::connect(path).await);
 
         // Set up filesystem handling
         let (tx, rx) = comms.get_channel(ChannelId::FileSystem).await;
         let fs_multiplexer = Multiplexer::new(tx, rx).await;
 
-        let (tx, rx) = comms.get_channel(ChannelId::Rpc).await;
+        let (tx, mut rx) = comms
+            .get_channel::<RPCResponse, RPCRequest>(ChannelId::Rpc)
+            .await;
+
+        // Handle cancellation, lazy outputs, and pings as soon as requests arrive (instead of when the runner asks
+        // for the next request) because runners generally handle one request at a time
+        let requests = Arc::new(Mutex::new(RequestTracker::default()));
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        {
+            let requests = requests.clone();
+            let comms = comms.clone();
+            let out = tx.clone();
+            tokio::spawn(async move {
+                while let Some(req) = rx.recv().await {
+                    let mut tracker = requests.lock().unwrap();
+                    match req.data {
+                        RPCRequestData::Cancel { req_id } => {
+                            // Ignore requests that already completed
+                            if tracker.active.contains(&req_id) {
+                                tracker.cancelled.insert(req_id);
+                            }
+                        }
+                        RPCRequestData::FetchOutput { req_id, name } => {
+                            // Each output is only fetched once so we don't need to keep it around
+                            let tensor = tracker
+                                .held_outputs
+                                .get_mut(&req_id)
+                                .and_then(|outputs| outputs.remove(&name));
+
+                            let comms = comms.clone();
+                            let out = out.clone();
+                            tokio::spawn(async move {
+                                let data = match tensor {
+                                    Some(tensor) => ResponseData::Infer {
+                                        tensors: [(name, tensor)].into(),
+                                    },
+                                    None => ResponseData::Error {
+                                        e: format!("Output `{name}` is not available. It may have already been fetched or released"),
+                                    },
+                                };
+
+                                let _ = out
+                                    .send(RPCResponse {
+                                        id: req.id,
+                                        complete: true,
+                                        data: data.to_rpc(&comms).await,
+                                    })
+                                    .await;
+                            });
+                        }
+                        RPCRequestData::ReleaseOutputs { req_id } => {
+                            tracker.held_outputs.remove(&req_id);
+                        }
+                        RPCRequestData::Ping => {
+                            // This doesn't wait for the runner to finish any in-flight requests
+                            let out = out.clone();
+                            tokio::spawn(async move {
+                                let _ = out
+                                    .send(RPCResponse {
+                                        id: req.id,
+                                        complete: true,
+                                        data: RPCResponseData::Pong,
+                                    })
+                                    .await;
+                            });
+                        }
+                        data => {
+                            if let RPCRequestData::InferWithTensorsLazy { .. } = data {
+                                tracker.lazy.insert(req.id);
+                            }
+
+                            tracker.active.insert(req.id);
+                            if incoming_tx.send(RPCRequest { id: req.id, data }).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        // Let the core library know which optional features we support (if it understands that message)
+        if std::env::var_os(CAPABILITIES_ENV_VAR).is_some() {
+            let _ = tx
+                .send(RPCResponse {
+                    id: 0,
+                    complete: true,
+                    data: RPCResponseData::Capabilities {
+                        features: capabilities::ALL.iter().map(|&v| v.to_owned()).collect(),
+                    },
+                })
+                .await;
+        }
 
         if let Some(logger) = logger {
             let mut messages = logger.get_rx();
@@ -290,16 +466,65 @@ impl Server {
         Server {
             comms,
             fs_multiplexer,
-            incoming: rx,
+            incoming,
             outgoing: tx,
+            requests,
             _keepalive: Vec::new(),
         }
     }
 
+    /// Get the next request to handle. Requests that were cancelled before they're returned
+    /// from this method are skipped
     pub async fn get_next_request(&mut self) -> Option<Request> {
-        match self.incoming.recv().await {
-            Some(req) => Some(Request::from(req, &self.comms).await),
-            None => None,
+        loop {
+            let req = Request::from(self.incoming.recv().await?, &self.comms).await;
+            if !self.is_cancelled(req.id) {
+                return Some(req);
+            }
+
+            // Let the core library know we're done with it
+            let _ = self
+                .send_response_for_request(
+                    req.id,
+                    ResponseData::Error {
+                        e: "Request was cancelled".into(),
+                    },
+                )
+                .await;
+        }
+    }
+
+    /// Check if the core library cancelled a request (e.g. because the caller is no longer waiting for
+    /// the result). Runners should check this periodically during long running requests (e.g. between
+    /// steps of text generation) and stop early if it returns true. Runners must still send a final
+    /// response for cancelled requests, but the core library ignores it
+    pub fn is_cancelled(&self, req_id: RpcId) -> bool {
+        self.requests.lock().unwrap().cancelled.contains(&req_id)
+    }
+
+    /// Mark a request as complete. If it was a lazy inference request, this holds on to the outputs
+    /// until they're fetched and returns the response to send. Otherwise, returns `res` unchanged
+    fn complete_request(
+        &self,
+        req_id: RpcId,
+        res: ResponseData,
+    ) -> Result<RPCResponseData, ResponseData> {
+        let mut tracker = self.requests.lock().unwrap();
+        let lazy = tracker.lazy.contains(&req_id);
+        let cancelled = tracker.cancelled.contains(&req_id);
+        tracker.complete(req_id);
+
+        match res {
+            // Nobody is going to fetch or release these outputs
+            ResponseData::Infer { .. } if lazy && cancelled => Ok(RPCResponseData::Error {
+                e: "Request was cancelled".into(),
+            }),
+            ResponseData::Infer { tensors } if lazy => {
+                let outputs = tensors.keys().cloned().collect();
+                tracker.held_outputs.insert(req_id, tensors);
+                Ok(RPCResponseData::LazyInfer { outputs })
+            }
+            res => Err(res),
         }
     }
 
@@ -308,22 +533,36 @@ impl Server {
         req_id: u64,
         res: ResponseData,
     ) -> Result<(), SendError<()>> {
+        let res = match self.complete_request(req_id, res) {
+            Ok(res) => res,
+            Err(res) => res.to_rpc(&self.comms).await,
+        };
+
         self.outgoing
             .send(RPCResponse {
                 id: req_id,
                 complete: true,
-                data: res.to_rpc(&self.comms).await,
+                data: res,
             })
             .await
             .map_err(|_| SendError(()))
     }
 
+    /// Send one response of a streaming request.
+    ///
+    /// This waits until there's space in the downstream buffers. If the consumer is slow, this
+    /// applies backpressure so runners should wait for this to complete before producing the next
+    /// response (e.g. the next step of a text generation loop)
     pub async fn send_streaming_response_for_request(
         &self,
         req_id: u64,
         complete: bool,
         res: ResponseData,
     ) -> Result<(), SendError<()>> {
+        if complete {
+            self.requests.lock().unwrap().complete(req_id);
+        }
+
         self.outgoing
             .send(RPCResponse {
                 id: req_id,
@@ -400,9 +639,15 @@ pub async fn init_runner() -> Server {
         }
         Err(_) => {
             // Initialize logging
+            // Records are sent to the main process so only forward the ones it wants to see
+            let level = std::env::var(LOG_LEVEL_ENV_VAR)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(log::LevelFilter::Trace);
+
             let logger: &'static PassThroughLogger = Box::leak(Box::new(PassThroughLogger::new()));
             log::set_logger(logger).unwrap();
-            log::set_max_level(log::LevelFilter::Trace);
+            log::set_max_level(level);
 
             pass_through_logger = Some(logger);
         }
@@ -445,11 +690,9 @@ impl PassThroughLogger {
 }
 
 impl log::Log for PassThroughLogger {
-    fn enabled(&self, _metadata: &log::Metadata) -> bool {
-        // This isn't ideal, but for now, lets always return true and let the
-        // main process handle it
-        // TODO: improve this
-        true
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        // The main process can do more filtering (e.g. by target) when it logs the record
+        metadata.level() <= log::max_level()
     }
 
     fn log(&self, record: &log::Record) {
@@ -0,0 +1,354 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A small harness for testing runners without the core library.
+//!
+//! This starts a runner binary, sends it `Load`, `Pack` and `Infer` requests over the normal
+//! transport, and lets tests make assertions about the responses. Enable the `testing` feature
+//! to use it (usually as a dev-dependency of a runner crate).
+//!
+//! ```ignore
+//! let harness = RunnerHarness::new(env!("CARGO_BIN_EXE_my-runner")).await.unwrap();
+//!
+//! let packed = harness
+//!     .send(TestRequest::pack("/path/to/model"))
+//!     .await
+//!     .expect_pack();
+//!
+//! harness
+//!     .send(TestRequest::load(packed, "my-runner", "*", 1))
+//!     .await
+//!     .expect_load();
+//!
+//! let outputs = harness
+//!     .send(TestRequest::infer([("a", tensor)]))
+//!     .await
+//!     .expect_infer();
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    types::{for_each_carton_type, Device, RunnerOpt, Tensor},
+    Runner,
+};
+
+/// A request to send to a runner under test
+#[derive(Debug)]
+pub enum TestRequest {
+    /// Load a model from a local directory
+    Load {
+        /// A local directory of the same structure as the output of `Pack`
+        model_dir: PathBuf,
+
+        runner_name: String,
+        required_framework_version: semver::VersionReq,
+        runner_compat_version: u64,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+        visible_device: Device,
+        carton_manifest_hash: Option<String>,
+    },
+
+    /// Pack a model at a local path
+    Pack { input_path: PathBuf },
+
+    /// Run inference with a set of tensors
+    Infer {
+        tensors: HashMap<String, Tensor>,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    },
+}
+
+impl TestRequest {
+    /// A `Load` request for `model_dir` on the CPU with no runner opts
+    ///
+    /// Panics if `required_framework_version` isn't a valid semver requirement
+    pub fn load<P: Into<PathBuf>, S: Into<String>>(
+        model_dir: P,
+        runner_name: S,
+        required_framework_version: &str,
+        runner_compat_version: u64,
+    ) -> Self {
+        Self::Load {
+            model_dir: model_dir.into(),
+            runner_name: runner_name.into(),
+            required_framework_version: semver::VersionReq::parse(required_framework_version)
+                .expect("Invalid framework version requirement"),
+            runner_compat_version,
+            runner_opts: None,
+            visible_device: Device::CPU,
+            carton_manifest_hash: None,
+        }
+    }
+
+    /// A `Pack` request for `input_path`
+    pub fn pack<P: Into<PathBuf>>(input_path: P) -> Self {
+        Self::Pack {
+            input_path: input_path.into(),
+        }
+    }
+
+    /// An `Infer` request with no per-request runner opts
+    pub fn infer<I, S>(tensors: I) -> Self
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        S: Into<String>,
+    {
+        Self::Infer {
+            tensors: tensors.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+            runner_opts: None,
+        }
+    }
+
+    /// Set runner opts on a `Load` or `Infer` request. This panics for `Pack` requests
+    pub fn with_runner_opts(mut self, opts: HashMap<String, RunnerOpt>) -> Self {
+        match &mut self {
+            Self::Load { runner_opts, .. } | Self::Infer { runner_opts, .. } => {
+                *runner_opts = Some(opts)
+            }
+            Self::Pack { .. } => panic!("Pack requests don't take runner opts"),
+        }
+
+        self
+    }
+
+    /// Set the visible device on a `Load` request. This panics for other requests
+    pub fn with_device(mut self, device: Device) -> Self {
+        match &mut self {
+            Self::Load { visible_device, .. } => *visible_device = device,
+            _ => panic!("Only load requests take a visible device"),
+        }
+
+        self
+    }
+}
+
+/// A response from a runner under test
+#[derive(Debug)]
+pub enum TestResponse {
+    /// Successful load
+    Load,
+
+    /// Successful pack. This is a local path to the output directory
+    Pack { output_path: PathBuf },
+
+    /// Successful inference
+    Infer { tensors: HashMap<String, Tensor> },
+
+    /// The runner returned an error
+    Error { e: String },
+}
+
+impl TestResponse {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error { .. })
+    }
+
+    /// Panics if this isn't a successful load
+    pub fn expect_load(self) {
+        match self {
+            Self::Load => {}
+            other => panic!("Expected a load response, but got {other:?}"),
+        }
+    }
+
+    /// Returns the output path of a successful pack. Panics otherwise
+    pub fn expect_pack(self) -> PathBuf {
+        match self {
+            Self::Pack { output_path } => output_path,
+            other => panic!("Expected a pack response, but got {other:?}"),
+        }
+    }
+
+    /// Returns the outputs of a successful inference. Panics otherwise
+    pub fn expect_infer(self) -> HashMap<String, Tensor> {
+        match self {
+            Self::Infer { tensors } => tensors,
+            other => panic!("Expected an infer response, but got {other:?}"),
+        }
+    }
+
+    /// Returns the error message if the runner returned an error. Panics otherwise
+    pub fn expect_error(self) -> String {
+        match self {
+            Self::Error { e } => e,
+            other => panic!("Expected an error response, but got {other:?}"),
+        }
+    }
+}
+
+/// A runner process under test
+pub struct RunnerHarness {
+    runner: Runner,
+
+    // Temp folders handed to the runner for `Pack` requests. These are kept alive as long as the
+    // harness so tests can inspect (and load) the packed output
+    temp_folders: std::sync::Mutex<Vec<tempfile::TempDir>>,
+}
+
+impl RunnerHarness {
+    /// Start the runner binary at `runner_path` with no visible GPUs
+    pub async fn new<P: AsRef<Path>>(runner_path: P) -> Result<Self, String> {
+        Self::new_with_device(runner_path, Device::CPU).await
+    }
+
+    /// Start the runner binary at `runner_path` and make `visible_device` available to it
+    pub async fn new_with_device<P: AsRef<Path>>(
+        runner_path: P,
+        visible_device: Device,
+    ) -> Result<Self, String> {
+        let runner = Runner::new(runner_path.as_ref(), visible_device).await?;
+        Ok(Self {
+            runner,
+            temp_folders: Default::default(),
+        })
+    }
+
+    /// Get the underlying runner (e.g. to make requests not covered by `TestRequest`)
+    pub fn runner(&self) -> &Runner {
+        &self.runner
+    }
+
+    /// Send a request to the runner and wait for the response
+    pub async fn send(&self, request: TestRequest) -> TestResponse {
+        let res = match request {
+            TestRequest::Load {
+                model_dir,
+                runner_name,
+                required_framework_version,
+                runner_compat_version,
+                runner_opts,
+                visible_device,
+                carton_manifest_hash,
+            } => {
+                let fs = match lunchbox::LocalFS::with_base_dir(model_dir).await {
+                    Ok(fs) => Arc::new(fs),
+                    Err(e) => {
+                        return TestResponse::Error {
+                            e: format!("Could not open model dir: {e}"),
+                        }
+                    }
+                };
+
+                self.runner
+                    .load(
+                        &fs,
+                        runner_name,
+                        required_framework_version,
+                        runner_compat_version,
+                        runner_opts,
+                        visible_device,
+                        carton_manifest_hash,
+                    )
+                    .await
+                    .map(|_| TestResponse::Load)
+            }
+            TestRequest::Pack { input_path } => {
+                // Pack uses absolute paths on the local filesystem (the same as the core library)
+                let tempdir = tempfile::tempdir().expect("Failed to create a temp folder");
+                let fs = Arc::new(lunchbox::LocalFS::new().unwrap());
+                let res = self
+                    .runner
+                    .pack(
+                        &fs,
+                        lunchbox::path::Path::new(input_path.to_str().unwrap()),
+                        lunchbox::path::Path::new(tempdir.path().to_str().unwrap()),
+                    )
+                    .await
+                    .map(|output_path| TestResponse::Pack {
+                        output_path: output_path.to_string().into(),
+                    });
+
+                self.temp_folders.lock().unwrap().push(tempdir);
+                res
+            }
+            TestRequest::Infer {
+                tensors,
+                runner_opts,
+            } => self
+                .runner
+                .infer_with_inputs(tensors, runner_opts)
+                .await
+                .map(|tensors| TestResponse::Infer { tensors }),
+        };
+
+        res.unwrap_or_else(|e| TestResponse::Error { e: e.to_string() })
+    }
+
+    /// Pack `input_path` and then load the output. Returns the packed output path
+    ///
+    /// Panics if either step fails
+    pub async fn pack_and_load<P: Into<PathBuf>, S: Into<String>>(
+        &self,
+        input_path: P,
+        runner_name: S,
+        required_framework_version: &str,
+        runner_compat_version: u64,
+    ) -> PathBuf {
+        let output_path = self.send(TestRequest::pack(input_path)).await.expect_pack();
+
+        self.send(TestRequest::load(
+            output_path.clone(),
+            runner_name,
+            required_framework_version,
+            runner_compat_version,
+        ))
+        .await
+        .expect_load();
+
+        output_path
+    }
+}
+
+/// Asserts that two sets of tensors have the same names, types, shapes and values
+pub fn assert_tensors_eq(actual: &HashMap<String, Tensor>, expected: &HashMap<String, Tensor>) {
+    let mut actual_keys: Vec<_> = actual.keys().collect();
+    let mut expected_keys: Vec<_> = expected.keys().collect();
+    actual_keys.sort();
+    expected_keys.sort();
+    assert_eq!(actual_keys, expected_keys, "Tensor names don't match");
+
+    for (k, v) in expected {
+        assert!(
+            tensors_eq(&actual[k], v),
+            "Tensor `{k}` doesn't match. Expected {v:?}, but got {:?}",
+            &actual[k]
+        );
+    }
+}
+
+for_each_carton_type! {
+    /// Returns true if two tensors have the same type, shape and values
+    pub fn tensors_eq(a: &Tensor, b: &Tensor) -> bool {
+        match (a, b) {
+            $(
+                (Tensor::$CartonType(a), Tensor::$CartonType(b)) => a.view() == b.view(),
+            )*
+            (Tensor::NestedTensor(a), Tensor::NestedTensor(b)) => nested_eq(a, b),
+            (
+                Tensor::LabeledNestedTensor { tensors: a, labels: a_labels },
+                Tensor::LabeledNestedTensor { tensors: b, labels: b_labels },
+            ) => a_labels == b_labels && nested_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+fn nested_eq(a: &[Tensor], b: &[Tensor]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| tensors_eq(a, b))
+}
@@ -75,6 +75,17 @@ async fn main() {
                     .await
                     .unwrap();
             }
+
+            RequestData::DeviceMemoryUsage => {
+                // This runner doesn't use any devices
+                server
+                    .send_response_for_request(
+                        req_id,
+                        ResponseData::DeviceMemoryUsage { memory: None },
+                    )
+                    .await
+                    .unwrap();
+            }
         }
     }
 }
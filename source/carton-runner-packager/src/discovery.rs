@@ -50,6 +50,7 @@ pub struct RunnerInfo {
     pub platform: String,
 }
 
+#[derive(Clone)]
 pub struct RunnerFilterConstraints {
     pub runner_name: Option<String>,
     pub framework_version_range: Option<semver::VersionReq>,
@@ -63,7 +64,7 @@ pub(crate) fn get_runner_dir() -> &'static PathBuf {
 }
 
 #[derive(Debug, Error)]
-enum DiscoveryError {
+pub enum DiscoveryError {
     #[error("IO error: {0}")]
     IOError(#[from] std::io::Error),
 
@@ -105,25 +106,9 @@ pub async fn discover_runners(installation_id_filter: &Option<String>) -> Vec<Ru
     }
 
     // Read all the configs
-    let futs = runner_tomls.into_iter().map(|path| async move {
-        let data = tokio::fs::read(&path).await?;
-        let mut config: Config = toml::from_slice(&data)?;
-
-        // This is safe because the last component is "runner.toml"
-        let parent = path.parent().unwrap();
-
-        // Since runner_path can be relative, let's turn it into an absolute one
-        for runner in &mut config.runner {
-            // (join handles absolute paths)
-            runner.runner_path = parent
-                .join(&runner.runner_path)
-                .to_str()
-                .unwrap()
-                .to_owned();
-        }
-
-        Ok::<_, DiscoveryError>(config)
-    });
+    let futs = runner_tomls
+        .into_iter()
+        .map(|path| async move { read_config(&path).await });
 
     // Join and flatten
     futures::future::join_all(futs)
@@ -147,6 +132,32 @@ pub async fn discover_runners(installation_id_filter: &Option<String>) -> Vec<Ru
         .collect()
 }
 
+/// Read the runners in a `runner.toml` file. Relative `runner_path`s are resolved against the directory
+/// containing the file. This doesn't check that the runner binaries exist
+pub async fn read_runner_toml(path: &std::path::Path) -> Result<Vec<RunnerInfo>, DiscoveryError> {
+    Ok(read_config(path).await?.runner)
+}
+
+async fn read_config(path: &std::path::Path) -> Result<Config, DiscoveryError> {
+    let data = tokio::fs::read(path).await?;
+    let mut config: Config = toml::from_slice(&data)?;
+
+    // The path ends with "runner.toml" so this is safe
+    let parent = path.parent().unwrap();
+
+    // Since runner_path can be relative, let's turn it into an absolute one
+    for runner in &mut config.runner {
+        // (join handles absolute paths)
+        runner.runner_path = parent
+            .join(&runner.runner_path)
+            .to_str()
+            .unwrap()
+            .to_owned();
+    }
+
+    Ok(config)
+}
+
 /// Get an installed runner that matches the constraints (or None)
 pub async fn get_matching_installed_runner(
     constraints: &RunnerFilterConstraints,
@@ -157,6 +168,36 @@ pub async fn get_matching_installed_runner(
     get_matching_runner(local_runners, constraints).await
 }
 
+/// Get the `runner_compat_version`s of all the installed runners that match `constraints` (ignoring
+/// `runner_compat_version`). This is useful to explain why an installed runner can't load a model
+pub async fn get_installed_compat_versions(constraints: &RunnerFilterConstraints) -> Vec<u64> {
+    let mut out: Vec<_> = discover_runners(&None)
+        .await
+        .into_iter()
+        .filter(|r| matches_except_compat_version(r, constraints))
+        .map(|r| r.runner_compat_version)
+        .collect();
+
+    out.sort();
+    out.dedup();
+    out
+}
+
+pub(crate) fn matches_except_compat_version<T>(
+    runner: &T,
+    constraints: &RunnerFilterConstraints,
+) -> bool
+where
+    T: FilterableAsRunner,
+{
+    let relaxed = RunnerFilterConstraints {
+        runner_compat_version: None,
+        ..constraints.clone()
+    };
+
+    runner_matches(runner, &relaxed)
+}
+
 pub(crate) trait FilterableAsRunner {
     fn runner_name(&self) -> &str;
     fn framework_version(&self) -> &semver::Version;
@@ -203,43 +244,49 @@ where
     // Filter the runners to ones that match our requirements
     runners
         .into_iter()
-        .filter_map(|runner| {
-            // The runner name must be the same as the model we're trying to load
-            if let Some(runner_name) = &constraints.runner_name {
-                if runner_name != runner.runner_name() {
-                    return None;
-                }
-            }
+        .filter(|runner| runner_matches(runner, constraints))
+        // Pick the newest one that matches the requirements
+        .max_by_key(|item| item.runner_release_date().clone())
+}
 
-            // The runner's framework_version must satisfy the model's required range
-            if let Some(framework_version_range) = &constraints.framework_version_range {
-                if !framework_version_range.matches(runner.framework_version()) {
-                    return None;
-                }
-            }
+/// Check if a runner matches the constraints
+pub(crate) fn runner_matches<T>(runner: &T, constraints: &RunnerFilterConstraints) -> bool
+where
+    T: FilterableAsRunner,
+{
+    // The runner name must be the same as the model we're trying to load
+    if let Some(runner_name) = &constraints.runner_name {
+        if runner_name != runner.runner_name() {
+            return false;
+        }
+    }
 
-            // The runner compat version must be the same as the model we're trying to load
-            // (this is kind of like a version for the `model` directory)
-            // If an expected runner_compat_version was specified, check if it matches
-            if let Some(runner_compat_version) = constraints.runner_compat_version {
-                if runner_compat_version != runner.runner_compat_version() {
-                    return None;
-                }
-            }
+    // The runner's framework_version must satisfy the model's required range
+    if let Some(framework_version_range) = &constraints.framework_version_range {
+        if !framework_version_range.matches(runner.framework_version()) {
+            return false;
+        }
+    }
 
-            // We need to be able to start the runner so its platform must match the requirements
-            if runner.platform() != constraints.platform {
-                return None;
-            }
+    // The runner compat version must be the same as the model we're trying to load
+    // (this is kind of like a version for the `model` directory)
+    // If an expected runner_compat_version was specified, check if it matches
+    if let Some(runner_compat_version) = constraints.runner_compat_version {
+        if runner_compat_version != runner.runner_compat_version() {
+            return false;
+        }
+    }
 
-            // Finally, we must be able to communicate with the runner (so its interface
-            // version should be one we support)
-            if runner.runner_interface_version() > constraints.max_runner_interface_version {
-                return None;
-            }
+    // We need to be able to start the runner so its platform must match the requirements
+    if runner.platform() != constraints.platform {
+        return false;
+    }
 
-            Some(runner)
-        })
-        // Pick the newest one that matches the requirements
-        .max_by_key(|item| item.runner_release_date().clone())
+    // Finally, we must be able to communicate with the runner (so its interface
+    // version should be one we support)
+    if runner.runner_interface_version() > constraints.max_runner_interface_version {
+        return false;
+    }
+
+    true
 }
@@ -14,7 +14,8 @@
 
 use crate::{
     discovery::{
-        get_matching_installed_runner, get_matching_runner, FilterableAsRunner,
+        discover_runners, get_installed_compat_versions, get_matching_installed_runner,
+        get_matching_runner, matches_except_compat_version, runner_matches, FilterableAsRunner,
         RunnerFilterConstraints, RunnerInfo,
     },
     install, DownloadInfo,
@@ -95,6 +96,94 @@ pub async fn get_or_install_runner(
             .ok_or("We just installed a matching runner, but none found. Please file an issue on GitHub if you get this error.")
 }
 
+/// Get the framework versions of all the installed and installable runners that match `filters`
+/// (ignoring `framework_version_range`). This is useful to explain why no runner matched a version range
+pub async fn get_available_framework_versions(
+    index_url: &str,
+    filters: &RunnerFilterConstraints,
+) -> Vec<semver::Version> {
+    let relaxed = RunnerFilterConstraints {
+        runner_name: filters.runner_name.clone(),
+        framework_version_range: None,
+        runner_compat_version: filters.runner_compat_version,
+        max_runner_interface_version: filters.max_runner_interface_version,
+        platform: filters.platform.clone(),
+    };
+
+    let installed = discover_runners(&None)
+        .await
+        .into_iter()
+        .filter(|r| runner_matches(r, &relaxed))
+        .map(|r| r.framework_version);
+
+    let installable = fetch_runners(index_url)
+        .await
+        .into_iter()
+        .filter(|r| runner_matches(r, &relaxed))
+        .map(|r| r.framework_version);
+
+    let mut out: Vec<_> = installed.chain(installable).collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Get the platforms (target triples) of all the installed and installable runners that match `filters`
+/// (ignoring `platform`). This is useful to explain why no runner is available for the current platform
+pub async fn get_available_platforms(
+    index_url: &str,
+    filters: &RunnerFilterConstraints,
+) -> Vec<String> {
+    let installed = discover_runners(&None)
+        .await
+        .into_iter()
+        .filter(|r| matches_except_platform(r, filters))
+        .map(|r| r.platform);
+
+    let installable = fetch_runners(index_url)
+        .await
+        .into_iter()
+        .filter(|r| matches_except_platform(r, filters))
+        .map(|r| r.platform);
+
+    let mut out: Vec<_> = installed.chain(installable).collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Get the `runner_compat_version`s of all the installed and installable runners that match `filters`
+/// (ignoring `runner_compat_version`). This is useful to explain whether a model or its runner is out of date
+pub async fn get_available_compat_versions(
+    index_url: &str,
+    filters: &RunnerFilterConstraints,
+) -> Vec<u64> {
+    let installed = get_installed_compat_versions(filters).await;
+
+    let installable = fetch_runners(index_url)
+        .await
+        .into_iter()
+        .filter(|r| matches_except_compat_version(r, filters))
+        .map(|r| r.runner_compat_version);
+
+    let mut out: Vec<_> = installed.into_iter().chain(installable).collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn matches_except_platform<T>(runner: &T, filters: &RunnerFilterConstraints) -> bool
+where
+    T: FilterableAsRunner,
+{
+    let relaxed = RunnerFilterConstraints {
+        platform: runner.platform().to_owned(),
+        ..filters.clone()
+    };
+
+    runner_matches(runner, &relaxed)
+}
+
 impl FilterableAsRunner for DownloadInfo {
     fn runner_name(&self) -> &str {
         &self.runner_name
@@ -0,0 +1,24 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The python runner. This is a library so tooling can use `packager` to prepare python models for packing
+
+mod env;
+pub mod loader;
+pub mod model;
+mod output_capture;
+pub mod packager;
+mod pip_utils;
+pub mod python_utils;
+mod wheel;
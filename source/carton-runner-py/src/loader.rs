@@ -15,7 +15,7 @@
 use std::collections::{HashMap, VecDeque};
 
 use carton_runner_interface::{slowlog::slowlog, types::RunnerOpt};
-use carton_utils::archive::extract_zip;
+use carton_utils::archive::{extract_zip_with_limits, ExtractLimits};
 use lunchbox::path::{LunchboxPathUtils, PathBuf};
 use path_clean::PathClean;
 use pyo3::{prelude::*, types::PyDict};
@@ -27,11 +27,11 @@ use crate::{
     model::{pyerr_to_string_with_traceback, Model},
     packager::CartonLock,
     python_utils::add_to_sys_path,
-    wheel::install_wheel_and_make_available,
+    wheel::{install_wheel_and_make_available, installed_wheel_path},
 };
 
 #[tracing::instrument(skip(fs))]
-pub(crate) async fn load<F>(
+pub async fn load<F>(
     fs: F,
     runner_opts: Option<HashMap<String, RunnerOpt>>,
 ) -> Result<Model, String>
@@ -56,6 +56,26 @@ where
             "Expected the `entrypoint_fn` option to be a string, but it was a different type.",
         )?;
 
+        // If `offline` is set, we never reach out to the network to fetch packages. Instead, we fail if
+        // any required packages aren't already available locally
+        let offline = match opts.get("offline") {
+            Some(v) => v.as_bool().ok_or(
+                "Expected the `offline` option to be a boolean, but it was a different type.",
+            )?,
+            None => false,
+        };
+
+        // If `capture_output` is set, anything the model prints is forwarded to carton as log messages
+        let capture_output = match opts.get("capture_output") {
+            Some(v) => v.as_bool().ok_or(
+                "Expected the `capture_output` option to be a boolean, but it was a different type.",
+            )?,
+            None => false,
+        };
+
+        // If the core library passed a seed, we seed python's RNGs (and numpy and torch if they're installed)
+        let seed = carton_runner_interface::seed(&opts);
+
         // Ensure we have a carton.lock file
         let lockfile_path = PathBuf::from(".carton/carton.lock");
         if !lockfile_path.exists(&fs).await {
@@ -68,6 +88,10 @@ where
             toml::from_slice(&fs.read(&lockfile_path).await.unwrap()).unwrap();
 
         let matching_entry = lockfile.entries.iter().find(|item| item.matches(&env));
+        if matching_entry.is_none() && offline {
+            return Err("A lockfile matching the current environment was not found and the `offline` option is set so dependencies cannot be fetched. Please generate a lockfile for this environment when packaging the model.".into());
+        }
+
         if matching_entry.is_none() {
             log::warn!("A lockfile matching the current environment was not found. It is highly recommended to generate a lockfile for all environments that you'll be running in. TODO: add link to docs. Attempting to fetch dependencies...");
             todo!();
@@ -82,15 +106,36 @@ where
         // Handles for our parallel copies
         let mut handles = Vec::new();
 
-        // Make sure we have all deps available
         let matching_entry = matching_entry.unwrap();
+
+        // In offline mode, check that every package we'd otherwise download is already installed before doing anything else
+        if offline {
+            let missing: Vec<_> = matching_entry
+                .locked_deps
+                .iter()
+                .filter_map(|dep| {
+                    dep.url
+                        .as_ref()
+                        .filter(|_| installed_wheel_path(&dep.sha256).is_none())
+                })
+                .map(|url| url.rsplit('/').next().unwrap_or(url.as_str()))
+                .collect();
+
+            if !missing.is_empty() {
+                return Err(format!("The `offline` option is set, but the following packages are not available locally and would need to be downloaded: {}. Load the model once with network access (without `offline`) to make them available.", missing.join(", ")));
+            }
+        }
+
+        // Make sure we have all deps available
         for dep in &matching_entry.locked_deps {
             if let Some(url) = &dep.url {
                 let url = url.clone();
                 let sha256 = dep.sha256.clone();
                 handles.push(tokio::spawn(async move {
                     // TODO: Make sure this is a PyPi URL
+                    // Note: in offline mode, we checked above that this is already installed so this won't download anything
                     install_wheel_and_make_available(&url, &sha256).await;
+                    Ok(())
                 }));
             } else if let Some(bundled_whl_path) = &dep.bundled_whl_path {
                 if PathBuf::from(bundled_whl_path).exists(&fs).await {
@@ -98,13 +143,23 @@ where
                     let local_path = bundled_wheels.path().join(&dep.sha256);
                     let mut target = tokio::fs::File::create(&local_path).await.unwrap();
                     let temp_packages_dir = temp_packages.path().to_owned();
+                    let bundled_whl_path = bundled_whl_path.clone();
 
                     handles.push(tokio::spawn(async move {
                         // Copy the lunchbox file to a local one
                         tokio::io::copy(&mut f, &mut target).await.unwrap();
 
                         // Unzip to our temp packages dir for this model
-                        extract_zip(&local_path, &temp_packages_dir).await;
+                        // The wheel comes from the carton so don't trust it (it may be a decompression bomb)
+                        extract_zip_with_limits(
+                            &local_path,
+                            &temp_packages_dir,
+                            ExtractLimits::default(),
+                        )
+                        .await
+                        .map_err(|e| {
+                            format!("Failed to extract the bundled wheel '{bundled_whl_path}': {e}")
+                        })
                     }));
                 } else {
                     return Err(format!("The .carton/carton.lock file references a file ({bundled_whl_path}) that does not exist. It is possible that the lockfile was added to version control but the referenced files were not. Please repackage the model and try again. TODO: link"));
@@ -114,7 +169,7 @@ where
 
         // Wait until all the copies and downloads are done
         for handle in handles {
-            handle.await.unwrap();
+            handle.await.unwrap()?;
         }
 
         // Add the temp packages to sys.path
@@ -234,6 +289,15 @@ where
 
         let model = tracing::info_span!("run_entrypoint").in_scope(|| {
             Python::with_gil(|py| {
+                if capture_output {
+                    crate::output_capture::capture_output(py).unwrap();
+                }
+
+                // Seed the RNGs before the model is created in case it samples anything while loading
+                if let Some(seed) = seed {
+                    seed_rngs(py, seed).unwrap();
+                }
+
                 // Import the module
                 let module = PyModule::import(py, module_name.as_str()).unwrap();
 
@@ -273,6 +337,33 @@ where
     }
 }
 
+fn seed_rngs(py: Python, seed: u64) -> PyResult<()> {
+    log::info!("Setting the random seed to {seed}");
+    let locals = PyDict::new(py);
+    locals.set_item("seed", seed)?;
+    py.run(
+        r#"
+import random
+random.seed(seed)
+
+try:
+    import numpy
+    # numpy only accepts 32 bit seeds
+    numpy.random.seed(seed % 2**32)
+except ImportError:
+    pass
+
+try:
+    import torch
+    torch.manual_seed(seed)
+except ImportError:
+    pass
+"#,
+        None,
+        Some(locals),
+    )
+}
+
 fn get_runner_opt_string(opt: &RunnerOpt) -> Option<&String> {
     if let RunnerOpt::String(item) = opt {
         Some(item)
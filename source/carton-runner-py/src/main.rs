@@ -19,20 +19,12 @@ use carton_runner_interface::{
     types::Tensor,
 };
 
+use carton_runner_py::{loader, packager::update_or_generate_lockfile, python_utils};
 use futures_util::{pin_mut, StreamExt};
-use packager::update_or_generate_lockfile;
-
-mod env;
-mod loader;
-mod model;
-mod packager;
-mod pip_utils;
-mod python_utils;
-mod wheel;
 
 // This is basically the expanded version of
 // #[pyo3_asyncio::tokio::main]
-// but modified to call `crate::python_utils::init();` before setting up python
+// but modified to call `python_utils::init();` before setting up python
 fn main() {
     async fn main() -> pyo3::PyResult<()> {
         main_inner().await;
@@ -40,7 +32,7 @@ fn main() {
     }
 
     // Setup the isolated python env
-    crate::python_utils::init();
+    python_utils::init();
 
     pyo3::prepare_freethreaded_python();
     let mut builder = pyo3_asyncio::tokio::re_exports::runtime::Builder::new_multi_thread();
@@ -65,7 +57,7 @@ async fn main_inner() {
         match req.data {
             RequestData::Load {
                 fs, runner_opts, ..
-            } => match crate::loader::load(
+            } => match loader::load(
                 server.get_readonly_filesystem(fs).await.unwrap(),
                 runner_opts,
             )
@@ -90,16 +82,16 @@ async fn main_inner() {
                 let fs = server.get_writable_filesystem(fs).await.unwrap();
 
                 // Update or generate a lockfile in the input dir
-                update_or_generate_lockfile(&fs, &input_path).await;
+                let response = match update_or_generate_lockfile(&fs, &input_path).await {
+                    // The dir that carton should pack is just the input path
+                    Ok(()) => ResponseData::Pack {
+                        output_path: input_path,
+                    },
+                    Err(e) => ResponseData::Error { e },
+                };
 
-                // The dir that carton should pack is just the input path
                 server
-                    .send_response_for_request(
-                        req_id,
-                        ResponseData::Pack {
-                            output_path: input_path,
-                        },
-                    )
+                    .send_response_for_request(req_id, response)
                     .await
                     .unwrap();
             }
@@ -121,15 +113,52 @@ async fn main_inner() {
                         .unwrap(),
                 }
             }
-            RequestData::InferWithTensors { tensors, streaming } => {
+            RequestData::InferWithTensors {
+                tensors,
+                streaming,
+                runner_opts,
+            } => {
+                // Other per-request options aren't currently exposed to python models
+                let mut max_time = None;
+                if let Some(runner_opts) = runner_opts {
+                    for (k, v) in runner_opts {
+                        match (k.as_str(), v.as_f64()) {
+                            ("max_time", Some(secs)) if secs >= 0.0 => {
+                                max_time = Some(std::time::Duration::from_secs_f64(secs))
+                            }
+                            _ => log::warn!("Ignoring unsupported per-request runner option `{k}`"),
+                        }
+                    }
+                }
+
                 // Call `model.infer_with_tensors`
                 let res = model.as_mut().unwrap().infer_with_tensors(tensors).await;
-                send_infer_response(&server, res, streaming, req_id, "infer_with_tensors").await;
+                send_infer_response(
+                    &server,
+                    res,
+                    streaming,
+                    req_id,
+                    "infer_with_tensors",
+                    max_time,
+                )
+                .await;
             }
             RequestData::InferWithHandle { handle, streaming } => {
                 // Call `model.infer_with_handle`
                 let res = model.as_mut().unwrap().infer_with_handle(handle).await;
-                send_infer_response(&server, res, streaming, req_id, "infer_with_handle").await;
+                send_infer_response(&server, res, streaming, req_id, "infer_with_handle", None)
+                    .await;
+            }
+
+            RequestData::DeviceMemoryUsage => {
+                // Python models can use any framework so we can't tell how much device memory they're using
+                server
+                    .send_response_for_request(
+                        req_id,
+                        ResponseData::DeviceMemoryUsage { memory: None },
+                    )
+                    .await
+                    .unwrap();
             }
         }
     }
@@ -144,20 +173,38 @@ fn transform_res(v: Result<HashMap<String, Tensor>, String>, method: &'static st
     }
 }
 
-/// A utility to send inference responses
+/// A utility to send inference responses.
+/// If `max_time` is set, we stop pulling values from the model once it has elapsed and respond with
+/// what we have so far (e.g. for latency-bounded generation)
 async fn send_infer_response(
     server: &Server,
     res: Result<impl futures::Stream<Item = Result<HashMap<String, Tensor>, String>>, String>,
     streaming: bool,
     req_id: u64,
     method: &'static str,
+    max_time: Option<std::time::Duration>,
 ) {
+    let deadline = max_time.map(|t| std::time::Instant::now() + t);
     match res {
         Ok(stream) => {
             pin_mut!(stream);
 
             let mut last_val = None;
             while let Some(item) = stream.next().await {
+                // Stop generating if the caller is no longer waiting for the result
+                if server.is_cancelled(req_id) {
+                    log::trace!("Request {req_id} was cancelled");
+                    return server
+                        .send_response_for_request(
+                            req_id,
+                            ResponseData::Error {
+                                e: "Request was cancelled".into(),
+                            },
+                        )
+                        .await
+                        .unwrap();
+                }
+
                 if streaming {
                     server
                         .send_streaming_response_for_request(
@@ -171,6 +218,11 @@ async fn send_infer_response(
                     // Not a streaming response so just store the values
                     last_val = Some(item);
                 }
+
+                if deadline.map_or(false, |d| std::time::Instant::now() >= d) {
+                    log::trace!("Request {req_id} reached its `max_time`");
+                    break;
+                }
             }
 
             if streaming {
@@ -21,7 +21,7 @@ use carton_runner_interface::{
 use carton_utils_py::tensor::PyStringArrayType;
 use futures_util::StreamExt;
 use numpy::{PyArrayDyn, ToPyArray};
-use pyo3::{FromPyObject, PyAny, PyErr, PyObject, Python, ToPyObject};
+use pyo3::{FromPyObject, PyAny, PyErr, PyObject, PyResult, Python, ToPyObject};
 
 enum SealImpl {
     /// Seal implemented in python
@@ -41,7 +41,7 @@ enum SealImpl {
 /// If both sets of methods are implemented, we just pass through to python for everything
 /// If only `infer_with_tensors` is implemented, we use our own seal implementation that just stores tensors in a map
 /// If only `infer_with_handle` and `seal` are implemented, we implement `infer_with_tensors` on top of those two
-pub(crate) struct Model {
+pub struct Model {
     // We store these to ensure that the relevant files don't get deleted while the model still exists
     _model_dir: tempfile::TempDir,
     _temp_packages: tempfile::TempDir,
@@ -206,16 +206,21 @@ enum PythonTensorType<'py> {
     U32(&'py PyArrayDyn<u32>),
     U64(&'py PyArrayDyn<u64>),
 
+    Complex64(&'py PyArrayDyn<num_complex::Complex<f32>>),
+    Complex128(&'py PyArrayDyn<num_complex::Complex<f64>>),
+
     String(PyStringArrayType<'py>),
 }
 
 trait ToTensorMap {
-    fn convert(self) -> HashMap<String, Tensor>;
+    fn convert(self) -> PyResult<HashMap<String, Tensor>>;
 }
 
 impl ToTensorMap for HashMap<String, PythonTensorType<'_>> {
-    fn convert(self) -> HashMap<String, Tensor> {
-        self.into_iter().map(|(k, v)| (k, v.into())).collect()
+    fn convert(self) -> PyResult<HashMap<String, Tensor>> {
+        self.into_iter()
+            .map(|(k, v)| Ok((k, v.try_into()?)))
+            .collect()
     }
 }
 
@@ -230,15 +235,17 @@ where
     storage.into()
 }
 
-impl From<PythonTensorType<'_>> for Tensor {
-    fn from(value: PythonTensorType) -> Self {
-        unsafe {
+impl TryFrom<PythonTensorType<'_>> for Tensor {
+    type Error = PyErr;
+
+    fn try_from(value: PythonTensorType) -> PyResult<Self> {
+        Ok(unsafe {
             match value {
                 PythonTensorType::Float(item) => convert_tensor(item),
                 PythonTensorType::Double(item) => convert_tensor(item),
                 PythonTensorType::String(item) => {
                     // TODO: this makes two copies... (one in to_ndarray and one in view().into())
-                    let arr = item.to_ndarray();
+                    let arr = item.to_ndarray()?;
                     let storage: TensorStorage<String> = arr.view().into();
                     storage.into()
                 }
@@ -250,8 +257,10 @@ impl From<PythonTensorType<'_>> for Tensor {
                 PythonTensorType::U16(item) => convert_tensor(item),
                 PythonTensorType::U32(item) => convert_tensor(item),
                 PythonTensorType::U64(item) => convert_tensor(item),
+                PythonTensorType::Complex64(item) => convert_tensor(item),
+                PythonTensorType::Complex128(item) => convert_tensor(item),
             }
-        }
+        })
     }
 }
 
@@ -274,7 +283,9 @@ fn to_numpy_arrays(tensors: HashMap<String, Tensor>) -> HashMap<String, pyo3::Py
                     Tensor::U16(item) => item.view().to_pyarray(py).to_object(py),
                     Tensor::U32(item) => item.view().to_pyarray(py).to_object(py),
                     Tensor::U64(item) => item.view().to_pyarray(py).to_object(py),
-                    Tensor::NestedTensor(_) => {
+                    Tensor::Complex64(item) => item.view().to_pyarray(py).to_object(py),
+                    Tensor::Complex128(item) => item.view().to_pyarray(py).to_object(py),
+                    Tensor::NestedTensor(_) | Tensor::LabeledNestedTensor { .. } => {
                         panic!("Nested tensors not implemented yet in the python runner")
                     }
                 };
@@ -295,13 +306,15 @@ async fn process_infer_output(
     let dict = Python::with_gil(|py| {
         // Try and extract a dictionary
         res.extract(py)
-            .map(|item: HashMap<String, PythonTensorType>| item.convert())
-            .map_err(pyerr_to_string_with_traceback)
+            .map(|item: HashMap<String, PythonTensorType>| {
+                item.convert().map_err(pyerr_to_string_with_traceback)
+            })
     });
 
     let stream = async_stream::try_stream! {
-        if dict.is_ok() {
+        if let Ok(dict) = dict {
             // We're returning a single response
+            // (Converting it can still fail, e.g. if a string array contains invalid unicode)
             yield dict?;
         } else {
             // Treat the response as an async iterator
@@ -310,7 +323,7 @@ async fn process_infer_output(
                 let dict = Python::with_gil(|py| {
                     // Try and extract a dictionary
                     item.extract(py)
-                        .map(|item: HashMap<String, PythonTensorType>| item.convert())
+                        .and_then(|item: HashMap<String, PythonTensorType>| item.convert())
                         .map_err(pyerr_to_string_with_traceback)
                 });
 
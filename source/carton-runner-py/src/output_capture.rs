@@ -0,0 +1,98 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Forward anything the model prints to stdout or stderr to carton as log messages. Log messages are
+//! forwarded from the runner to the process that loaded the model so this lets serving applications
+//! surface model output.
+//!
+//! Enabled by the `capture_output` runner option
+
+use std::sync::Mutex;
+
+use pyo3::prelude::*;
+
+/// The log target for a captured stream (e.g. `model_stdout`)
+fn log_target(stream: &str) -> String {
+    format!("model_{stream}")
+}
+
+/// A file-like object that logs each line written to it
+#[pyclass]
+struct OutputCapture {
+    /// `stdout` or `stderr`
+    stream: &'static str,
+
+    /// Anything written since the last newline
+    partial: Mutex<String>,
+}
+
+impl OutputCapture {
+    fn log_line(&self, line: &str) {
+        let target = log_target(self.stream);
+        if self.stream == "stderr" {
+            log::warn!(target: &target, "{line}");
+        } else {
+            log::info!(target: &target, "{line}");
+        }
+    }
+}
+
+#[pymethods]
+impl OutputCapture {
+    fn write(&self, data: &str) -> usize {
+        let mut partial = self.partial.lock().unwrap();
+        partial.push_str(data);
+
+        // Log all complete lines
+        if let Some(idx) = partial.rfind('\n') {
+            let rest = partial.split_off(idx + 1);
+            for line in partial.lines() {
+                self.log_line(line);
+            }
+            *partial = rest;
+        }
+
+        data.len()
+    }
+
+    fn flush(&self) {
+        let mut partial = self.partial.lock().unwrap();
+        if !partial.is_empty() {
+            self.log_line(&partial);
+            partial.clear();
+        }
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+/// Replace `sys.stdout` and `sys.stderr` with objects that forward output as log messages
+pub(crate) fn capture_output(py: Python) -> PyResult<()> {
+    let sys = py.import("sys")?;
+    for stream in ["stdout", "stderr"] {
+        let capture = Py::new(
+            py,
+            OutputCapture {
+                stream,
+                partial: Default::default(),
+            },
+        )?;
+
+        sys.setattr(stream, capture)?;
+    }
+
+    Ok(())
+}
@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use carton_runner_interface::slowlog::slowlog;
+use std::collections::HashMap;
+
+use carton_runner_interface::{slowlog::slowlog, types::RunnerOpt};
 use lunchbox::path::LunchboxPathUtils;
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
@@ -93,7 +95,8 @@ impl LockfileEntry {
 
 /// Generates a lockfile in a python project based on the requirements.txt
 /// Avoids unnecessarily regenerating
-pub async fn update_or_generate_lockfile<F, P>(fs: &F, code_dir: P)
+/// Returns an error message suitable for model authors if this fails
+pub async fn update_or_generate_lockfile<F, P>(fs: &F, code_dir: P) -> Result<(), String>
 where
     F: lunchbox::WritableFileSystem + Sync,
     F::FileType: lunchbox::types::WritableFile + Unpin,
@@ -103,7 +106,11 @@ where
 
     // Load the requirements.txt file
     let requirements_file_path = code_dir.join("requirements.txt");
-    let requirements_file = fs.read(&requirements_file_path).await.unwrap();
+    let requirements_file = fs.read(&requirements_file_path).await.map_err(|e| {
+        format!(
+            "Python models must have a `requirements.txt` file at {requirements_file_path}: {e}"
+        )
+    })?;
 
     // Generate a hash of the requirements.txt
     let mut hasher = Sha256::new();
@@ -122,8 +129,14 @@ where
     let lockfile_path = code_dir.join(".carton/carton.lock");
     if lockfile_path.exists(fs).await {
         // Load the file
-        let old_lockfile: CartonLock =
-            toml::from_slice(&fs.read(&lockfile_path).await.unwrap()).unwrap();
+        let old_lockfile: CartonLock = fs
+            .read(&lockfile_path)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|data| toml::from_slice(&data).map_err(|e| e.to_string()))
+            .map_err(|e| {
+                format!("Failed to load the existing lockfile at {lockfile_path}: {e}. Deleting it will regenerate it.")
+            })?;
 
         if old_lockfile.orig_deps_hash != lockfile.orig_deps_hash {
             // If orig_deps_hash doesn't match the one we just generated, we have to start from scratch
@@ -133,7 +146,7 @@ where
 
             // If we have any matching entries and orig_deps_hash matches the one we just generated, we don't need to do anything else
             if has_matching_entries {
-                return;
+                return Ok(());
             }
 
             // We can start with the old lockfile
@@ -141,7 +154,9 @@ where
         }
     }
 
-    let locked_deps = get_pip_deps_report(String::from_utf8(requirements_file).unwrap()).await;
+    let requirements_file = String::from_utf8(requirements_file)
+        .map_err(|_| format!("{requirements_file_path} is not valid UTF-8"))?;
+    let locked_deps = get_pip_deps_report(requirements_file).await?;
 
     // Utils
     let is_pypi = |item: &PipInstallInfo| {
@@ -168,7 +183,7 @@ where
 
     // Wheels other than pypi ones will be stored in the carton (including the wheels we're going to build from source)
     // .carton/bundled_wheels/{sha256}/{wheel_name}.whl
-    let client = reqwest::Client::new();
+    let client = carton_utils::download::client_builder().build().unwrap();
     let other_wheels = locked_deps
         .install
         .iter()
@@ -195,8 +210,16 @@ where
             let mut outfile = fs.create(&bundled_path).await.unwrap();
 
             // Download and copy to the target file
-            let mut res = client.get(&item.download_info.url).send().await.unwrap();
-            while let Some(chunk) = res.chunk().await.unwrap() {
+            let download_err = |e: reqwest::Error| {
+                format!("Failed to download '{}': {e}", &item.download_info.url)
+            };
+            let mut res = client
+                .get(&item.download_info.url)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map_err(download_err)?;
+            while let Some(chunk) = res.chunk().await.map_err(download_err)? {
                 tokio::io::copy(&mut chunk.as_ref(), &mut outfile)
                     .await
                     .unwrap();
@@ -259,10 +282,10 @@ where
 
         if !build_success {
             // Don't delete the log dir if it failed
-            panic!(
+            return Err(format!(
                 "Failed to build wheels for dependencies! See the logs in {:?}",
                 log_dir.into_path()
-            );
+            ));
         }
     }
 
@@ -327,12 +350,100 @@ where
     fs.write(lockfile_path, header.to_string() + &serialized)
         .await
         .unwrap();
+
+    Ok(())
+}
+
+/// Create a model directory in the layout the Python runner expects so it can be packed:
+///
+/// ```text
+/// output_dir/
+///     {module}.py           (a copy of `entrypoint_file`)
+///     requirements.txt      (one line per item in `requirements`)
+///     .carton/carton.lock   (generated with `update_or_generate_lockfile`)
+/// ```
+///
+/// `entrypoint_fn` is the function in `entrypoint_file` that returns the model. Returns the runner options
+/// to pack the directory with (`entrypoint_package` and `entrypoint_fn`).
+/// Returns an error message suitable for model authors if this fails
+pub async fn scaffold_model_dir(
+    output_dir: &std::path::Path,
+    entrypoint_file: &std::path::Path,
+    entrypoint_fn: &str,
+    requirements: &[String],
+) -> Result<HashMap<String, RunnerOpt>, String> {
+    // The entrypoint is imported as a module of the model dir so its name must be a valid identifier
+    let entrypoint_package = match (
+        entrypoint_file.extension().and_then(|v| v.to_str()),
+        entrypoint_file.file_stem().and_then(|v| v.to_str()),
+    ) {
+        (Some("py"), Some(stem)) if is_python_identifier(stem) => stem.to_owned(),
+        _ => {
+            return Err(format!(
+                "The entrypoint must be a `.py` file with a name that's a valid python module name, but got {entrypoint_file:?}"
+            ))
+        }
+    };
+
+    if !is_python_identifier(entrypoint_fn) {
+        return Err(format!(
+            "`{entrypoint_fn}` is not a valid python function name"
+        ));
+    }
+
+    tokio::fs::create_dir_all(output_dir)
+        .await
+        .map_err(|e| format!("Failed to create {output_dir:?}: {e}"))?;
+
+    tokio::fs::copy(
+        entrypoint_file,
+        output_dir.join(format!("{entrypoint_package}.py")),
+    )
+    .await
+    .map_err(|e| format!("Failed to copy the entrypoint at {entrypoint_file:?}: {e}"))?;
+
+    let mut requirements_txt = requirements.join("\n");
+    requirements_txt.push('\n');
+    tokio::fs::write(output_dir.join("requirements.txt"), requirements_txt)
+        .await
+        .map_err(|e| format!("Failed to write requirements.txt: {e}"))?;
+
+    let output_dir_str = output_dir
+        .to_str()
+        .ok_or_else(|| format!("{output_dir:?} is not valid UTF-8"))?;
+    let fs = lunchbox::LocalFS::new().map_err(|e| e.to_string())?;
+    update_or_generate_lockfile(&fs, output_dir_str).await?;
+
+    Ok([
+        (
+            "entrypoint_package".to_owned(),
+            RunnerOpt::String(entrypoint_package),
+        ),
+        (
+            "entrypoint_fn".to_owned(),
+            RunnerOpt::String(entrypoint_fn.to_owned()),
+        ),
+    ]
+    .into())
+}
+
+/// Whether `name` can be used as a python module or function name (ignoring keywords)
+fn is_python_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{update_or_generate_lockfile, CartonLock};
+    use super::{
+        is_python_identifier, scaffold_model_dir, update_or_generate_lockfile, CartonLock,
+    };
 
     #[tokio::test]
     async fn test_generate_lockfile() {
@@ -342,7 +453,9 @@ mod tests {
         std::fs::write(&requirements_file_path, "xgboost==1.7.3").unwrap();
 
         let fs = lunchbox::LocalFS::new().unwrap();
-        update_or_generate_lockfile(&fs, tempdir.path().to_str().unwrap()).await;
+        update_or_generate_lockfile(&fs, tempdir.path().to_str().unwrap())
+            .await
+            .unwrap();
 
         let lockfile: CartonLock = toml::from_slice(
             &tokio::fs::read(&tempdir.path().join(".carton/carton.lock"))
@@ -367,4 +480,59 @@ mod tests {
             .unwrap()
             .contains("numpy")));
     }
+
+    #[tokio::test]
+    async fn test_missing_requirements() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        // This should return an error instead of panicking
+        let fs = lunchbox::LocalFS::new().unwrap();
+        let err = update_or_generate_lockfile(&fs, tempdir.path().to_str().unwrap())
+            .await
+            .unwrap_err();
+        assert!(err.contains("requirements.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_scaffold_model_dir() {
+        assert!(is_python_identifier("main"));
+        assert!(is_python_identifier("_get_model2"));
+        assert!(!is_python_identifier("2model"));
+        assert!(!is_python_identifier("my-model"));
+        assert!(!is_python_identifier(""));
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let entrypoint = tempdir.path().join("main.py");
+        std::fs::write(&entrypoint, "def get_model():\n    pass\n").unwrap();
+
+        let output_dir = tempdir.path().join("model");
+        let opts = scaffold_model_dir(
+            &output_dir,
+            &entrypoint,
+            "get_model",
+            &["xgboost==1.7.3".to_owned()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(opts["entrypoint_package"].as_str(), Some("main"));
+        assert_eq!(opts["entrypoint_fn"].as_str(), Some("get_model"));
+        assert!(output_dir.join("main.py").exists());
+        assert_eq!(
+            std::fs::read_to_string(output_dir.join("requirements.txt")).unwrap(),
+            "xgboost==1.7.3\n"
+        );
+        assert!(output_dir.join(".carton/carton.lock").exists());
+
+        // Invalid entrypoints are rejected
+        let err = scaffold_model_dir(
+            &output_dir,
+            &tempdir.path().join("my-model.py"),
+            "get_model",
+            &[],
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("module name"));
+    }
 }
@@ -65,7 +65,9 @@ async fn ensure_has_pip() {
 /// Effectively run
 /// `python3 -m pip install --dry-run --ignore-installed --report {output_file} -r {requirements_file_path}`
 /// and load the output
-pub(crate) async fn get_pip_deps_report(requirements_file_contents: String) -> PipReport {
+pub(crate) async fn get_pip_deps_report(
+    requirements_file_contents: String,
+) -> Result<PipReport, String> {
     // Make sure we have pip 23.0
     ensure_has_pip().await;
 
@@ -114,15 +116,15 @@ pub(crate) async fn get_pip_deps_report(requirements_file_contents: String) -> P
 
     if !success {
         // Don't delete the log dir if it failed
-        panic!(
+        return Err(format!(
             "Getting dependencies with pip failed! See the logs in {:?}",
             log_dir.into_path()
-        );
+        ));
     }
 
     // Load the deps
     let locked_deps_data = tokio::fs::read(&output_file_path).await.unwrap();
-    serde_json::from_slice(&locked_deps_data).unwrap()
+    Ok(serde_json::from_slice(&locked_deps_data).unwrap())
 }
 
 #[cfg(test)]
@@ -138,7 +140,9 @@ mod tests {
     async fn test_get_lightgbm_deps() {
         let requirements_file_contents = "lightgbm==3.3.5".to_owned();
 
-        let report = get_pip_deps_report(requirements_file_contents).await;
+        let report = get_pip_deps_report(requirements_file_contents)
+            .await
+            .unwrap();
 
         assert!(report
             .install
@@ -19,7 +19,7 @@ use std::{ffi::OsStr, path::Path, sync::Once};
 /// Initialize python with an isolated environment
 /// This must be called before attempting to use python (otherwise any PyO3 code will panic)
 /// (safe to call multiple times)
-pub(crate) fn init() {
+pub fn init() {
     static INIT: Once = Once::new();
     INIT.call_once(|| init_inner())
 }
@@ -36,6 +36,12 @@ lazy_static! {
     static ref CLIENT: reqwest::Client = reqwest::Client::new();
 }
 
+/// Returns the path to add to `sys.path` for a wheel if it's already installed
+pub fn installed_wheel_path(sha256: &str) -> Option<PathBuf> {
+    let target_dir = PACKAGE_BASE_DIR.join(sha256);
+    target_dir.exists().then_some(target_dir)
+}
+
 /// Installs a wheel (if not already installed) and adds it to `sys.path`
 pub async fn install_wheel_and_make_available(url: &str, sha256: &str) {
     let path = install_wheel(url, sha256).await;
@@ -47,13 +53,14 @@ pub async fn install_wheel_and_make_available(url: &str, sha256: &str) {
 /// See the wheel spec at https://packaging.python.org/en/latest/specifications/binary-distribution-format/
 /// There's a bit more to it, but a basic install just unzips the file into the target directory
 pub async fn install_wheel(url: &str, sha256: &str) -> PathBuf {
-    let target_dir = PACKAGE_BASE_DIR.join(sha256);
-    if target_dir.exists() {
+    if let Some(target_dir) = installed_wheel_path(sha256) {
         // This already exists
         // TODO: we should probably also do some locking to avoid wasted parallel installations
         return target_dir;
     }
 
+    let target_dir = PACKAGE_BASE_DIR.join(sha256);
+
     // Create a temp dir
     let tempdir = tempfile::tempdir().unwrap();
     let download_path = tempdir.path().join("download");
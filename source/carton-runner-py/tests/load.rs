@@ -187,7 +187,7 @@ def get_model(an_example_custom_option, another_example_custom_option):
         model_dir.path().to_str().unwrap().to_owned(),
         PackOpts {
             info: info.clone(),
-            linked_files: None,
+            ..Default::default()
         },
         LoadOpts::default(),
     )
@@ -211,7 +211,7 @@ def get_model(an_example_custom_option, another_example_custom_option):
         model_dir.path().to_str().unwrap().to_owned(),
         PackOpts {
             info,
-            linked_files: None,
+            ..Default::default()
         },
     )
     .await
@@ -16,7 +16,10 @@ use std::{collections::HashMap, path::Path};
 
 use async_trait::async_trait;
 use carton::info::LinkedFile;
-use carton_runner_interface::{slowlog::slowlog, types::Tensor};
+use carton_runner_interface::{
+    slowlog::slowlog,
+    types::{RunnerOpt, Tensor},
+};
 use lunchbox::{types::ReadableFile, ReadableFileSystem};
 use masked_language::CartonMaskedLanguageConfig;
 use qa::CartonQAConfig;
@@ -64,6 +67,22 @@ pub trait ModelFromConfig {
 
 pub trait Model {
     fn infer(&self, tensors: HashMap<String, Tensor>) -> HashMap<String, Tensor>;
+
+    /// Run inference with per-request runner options.
+    /// By default, models don't support any options so we warn and ignore them
+    fn infer_with_opts(
+        &self,
+        tensors: HashMap<String, Tensor>,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> HashMap<String, Tensor> {
+        if let Some(runner_opts) = runner_opts {
+            for k in runner_opts.keys() {
+                log::warn!("Ignoring unsupported per-request runner option `{k}`");
+            }
+        }
+
+        self.infer(tensors)
+    }
 }
 
 pub(crate) async fn copy_to_local<F>(fs: &F, base: &Path, path: &str)
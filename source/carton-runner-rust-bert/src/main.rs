@@ -98,9 +98,15 @@ async fn main() {
 
                 seal_counter += 1;
             }
-            RequestData::InferWithTensors { tensors, .. } => {
+            RequestData::InferWithTensors {
+                tensors,
+                runner_opts,
+                ..
+            } => {
                 // TODO: error handling
-                let result = model.as_ref().map(|m| m.infer(tensors));
+                let result = model
+                    .as_ref()
+                    .map(|m| m.infer_with_opts(tensors, runner_opts));
 
                 server
                     .send_response_for_request(
@@ -128,6 +134,17 @@ async fn main() {
                     .await
                     .unwrap();
             }
+
+            RequestData::DeviceMemoryUsage => {
+                // TODO: report the memory used by the underlying torch model
+                server
+                    .send_response_for_request(
+                        req_id,
+                        ResponseData::DeviceMemoryUsage { memory: None },
+                    )
+                    .await
+                    .unwrap();
+            }
         }
     }
 }
@@ -117,7 +117,8 @@ impl Model for CartonMaskedLanguageModel {
                     .map(|v| (*v) as _)
                     .chain([max_tokens as _])
                     .collect(),
-            );
+            )
+            .unwrap();
             let mut tokens_output_view = tokens_output_tensor.view_mut();
 
             // Create an output scores tensor with shape [input_shape, max_tokens]
@@ -128,7 +129,8 @@ impl Model for CartonMaskedLanguageModel {
                     .map(|v| (*v) as _)
                     .chain([max_tokens as _])
                     .collect(),
-            );
+            )
+            .unwrap();
             let mut scores_output_view = scores_output_tensor.view_mut();
 
             // Come up with candidate tokens and store in the output
@@ -216,24 +218,24 @@ pub mod pack {
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/bert-base-uncased/resolve/1dbc166cf8765166998eff31ade2eb64c8a40076/rust_model.ot".into()],
-                    sha256: "afd9aa425fd45c5655d3d43a0d041f9b76729bf475d6c017a0e9304a38f89972".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/bert-base-uncased/resolve/1dbc166cf8765166998eff31ade2eb64c8a40076/rust_model.ot".into()],
+                    "afd9aa425fd45c5655d3d43a0d041f9b76729bf475d6c017a0e9304a38f89972".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/bert-base-uncased/resolve/1dbc166cf8765166998eff31ade2eb64c8a40076/config.json".into()],
-                    sha256: "7160e1553ad2ca51d8c1cb066be533db31826e12d173824c1bb0cb1a4f187d20".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/bert-base-uncased/resolve/1dbc166cf8765166998eff31ade2eb64c8a40076/config.json".into()],
+                    "7160e1553ad2ca51d8c1cb066be533db31826e12d173824c1bb0cb1a4f187d20".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/bert-base-uncased/resolve/1dbc166cf8765166998eff31ade2eb64c8a40076/vocab.txt".into()],
-                    sha256: "07eced375cec144d27c900241f3e339478dec958f92fddbc551f295c992038a3".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/bert-base-uncased/resolve/1dbc166cf8765166998eff31ade2eb64c8a40076/vocab.txt".into()],
+                    "07eced375cec144d27c900241f3e339478dec958f92fddbc551f295c992038a3".into(),
+                ),
                 model_dir.join("vocab.txt"),
             ),
         );
@@ -256,14 +258,18 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into())]),
                     description: Some("The sentences to fill `[MASK]` tokens in.".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "max_tokens".into(),
                     dtype: DataType::U32,
                     shape: Shape::Shape(vec![]),
                     description: Some("The maximum number of tokens to predict for each mask. Optional, defaults to 1.".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: true,
+                    default: None
                 },
             ]),
             outputs: Some(vec![
@@ -272,14 +278,18 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into()), Dimension::Any]),
                     description: Some("The predicted tokens for each input sentence. This will have shape `[N, max_tokens]`, but some cells may be empty.".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "scores".into(),
                     dtype: DataType::Float,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into()), Dimension::Any]),
                     description: Some("The scores for each predicted token. This will have shape `[N, max_tokens]`, but some cells may have a score of zero.".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -310,6 +320,7 @@ pub mod pack {
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
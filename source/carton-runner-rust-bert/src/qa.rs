@@ -101,7 +101,8 @@ impl Model for CartonQAModel {
 
             // Create an output tensor with the same shape
             let mut output_tensor =
-                TensorStorage::new(question_tensor.shape().iter().map(|v| (*v) as _).collect());
+                TensorStorage::new(question_tensor.shape().iter().map(|v| (*v) as _).collect())
+                    .unwrap();
             let mut output_view = output_tensor.view_mut();
             let sliced_output_view = output_view.as_slice_mut().unwrap();
 
@@ -173,24 +174,24 @@ pub mod pack {
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/distilbert-base-cased-distilled-squad/resolve/50ba811384f02cb99cdabe5cdc02f7ddc4f69e10/rust_model.ot".into()],
-                    sha256: "8a9f9b2f153ac9ff230aca4548fa3286be9d2f9ea4eb7e9169665b1a8e983f44".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/distilbert-base-cased-distilled-squad/resolve/50ba811384f02cb99cdabe5cdc02f7ddc4f69e10/rust_model.ot".into()],
+                    "8a9f9b2f153ac9ff230aca4548fa3286be9d2f9ea4eb7e9169665b1a8e983f44".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/distilbert-base-cased-distilled-squad/resolve/50ba811384f02cb99cdabe5cdc02f7ddc4f69e10/config.json".into()],
-                    sha256: "0b5cb15ec08645604ef7085acfaf9c4131158ac22207a76634574cf2771b1515".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/distilbert-base-cased-distilled-squad/resolve/50ba811384f02cb99cdabe5cdc02f7ddc4f69e10/config.json".into()],
+                    "0b5cb15ec08645604ef7085acfaf9c4131158ac22207a76634574cf2771b1515".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/distilbert-base-cased-distilled-squad/resolve/50ba811384f02cb99cdabe5cdc02f7ddc4f69e10/vocab.txt".into()],
-                    sha256: "eeaa9875b23b04b4c54ef759d03db9d1ba1554838f8fb26c5d96fa551df93d02".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/distilbert-base-cased-distilled-squad/resolve/50ba811384f02cb99cdabe5cdc02f7ddc4f69e10/vocab.txt".into()],
+                    "eeaa9875b23b04b4c54ef759d03db9d1ba1554838f8fb26c5d96fa551df93d02".into(),
+                ),
                 model_dir.join("vocab.txt"),
             ),
         );
@@ -213,14 +214,18 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("Questions for the model to answer".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "context".into(),
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("Context for each of the questions. In the same shape as `question`".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             outputs: Some(vec![
@@ -229,7 +234,9 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("Answers to the questions in the same shape as `question`".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -260,6 +267,7 @@ pub mod pack {
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
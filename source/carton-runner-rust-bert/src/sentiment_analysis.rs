@@ -101,7 +101,8 @@ impl Model for CartonSentimentAnalysisModel {
 
             // Create an output tensor with the same shape
             let mut output_tensor =
-                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect());
+                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect())
+                    .unwrap();
             let mut output_view = output_tensor.view_mut();
             let sliced_output_view = output_view.as_slice_mut().unwrap();
 
@@ -166,24 +167,24 @@ pub mod pack {
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/distilbert-base-uncased-finetuned-sst-2-english/resolve/3d65bad49c7ba6f71920504507a8927f4b9db6c0/rust_model.ot".into()],
-                    sha256: "9db97da21b97a5e6db1212ce6a810a0c5e22c99daefe3355bae2117f78a0abb9".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/distilbert-base-uncased-finetuned-sst-2-english/resolve/3d65bad49c7ba6f71920504507a8927f4b9db6c0/rust_model.ot".into()],
+                    "9db97da21b97a5e6db1212ce6a810a0c5e22c99daefe3355bae2117f78a0abb9".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/distilbert-base-uncased-finetuned-sst-2-english/resolve/3d65bad49c7ba6f71920504507a8927f4b9db6c0/config.json".into()],
-                    sha256: "582122c8f414793d131e10022ce9ba04e3811a9da6389137ee2f18665b4f4d15".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/distilbert-base-uncased-finetuned-sst-2-english/resolve/3d65bad49c7ba6f71920504507a8927f4b9db6c0/config.json".into()],
+                    "582122c8f414793d131e10022ce9ba04e3811a9da6389137ee2f18665b4f4d15".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/distilbert-base-uncased-finetuned-sst-2-english/resolve/3d65bad49c7ba6f71920504507a8927f4b9db6c0/vocab.txt".into()],
-                    sha256: "07eced375cec144d27c900241f3e339478dec958f92fddbc551f295c992038a3".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/distilbert-base-uncased-finetuned-sst-2-english/resolve/3d65bad49c7ba6f71920504507a8927f4b9db6c0/vocab.txt".into()],
+                    "07eced375cec144d27c900241f3e339478dec958f92fddbc551f295c992038a3".into(),
+                ),
                 model_dir.join("vocab.txt"),
             ),
         );
@@ -206,16 +207,20 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("The strings to analyze the sentiment of".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             outputs: Some(vec![
                 TensorSpec {
                     name: "scores".into(),
-                    dtype: DataType::String,
+                    dtype: DataType::Float,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("Scores between -1 and 1 for each element of `input`. Negative scores correspond to a negative sentiment.".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -245,6 +250,7 @@ pub mod pack {
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
@@ -96,7 +96,8 @@ impl Model for CartonSummarizationModel {
 
             // Create an output tensor with the same shape
             let mut output_tensor =
-                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect());
+                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect())
+                    .unwrap();
             let mut output_view = output_tensor.view_mut();
             let sliced_output_view = output_view.as_slice_mut().unwrap();
 
@@ -167,31 +168,31 @@ Since Hubble’s discovery of Earendel, Webb has detected other very distant sta
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/rust_model.ot".into()],
-                    sha256: "cd0d1586babffa4e90ca71e230290b55b8ebf634319a1c4200c8506ddbae0ab0".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/rust_model.ot".into()],
+                    "cd0d1586babffa4e90ca71e230290b55b8ebf634319a1c4200c8506ddbae0ab0".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/config.json".into()],
-                    sha256: "c6cb642aec929b65f514ee0ec7c04f9de19f705c143491577ecd8b7cc923c6ed".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/config.json".into()],
+                    "c6cb642aec929b65f514ee0ec7c04f9de19f705c143491577ecd8b7cc923c6ed".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/vocab.json".into()],
-                    sha256: "9e7f63c2d15d666b52e21d250d2e513b87c9b713cfa6987a82ed89e5e6e50655".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/vocab.json".into()],
+                    "9e7f63c2d15d666b52e21d250d2e513b87c9b713cfa6987a82ed89e5e6e50655".into(),
+                ),
                 model_dir.join("vocab.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/merges.txt".into()],
-                    sha256: "1ce1664773c50f3e0cc8842619a93edc4624525b728b188a9e0be33b7726adc5".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-cnn/resolve/3d224934c6541b2b9147e023c2f6f6fe49bd27e1/merges.txt".into()],
+                    "1ce1664773c50f3e0cc8842619a93edc4624525b728b188a9e0be33b7726adc5".into(),
+                ),
                 model_dir.join("merges.txt"),
             ),
         );
@@ -219,7 +220,9 @@ Since Hubble’s discovery of Earendel, Webb has detected other very distant sta
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("The strings to summarize".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             outputs: Some(vec![
@@ -228,7 +231,9 @@ Since Hubble’s discovery of Earendel, Webb has detected other very distant sta
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("The summarized strings in the same shape as `input`".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -258,6 +263,7 @@ Since Hubble’s discovery of Earendel, Webb has detected other very distant sta
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
@@ -15,7 +15,7 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use carton_runner_interface::types::{Tensor, TensorStorage};
+use carton_runner_interface::types::{RunnerOpt, Tensor, TensorStorage};
 use lunchbox::{types::ReadableFile, ReadableFileSystem};
 use rust_bert::{
     pipelines::{
@@ -88,6 +88,24 @@ impl ModelFromConfig for CartonTextGenerationConfig {
 
 impl Model for CartonTextGenerationModel {
     fn infer(&self, tensors: HashMap<String, Tensor>) -> HashMap<String, Tensor> {
+        self.infer_with_opts(tensors, None)
+    }
+
+    /// Supported per-request options:
+    /// - `prefix` (string): a prefix to prepend to each input before generation
+    fn infer_with_opts(
+        &self,
+        tensors: HashMap<String, Tensor>,
+        runner_opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> HashMap<String, Tensor> {
+        let mut prefix = None;
+        for (k, v) in runner_opts.iter().flatten() {
+            match (k.as_str(), v.as_str()) {
+                ("prefix", Some(v)) => prefix = Some(v),
+                _ => log::warn!("Ignoring unsupported per-request runner option `{k}`: {v:?}"),
+            }
+        }
+
         // TODO: don't unwrap
         let input_tensor = tensors.get("input").unwrap();
 
@@ -96,12 +114,15 @@ impl Model for CartonTextGenerationModel {
 
             // Create an output tensor with the same shape
             let mut output_tensor =
-                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect());
+                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect())
+                    .unwrap();
             let mut output_view = output_tensor.view_mut();
             let sliced_output_view = output_view.as_slice_mut().unwrap();
 
             // Generate text and store in the output
-            let generated_text = self.model.generate(input_tensor.as_slice().unwrap(), None);
+            let generated_text = self
+                .model
+                .generate(input_tensor.as_slice().unwrap(), prefix);
             sliced_output_view.clone_from_slice(&generated_text);
 
             let mut out = HashMap::new();
@@ -147,31 +168,31 @@ pub mod pack {
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/rust_model.ot".into()],
-                    sha256: "064e9fde8e3a539c41b186a6ca94e6fb7c6520f49f903fb236f6e89912fedd32".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/rust_model.ot".into()],
+                    "064e9fde8e3a539c41b186a6ca94e6fb7c6520f49f903fb236f6e89912fedd32".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/config.json".into()],
-                    sha256: "ef1a44d889ad1a0acc7731c78134f1b87d2d222f110e97dd10fd4117331caf22".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/config.json".into()],
+                    "ef1a44d889ad1a0acc7731c78134f1b87d2d222f110e97dd10fd4117331caf22".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/vocab.json".into()],
-                    sha256: "196139668be63f3b5d6574427317ae82f612a97c5d1cdaf36ed2256dbf636783".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/vocab.json".into()],
+                    "196139668be63f3b5d6574427317ae82f612a97c5d1cdaf36ed2256dbf636783".into(),
+                ),
                 model_dir.join("vocab.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/merges.txt".into()],
-                    sha256: "1ce1664773c50f3e0cc8842619a93edc4624525b728b188a9e0be33b7726adc5".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/gpt2-medium/resolve/f65d4965d1221eff2bcf34f53a2ba12120e18f24/merges.txt".into()],
+                    "1ce1664773c50f3e0cc8842619a93edc4624525b728b188a9e0be33b7726adc5".into(),
+                ),
                 model_dir.join("merges.txt"),
             ),
         );
@@ -199,7 +220,9 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("The prompts to pass to the model".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             outputs: Some(vec![
@@ -208,7 +231,9 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Symbol("input_shape".into()),
                     description: Some("The continued strings in the same shape as `input`".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -238,6 +263,7 @@ pub mod pack {
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
@@ -102,7 +102,8 @@ impl Model for CartonTranslationModel {
         if let Tensor::String(input_tensor) = input_tensor {
             let input_tensor = input_tensor.view();
             let mut output_tensor =
-                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect());
+                TensorStorage::new(input_tensor.shape().iter().map(|v| (*v) as _).collect())
+                    .unwrap();
             let mut output_view = output_tensor.view_mut();
 
             if let Tensor::String(source_language) = source_language {
@@ -196,31 +197,31 @@ pub mod pack {
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/rust_model.ot".into()],
-                    sha256: "f170f6a277d00b20144fa6dac6ecd781c5a5e66844c022244437dd2da3a83655".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/rust_model.ot".into()],
+                    "f170f6a277d00b20144fa6dac6ecd781c5a5e66844c022244437dd2da3a83655".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/config.json".into()],
-                    sha256: "df0ae43e4e4b0d7e3c97b7f447857a70ef6b6a2aa1f145cedbcc730d95f67134".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/config.json".into()],
+                    "df0ae43e4e4b0d7e3c97b7f447857a70ef6b6a2aa1f145cedbcc730d95f67134".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/vocab.json".into()],
-                    sha256: "b6e77e474aeea8f441363aca7614317c06381f3eacfe10fb9856d5081d1074cc".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/vocab.json".into()],
+                    "b6e77e474aeea8f441363aca7614317c06381f3eacfe10fb9856d5081d1074cc".into(),
+                ),
                 model_dir.join("vocab.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/sentencepiece.bpe.model".into()],
-                    sha256: "d8f7c76ed2a5e0822be39f0a4f95a55eb19c78f4593ce609e2edbc2aea4d380a".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/m2m100_418M/resolve/a84767a43c9159c5c15eb3964dce2179684647f6/sentencepiece.bpe.model".into()],
+                    "d8f7c76ed2a5e0822be39f0a4f95a55eb19c78f4593ce609e2edbc2aea4d380a".into(),
+                ),
                 model_dir.join("sentencepiece.bpe.model"),
             ),
         );
@@ -248,21 +249,27 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into()), Dimension::Any]),
                     description: Some("The strings to translate as batches grouped by language".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "source_language".into(),
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into())]),
                     description: Some("The source language (or empty string) for every batch item".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "target_language".into(),
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into())]),
                     description: Some("The target language for every batch item".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 }
             ]),
             outputs: Some(vec![
@@ -271,7 +278,9 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into()), Dimension::Any]),
                     description: Some("The translated strings in the same shape as `input`".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -303,6 +312,7 @@ pub mod pack {
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
@@ -96,6 +96,7 @@ impl Model for CartonZeroShotModel {
         let candidate_labels = tensors.get("candidate_labels").unwrap();
         let template = tensors.get("template");
         let max_length = tensors.get("max_length");
+        let multilabel = tensors.get("multilabel");
 
         // Get all of them as string tensors
         if let Tensor::String(input_tensor) = input_tensor {
@@ -104,6 +105,11 @@ impl Model for CartonZeroShotModel {
             if let Tensor::String(candidate_labels) = candidate_labels {
                 let candidate_labels = candidate_labels.view();
 
+                // Otherwise, we'd produce an output with a zero-width dimension
+                if candidate_labels.is_empty() {
+                    panic!("Tensor `candidate_labels` must contain at least one label")
+                }
+
                 // Create an output tensor with the appropriate shape (input shape with an extra dimension)
                 let mut output_tensor = TensorStorage::new(
                     input_tensor
@@ -112,7 +118,8 @@ impl Model for CartonZeroShotModel {
                         .chain(&[candidate_labels.len()])
                         .map(|v| (*v) as _)
                         .collect(),
-                );
+                )
+                .unwrap();
 
                 // Reshape to [input_tensor.len(), candidate_labels.len()]
                 let mut output_view = output_tensor
@@ -144,6 +151,14 @@ impl Model for CartonZeroShotModel {
                     }
                 });
 
+                let multilabel = multilabel.map_or(true, |t| {
+                    if let Tensor::U8(t) = t {
+                        *t.view().first().unwrap() != 0
+                    } else {
+                        panic!("Tensor `multilabel` exists, but did not contain u8s")
+                    }
+                });
+
                 let predicted = self
                     .model
                     .predict_multilabel(
@@ -174,6 +189,19 @@ impl Model for CartonZeroShotModel {
                     }
                 }
 
+                if !multilabel {
+                    // Single-label classification: exactly one label is true for each input so we
+                    // normalize the scores for each input into a probability distribution.
+                    // Note: rust-bert's `predict` only returns the top label (not the whole distribution)
+                    // so we normalize the per-label scores instead
+                    for mut row in output_view.rows_mut() {
+                        let total = row.sum();
+                        if total > 0.0 {
+                            row.mapv_inplace(|v| v / total);
+                        }
+                    }
+                }
+
                 let mut out = HashMap::new();
                 out.insert("scores".to_owned(), Tensor::Float(output_tensor));
                 return out;
@@ -221,31 +249,31 @@ pub mod pack {
         tokio::fs::create_dir(&model_dir).await.unwrap();
         let res = tokio::join!(
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/rust_model.ot".into()],
-                    sha256: "b48c2b60d9a63b6ad67d99720b4d41ecb235287f10fcaeaae412291cdaf28578".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/rust_model.ot".into()],
+                    "b48c2b60d9a63b6ad67d99720b4d41ecb235287f10fcaeaae412291cdaf28578".into(),
+                ),
                 model_dir.join("rust_model.ot"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/config.json".into()],
-                    sha256: "a0f9bcb245b680a96ccae0ad8d155f267ec3e3c971ef4a4937e52ea9ba368a86".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/config.json".into()],
+                    "a0f9bcb245b680a96ccae0ad8d155f267ec3e3c971ef4a4937e52ea9ba368a86".into(),
+                ),
                 model_dir.join("config.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/vocab.json".into()],
-                    sha256: "06b4d46c8e752d410213d9548eb27a54db70fda0319b6271fb8d59dead5e1cab".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/vocab.json".into()],
+                    "06b4d46c8e752d410213d9548eb27a54db70fda0319b6271fb8d59dead5e1cab".into(),
+                ),
                 model_dir.join("vocab.json"),
             ),
             download_file(
-                LinkedFile {
-                    urls: vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/merges.txt".into()],
-                    sha256: "1ce1664773c50f3e0cc8842619a93edc4624525b728b188a9e0be33b7726adc5".into(),
-                },
+                LinkedFile::new(
+                    vec!["https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/merges.txt".into()],
+                    "1ce1664773c50f3e0cc8842619a93edc4624525b728b188a9e0be33b7726adc5".into(),
+                ),
                 model_dir.join("merges.txt"),
             ),
         );
@@ -262,7 +290,7 @@ pub mod pack {
         let info = CartonInfo {
             model_name: Some("BART Large MNLI".into()),
             short_description: Some("BART Large MNLI is a model that can do zero shot classificiation.".into()),
-            model_description: Some("See [here](https://huggingface.co/facebook/bart-large-mnli) for more details.\n\nNote: This model performs multi-label classification (i.e. zero or more labels may be true for each input).".into()),
+            model_description: Some("See [here](https://huggingface.co/facebook/bart-large-mnli) for more details.\n\nNote: By default, this model performs multi-label classification (i.e. zero or more labels may be true for each input). Set `multilabel` to 0 for single-label classification.".into()),
             license: Some("MIT".into()),
             repository: None,
             homepage: Some("https://huggingface.co/facebook/bart-large-mnli".into()),
@@ -273,37 +301,56 @@ pub mod pack {
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into())]),
                     description: Some("The strings to classifiy".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "candidate_labels".into(),
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![Dimension::Symbol("L".into())]),
                     description: Some("The candidate labels".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
                 TensorSpec {
                     name: "template".into(),
                     dtype: DataType::String,
                     shape: Shape::Shape(vec![]),
                     description: Some("An optional template string for the model to use. Defaults to 'This example is about {}.'".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: true,
+                    default: None
                 },
                 TensorSpec {
                     name: "max_length".into(),
                     dtype: DataType::U32,
                     shape: Shape::Shape(vec![]),
                     description: Some("An optional max_length to pass to the model. Defaults to 128.".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: true,
+                    default: None
+                },
+                TensorSpec {
+                    name: "multilabel".into(),
+                    dtype: DataType::U8,
+                    shape: Shape::Shape(vec![]),
+                    description: Some("Optional. If 0, do single-label classification and return a probability distribution over `candidate_labels` for each input. Defaults to 1 (multi-label classification).".into()),
+                    internal_name: None,
+                    optional: true,
+                    default: None
                 }
             ]),
             outputs: Some(vec![
                 TensorSpec {
                     name: "scores".into(),
-                    dtype: DataType::String,
+                    dtype: DataType::Float,
                     shape: Shape::Shape(vec![Dimension::Symbol("N".into()), Dimension::Symbol("L".into())]),
                     description: Some("Scores between 0 and 1 for each element of `input` for each label in `candidate_labels`".into()),
-                    internal_name: None
+                    internal_name: None,
+                    optional: false,
+                    default: None
                 },
             ]),
             self_tests: None,
@@ -335,6 +382,7 @@ pub mod pack {
             PackOpts {
                 info,
                 linked_files: Some(linked_files),
+                ..Default::default()
             },
         )
         .await
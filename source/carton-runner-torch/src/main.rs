@@ -14,10 +14,13 @@
 
 use carton_runner_interface::{
     server::{init_runner, RequestData, ResponseData, SealHandle},
-    types::{RunnerOpt, Tensor, TensorStorage},
+    types::{DeviceMemory, RunnerOpt, Tensor, TensorStorage},
 };
 use lunchbox::{path::Path, types::WritableFileSystem, ReadableFileSystem};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 #[tokio::main]
 async fn main() {
@@ -27,6 +30,9 @@ async fn main() {
     let mut sealed_tensors = HashMap::new();
 
     let mut model = None;
+    let mut pin_memory = false;
+    let mut fallback_to_cpu = false;
+    let mut cpu_fallback = None;
     let device = tch::Device::cuda_if_available();
 
     while let Some(req) = server.get_next_request().await {
@@ -38,16 +44,53 @@ async fn main() {
                 // Handle options
                 if let Some(opts) = runner_opts {
                     opts.get("num_threads")
-                        .and_then(get_int_opt)
+                        .and_then(RunnerOpt::as_i64)
                         .map(|v| tch::set_num_threads(v as _));
                     opts.get("num_interop_threads")
-                        .and_then(get_int_opt)
+                        .and_then(RunnerOpt::as_i64)
                         .map(|v| tch::set_num_interop_threads(v as _));
+
+                    if opts
+                        .get("deterministic")
+                        .and_then(RunnerOpt::as_bool)
+                        .unwrap_or(false)
+                    {
+                        enable_deterministic_mode();
+                    }
+
+                    pin_memory = opts
+                        .get("pin_memory")
+                        .and_then(RunnerOpt::as_bool)
+                        .unwrap_or(false);
+
+                    if pin_memory && !device.is_cuda() {
+                        log::info!("`pin_memory` was set, but no GPU is available. Inputs will not be pinned.");
+                        pin_memory = false;
+                    }
+
+                    fallback_to_cpu = opts
+                        .get("fallback_to_cpu")
+                        .and_then(RunnerOpt::as_bool)
+                        .unwrap_or(false);
+
+                    // This seeds the RNGs for all devices
+                    if let Some(seed) = carton_runner_interface::seed(&opts) {
+                        log::info!("Setting the random seed to {seed}");
+                        tch::manual_seed(seed as _);
+                    }
                 }
 
                 // TODO: error handling
                 let fs = server.get_readonly_filesystem(fs).await.unwrap();
                 let model_data = fs.read("model.pt").await.unwrap();
+
+                // Keep the serialized model around so we can load it on the CPU if we run out of GPU memory
+                cpu_fallback = if fallback_to_cpu && device.is_cuda() {
+                    Some(Arc::new(CpuFallback::new(model_data.clone())))
+                } else {
+                    None
+                };
+
                 model = tokio::task::spawn_blocking(move || {
                     Some(Arc::new(
                         tch::CModule::load_data_on_device(&mut model_data.as_slice(), device)
@@ -72,18 +115,27 @@ async fn main() {
 
                 // The input path should be a pt file. Let's symlink it into the temp dir
                 // (this symlink will be resolved when packing the model since it's an external one)
-                fs.symlink(input_path, Path::new(&temp_folder).join("model.pt"))
-                    .await
-                    .unwrap();
-
-                // Return the temp folder
-                server
-                    .send_response_for_request(
-                        req_id,
+                let response = match fs.metadata(&input_path).await {
+                    Ok(metadata) if metadata.is_dir() => ResponseData::Error {
+                        e: format!("Expected `{input_path}` to be a TorchScript file, but it's a directory"),
+                    },
+                    Ok(_) => {
+                        fs.symlink(&input_path, Path::new(&temp_folder).join("model.pt"))
+                            .await
+                            .unwrap();
+
+                        // Return the temp folder
                         ResponseData::Pack {
                             output_path: temp_folder,
-                        },
-                    )
+                        }
+                    }
+                    Err(e) => ResponseData::Error {
+                        e: format!("Could not read TorchScript file `{input_path}`: {e}"),
+                    },
+                };
+
+                server
+                    .send_response_for_request(req_id, response)
                     .await
                     .unwrap();
             }
@@ -107,12 +159,15 @@ async fn main() {
             RequestData::InferWithTensors { tensors, .. } => {
                 // TODO: error handling
                 let m = model.as_ref().unwrap().clone();
-                let out = tokio::task::spawn_blocking(move || infer(m, tensors, device))
-                    .await
-                    .unwrap();
+                let fallback = cpu_fallback.clone();
+                let out = tokio::task::spawn_blocking(move || {
+                    infer(m, tensors, device, pin_memory, fallback)
+                })
+                .await
+                .unwrap();
 
                 server
-                    .send_response_for_request(req_id, ResponseData::Infer { tensors: out })
+                    .send_response_for_request(req_id, infer_response(out))
                     .await
                     .unwrap();
             }
@@ -121,13 +176,36 @@ async fn main() {
                 // TODO: error handling
                 let tensors = sealed_tensors.remove(&handle.get()).unwrap();
                 let m = model.as_ref().unwrap().clone();
-                let out = tokio::task::spawn_blocking(move || infer(m, tensors, device))
+                let fallback = cpu_fallback.clone();
+                let out = tokio::task::spawn_blocking(move || {
+                    infer(m, tensors, device, pin_memory, fallback)
+                })
+                .await
+                .unwrap();
+
+                // Let's just return the input tensors for now
+                server
+                    .send_response_for_request(req_id, infer_response(out))
                     .await
                     .unwrap();
+            }
+
+            RequestData::DeviceMemoryUsage => {
+                let response = match model.as_ref() {
+                    Some(m) if device.is_cuda() => match cuda_parameter_bytes(m) {
+                        Ok(allocated_bytes) => ResponseData::DeviceMemoryUsage {
+                            memory: Some(DeviceMemory {
+                                allocated_bytes,
+                                reserved_bytes: None,
+                            }),
+                        },
+                        Err(e) => ResponseData::Error { e },
+                    },
+                    _ => ResponseData::DeviceMemoryUsage { memory: None },
+                };
 
-                // Let's just return the input tensors for now
                 server
-                    .send_response_for_request(req_id, ResponseData::Infer { tensors: out })
+                    .send_response_for_request(req_id, response)
                     .await
                     .unwrap();
             }
@@ -135,87 +213,308 @@ async fn main() {
     }
 }
 
+/// Configure torch to prefer reproducible results over performance (if the `deterministic` option is set)
+fn enable_deterministic_mode() {
+    log::info!("Enabling deterministic mode. This may make inference slower.");
+
+    // Don't let cuDNN benchmark and pick the fastest (possibly nondeterministic) algorithm for each input shape.
+    // cuDNN doesn't guarantee deterministic results for all of its algorithms so we disable it completely
+    tch::Cuda::cudnn_set_benchmark(false);
+    tch::Cuda::set_user_enabled_cudnn(false);
+
+    // cuBLAS needs `CUBLAS_WORKSPACE_CONFIG` to produce deterministic results on CUDA 10.2 and newer.
+    // Setting it here isn't safe because other threads are running so users set it for the runner
+    // process with `LoadOpts::runner_env`.
+    // See https://docs.nvidia.com/cuda/cublas/index.html#results-reproducibility
+    if std::env::var_os("CUBLAS_WORKSPACE_CONFIG").is_none() {
+        log::warn!("`deterministic` was set, but `CUBLAS_WORKSPACE_CONFIG` wasn't. cuBLAS results on GPU may not be reproducible. Set it to `:4096:8` using `runner_env` when loading the model.");
+    }
+}
+
+/// The size of the model's parameters that are on a GPU. `tch` doesn't expose the stats of libtorch's
+/// caching allocator so this doesn't include intermediate tensors or memory reserved by the allocator
+fn cuda_parameter_bytes(model: &tch::CModule) -> Result<u64, String> {
+    let params = model
+        .named_parameters()
+        .map_err(|e| format!("Error getting model parameters: {e}"))?;
+
+    Ok(params
+        .iter()
+        .filter(|(_, t)| t.device().is_cuda())
+        .map(|(_, t)| (t.numel() * t.kind().elt_size_in_bytes()) as u64)
+        .sum())
+}
+
+fn infer_response(out: Result<HashMap<String, Tensor>, InferError>) -> ResponseData {
+    match out {
+        Ok(tensors) => ResponseData::Infer { tensors },
+        Err(InferError::OutOfMemory(e)) => ResponseData::OutOfMemory { e },
+        Err(InferError::Other(e)) => ResponseData::Error { e },
+    }
+}
+
+enum InferError {
+    /// Torch ran out of (GPU or CPU) memory. This is sent to the core library as `ResponseData::OutOfMemory`
+    /// so it can return a dedicated error
+    OutOfMemory(String),
+    Other(String),
+}
+
+impl From<String> for InferError {
+    fn from(value: String) -> Self {
+        Self::Other(value)
+    }
+}
+
+/// A CPU copy of the model that's loaded the first time we run out of GPU memory
+/// (if the `fallback_to_cpu` option is set)
+struct CpuFallback {
+    model_data: Vec<u8>,
+    model: Mutex<Option<Arc<tch::CModule>>>,
+}
+
+impl CpuFallback {
+    fn new(model_data: Vec<u8>) -> Self {
+        Self {
+            model_data,
+            model: Mutex::new(None),
+        }
+    }
+
+    fn model(&self) -> Result<Arc<tch::CModule>, String> {
+        let mut model = self.model.lock().unwrap();
+        if model.is_none() {
+            log::info!("Loading the model on the CPU");
+            let m = tch::CModule::load_data_on_device(
+                &mut self.model_data.as_slice(),
+                tch::Device::Cpu,
+            )
+            .map_err(|e| format!("Error loading the model on the CPU: {e}"))?;
+
+            *model = Some(Arc::new(m));
+        }
+
+        Ok(model.as_ref().unwrap().clone())
+    }
+}
+
 fn infer(
     model: Arc<tch::CModule>,
     tensors: HashMap<String, Tensor>,
     device: tch::Device,
-) -> HashMap<String, Tensor> {
-    let tensors = tensors_to_tch(tensors, device);
+    pin_memory: bool,
+    cpu_fallback: Option<Arc<CpuFallback>>,
+) -> Result<HashMap<String, Tensor>, InferError> {
+    check_inputs(&tensors)?;
+
+    // Return errors from torch (e.g. an unsupported op or running out of memory) to the caller instead of panicking
+    match run_model(&model, &tensors, device, pin_memory) {
+        Ok(out) => Ok(out),
+        Err(e) if is_out_of_memory(&e) => {
+            let message = format!(
+                "Ran out of memory running the torchscript model on {} with inputs {}: {e}",
+                device_name(device),
+                describe_inputs(&tensors)
+            );
+
+            match cpu_fallback {
+                Some(fallback) => {
+                    log::warn!("{message}. Retrying on the CPU because `fallback_to_cpu` is set");
+                    run_model(&fallback.model()?, &tensors, tch::Device::Cpu, false).map_err(|e| {
+                        let message = format!("Error running torchscript model on the CPU after running out of GPU memory: {e}");
+                        if is_out_of_memory(&e) {
+                            InferError::OutOfMemory(message)
+                        } else {
+                            InferError::Other(message)
+                        }
+                    })
+                }
+                None => Err(InferError::OutOfMemory(message)),
+            }
+        }
+        Err(e) => Err(format!("Error running torchscript model: {e}").into()),
+    }
+}
 
-    // TODO: error handling
-    let out = model.forward_is(&[tensors]).unwrap();
+fn run_model(
+    model: &tch::CModule,
+    tensors: &HashMap<String, Tensor>,
+    device: tch::Device,
+    pin_memory: bool,
+) -> Result<HashMap<String, Tensor>, tch::TchError> {
+    let tensors = tensors_to_tch(tensors, device, pin_memory)?;
+    let out = model.forward_is(&[tensors])?;
 
     // Type conversion on the way out
     let out: Vec<(tch::IValue, tch::IValue)> = out.try_into().unwrap();
     out.into_iter()
         .map(|(k, v)| {
-            (
+            Ok((
                 k.try_into().unwrap(),
-                tensor_from_ivalue(v.try_into().unwrap()),
-            )
+                tensor_from_ivalue(v.try_into().unwrap())?,
+            ))
         })
         .collect()
 }
 
-fn tensors_to_tch(tensors: HashMap<String, Tensor>, device: tch::Device) -> tch::IValue {
-    tensors
+/// Whether an error from torch was caused by running out of (GPU or CPU) memory.
+/// `tch` only gives us the message of the exception libtorch threw (not its type) so this checks for the
+/// messages of `c10::OutOfMemoryError` and the CPU allocator. The result is sent to the core library as a
+/// dedicated response so nothing downstream needs to parse the message
+fn is_out_of_memory(e: &tch::TchError) -> bool {
+    let message = e.to_string().to_lowercase();
+    message.contains("out of memory") || message.contains("can't allocate memory")
+}
+
+fn device_name(device: tch::Device) -> String {
+    match device {
+        tch::Device::Cpu => "cpu".into(),
+        tch::Device::Cuda(idx) => format!("cuda:{idx}"),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+/// Describes the dtype and shape of each input for error messages (e.g. "`x`: float32 [1, 3, 224, 224]")
+fn describe_inputs(tensors: &HashMap<String, Tensor>) -> String {
+    let mut names: Vec<_> = tensors.keys().collect();
+    names.sort();
+
+    names
         .into_iter()
-        .map(|(k, v)| (k.into(), tensor_to_ivalue(v, device)))
-        .collect::<Vec<(tch::IValue, tch::IValue)>>()
-        .into()
+        .map(|name| {
+            let (dtype, shape) = dtype_and_shape(&tensors[name]);
+            format!("`{name}`: {dtype} {shape:?}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn dtype_and_shape(tensor: &Tensor) -> (&'static str, Vec<usize>) {
+    match tensor {
+        Tensor::Float(v) => ("float32", v.view().shape().to_vec()),
+        Tensor::Double(v) => ("float64", v.view().shape().to_vec()),
+        Tensor::String(v) => ("string", v.view().shape().to_vec()),
+        Tensor::I8(v) => ("int8", v.view().shape().to_vec()),
+        Tensor::I16(v) => ("int16", v.view().shape().to_vec()),
+        Tensor::I32(v) => ("int32", v.view().shape().to_vec()),
+        Tensor::I64(v) => ("int64", v.view().shape().to_vec()),
+        Tensor::U8(v) => ("uint8", v.view().shape().to_vec()),
+        Tensor::U16(v) => ("uint16", v.view().shape().to_vec()),
+        Tensor::U32(v) => ("uint32", v.view().shape().to_vec()),
+        Tensor::U64(v) => ("uint64", v.view().shape().to_vec()),
+        Tensor::Complex64(v) => ("complex64", v.view().shape().to_vec()),
+        Tensor::Complex128(v) => ("complex128", v.view().shape().to_vec()),
+        Tensor::NestedTensor(v) => ("nested", vec![v.len()]),
+        Tensor::LabeledNestedTensor { tensors, .. } => ("nested", vec![tensors.len()]),
+    }
+}
+
+/// Returns an error naming the first input that can't be converted to a torch value
+fn check_inputs(tensors: &HashMap<String, Tensor>) -> Result<(), String> {
+    let mut names: Vec<_> = tensors.keys().collect();
+    names.sort();
+
+    for name in names {
+        let (dtype, shape) = dtype_and_shape(&tensors[name]);
+        let reason = match &tensors[name] {
+            Tensor::U16(_) | Tensor::U32(_) | Tensor::U64(_) => "torch doesn't support this dtype",
+            Tensor::NestedTensor(_) | Tensor::LabeledNestedTensor { .. } => {
+                "nested tensors are not yet supported"
+            }
+            Tensor::String(_) if shape.len() > 1 => {
+                "TorchScript represents string tensors as a `str` or a `List[str]` so only string tensors with 0 or 1 dims are supported"
+            }
+            _ => continue,
+        };
+
+        return Err(format!(
+            "Input `{name}` ({dtype} {shape:?}) isn't supported by the torch runner: {reason}"
+        ));
+    }
+
+    Ok(())
+}
+
+fn tensors_to_tch(
+    tensors: &HashMap<String, Tensor>,
+    device: tch::Device,
+    pin_memory: bool,
+) -> Result<tch::IValue, tch::TchError> {
+    Ok(tensors
+        .iter()
+        .map(|(k, v)| Ok((k.clone().into(), tensor_to_ivalue(v, device, pin_memory)?)))
+        .collect::<Result<Vec<(tch::IValue, tch::IValue)>, tch::TchError>>()?
+        .into())
 }
 
 // Conversion from carton tensors to torch IValues.
-fn tensor_to_ivalue(value: Tensor, device: tch::Device) -> tch::IValue {
+// If `pin_memory` is true, numeric tensors are staged in page-locked memory before being copied to the GPU
+fn tensor_to_ivalue(
+    value: &Tensor,
+    device: tch::Device,
+    pin_memory: bool,
+) -> Result<tch::IValue, tch::TchError> {
     match value {
-        Tensor::Float(v) => storage_to_tensor(v, tch::Kind::Float, device),
-        Tensor::Double(v) => storage_to_tensor(v, tch::Kind::Double, device),
-        Tensor::I8(v) => storage_to_tensor(v, tch::Kind::Int8, device),
-        Tensor::I16(v) => storage_to_tensor(v, tch::Kind::Int16, device),
-        Tensor::I32(v) => storage_to_tensor(v, tch::Kind::Int, device),
-        Tensor::I64(v) => storage_to_tensor(v, tch::Kind::Int64, device),
-        Tensor::U8(v) => storage_to_tensor(v, tch::Kind::Uint8, device),
-
-        // TODO: don't panic
-        Tensor::U16(_) => panic!("Torch doesn't support uint16"),
-        Tensor::U32(_) => panic!("Torch doesn't support uint32"),
-        Tensor::U64(_) => panic!("Torch doesn't support uint64"),
-        Tensor::NestedTensor(_) => panic!("Nested tensors are not yet supported"),
+        Tensor::Float(v) => storage_to_tensor(v, tch::Kind::Float, device, pin_memory),
+        Tensor::Double(v) => storage_to_tensor(v, tch::Kind::Double, device, pin_memory),
+        Tensor::I8(v) => storage_to_tensor(v, tch::Kind::Int8, device, pin_memory),
+        Tensor::I16(v) => storage_to_tensor(v, tch::Kind::Int16, device, pin_memory),
+        Tensor::I32(v) => storage_to_tensor(v, tch::Kind::Int, device, pin_memory),
+        Tensor::I64(v) => storage_to_tensor(v, tch::Kind::Int64, device, pin_memory),
+        Tensor::U8(v) => storage_to_tensor(v, tch::Kind::Uint8, device, pin_memory),
+        Tensor::Complex64(v) => storage_to_tensor(v, tch::Kind::ComplexFloat, device, pin_memory),
+        Tensor::Complex128(v) => storage_to_tensor(v, tch::Kind::ComplexDouble, device, pin_memory),
 
         Tensor::String(v) => {
             // Special handling for strings
+            // TorchScript represents these as a `str` or a `List[str]` so we only support scalars and flat lists
             let view = v.view();
-
-            // Currently only support flat lists or scalars
-            match view.ndim() {
-                0 => {
-                    // Scalar
-                    view.first().unwrap().to_owned().into()
-                },
-                1 => {
-                    view.as_slice().unwrap().to_vec().into()
-                }
-                dim => panic!("Tried using a string tensor with {dim} dims. Currently, only string tensors of 0 or 1 dims are supported.")
+            if view.ndim() == 0 {
+                Ok(view.first().unwrap().to_owned().into())
+            } else {
+                Ok(view.iter().cloned().collect::<Vec<_>>().into())
             }
         }
+
+        Tensor::U16(_)
+        | Tensor::U32(_)
+        | Tensor::U64(_)
+        | Tensor::NestedTensor(_)
+        | Tensor::LabeledNestedTensor { .. } => {
+            unreachable!("Unsupported inputs are rejected by `check_inputs`")
+        }
     }
 }
 
-fn storage_to_tensor<T>(v: TensorStorage<T>, kind: tch::Kind, device: tch::Device) -> tch::IValue {
+fn storage_to_tensor<T>(
+    v: &TensorStorage<T>,
+    kind: tch::Kind,
+    device: tch::Device,
+    pin_memory: bool,
+) -> Result<tch::IValue, tch::TchError> {
     let view = v.view();
     let ptr = view.as_ptr();
     let size: Vec<_> = view.shape().into_iter().map(|v| (*v) as _).collect();
     let strides: Vec<_> = view.strides().into_iter().map(|v| (*v) as _).collect();
 
+    let t = unsafe { tch::Tensor::from_blob(ptr as _, &size, &strides, kind, tch::Device::Cpu) };
+
+    if pin_memory {
+        // Copying from pageable memory to the GPU is always synchronous so we first copy into
+        // page-locked memory. This also decouples the torch tensor from our input data.
+        // Torch's pinned memory allocator caches these buffers so they're reused across requests
+        match t.f_pin_memory(device) {
+            Ok(pinned) => return Ok(pinned.f_to_device_(device, kind, true, false)?.into()),
+            Err(e) => {
+                log::warn!("Failed to pin input tensor. Falling back to pageable memory: {e}")
+            }
+        }
+    }
+
     // Note the `copy: true`. This decouples the torch tensor from our input so we can safely drop the input data.
     // tch doesn't currently support deleters in `from_blob` so we don't have a better alternative
     // This also attempts to do a non blocking copy to the target device
-    let t = unsafe {
-        tch::Tensor::from_blob(ptr as _, &size, &strides, kind, tch::Device::Cpu)
-            .to_device_(device, kind, true, true)
-    };
-
-    t.into()
+    Ok(t.f_to_device_(device, kind, true, true)?.into())
 }
 
 // Macro for conversions from torch to carton
@@ -223,22 +522,53 @@ macro_rules! impl_output_copy {
     ($tensor:ident, $type:ty) => {{
         // Create an output tensor with the same shape
         let mut output_tensor =
-            TensorStorage::<$type>::new($tensor.size().iter().map(|v| (*v) as _).collect());
+            TensorStorage::<$type>::new($tensor.size().iter().map(|v| (*v) as _).collect())
+                .map_err(tch::TchError::Shape)?;
         let mut output_view = output_tensor.view_mut();
         let sliced_output_view = output_view.as_slice_mut().unwrap();
 
-        // Copy the data in
-        $tensor
-            .to(tch::Device::Cpu)
-            .f_copy_data(sliced_output_view, $tensor.numel())
-            .unwrap();
+        // Copy the data in (there's nothing to copy for zero-element tensors)
+        if $tensor.numel() > 0 {
+            $tensor
+                .f_to(tch::Device::Cpu)?
+                .f_copy_data(sliced_output_view, $tensor.numel())?;
+        }
 
         output_tensor.into()
     }};
 }
 
-fn tensor_from_ivalue(value: tch::IValue) -> Tensor {
-    match value {
+// Complex types don't implement `tch::kind::Element` so we copy them as (real, imaginary) pairs of `$real`
+macro_rules! impl_complex_output_copy {
+    ($tensor:ident, $type:ty, $real:ty) => {{
+        // Create an output tensor with the same shape
+        let mut output_tensor =
+            TensorStorage::<$type>::new($tensor.size().iter().map(|v| (*v) as _).collect())
+                .map_err(tch::TchError::Shape)?;
+
+        // Copy the data in (there's nothing to copy for zero-element tensors)
+        if $tensor.numel() > 0 {
+            // `view_as_real` adds a trailing dimension with the real and imaginary parts
+            let num_parts = 2 * $tensor.numel();
+            let mut parts = vec![<$real>::default(); num_parts];
+            $tensor
+                .f_to(tch::Device::Cpu)?
+                .f_view_as_real()?
+                .f_copy_data(parts.as_mut_slice(), num_parts)?;
+
+            for (out, pair) in
+                std::iter::zip(output_tensor.view_mut().iter_mut(), parts.chunks_exact(2))
+            {
+                *out = num_complex::Complex::new(pair[0], pair[1]);
+            }
+        }
+
+        output_tensor.into()
+    }};
+}
+
+fn tensor_from_ivalue(value: tch::IValue) -> Result<Tensor, tch::TchError> {
+    Ok(match value {
         tch::IValue::Tensor(tensor) => match tensor.kind() {
             tch::Kind::Uint8 => impl_output_copy!(tensor, u8),
             tch::Kind::Int8 => impl_output_copy!(tensor, i8),
@@ -247,24 +577,32 @@ fn tensor_from_ivalue(value: tch::IValue) -> Tensor {
             tch::Kind::Int64 => impl_output_copy!(tensor, i64),
             tch::Kind::Float => impl_output_copy!(tensor, f32),
             tch::Kind::Double => impl_output_copy!(tensor, f64),
+            tch::Kind::ComplexFloat => {
+                impl_complex_output_copy!(tensor, num_complex::Complex<f32>, f32)
+            }
+            tch::Kind::ComplexDouble => {
+                impl_complex_output_copy!(tensor, num_complex::Complex<f64>, f64)
+            }
             other => panic!("Tensor kind {other:?} is currently unsupported as an output!"),
         },
         tch::IValue::String(scalar_string) => {
-            let mut output_tensor = TensorStorage::new(vec![]);
+            let mut output_tensor = TensorStorage::new(vec![]).map_err(tch::TchError::Shape)?;
             let mut view = output_tensor.view_mut();
             *view.first_mut().unwrap() = scalar_string;
 
             output_tensor.into()
         }
         tch::IValue::StringList(string_list) => {
-            let mut output_tensor = TensorStorage::new(vec![string_list.len() as _]);
+            let mut output_tensor =
+                TensorStorage::new(vec![string_list.len() as _]).map_err(tch::TchError::Shape)?;
             let mut view = output_tensor.view_mut();
             view.as_slice_mut().unwrap().clone_from_slice(&string_list);
 
             output_tensor.into()
         }
         tch::IValue::GenericList(list) => {
-            let mut output_tensor = TensorStorage::new(vec![list.len() as _]);
+            let mut output_tensor =
+                TensorStorage::new(vec![list.len() as _]).map_err(tch::TchError::Shape)?;
             let mut view = output_tensor.view_mut();
 
             for (a, item) in std::iter::zip(view.as_slice_mut().unwrap(), list) {
@@ -279,14 +617,7 @@ fn tensor_from_ivalue(value: tch::IValue) -> Tensor {
             output_tensor.into()
         }
         other => panic!("Unsupported IValue type {other:?}"),
-    }
-}
-
-fn get_int_opt(opt: &RunnerOpt) -> Option<i64> {
-    match opt {
-        RunnerOpt::Integer(v) => Some(*v),
-        _ => None,
-    }
+    })
 }
 
 #[cfg(test)]
@@ -309,4 +640,59 @@ mod tests {
         assert_eq!(arr.len(), 1);
         *arr.first_mut().unwrap() = 32.0;
     }
+
+    #[test]
+    fn test_check_inputs() {
+        use carton_runner_interface::types::{Tensor, TensorStorage};
+
+        let mut tensors: std::collections::HashMap<String, Tensor> =
+            std::collections::HashMap::new();
+        tensors.insert(
+            "a".to_owned(),
+            TensorStorage::<String>::new(vec![]).unwrap().into(),
+        );
+        tensors.insert(
+            "b".to_owned(),
+            TensorStorage::<String>::new(vec![3]).unwrap().into(),
+        );
+        assert!(super::check_inputs(&tensors).is_ok());
+
+        tensors.insert(
+            "c".to_owned(),
+            TensorStorage::<String>::new(vec![2, 3]).unwrap().into(),
+        );
+        let err = super::check_inputs(&tensors).unwrap_err();
+        assert!(err.starts_with("Input `c` (string [2, 3])"), "{err}");
+
+        tensors.remove("c");
+        tensors.insert(
+            "d".to_owned(),
+            TensorStorage::<u16>::new(vec![4]).unwrap().into(),
+        );
+        let err = super::check_inputs(&tensors).unwrap_err();
+        assert!(err.starts_with("Input `d` (uint16 [4])"), "{err}");
+    }
+
+    #[test]
+    fn test_describe_inputs() {
+        let mut tensors: std::collections::HashMap<String, carton_runner_interface::types::Tensor> =
+            std::collections::HashMap::new();
+        tensors.insert(
+            "b".to_owned(),
+            carton_runner_interface::types::TensorStorage::<i64>::new(vec![4])
+                .unwrap()
+                .into(),
+        );
+        tensors.insert(
+            "a".to_owned(),
+            carton_runner_interface::types::TensorStorage::<f32>::new(vec![1, 3, 224, 224])
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            super::describe_inputs(&tensors),
+            "`a`: float32 [1, 3, 224, 224], `b`: int64 [4]"
+        );
+    }
 }
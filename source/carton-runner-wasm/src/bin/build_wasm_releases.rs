@@ -8,7 +8,8 @@ use carton_runner_interface::slowlog::slowlog;
 use carton_runner_packager::discovery::RunnerInfo;
 
 // TODO: This should be the version of carton-interface-wasm, but it's not done yet.
-const INTERFACE_VERSION: semver::Version = semver::Version::new(0, 0, 1);
+// This must match the package version in `wit/lib.wit`
+const INTERFACE_VERSION: semver::Version = semver::Version::new(0, 0, 2);
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -3,9 +3,39 @@ wasmtime::component::bindgen!({
     path: "./wit",
 });
 
+use wasmtime_wasi::preview2::{Table, WasiCtx, WasiView};
+
 use crate::component::carton_wasm::lib::types::Host;
 pub(crate) use carton_wasm::lib::types::{Dtype, TensorNumeric, TensorString};
 
-pub(crate) struct HostImpl;
+/// The state of a model instance. The WASI context is only used if WASI was enabled for the model
+pub(crate) struct HostImpl {
+    table: Table,
+    wasi: WasiCtx,
+}
+
+impl HostImpl {
+    pub(crate) fn new(table: Table, wasi: WasiCtx) -> Self {
+        Self { table, wasi }
+    }
+}
 
 impl Host for HostImpl {}
+
+impl WasiView for HostImpl {
+    fn table(&self) -> &Table {
+        &self.table
+    }
+
+    fn table_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+
+    fn ctx(&self) -> &WasiCtx {
+        &self.wasi
+    }
+
+    fn ctx_mut(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
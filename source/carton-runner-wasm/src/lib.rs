@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use color_eyre::eyre::{eyre, Result};
 use wasmtime::component::{Component, Linker};
 use wasmtime::{Engine, Store};
+use wasmtime_wasi::preview2::{DirPerms, FilePerms, Table, WasiCtxBuilder};
 
 use carton_runner_interface::types::Tensor as CartonTensor;
 
@@ -17,7 +18,16 @@ pub struct WASMModelInstance {
 }
 
 impl WASMModelInstance {
-    pub fn from_bytes(engine: &Engine, bytes: &[u8]) -> Result<Self> {
+    /// Load a model from the bytes of a WASM component.
+    ///
+    /// If `model_dir` is specified, WASI is enabled and the model can read (but not write) the files in
+    /// that directory. They're available at `/` within the model. Otherwise, models that import WASI
+    /// interfaces fail to load.
+    pub fn from_bytes(
+        engine: &Engine,
+        bytes: &[u8],
+        model_dir: Option<&std::path::Path>,
+    ) -> Result<Self> {
         /*
         see https://docs.wasmtime.dev/api/wasmtime/component/macro.bindgen.html
         Some of the names may be confusing, here is the general idea from my
@@ -32,7 +42,20 @@ impl WASMModelInstance {
         let comp = Component::from_binary(&engine, bytes).unwrap();
         let mut linker = Linker::<HostImpl>::new(&engine);
         Model::add_to_linker(&mut linker, |state: &mut HostImpl| state).unwrap();
-        let mut store = Store::new(&engine, HostImpl);
+
+        // WASI expands what a model can access so it's only available if explicitly enabled
+        let mut table = Table::new();
+        let mut wasi = WasiCtxBuilder::new();
+        if let Some(model_dir) = model_dir {
+            wasmtime_wasi::preview2::command::sync::add_to_linker(&mut linker)
+                .map_err(|e| eyre!(e))?;
+
+            let dir = cap_std::fs::Dir::open_ambient_dir(model_dir, cap_std::ambient_authority())?;
+            wasi.preopened_dir(dir, DirPerms::READ, FilePerms::READ, "/");
+        }
+
+        let wasi = wasi.build(&mut table).map_err(|e| eyre!(e))?;
+        let mut store = Store::new(&engine, HostImpl::new(table, wasi));
         let (model, _) = Model::instantiate(&mut store, &comp, &linker).unwrap();
         Ok(Self { store, model })
     }
@@ -51,7 +74,7 @@ impl WASMModelInstance {
             .map_err(|e| eyre!(e))?;
         let mut ret = HashMap::new();
         for (k, v) in outputs.into_iter() {
-            ret.insert(k, v.into());
+            ret.insert(k, v.try_into()?);
         }
         Ok(ret)
     }
@@ -1,8 +1,17 @@
+use std::collections::VecDeque;
+
 use color_eyre::eyre::{eyre, Result};
-use lunchbox::{path::Path, types::WritableFileSystem, ReadableFileSystem};
+use lunchbox::{
+    path::{LunchboxPathUtils, Path, PathBuf},
+    types::{ReadableFile, WritableFileSystem},
+    ReadableFileSystem,
+};
 use wasmtime::{Config, Engine};
 
-use carton_runner_interface::server::{init_runner, RequestData, ResponseData};
+use carton_runner_interface::{
+    server::{init_runner, RequestData, ResponseData},
+    types::RunnerOpt,
+};
 use carton_runner_wasm::WASMModelInstance;
 
 fn new_engine() -> Result<Engine> {
@@ -11,6 +20,31 @@ fn new_engine() -> Result<Engine> {
     Engine::new(&config).map_err(|e| eyre!(e))
 }
 
+/// Copy all the files in `fs` to the local directory `target` (following symlinks)
+async fn copy_to_local<F>(fs: &F, target: &std::path::Path) -> Result<()>
+where
+    F: ReadableFileSystem,
+    F::FileType: ReadableFile,
+{
+    let mut to_process = VecDeque::new();
+    to_process.push_back(PathBuf::from("/"));
+    while let Some(dir_path) = to_process.pop_front() {
+        std::fs::create_dir_all(dir_path.to_path(target))?;
+
+        let mut dir = fs.read_dir(&dir_path).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let entry_path = entry.path();
+            if fs.metadata(&entry_path).await?.is_dir() {
+                to_process.push_back(entry_path);
+            } else {
+                std::fs::write(entry_path.to_path(target), fs.read(&entry_path).await?)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     color_eyre::install().unwrap();
@@ -18,15 +52,42 @@ async fn main() {
     let engine = new_engine().unwrap();
     let mut model: Option<WASMModelInstance> = None;
 
+    // A local copy of the model's files if WASI is enabled. This must be kept alive as long as the model is
+    let mut _model_dir = None;
+
     while let Some(req) = server.get_next_request().await {
         let req_id = req.id;
         match req.data {
-            RequestData::Load { fs, .. } => {
+            RequestData::Load {
+                fs, runner_opts, ..
+            } => {
                 let fs = server.get_readonly_filesystem(fs).await.unwrap();
                 let bin = &fs.read("model.wasm").await.unwrap();
+
+                // If WASI is enabled, the model can read the files bundled with it
+                let enable_wasi = runner_opts
+                    .as_ref()
+                    .and_then(|opts| opts.get("enable_wasi"))
+                    .and_then(RunnerOpt::as_bool)
+                    .unwrap_or(false);
+
+                _model_dir = if enable_wasi {
+                    let dir = tempfile::tempdir().unwrap();
+                    copy_to_local(&fs, dir.path())
+                        .await
+                        .expect("Failed to copy model files for WASI");
+                    Some(dir)
+                } else {
+                    None
+                };
+
                 model = Some(
-                    WASMModelInstance::from_bytes(&engine, bin)
-                        .expect("Failed to initialize WASM model"),
+                    WASMModelInstance::from_bytes(
+                        &engine,
+                        bin,
+                        _model_dir.as_ref().map(|dir| dir.path()),
+                    )
+                    .expect("Failed to initialize WASM model"),
                 );
                 server
                     .send_response_for_request(req_id, ResponseData::Load)
@@ -39,16 +100,40 @@ async fn main() {
                 fs,
             } => {
                 let fs = server.get_writable_filesystem(fs).await.unwrap();
-                fs.symlink(input_path, Path::new(&temp_folder).join("model.wasm"))
-                    .await
-                    .unwrap();
-                server
-                    .send_response_for_request(
-                        req_id,
+
+                // The input is either a `.wasm` file or a directory containing a `model.wasm` file along with
+                // any other files the model needs (see the `enable_wasi` option)
+                let response = match fs.metadata(&input_path).await {
+                    Ok(metadata) if metadata.is_dir() => {
+                        if fs
+                            .metadata(Path::new(&input_path).join("model.wasm"))
+                            .await
+                            .is_ok()
+                        {
+                            ResponseData::Pack {
+                                output_path: input_path,
+                            }
+                        } else {
+                            ResponseData::Error {
+                                e: format!("Expected a `model.wasm` file in `{input_path}`"),
+                            }
+                        }
+                    }
+                    Ok(_) => {
+                        fs.symlink(&input_path, Path::new(&temp_folder).join("model.wasm"))
+                            .await
+                            .unwrap();
                         ResponseData::Pack {
                             output_path: temp_folder,
-                        },
-                    )
+                        }
+                    }
+                    Err(e) => ResponseData::Error {
+                        e: format!("Could not read `{input_path}`: {e}"),
+                    },
+                };
+
+                server
+                    .send_response_for_request(req_id, response)
                     .await
                     .unwrap();
             }
@@ -70,6 +155,17 @@ async fn main() {
             RequestData::InferWithHandle { .. } => {
                 todo!()
             }
+
+            RequestData::DeviceMemoryUsage => {
+                // WASM models only run on the CPU
+                server
+                    .send_response_for_request(
+                        req_id,
+                        ResponseData::DeviceMemoryUsage { memory: None },
+                    )
+                    .await
+                    .unwrap();
+            }
         }
     }
 }
@@ -2,16 +2,19 @@ use color_eyre::eyre::{ensure, eyre};
 use color_eyre::{Report, Result};
 
 use carton_runner_interface::types::{
-    for_each_numeric_carton_type, Tensor as CartonTensor, TensorStorage as CartonStorage,
+    for_each_numeric_carton_type, Allocatable, Tensor as CartonTensor,
+    TensorStorage as CartonStorage,
 };
 
 use crate::component::{Dtype, Tensor as WasmTensor, TensorNumeric, TensorString};
 
-impl Into<CartonTensor> for WasmTensor {
-    fn into(self) -> CartonTensor {
-        match self {
-            WasmTensor::Numeric(t) => t.into(),
-            WasmTensor::String(t) => t.into(),
+impl TryFrom<WasmTensor> for CartonTensor {
+    type Error = Report;
+
+    fn try_from(value: WasmTensor) -> Result<Self> {
+        match value {
+            WasmTensor::Numeric(t) => t.try_into(),
+            WasmTensor::String(t) => t.try_into(),
         }
     }
 }
@@ -26,55 +29,52 @@ fn bytes_to_slice<T>(b: &[u8]) -> Result<&[T]> {
     })
 }
 
-fn copy_to_storage<T: Clone + Default>(mut s: CartonStorage<T>, b: &[u8]) -> CartonStorage<T> {
+/// Allocate a tensor with `shape` and copy `b` into it. The shape comes from the model so this
+/// returns an error if it's too large to allocate
+fn copy_to_storage<T: Clone + Default + Allocatable>(
+    shape: Vec<u64>,
+    b: &[u8],
+) -> Result<CartonStorage<T>> {
+    let mut s = CartonStorage::<T>::new(shape).map_err(|e| eyre!(e))?;
     s.view_mut()
         .as_slice_mut()
         .unwrap()
         .clone_from_slice(bytes_to_slice(b).unwrap());
-    s
+    Ok(s)
 }
 
-impl Into<CartonTensor> for TensorNumeric {
-    fn into(self) -> CartonTensor {
-        match self.dtype {
-            Dtype::Float => {
-                copy_to_storage(CartonStorage::<f32>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::Double => {
-                copy_to_storage(CartonStorage::<f64>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::I8 => copy_to_storage(CartonStorage::<i8>::new(self.shape), &self.buffer).into(),
-            Dtype::I16 => {
-                copy_to_storage(CartonStorage::<i16>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::I32 => {
-                copy_to_storage(CartonStorage::<i32>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::I64 => {
-                copy_to_storage(CartonStorage::<i64>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::U8 => copy_to_storage(CartonStorage::<u8>::new(self.shape), &self.buffer).into(),
-            Dtype::U16 => {
-                copy_to_storage(CartonStorage::<u16>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::U32 => {
-                copy_to_storage(CartonStorage::<u32>::new(self.shape), &self.buffer).into()
-            }
-            Dtype::U64 => {
-                copy_to_storage(CartonStorage::<u64>::new(self.shape), &self.buffer).into()
-            }
-        }
+impl TryFrom<TensorNumeric> for CartonTensor {
+    type Error = Report;
+
+    fn try_from(value: TensorNumeric) -> Result<Self> {
+        let (shape, b) = (value.shape, &value.buffer);
+        Ok(match value.dtype {
+            Dtype::Float => copy_to_storage::<f32>(shape, b)?.into(),
+            Dtype::Double => copy_to_storage::<f64>(shape, b)?.into(),
+            Dtype::I8 => copy_to_storage::<i8>(shape, b)?.into(),
+            Dtype::I16 => copy_to_storage::<i16>(shape, b)?.into(),
+            Dtype::I32 => copy_to_storage::<i32>(shape, b)?.into(),
+            Dtype::I64 => copy_to_storage::<i64>(shape, b)?.into(),
+            Dtype::U8 => copy_to_storage::<u8>(shape, b)?.into(),
+            Dtype::U16 => copy_to_storage::<u16>(shape, b)?.into(),
+            Dtype::U32 => copy_to_storage::<u32>(shape, b)?.into(),
+            Dtype::U64 => copy_to_storage::<u64>(shape, b)?.into(),
+            Dtype::C64 => copy_to_storage::<num_complex::Complex<f32>>(shape, b)?.into(),
+            Dtype::C128 => copy_to_storage::<num_complex::Complex<f64>>(shape, b)?.into(),
+        })
     }
 }
 
-impl Into<CartonTensor> for TensorString {
-    fn into(self) -> CartonTensor {
-        let mut t = CartonStorage::new(self.shape);
+impl TryFrom<TensorString> for CartonTensor {
+    type Error = Report;
+
+    fn try_from(value: TensorString) -> Result<Self> {
+        let mut t = CartonStorage::new(value.shape).map_err(|e| eyre!(e))?;
         t.view_mut()
             .as_slice_mut()
             .unwrap()
-            .clone_from_slice(&self.buffer);
-        t.into()
+            .clone_from_slice(&value.buffer);
+        Ok(t.into())
     }
 }
 
@@ -93,8 +93,12 @@ impl TryFrom<CartonTensor> for WasmTensor {
             CartonTensor::U16(t) => WasmTensor::Numeric(t.into()),
             CartonTensor::U32(t) => WasmTensor::Numeric(t.into()),
             CartonTensor::U64(t) => WasmTensor::Numeric(t.into()),
+            CartonTensor::Complex64(t) => WasmTensor::Numeric(t.into()),
+            CartonTensor::Complex128(t) => WasmTensor::Numeric(t.into()),
             CartonTensor::String(t) => WasmTensor::String(t.into()),
-            CartonTensor::NestedTensor(_) => return Err(eyre!("Nested tensors are not supported")),
+            CartonTensor::NestedTensor(_) | CartonTensor::LabeledNestedTensor { .. } => {
+                return Err(eyre!("Nested tensors are not supported"))
+            }
         })
     }
 }
@@ -141,6 +145,18 @@ for_each_numeric_carton_type! {
     )*
 }
 
+impl DTypeOf for num_complex::Complex<f32> {
+    fn dtype() -> Dtype {
+        Dtype::C64
+    }
+}
+
+impl DTypeOf for num_complex::Complex<f64> {
+    fn dtype() -> Dtype {
+        Dtype::C128
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,14 +166,13 @@ mod tests {
             paste::item! {
                 #[test]
                 fn [< $TypeStr "_tensor_carton_to_wasm" >]() {
-                    let storage = CartonStorage::<$RustType>::new(vec![3]);
                     let carton_tensor = CartonTensor::$CartonType(
                         copy_to_storage(
-                            storage,
+                            vec![3],
                             slice_to_bytes(
                                 &[1.0 as $RustType, 2.0 as $RustType, 3.0 as $RustType]
                             )
-                        )
+                        ).unwrap()
                     );
                     let wasm_tensor = WasmTensor::try_from(carton_tensor).unwrap();
                     match wasm_tensor {
@@ -181,7 +196,7 @@ mod tests {
                         dtype: Dtype::$CartonType,
                         shape: vec![3],
                     });
-                    let carton_tensor: CartonTensor = tensor.into();
+                    let carton_tensor = CartonTensor::try_from(tensor).unwrap();
                     match carton_tensor {
                         CartonTensor::$CartonType(storage) => {
                             assert_eq!(
@@ -198,10 +213,39 @@ mod tests {
         )*
     }
 
+    #[test]
+    fn complex_tensor_roundtrip() {
+        let data = [
+            num_complex::Complex::new(1.0f64, -2.0),
+            num_complex::Complex::new(0.5, 3.0),
+        ];
+        let carton_tensor =
+            CartonTensor::Complex128(copy_to_storage(vec![2], slice_to_bytes(&data)).unwrap());
+        let wasm_tensor = WasmTensor::try_from(carton_tensor).unwrap();
+        match &wasm_tensor {
+            WasmTensor::Numeric(tensor_numeric) => {
+                assert_eq!(tensor_numeric.dtype, Dtype::C128);
+                assert_eq!(tensor_numeric.buffer, slice_to_bytes(&data));
+            }
+            _ => {
+                panic!("Expected WasmTensor::Numeric variant");
+            }
+        }
+
+        match CartonTensor::try_from(wasm_tensor).unwrap() {
+            CartonTensor::Complex128(storage) => {
+                assert_eq!(storage.view().as_slice().unwrap(), &data);
+            }
+            _ => {
+                panic!("Expected CartonTensor::Complex128 variant");
+            }
+        }
+    }
+
     #[test]
     fn string_tensor_carton_to_wasm() {
         let buffer = vec!["hello".to_string(), "world".to_string()];
-        let mut storage = CartonStorage::<String>::new(vec![2]);
+        let mut storage = CartonStorage::<String>::new(vec![2]).unwrap();
         storage
             .view_mut()
             .as_slice_mut()
@@ -226,7 +270,7 @@ mod tests {
             buffer: buffer.clone(),
             shape: vec![2],
         });
-        let carton_tensor: CartonTensor = tensor.into();
+        let carton_tensor = CartonTensor::try_from(tensor).unwrap();
         match carton_tensor {
             CartonTensor::String(storage) => {
                 assert_eq!(storage.view().as_slice().unwrap(), &buffer);
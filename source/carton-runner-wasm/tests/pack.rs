@@ -64,7 +64,7 @@ async fn test_pack() {
         model_path.to_str().unwrap(),
         RunnerInfo {
             runner_name: "wasm".into(),
-            required_framework_version: semver::VersionReq::parse("=0.0.1").unwrap(),
+            required_framework_version: semver::VersionReq::parse("=0.0.2").unwrap(),
             runner_compat_version: None,
             opts: None,
         },
@@ -13,7 +13,10 @@
 // limitations under the License.
 
 use numpy::PyArrayDyn;
-use pyo3::{types::PyTuple, AsPyPointer, FromPyObject, PyAny, PyDowncastError, PyObject, Python};
+use pyo3::{
+    exceptions::PyValueError, types::PyTuple, AsPyPointer, FromPyObject, PyAny, PyDowncastError,
+    PyObject, PyResult, Python,
+};
 
 pub struct PyStringArrayType<'a> {
     inner: &'a PyAny,
@@ -47,7 +50,10 @@ impl<'py> FromPyObject<'py> for PyStringArrayType<'py> {
 impl<'a> PyStringArrayType<'a> {
     /// Strings are a bit complex and require conversion
     /// This is unfortunate but necessary because most frameworks have different internal representations of strings
-    pub fn to_ndarray(&self) -> ndarray::ArrayD<String> {
+    ///
+    /// Numpy doesn't check that unicode arrays only contain valid codepoints (e.g. lone surrogates can be
+    /// stored) so this returns a `ValueError` with the (flattened) index of the first invalid element
+    pub fn to_ndarray(&self) -> PyResult<ndarray::ArrayD<String>> {
         let item = self.inner;
         let itemsize: usize = item.getattr("itemsize").unwrap().extract().unwrap();
 
@@ -75,7 +81,8 @@ impl<'a> PyStringArrayType<'a> {
         // For each elem
         let data = data
             .chunks(num_chars_per_item)
-            .map(|item| {
+            .enumerate()
+            .map(|(index, item)| {
                 let iter = item
                     .iter()
                     // Reverse and remove trailing zeros
@@ -83,16 +90,20 @@ impl<'a> PyStringArrayType<'a> {
                     .skip_while(|item| **item == 0);
 
                 // Convert to codepoints
-                let chars: Vec<char> = widestring::decode_utf32(iter.copied())
-                    .map(|v| v.unwrap())
-                    .collect();
+                let chars = widestring::decode_utf32(iter.copied())
+                    .collect::<Result<Vec<char>, _>>()
+                    .map_err(|e| {
+                        PyValueError::new_err(format!(
+                            "Element {index} of the string array isn't a valid unicode string: {e}"
+                        ))
+                    })?;
 
                 // Reverse again and collect into a string
-                chars.into_iter().rev().collect()
+                Ok(chars.into_iter().rev().collect())
             })
-            .collect();
+            .collect::<PyResult<Vec<String>>>()?;
 
-        ndarray::ArrayD::from_shape_vec(target_shape, data).unwrap()
+        Ok(ndarray::ArrayD::from_shape_vec(target_shape, data).unwrap())
     }
 
     /// Strings are a bit complex and require conversion
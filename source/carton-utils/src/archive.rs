@@ -18,15 +18,59 @@ use std::{
     future::Future,
     io::Read,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
-use tokio::sync::Semaphore;
+use tokio::{io::AsyncReadExt, sync::Semaphore};
 
 use async_zip::read::fs::ZipFileReader;
 
+use crate::error::ExtractionError;
+
+/// The default max total uncompressed size of the contents of an archive (128 GiB)
+pub const DEFAULT_MAX_TOTAL_SIZE: u64 = 128 * 1024 * 1024 * 1024;
+
+/// The default max uncompressed size of a single file in an archive (32 GiB)
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 32 * 1024 * 1024 * 1024;
+
+/// Limits on the uncompressed size of the contents of an archive.
+/// These protect against decompression bombs when extracting untrusted archives
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    /// The max total uncompressed size (in bytes) of all the files in an archive
+    pub max_total_size: u64,
+
+    /// The max uncompressed size (in bytes) of a single file in an archive
+    pub max_file_size: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: DEFAULT_MAX_TOTAL_SIZE,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
 // Based on https://github.com/Majored/rs-async-zip/blob/main/examples/file_extraction.rs
 /// Extracts a ZIP archive to the output directory
 pub async fn extract_zip<P: AsRef<Path>>(archive: P, out_dir: P) {
+    // TODO: don't panic
+    extract_zip_with_limits(archive, out_dir, ExtractLimits::default())
+        .await
+        .expect("Failed to extract zip file")
+}
+
+/// Extracts a ZIP archive to the output directory and fails if the contents exceed `limits`.
+/// The output directory may contain partially extracted files if this returns an error
+pub async fn extract_zip_with_limits<P: AsRef<Path>>(
+    archive: P,
+    out_dir: P,
+    limits: ExtractLimits,
+) -> Result<(), ExtractionError> {
     let out_dir = out_dir.as_ref();
     let mut handles = Vec::new();
     let reader = ZipFileReader::new(archive)
@@ -37,6 +81,9 @@ pub async fn extract_zip<P: AsRef<Path>>(archive: P, out_dir: P) {
     // This should let 64 file extractions run concurrently
     let open_files_semaphore = Arc::new(Semaphore::new(64));
 
+    // The total number of bytes extracted so far
+    let total_size = Arc::new(AtomicU64::new(0));
+
     for index in 0..reader.file().entries().len() {
         let entry = &reader.file().entries().get(index).unwrap().entry();
 
@@ -74,7 +121,9 @@ pub async fn extract_zip<P: AsRef<Path>>(archive: P, out_dir: P) {
 
             // Spawn a task to extract
             let open_files_semaphore = open_files_semaphore.clone();
+            let total_size = total_size.clone();
             let reader = reader.clone();
+            let filename = entry.filename().to_owned();
             let perms = entry.unix_permissions().unwrap() as u32;
             handles.push(tokio::spawn(async move {
                 // Limit number of open files
@@ -87,56 +136,161 @@ pub async fn extract_zip<P: AsRef<Path>>(archive: P, out_dir: P) {
                     .open(&path)
                     .await
                     .expect("Failed to create extracted file");
-                let mut entry_reader = reader.entry(index).await.expect("Failed to read ZipEntry");
-                tokio::io::copy(&mut entry_reader, &mut writer)
+                let entry_reader = reader.entry(index).await.expect("Failed to read ZipEntry");
+
+                // Don't trust the sizes in the archive. Instead, read at most one byte more than
+                // the limit so we can tell if it was exceeded
+                let limit = limits.max_file_size.min(limits.max_total_size);
+                let mut entry_reader = entry_reader.take(limit + 1);
+                let copied = tokio::io::copy(&mut entry_reader, &mut writer)
                     .await
                     .expect("Failed to copy to extracted file");
+
+                if copied > limits.max_file_size {
+                    return Err(ExtractionError::FileTooLarge {
+                        path: filename,
+                        limit: limits.max_file_size,
+                    });
+                }
+
+                let total = total_size.fetch_add(copied, Ordering::SeqCst) + copied;
+                if total > limits.max_total_size {
+                    return Err(ExtractionError::TotalTooLarge {
+                        limit: limits.max_total_size,
+                    });
+                }
+
+                Ok(())
             }));
         }
     }
 
     // Wait until all the files are extracted
     for handle in handles {
-        handle.await.unwrap();
+        handle.await.unwrap()?;
     }
+
+    Ok(())
 }
 
 /// Extracts a tar.gz archive to the output directory
 pub async fn extract_tar_gz<P: Into<PathBuf>>(archive: P, out_dir: P) {
+    // TODO: don't panic
+    extract_tar_gz_with_limits(archive, out_dir, ExtractLimits::default())
+        .await
+        .expect("Failed to extract tar.gz file")
+}
+
+/// Extracts a tar.gz archive to the output directory and fails if the contents exceed `limits`.
+/// The output directory may contain partially extracted files if this returns an error
+pub async fn extract_tar_gz_with_limits<P: Into<PathBuf>>(
+    archive: P,
+    out_dir: P,
+    limits: ExtractLimits,
+) -> Result<(), ExtractionError> {
     let archive = archive.into();
     let out_dir = out_dir.into();
     tokio::task::spawn_blocking(move || {
         let gz = std::fs::File::open(archive).unwrap();
         let tar = GzDecoder::new(gz);
-        let mut archive = tar::Archive::new(tar);
-        archive.unpack(&out_dir).unwrap();
+        unpack_tar(tar::Archive::new(tar), &out_dir, limits)
     })
     .await
-    .unwrap();
+    .unwrap()
 }
 
 /// Extracts a tar archive to the output directory
 pub async fn extract_tar<P: Into<PathBuf>>(archive: P, out_dir: P) {
+    // TODO: don't panic
+    extract_tar_with_limits(archive, out_dir, ExtractLimits::default())
+        .await
+        .expect("Failed to extract tar file")
+}
+
+/// Extracts a tar archive to the output directory and fails if the contents exceed `limits`.
+/// The output directory may contain partially extracted files if this returns an error
+pub async fn extract_tar_with_limits<P: Into<PathBuf>>(
+    archive: P,
+    out_dir: P,
+    limits: ExtractLimits,
+) -> Result<(), ExtractionError> {
     let archive = archive.into();
     let out_dir = out_dir.into();
     tokio::task::spawn_blocking(move || {
         let tar = std::fs::File::open(archive).unwrap();
-        let mut archive = tar::Archive::new(tar);
-        archive.unpack(&out_dir).unwrap();
+        unpack_tar(tar::Archive::new(tar), &out_dir, limits)
     })
     .await
-    .unwrap();
+    .unwrap()
+}
+
+/// The same as `tar::Archive::unpack`, but checks the size of each entry before unpacking it.
+/// The tar reader yields exactly as many bytes as an entry's header specifies so we can trust the sizes
+fn unpack_tar<R: Read>(
+    mut archive: tar::Archive<R>,
+    out_dir: &Path,
+    limits: ExtractLimits,
+) -> Result<(), ExtractionError> {
+    std::fs::create_dir_all(out_dir).unwrap();
+
+    let mut total_size = 0u64;
+
+    // Like `tar::Archive::unpack`, we unpack directories last so that read-only directories
+    // don't prevent us from unpacking files into them
+    let mut directories = Vec::new();
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+
+        let size = entry.size();
+        if size > limits.max_file_size {
+            return Err(ExtractionError::FileTooLarge {
+                path: entry.path().unwrap().display().to_string(),
+                limit: limits.max_file_size,
+            });
+        }
+
+        total_size += size;
+        if total_size > limits.max_total_size {
+            return Err(ExtractionError::TotalTooLarge {
+                limit: limits.max_total_size,
+            });
+        }
+
+        if entry.header().entry_type() == tar::EntryType::Directory {
+            directories.push(entry);
+        } else {
+            entry.unpack_in(out_dir).unwrap();
+        }
+    }
+
+    for mut dir in directories {
+        dir.unpack_in(out_dir).unwrap();
+    }
+
+    Ok(())
 }
 
 /// Extract an archive (either zip, tar, or tar.gz)
 pub async fn extract(archive: &Path, out_dir: &Path) {
+    // TODO: don't panic
+    extract_with_limits(archive, out_dir, ExtractLimits::default())
+        .await
+        .expect("Failed to extract archive")
+}
+
+/// Extract an archive (either zip, tar, or tar.gz) and fail if the contents exceed `limits`
+pub async fn extract_with_limits(
+    archive: &Path,
+    out_dir: &Path,
+    limits: ExtractLimits,
+) -> Result<(), ExtractionError> {
     // TODO: don't use `expect` and return an error
     let kind = infer::get_from_path(archive)
         .expect("file is read successfully")
         .expect("file type is known");
 
     match kind.mime_type() {
-        "application/zip" => extract_zip(archive, out_dir).await,
+        "application/zip" => extract_zip_with_limits(archive, out_dir, limits).await,
         "application/gzip" => {
             let gz = std::fs::File::open(archive).unwrap();
             let decoder = GzDecoder::new(gz);
@@ -145,18 +299,87 @@ pub async fn extract(archive: &Path, out_dir: &Path) {
             let mut buf = Vec::with_capacity(512);
             decoder.take(512).read_to_end(&mut buf).unwrap();
             if infer::archive::is_tar(&buf) {
-                extract_tar_gz(&archive, &out_dir).await;
+                extract_tar_gz_with_limits(archive, out_dir, limits).await
             } else {
                 panic!("Got a gz file but it wasn't a tar.gz");
             }
         }
-        "application/x-tar" => {
-            extract_tar(&archive, &out_dir).await;
-        }
+        "application/x-tar" => extract_tar_with_limits(archive, out_dir, limits).await,
         other => panic!("Got an unsupported archive type: {other}"),
     }
 }
 
+/// A file in an archive (see `list_entries`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// The path of the file within the archive
+    pub path: String,
+
+    /// The offset of the file's data from the start of the archive
+    pub offset: u64,
+
+    /// The number of bytes the file's data takes up in the archive (i.e. the compressed size for
+    /// compressed files)
+    pub size: u64,
+
+    /// Whether the file is compressed. Only uncompressed files can be read in place
+    pub compressed: bool,
+}
+
+/// List the files in a zip or tar archive along with where their data is in the archive.
+/// This doesn't extract anything. Compressed tar archives (e.g. tar.gz) aren't supported because
+/// their entries can't be found without decompressing the whole archive
+pub fn list_entries(archive: &Path) -> std::io::Result<Vec<ArchiveEntry>> {
+    let kind = infer::get_from_path(archive)?;
+    match kind.map(|k| k.mime_type()) {
+        Some("application/zip") => list_zip_entries(archive),
+        Some("application/x-tar") => list_tar_entries(archive),
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Expected an uncompressed zip or tar archive, but got {}",
+                other.unwrap_or("an unknown file type")
+            ),
+        )),
+    }
+}
+
+fn list_zip_entries(archive: &Path) -> std::io::Result<Vec<ArchiveEntry>> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(archive)?)?;
+    let mut out = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i)?;
+        if entry.is_file() {
+            out.push(ArchiveEntry {
+                path: entry.name().to_owned(),
+                offset: entry.data_start(),
+                size: entry.compressed_size(),
+                compressed: entry.compression() != zip::CompressionMethod::Stored,
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+fn list_tar_entries(archive: &Path) -> std::io::Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(std::fs::File::open(archive)?);
+    let mut out = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            out.push(ArchiveEntry {
+                path: entry.path()?.to_string_lossy().into_owned(),
+                offset: entry.raw_file_position(),
+                size: entry.size(),
+                compressed: false,
+            });
+        }
+    }
+
+    Ok(out)
+}
+
 /// This calls the provided function `do_extract` with a temporary path to extract into and then moves that dir to `target_dir`
 /// This should be atomic so it won't cause broken output if multiple extractions happen at the same time.
 /// This temporary directory is created in `target_dir.parent()` (with a name that starts with `.tmp`). This is necessary because
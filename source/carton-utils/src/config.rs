@@ -45,8 +45,49 @@ pub struct CartonConfig {
     /// Defaults to `~/.carton/cache/`
     /// Env: CARTON_CACHE_DIR
     pub cache_dir: PathBuf,
+
+    /// A PEM file with additional root certificates to trust for downloads (e.g. the CA of a
+    /// TLS-intercepting proxy)
+    /// Defaults to None
+    /// Env: CARTON_CA_BUNDLE
+    pub ca_bundle: Option<PathBuf>,
+
+    /// A proxy URL to use for all downloads. Hosts in the `NO_PROXY` env var are not proxied.
+    /// If this isn't set, the standard `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY` env vars are used
+    /// Defaults to None
+    /// Env: CARTON_PROXY
+    pub proxy: Option<String>,
+
+    /// IPFS HTTP gateways to use when loading `ipfs://` URLs. These are tried in order until one
+    /// of them has the requested content
+    /// Defaults to `DEFAULT_IPFS_GATEWAYS`
+    /// Env: CARTON_IPFS_GATEWAYS (a comma separated list)
+    pub ipfs_gateways: Vec<String>,
+
+    /// How long to wait (in seconds) for a connection, a response or the next chunk of data from a
+    /// server before failing with a timeout error
+    /// Defaults to 30
+    /// Env: CARTON_HTTP_READ_TIMEOUT_SECS
+    pub http_read_timeout_secs: u64,
+
+    /// How many times to retry an HTTP request that failed with a transient error (e.g. a timeout,
+    /// a connection error or a 5xx response)
+    /// Defaults to 3
+    /// Env: CARTON_HTTP_RETRIES
+    pub http_retries: u32,
+
+    /// The model registry used to expand shorthand references like `org/model` (see `Carton::resolve`)
+    /// Defaults to `DEFAULT_REGISTRY_URL`
+    /// Env: CARTON_REGISTRY_URL
+    pub registry_url: String,
 }
 
+/// The IPFS gateways used if `ipfs_gateways` isn't set
+pub const DEFAULT_IPFS_GATEWAYS: &[&str] = &["https://ipfs.io/ipfs/", "https://dweb.link/ipfs/"];
+
+/// The registry used if `registry_url` isn't set
+pub const DEFAULT_REGISTRY_URL: &str = "https://carton.pub";
+
 impl Default for CartonConfig {
     fn default() -> Self {
         Self {
@@ -55,6 +96,15 @@ impl Default for CartonConfig {
                 .to_string()
                 .into(),
             cache_dir: shellexpand::tilde("~/.carton/cache/").to_string().into(),
+            ca_bundle: None,
+            proxy: None,
+            ipfs_gateways: DEFAULT_IPFS_GATEWAYS
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            http_read_timeout_secs: 30,
+            http_retries: 3,
+            registry_url: DEFAULT_REGISTRY_URL.to_owned(),
         }
     }
 }
@@ -98,6 +148,39 @@ impl CartonConfig {
             config.cache_dir = shellexpand::tilde(&v).to_string().into();
         }
 
+        if let Ok(v) = std::env::var("CARTON_CA_BUNDLE") {
+            config.ca_bundle = Some(shellexpand::tilde(&v).to_string().into());
+        }
+
+        if let Ok(v) = std::env::var("CARTON_PROXY") {
+            config.proxy = Some(v);
+        }
+
+        if let Ok(v) = std::env::var("CARTON_IPFS_GATEWAYS") {
+            config.ipfs_gateways = v
+                .split(',')
+                .map(|item| item.trim())
+                .filter(|item| !item.is_empty())
+                .map(|item| item.to_owned())
+                .collect();
+        }
+
+        if let Ok(v) = std::env::var("CARTON_REGISTRY_URL") {
+            config.registry_url = v;
+        }
+
+        if let Ok(v) = std::env::var("CARTON_HTTP_READ_TIMEOUT_SECS") {
+            config.http_read_timeout_secs = v.parse().unwrap_or_else(|_| {
+                panic!("Invalid value for CARTON_HTTP_READ_TIMEOUT_SECS: `{v}`")
+            });
+        }
+
+        if let Ok(v) = std::env::var("CARTON_HTTP_RETRIES") {
+            config.http_retries = v
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value for CARTON_HTTP_RETRIES: `{v}`"));
+        }
+
         config
     }
 }
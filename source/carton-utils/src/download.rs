@@ -12,12 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::path::Path;
-use tokio::sync::mpsc;
+use std::{path::Path, time::Duration};
+use tokio::{io::AsyncWriteExt, sync::mpsc};
 use tokio_util::io::ReaderStream;
 
 use crate::{
@@ -27,14 +27,142 @@ use crate::{
 };
 
 lazy_static! {
+    static ref CLIENT: reqwest::Client = client_builder().build().unwrap();
+}
+
+/// Create a `reqwest::ClientBuilder` with the TLS and proxy settings from the carton config
+/// (see `config.rs`). All clients used for downloads should be created from this.
+pub fn client_builder() -> reqwest::ClientBuilder {
     // TODO: for some reason, if we allow HTTP2, requests hang when making
     // multiple parallel requests (e.g. when loading a model)
     // This is likely a bug within reqwest or something it uses under the hood
-    static ref CLIENT: reqwest::Client = reqwest::ClientBuilder::new()
+    let mut builder = reqwest::ClientBuilder::new()
         .http1_only()
         .use_rustls_tls()
-        .build()
-        .unwrap();
+        // Keep idle connections around so repeated range requests (e.g. when loading a remote
+        // carton) don't need to reconnect every time
+        .pool_idle_timeout(Duration::from_secs(90))
+        .tcp_keepalive(Duration::from_secs(30))
+        .connect_timeout(read_timeout());
+
+    // Trust any additional root certificates
+    if let Some(path) = &CONFIG.ca_bundle {
+        let data = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("Failed to read the CA bundle at {path:?}: {e}"));
+
+        for cert in parse_pem_bundle(&data) {
+            let cert = reqwest::Certificate::from_pem(cert.as_bytes()).unwrap_or_else(|e| {
+                panic!("Invalid certificate in the CA bundle at {path:?}: {e}")
+            });
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    // If a proxy isn't explicitly configured, reqwest uses the standard proxy env vars
+    if let Some(proxy) = &CONFIG.proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .unwrap_or_else(|e| panic!("Invalid proxy URL `{proxy}`: {e}"))
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+
+    builder
+}
+
+/// How long to wait for a response or the next chunk of data before timing out
+fn read_timeout() -> Duration {
+    Duration::from_secs(CONFIG.http_read_timeout_secs)
+}
+
+/// Returns true if a request that failed with this error may succeed if retried
+fn is_transient(e: &DownloadError) -> bool {
+    match e {
+        DownloadError::Timeout { .. } => true,
+        DownloadError::FetchError(e) => e.is_timeout() || e.is_connect(),
+        _ => false,
+    }
+}
+
+/// Send a request, retrying transient failures (timeouts, connection errors and 5xx responses) up to
+/// `CONFIG.http_retries` times with exponential backoff. `make_request` is called once per attempt.
+///
+/// Responses with other non-success statuses are returned as-is so callers can handle them.
+pub async fn send_with_retries(
+    url: &str,
+    make_request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response> {
+    let timeout = read_timeout();
+    let mut attempt = 0;
+    loop {
+        let res = match tokio::time::timeout(timeout, make_request().send()).await {
+            Ok(res) => res.map_err(DownloadError::from),
+            Err(_) => Err(DownloadError::Timeout {
+                url: url.to_owned(),
+                timeout,
+            }),
+        };
+
+        let retry = match &res {
+            Ok(res) => res.status().is_server_error(),
+            Err(e) => is_transient(e),
+        };
+
+        if !retry || attempt >= CONFIG.http_retries {
+            return res;
+        }
+
+        let backoff = Duration::from_millis(500 * 2u64.pow(attempt));
+        match &res {
+            Ok(res) => log::warn!(
+                "Got status {} from {url}. Retrying in {backoff:?}",
+                res.status()
+            ),
+            Err(e) => log::warn!("Request to {url} failed: {e}. Retrying in {backoff:?}"),
+        }
+
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    }
+}
+
+/// Get the next chunk of a response body, failing with a timeout error if no data arrives within
+/// `CONFIG.http_read_timeout_secs`
+pub async fn next_chunk(url: &str, res: &mut reqwest::Response) -> Result<Option<bytes::Bytes>> {
+    let timeout = read_timeout();
+    match tokio::time::timeout(timeout, res.chunk()).await {
+        Ok(chunk) => Ok(chunk?),
+        Err(_) => Err(DownloadError::Timeout {
+            url: url.to_owned(),
+            timeout,
+        }),
+    }
+}
+
+/// Turn a response body into a stream of chunks where each chunk must arrive within
+/// `CONFIG.http_read_timeout_secs` (see `next_chunk`)
+pub fn body_stream(
+    url: String,
+    res: reqwest::Response,
+) -> impl Stream<Item = Result<bytes::Bytes>> {
+    futures::stream::try_unfold((url, res), |(url, mut res)| async move {
+        Ok(next_chunk(&url, &mut res)
+            .await?
+            .map(|chunk| (chunk, (url, res))))
+    })
+}
+
+/// Split a PEM file into the individual certificates it contains
+fn parse_pem_bundle(data: &str) -> Vec<&str> {
+    const END: &str = "-----END CERTIFICATE-----";
+
+    data.split_inclusive(END)
+        .filter_map(|chunk| {
+            chunk
+                .find("-----BEGIN CERTIFICATE-----")
+                .filter(|_| chunk.ends_with(END))
+                .map(|start| &chunk[start..])
+        })
+        .collect()
 }
 
 /// Download a file with progress updates
@@ -55,7 +183,7 @@ pub async fn uncached_download<P: AsRef<Path>>(
 
     // Download and copy to the target file while computing the sha256
     let mut hasher = Sha256::new();
-    let mut res = CLIENT.get(url).send().await?;
+    let mut res = send_with_retries(url, || CLIENT.get(url)).await?;
 
     if !res.status().is_success() {
         // TODO: return an error instead of panic
@@ -65,7 +193,7 @@ pub async fn uncached_download<P: AsRef<Path>>(
     on_content_len(res.content_length());
     let mut downloaded = 0;
 
-    while let Some(chunk) = res.chunk().await? {
+    while let Some(chunk) = next_chunk(url, &mut res).await? {
         // Compute hash in a blocking task
         let b = chunk.clone();
         let jh1 = tokio::task::spawn_blocking(move || hasher.chain_update(&b));
@@ -97,6 +225,115 @@ pub async fn uncached_download<P: AsRef<Path>>(
     Ok(())
 }
 
+/// Download a file to `partial_path`, resuming from any data that's already there (e.g. from an
+/// interrupted download). If the file at `partial_path` is already complete, no request is made.
+/// If the sha256 of the downloaded file doesn't match, the file is deleted so the next attempt starts over.
+// TODO: we should probably do some locking to avoid multiple processes writing to the same partial file
+pub async fn resumable_download(
+    url: &str,
+    sha256: &str,
+    partial_path: &Path,
+    mut on_content_len: impl FnMut(/* total */ Option<u64>),
+    mut progress_update: impl FnMut(/* downloaded */ u64),
+) -> Result<()> {
+    // Hash the data we already have (if any)
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0;
+    if let Ok(f) = tokio::fs::File::open(partial_path).await {
+        let mut stream = ReaderStream::with_capacity(f, 1_000_000);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            downloaded += chunk.len() as u64;
+            hasher = tokio::task::spawn_blocking(move || hasher.chain_update(&chunk))
+                .await
+                .unwrap();
+        }
+    }
+
+    if downloaded > 0 {
+        // Check if we already have the whole file
+        if format!("{:x}", hasher.clone().finalize()) == sha256 {
+            on_content_len(Some(downloaded));
+            progress_update(downloaded);
+            return Ok(());
+        }
+
+        log::info!("Resuming download of {url} from byte {downloaded}");
+    }
+
+    // Only request the data we don't have yet
+    let mut res = send_with_retries(url, || {
+        let req = CLIENT.get(url);
+        if downloaded > 0 {
+            req.header(reqwest::header::RANGE, format!("bytes={downloaded}-"))
+        } else {
+            req
+        }
+    })
+    .await?;
+
+    // The partial file may be corrupted (e.g. if it's at least as large as the actual file) so the server
+    // can't satisfy the range. Start over without a range
+    if downloaded > 0 && res.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        log::info!("Can't resume the download of {url}. Restarting download");
+        hasher = Sha256::new();
+        downloaded = 0;
+        res = send_with_retries(url, || CLIENT.get(url)).await?;
+    }
+
+    let mut outfile = if downloaded > 0 && res.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(partial_path)
+            .await
+            .unwrap()
+    } else if res.status().is_success() {
+        // This is either a new download or the server doesn't support range requests.
+        // Either way, start from the beginning
+        if downloaded > 0 {
+            log::info!("Server doesn't support resuming downloads. Restarting download of {url}");
+        }
+
+        hasher = Sha256::new();
+        downloaded = 0;
+        tokio::fs::File::create(partial_path).await.unwrap()
+    } else {
+        return Err(DownloadError::UnexpectedStatus {
+            url: url.to_owned(),
+            status: res.status(),
+        });
+    };
+
+    on_content_len(res.content_length().map(|len| len + downloaded));
+    progress_update(downloaded);
+
+    while let Some(chunk) = next_chunk(url, &mut res).await? {
+        // Compute hash in a blocking task
+        let b = chunk.clone();
+        let jh1 = tokio::task::spawn_blocking(move || hasher.chain_update(&b));
+
+        outfile.write_all(&chunk).await.unwrap();
+
+        hasher = jh1.await.unwrap();
+        downloaded += chunk.len() as u64;
+        progress_update(downloaded);
+    }
+
+    outfile.flush().await.unwrap();
+
+    // Make sure the sha256 matches the expected value
+    let actual_sha256 = format!("{:x}", hasher.finalize());
+    if sha256 != actual_sha256 {
+        tokio::fs::remove_file(partial_path).await.unwrap();
+        return Err(DownloadError::Sha256Mismatch {
+            actual: actual_sha256,
+            expected: sha256.into(),
+        });
+    }
+
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize)]
 struct InfoJson {
     url: String,
@@ -104,6 +341,7 @@ struct InfoJson {
 
 /// Download a file with progress updates
 /// Either download to a file or get a stream of chunks as the file is being downloaded (or both)
+/// If `chunk_stream` is None, interrupted downloads are resumed on the next call
 pub async fn cached_download<P: AsRef<Path>>(
     url: &str,
     sha256: &str,
@@ -116,6 +354,9 @@ pub async fn cached_download<P: AsRef<Path>>(
     let files_cache_dir = CONFIG.cache_dir.join("files");
     tokio::fs::create_dir_all(&files_cache_dir).await.unwrap();
 
+    // Where to store the file while we're downloading it. This persists across attempts
+    let partial_path = files_cache_dir.join(format!("{sha256}.partial"));
+
     // Download if necessary
     // This is a noop if the target exists already
     with_atomic_extraction(
@@ -126,16 +367,28 @@ pub async fn cached_download<P: AsRef<Path>>(
             tokio::fs::create_dir(&download_dir).await.unwrap();
 
             // Download
-            uncached_download(
-                url,
-                sha256,
-                Some(download_dir.join("file")),
-                chunk_stream.take(),
-                on_content_len,
-                progress_update,
-            )
-            .await
-            .unwrap();
+            if chunk_stream.is_none() {
+                // Nothing needs the chunks as they're downloaded so we can download to a file that
+                // persists across attempts. This lets us resume interrupted downloads of large files
+                resumable_download(url, sha256, &partial_path, on_content_len, progress_update)
+                    .await
+                    .unwrap();
+
+                tokio::fs::rename(&partial_path, download_dir.join("file"))
+                    .await
+                    .unwrap();
+            } else {
+                uncached_download(
+                    url,
+                    sha256,
+                    Some(download_dir.join("file")),
+                    chunk_stream.take(),
+                    on_content_len,
+                    progress_update,
+                )
+                .await
+                .unwrap();
+            }
 
             // Write the info.json file
             let info = InfoJson {
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, DownloadError>;
@@ -25,6 +27,38 @@ pub enum DownloadError {
     #[error("Sha256 Mismatch. Expected {expected}, but got {actual}")]
     Sha256Mismatch { actual: String, expected: String },
 
+    #[error("Timed out after {timeout:?} waiting for a response from {url}")]
+    Timeout { url: String, timeout: Duration },
+
+    #[error("Error fetching URL {url}: {status}")]
+    UnexpectedStatus {
+        url: String,
+        status: reqwest::StatusCode,
+    },
+
     #[error("Error: {0}")]
     Other(&'static str),
 }
+
+impl From<DownloadError> for std::io::Error {
+    fn from(value: DownloadError) -> Self {
+        let kind = match &value {
+            DownloadError::Timeout { .. } => std::io::ErrorKind::TimedOut,
+            DownloadError::FetchError(e) if e.is_timeout() => std::io::ErrorKind::TimedOut,
+            _ => std::io::ErrorKind::Other,
+        };
+
+        std::io::Error::new(kind, value)
+    }
+}
+
+/// Errors returned when extracting an archive
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ExtractionError {
+    #[error("File '{path}' in the archive is larger than the max allowed size of {limit} bytes")]
+    FileTooLarge { path: String, limit: u64 },
+
+    #[error("Archive contents are larger than the max allowed total size of {limit} bytes")]
+    TotalTooLarge { limit: u64 },
+}
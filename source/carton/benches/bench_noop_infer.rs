@@ -96,7 +96,7 @@ platform = "{}"
             "/tmp",
             PackOpts {
                 info,
-                linked_files: None,
+                ..Default::default()
             },
             load_opts,
         ))
@@ -0,0 +1,249 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Convert tensors to Apache Arrow `RecordBatch`es (e.g. to hand model outputs to DataFusion or Polars).
+//! Requires the `arrow` feature
+
+use std::{collections::HashMap, panic::AssertUnwindSafe, ptr::NonNull, sync::Arc};
+
+use ::arrow::{
+    array::{ArrayRef, FixedSizeListArray, PrimitiveArray, StringArray},
+    buffer::{Buffer, ScalarBuffer},
+    datatypes::{
+        ArrowPrimitiveType, Field, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+        Int8Type, Schema, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    },
+    record_batch::RecordBatch,
+};
+
+use crate::{
+    error::{CartonError, Result},
+    types::{GenericTensorStorage, Tensor},
+};
+
+/// Convert a set of tensors (e.g. the outputs of `infer`) into a `RecordBatch` with one column per tensor.
+/// Columns are sorted by name.
+///
+/// The first dimension of each tensor is the row dimension so all the tensors must have the same size
+/// in that dimension. Scalars are treated as a single row.
+///
+/// - Numeric tensors with one dimension become primitive columns (e.g. `Float32`)
+/// - Numeric tensors with more dimensions become (nested) `FixedSizeList` columns. For example, a tensor
+///   with shape `[N, 3, 4]` becomes a column of `N` rows of type `FixedSizeList<FixedSizeList<Float32, 4>, 3>`
+/// - String tensors are handled the same way with `Utf8` values
+///
+/// Numeric data in standard layout is not copied; the returned batch keeps the tensors alive instead.
+/// Nested and complex tensors aren't supported.
+pub fn to_record_batch(tensors: HashMap<String, Tensor>) -> Result<RecordBatch> {
+    let mut tensors: Vec<_> = tensors.into_iter().collect();
+    tensors.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut fields = Vec::with_capacity(tensors.len());
+    let mut columns = Vec::with_capacity(tensors.len());
+    for (name, tensor) in tensors {
+        let column = to_column(&name, tensor)?;
+        fields.push(Field::new(name, column.data_type().clone(), false));
+        columns.push(column);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|e| CartonError::ArrowConversionFailed(e.to_string()))
+}
+
+/// Convert a tensor into a column
+fn to_column(name: &str, tensor: Tensor) -> Result<ArrayRef> {
+    let (values, shape) = match tensor {
+        Tensor::Float(v) => primitive_values::<Float32Type>(v),
+        Tensor::Double(v) => primitive_values::<Float64Type>(v),
+        Tensor::I8(v) => primitive_values::<Int8Type>(v),
+        Tensor::I16(v) => primitive_values::<Int16Type>(v),
+        Tensor::I32(v) => primitive_values::<Int32Type>(v),
+        Tensor::I64(v) => primitive_values::<Int64Type>(v),
+        Tensor::U8(v) => primitive_values::<UInt8Type>(v),
+        Tensor::U16(v) => primitive_values::<UInt16Type>(v),
+        Tensor::U32(v) => primitive_values::<UInt32Type>(v),
+        Tensor::U64(v) => primitive_values::<UInt64Type>(v),
+        Tensor::String(v) => {
+            let view = v.view();
+            let values: StringArray = view.iter().map(Some).collect();
+            (Arc::new(values) as ArrayRef, view.shape().to_vec())
+        }
+        Tensor::Complex64(_) | Tensor::Complex128(_) => {
+            return Err(CartonError::ArrowConversionFailed(format!(
+                "tensor '{name}' is complex and Arrow doesn't have a complex type"
+            )))
+        }
+        Tensor::NestedTensor(_) | Tensor::LabeledNestedTensor { .. } => {
+            return Err(CartonError::ArrowConversionFailed(format!(
+                "tensor '{name}' is a nested tensor"
+            )))
+        }
+    };
+
+    // Wrap the values in fixed size lists for every dimension other than the first
+    let mut column = values;
+    for &size in shape.iter().skip(1).rev() {
+        if size == 0 {
+            return Err(CartonError::ArrowConversionFailed(format!(
+                "tensor '{name}' has shape {shape:?}. Only the first dimension can be empty"
+            )));
+        }
+
+        let size = i32::try_from(size).map_err(|_| {
+            CartonError::ArrowConversionFailed(format!(
+                "tensor '{name}' has shape {shape:?}, which has a dimension that's too large for Arrow"
+            ))
+        })?;
+
+        let item = Arc::new(Field::new("item", column.data_type().clone(), false));
+        column = Arc::new(FixedSizeListArray::new(item, size, column, None));
+    }
+
+    Ok(column)
+}
+
+/// Keeps tensor storage alive while Arrow uses it
+struct TensorOwner<T: 'static> {
+    _storage: AssertUnwindSafe<GenericTensorStorage<T>>,
+}
+
+/// Get a flat array of the values in `storage` along with the shape of the tensor.
+/// This doesn't copy if `storage` is in standard layout
+fn primitive_values<A>(storage: GenericTensorStorage<A::Native>) -> (ArrayRef, Vec<usize>)
+where
+    A: ArrowPrimitiveType,
+    A::Native: Send + Sync,
+{
+    // Copy the data if we can't use it in place
+    let (shape, ptr, numel, copied) = {
+        let view = storage.view();
+        let ptr = view.as_ptr();
+        let zero_copy = view.is_standard_layout()
+            && !view.is_empty()
+            && ptr as usize % std::mem::align_of::<A::Native>() == 0;
+
+        let copied = if zero_copy {
+            None
+        } else {
+            Some(view.iter().copied().collect::<Vec<_>>())
+        };
+
+        (view.shape().to_vec(), ptr, view.len(), copied)
+    };
+
+    let values: ScalarBuffer<A::Native> = match copied {
+        Some(copied) => copied.into(),
+        None => {
+            let ptr = NonNull::new(ptr as *mut u8).unwrap();
+            let len = numel * std::mem::size_of::<A::Native>();
+
+            // SAFETY: `ptr` points to `len` bytes of initialized data owned by `storage` and the data
+            // doesn't move or get freed as long as `storage` is alive (which the buffer ensures)
+            let buffer = unsafe {
+                Buffer::from_custom_allocation(
+                    ptr,
+                    len,
+                    Arc::new(TensorOwner {
+                        _storage: AssertUnwindSafe(storage),
+                    }),
+                )
+            };
+
+            ScalarBuffer::new(buffer, 0, numel)
+        }
+    };
+
+    (
+        Arc::new(PrimitiveArray::<A>::new(values, None)) as ArrayRef,
+        shape,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use ::arrow::{
+        array::{Array, AsArray},
+        datatypes::{DataType, Float32Type, Int64Type},
+    };
+
+    use super::to_record_batch;
+    use crate::types::Tensor;
+
+    #[test]
+    fn test_to_record_batch() {
+        let floats = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2, 3], vec![1f32, 2., 3., 4., 5., 6.]).unwrap(),
+        );
+        let ints = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![2], vec![7i64, 8]).unwrap());
+        let strings = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2], vec!["a".to_owned(), "b".to_owned()]).unwrap(),
+        );
+
+        let batch = to_record_batch(
+            [
+                ("floats".to_owned(), floats),
+                ("ints".to_owned(), ints),
+                ("strings".to_owned(), strings),
+            ]
+            .into(),
+        )
+        .unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 3);
+
+        // `floats` is a column of fixed size lists
+        let floats = batch.column(0).as_fixed_size_list();
+        assert_eq!(floats.value_length(), 3);
+        assert_eq!(
+            floats.values().as_primitive::<Float32Type>().values(),
+            &[1f32, 2., 3., 4., 5., 6.]
+        );
+
+        let ints = batch.column(1).as_primitive::<Int64Type>();
+        assert_eq!(ints.values(), &[7, 8]);
+
+        let strings = batch.column(2).as_string::<i32>();
+        assert_eq!(strings.value(1), "b");
+        assert_eq!(batch.schema().field(2).data_type(), &DataType::Utf8);
+
+        // Non-standard layouts are copied
+        let transposed = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1f32, 2., 3., 4.])
+                .unwrap()
+                .reversed_axes(),
+        );
+        let batch = to_record_batch([("t".to_owned(), transposed)].into()).unwrap();
+        let t = batch.column(0).as_fixed_size_list();
+        assert_eq!(t.len(), 2);
+        assert_eq!(
+            t.values().as_primitive::<Float32Type>().values(),
+            &[1f32, 3., 2., 4.]
+        );
+    }
+
+    #[test]
+    fn test_to_record_batch_errors() {
+        let a = Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![2]));
+        let b = Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![3]));
+
+        // Mismatched row counts
+        assert!(
+            to_record_batch([("a".to_owned(), a.clone()), ("b".to_owned(), b)].into()).is_err()
+        );
+
+        // Nested tensors
+        assert!(to_record_batch([("n".to_owned(), Tensor::NestedTensor(vec![a]))].into()).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Loading a model from a bundle of several packed models (see `Carton::load_from_bundle`)
+
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{ready, Poll},
+};
+
+use async_trait::async_trait;
+use carton_utils::archive::ArchiveEntry;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, ReadBuf};
+use zipfs::GetReader;
+
+use crate::{error::CartonError, load::ReturnType, types::LoadOpts};
+
+/// Load the model named `model_name` from the bundle at `bundle_path`.
+/// The model is read in place so the other models in the bundle aren't extracted
+pub(crate) async fn load(bundle_path: &Path, model_name: &str, opts: LoadOpts) -> ReturnType {
+    let path = bundle_path.to_owned();
+    let entries = tokio::task::spawn_blocking(move || carton_utils::archive::list_entries(&path))
+        .await
+        .unwrap()?;
+
+    let entry = find_model(entries, model_name)?;
+    if entry.compressed {
+        return Err(CartonError::CompressedBundleEntry(entry.path));
+    }
+
+    let entry = BundleEntry {
+        bundle_path: bundle_path.to_owned(),
+        offset: entry.offset,
+        len: entry.size,
+    };
+
+    // The offsets of stored entries are relative to the bundle so we don't memory map anything
+    crate::load::unwrap_container(entry, opts, false, None).await
+}
+
+/// Find the entry for `model_name` (with or without the `.carton` extension)
+fn find_model(entries: Vec<ArchiveEntry>, model_name: &str) -> crate::error::Result<ArchiveEntry> {
+    let mut available = Vec::new();
+    for entry in entries {
+        let name = entry.path.trim_start_matches("./");
+        if name == model_name || name.strip_suffix(".carton") == Some(model_name) {
+            return Ok(entry);
+        }
+
+        if let Some(name) = name.strip_suffix(".carton") {
+            available.push(name.to_owned());
+        }
+    }
+
+    available.sort();
+    Err(CartonError::ModelNotInBundle {
+        name: model_name.to_owned(),
+        available,
+    })
+}
+
+/// A model stored uncompressed within a bundle
+struct BundleEntry {
+    bundle_path: PathBuf,
+    offset: u64,
+    len: u64,
+}
+
+#[async_trait]
+impl GetReader for BundleEntry {
+    type R = FileSlice;
+
+    async fn get(&self) -> Self::R {
+        let mut file = tokio::fs::File::open(&self.bundle_path).await.unwrap();
+        file.seek(SeekFrom::Start(self.offset)).await.unwrap();
+
+        FileSlice {
+            file,
+            start: self.offset,
+            len: self.len,
+            pos: 0,
+        }
+    }
+}
+
+/// Implements [`AsyncRead`] and [`AsyncSeek`] for the `len` bytes of `file` starting at `start`
+/// (as if they were a separate file)
+struct FileSlice {
+    file: tokio::fs::File,
+    start: u64,
+    len: u64,
+
+    /// The current position relative to `start`
+    pos: u64,
+}
+
+impl AsyncRead for FileSlice {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let remaining = self.len.saturating_sub(self.pos);
+        if remaining == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Don't read past the end of the slice (this is the same as `tokio::io::Take`)
+        let mut limited = buf.take(remaining.min(usize::MAX as u64) as usize);
+        ready!(Pin::new(&mut self.file).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+
+        // SAFETY: `limited` is a view of the unfilled part of `buf` and the read initialized `n` bytes of it
+        unsafe { buf.assume_init(n) };
+        buf.advance(n);
+        self.pos += n as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for FileSlice {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(offset) => self.len.saturating_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.saturating_add_signed(offset),
+        }
+        .min(self.len);
+
+        let start = self.start;
+        Pin::new(&mut self.file).start_seek(SeekFrom::Start(start + target))
+    }
+
+    fn poll_complete(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<std::io::Result<u64>> {
+        let absolute = ready!(Pin::new(&mut self.file).poll_complete(cx))?;
+        self.pos = absolute - self.start;
+        Poll::Ready(Ok(self.pos))
+    }
+}
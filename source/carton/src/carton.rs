@@ -15,26 +15,122 @@
 use std::collections::HashMap;
 
 use carton_macros::for_each_carton_type;
-use futures::Stream;
+use futures::{Stream, StreamExt};
 
 use crate::error::Result;
 use crate::load::discover_or_get_runner_and_launch;
 use crate::types::DataType;
 use crate::{
-    conversion_utils::convert_map,
+    conversion_utils::{convert_map, convert_opt_map},
     error::CartonError,
-    info::CartonInfoWithExtras,
+    info::{CartonInfoWithExtras, Example, PossiblyLoaded, TensorOrMisc},
     load::Runner,
-    types::{LoadOpts, PackOpts, SealHandle, Tensor},
+    queue::{InferenceQueue, QueueCaller, QueueOpts, QueuePermit, QueueStats},
+    types::{
+        tensor_map_bytes, DeviceMemory, LazyTensor, LoadOpts, PackOpts, RunnerOpt, SealHandle,
+        Tensor,
+    },
 };
 
+/// How long `Carton::ping` waits for the runner to respond
+#[cfg(not(target_family = "wasm"))]
+pub const PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A loaded model.
+///
+/// `Carton` is cheap to clone and all clones share the same runner. It's `Send` and `Sync` so it can be
+/// shared across threads and tasks. Concurrent calls to `infer` (and the other inference methods) are
+/// safe: every request to the runner is tagged with a unique ID and each response is routed back to the
+/// request that created it. Whether requests actually run in parallel depends on the runner.
+/// To limit the number of requests in flight (and control which ones go first), see `with_queue`.
 pub struct Carton {
-    info: CartonInfoWithExtras,
-    runner: Runner,
+    info: std::sync::Arc<CartonInfoWithExtras>,
+    runner: std::sync::Arc<Runner>,
+
+    /// An optional queue for inference requests (see `with_queue`). This is shared across clones, but each
+    /// clone is a separate caller of the queue
+    queue: Option<QueueCaller>,
 
     /// An optional temp dir. This is used in `load_unpacked` to make sure the directory doesn't get
     /// deleted while we need it
-    _tempdir: Option<tempfile::TempDir>,
+    _tempdir: Option<std::sync::Arc<tempfile::TempDir>>,
+
+    /// The memory used by the most recent inference request (see `last_infer_memory`). This is shared
+    /// across clones
+    last_infer_memory: std::sync::Arc<std::sync::Mutex<Option<InferMemory>>>,
+}
+
+/// The estimated memory used by the inputs and outputs of an inference request (see
+/// `Carton::last_infer_memory` and `tensor_map_bytes`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InferMemory {
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+}
+
+impl InferMemory {
+    pub fn total_bytes(&self) -> u64 {
+        self.input_bytes + self.output_bytes
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+type BoxedOutputStream<'a> =
+    std::pin::Pin<Box<dyn Stream<Item = Result<HashMap<String, Tensor>>> + Send + 'a>>;
+
+/// The stream returned by `Carton::streaming_infer_with_deadline`. It yields the same items as
+/// `streaming_infer` and ends at the deadline. Use `truncated` after the stream ends to tell whether it
+/// was cut off by the deadline or finished on its own
+#[cfg(not(target_family = "wasm"))]
+pub struct DeadlineStream<'a> {
+    inner: BoxedOutputStream<'a>,
+    truncated: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl DeadlineStream<'_> {
+    /// Whether the stream was ended by the deadline before the model finished
+    pub fn truncated(&self) -> bool {
+        self.truncated.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Stream for DeadlineStream<'_> {
+    type Item = Result<HashMap<String, Tensor>>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// Tells the runner to free the outputs of a lazy inference request when dropped (see `infer_lazy`)
+struct ReleaseLazyOutputs {
+    runner: std::sync::Arc<Runner>,
+    id: u64,
+}
+
+impl Drop for ReleaseLazyOutputs {
+    fn drop(&mut self) {
+        match self.runner.as_ref() {
+            Runner::V1(runner) => runner.release_lazy_outputs(self.id),
+        }
+    }
+}
+
+impl Clone for Carton {
+    fn clone(&self) -> Self {
+        Self {
+            info: self.info.clone(),
+            runner: self.runner.clone(),
+            queue: self.queue.as_ref().map(|q| q.duplicate()),
+            _tempdir: self._tempdir.clone(),
+            last_infer_memory: self.last_infer_memory.clone(),
+        }
+    }
 }
 
 impl Carton {
@@ -43,9 +139,62 @@ impl Carton {
         let (info, runner) = crate::load::load(url_or_path.as_ref(), opts).await?;
 
         Ok(Self {
-            info,
-            runner: runner.unwrap(),
+            info: std::sync::Arc::new(info),
+            runner: std::sync::Arc::new(runner.unwrap()),
+            queue: None,
+            _tempdir: None,
+            last_infer_memory: Default::default(),
+        })
+    }
+
+    /// Load a model with a runner from an unpacked runner directory (containing a `runner.toml` and the
+    /// runner binary) instead of finding or installing one. This doesn't download or install anything so
+    /// it's useful for testing runners during development. The runner in `runner.toml` with the same name
+    /// as the model's runner (or `override_runner_name`) is used. This replaces `runner_path_override`
+    #[cfg(all(feature = "test-utils", not(target_family = "wasm")))]
+    pub async fn load_with_local_runner_dir<P: AsRef<str>, D: AsRef<std::path::Path>>(
+        url_or_path: P,
+        runner_dir: D,
+        mut opts: LoadOpts,
+    ) -> Result<Self> {
+        let runner_name = match &opts.override_runner_name {
+            Some(name) => name.clone(),
+            None => {
+                crate::load::get_carton_info(url_or_path.as_ref())
+                    .await?
+                    .info
+                    .runner
+                    .runner_name
+            }
+        };
+
+        let runner_path = crate::load::find_local_runner(runner_dir.as_ref(), &runner_name).await?;
+        opts.runner_path_override = Some(runner_path);
+
+        Self::load(url_or_path, opts).await
+    }
+
+    /// Load a model from a bundle of several packed models (a tar or zip file containing `.carton` files).
+    /// `model_name` is the path of the model within the bundle with or without the `.carton` extension
+    /// (e.g. `small` for `small.carton`).
+    ///
+    /// The model is read in place so the other models in the bundle aren't extracted. Because of this,
+    /// models in a zip bundle must be stored uncompressed (cartons are already compressed so this doesn't
+    /// make the bundle much larger). Compressed tar bundles (e.g. `.tar.gz`) aren't supported
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn load_from_bundle<P: AsRef<std::path::Path>>(
+        bundle_path: P,
+        model_name: &str,
+        opts: LoadOpts,
+    ) -> Result<Self> {
+        let (info, runner) = crate::bundle::load(bundle_path.as_ref(), model_name, opts).await?;
+
+        Ok(Self {
+            info: std::sync::Arc::new(info),
+            runner: std::sync::Arc::new(runner.unwrap()),
+            queue: None,
             _tempdir: None,
+            last_infer_memory: Default::default(),
         })
     }
 
@@ -56,18 +205,248 @@ impl Carton {
         I: IntoIterator<Item = (S, Tensor)>,
         String: From<S>,
     {
-        match &self.runner {
-            Runner::V1(runner) => runner
-                .infer_with_inputs(
-                    tensors
-                        .into_iter()
-                        .map(|(k, v)| (k.into(), v.into()))
-                        .collect(),
+        self.infer_inner(convert_inputs(tensors), None).await
+    }
+
+    /// Infer using a set of borrowed inputs. This is useful when running many inferences with the same
+    /// (or mostly the same) inputs as it avoids having to clone them for each call.
+    ///
+    /// For example:
+    /// ```ignore
+    /// let inputs: HashMap<String, Tensor> = ...;
+    /// let out1 = model.infer_ref(&inputs).await?;
+    /// let out2 = model.infer_ref(&inputs).await?;
+    /// ```
+    pub async fn infer_ref<'a, I, S>(&self, tensors: I) -> Result<HashMap<String, Tensor>>
+    where
+        I: IntoIterator<Item = (S, &'a Tensor)>,
+        String: From<S>,
+    {
+        self.infer_inner(
+            tensors
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+            None,
+        )
+        .await
+    }
+
+    /// Infer using a set of inputs along with runner options that only apply to this request
+    /// (e.g. generation parameters for text generation models).
+    ///
+    /// These are passed to the runner in addition to the `runner_opts` specified at load time.
+    /// Runners ignore options they don't recognize. Runners built with older versions of Carton don't
+    /// support per-request options at all so this returns an error for them
+    pub async fn infer_with_opts<I, S>(
+        &self,
+        tensors: I,
+        opts: HashMap<String, RunnerOpt>,
+    ) -> Result<HashMap<String, Tensor>>
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        String: From<S>,
+    {
+        self.infer_inner(convert_inputs(tensors), Some(opts)).await
+    }
+
+    /// Infer using an ordered list of inputs and get the outputs as an ordered list.
+    ///
+    /// Outputs are in the same order as the model's output specs (see `CartonInfo::outputs`). Any
+    /// outputs that aren't in the specs come after those and are sorted by name. This means the order
+    /// of the results is stable (unlike iterating over the `HashMap` returned by `infer`).
+    ///
+    /// Input names must be unique.
+    pub async fn infer_ordered<I, S>(&self, tensors: I) -> Result<Vec<(String, Tensor)>>
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        String: From<S>,
+    {
+        let tensors: Vec<(String, Tensor)> =
+            tensors.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        let num_inputs = tensors.len();
+
+        let inputs = convert_inputs::<_, String>(tensors);
+        if inputs.len() != num_inputs {
+            return Err(CartonError::Other(
+                "Input names passed to `infer_ordered` must be unique",
+            ));
+        }
+
+        let mut outputs = self.infer_inner(inputs, None).await?;
+
+        // First, add outputs in the order of the output specs
+        let mut out = Vec::with_capacity(outputs.len());
+        if let Some(specs) = &self.info.info.outputs {
+            for spec in specs {
+                if let Some(tensor) = outputs.remove(&spec.name) {
+                    out.push((spec.name.clone(), tensor));
+                }
+            }
+        }
+
+        // Then add any remaining outputs sorted by name
+        let mut remaining: Vec<_> = outputs.into_iter().collect();
+        remaining.sort_by(|a, b| a.0.cmp(&b.0));
+        out.extend(remaining);
+
+        Ok(out)
+    }
+
+    /// Infer using a set of inputs and return the outputs as an Arrow `RecordBatch` with one column per
+    /// output. See `carton::arrow::to_record_batch` for how tensors are converted.
+    /// Requires the `arrow` feature
+    #[cfg(feature = "arrow")]
+    pub async fn infer_record_batch<I, S>(
+        &self,
+        tensors: I,
+    ) -> Result<::arrow::record_batch::RecordBatch>
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        String: From<S>,
+    {
+        crate::arrow::to_record_batch(self.infer(tensors).await?)
+    }
+
+    /// Infer using a set of inputs, but leave the outputs in the runner until they're used.
+    /// Each output is only fetched from the runner the first time `get` is called on it. This is useful
+    /// for models with large outputs when only some of them are needed.
+    ///
+    /// Outputs that are never fetched are freed once all the returned values are dropped.
+    /// If the runner doesn't support lazy outputs, this runs inference normally and all the outputs
+    /// are already loaded.
+    pub async fn infer_lazy<I, S>(&self, tensors: I) -> Result<HashMap<String, LazyTensor>>
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        String: From<S>,
+    {
+        let runner = match self.runner.as_ref() {
+            Runner::V1(runner) => runner,
+        };
+
+        if !runner.supports_lazy_outputs() {
+            return Ok(self
+                .infer(tensors)
+                .await?
+                .into_iter()
+                .map(|(k, v)| {
+                    (
+                        k,
+                        LazyTensor {
+                            inner: PossiblyLoaded::from_value(Ok(v)),
+                        },
+                    )
+                })
+                .collect());
+        }
+
+        let tensors = self.fill_optional_inputs(convert_inputs(tensors)).await?;
+        let (id, names) = {
+            let _permit = self.wait_for_queue().await;
+            runner.infer_with_inputs_lazy(tensors, None).await?
+        };
+
+        // Frees any outputs that weren't fetched once all the loaders are gone
+        let release = std::sync::Arc::new(ReleaseLazyOutputs {
+            runner: self.runner.clone(),
+            id,
+        });
+
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let release = release.clone();
+                let key = name.clone();
+                let loader = Box::pin(async move {
+                    let res = match release.runner.as_ref() {
+                        Runner::V1(runner) => {
+                            runner.fetch_lazy_output(release.id, key.clone()).await
+                        }
+                    };
+
+                    res.map(|v| v.into())
+                        .map_err(|e| format!("Failed to fetch output `{key}` from the runner: {e}"))
+                });
+
+                (
+                    name,
+                    LazyTensor {
+                        inner: PossiblyLoaded::from_loader(loader),
+                    },
                 )
+            })
+            .collect())
+    }
+
+    async fn infer_inner(
+        &self,
+        tensors: HashMap<String, runner_interface_v1::types::Tensor>,
+        opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> Result<HashMap<String, Tensor>> {
+        self.check_infer_opts(&opts)?;
+        let tensors = self.fill_optional_inputs(tensors).await?;
+        let input_bytes = tensors
+            .values()
+            .map(crate::runner_interface::v1::types::estimated_bytes)
+            .sum();
+
+        let _permit = self.wait_for_queue().await;
+        let outputs = match self.runner.as_ref() {
+            Runner::V1(runner) => runner
+                .infer_with_inputs(tensors, convert_opt_map(opts))
                 .await
-                .map_err(|e| CartonError::ErrorFromRunner(e))
+                .map_err(CartonError::from)
                 .map(|v| convert_map(v)),
+        }?;
+
+        *self.last_infer_memory.lock().unwrap() = Some(InferMemory {
+            input_bytes,
+            output_bytes: tensor_map_bytes(&outputs),
+        });
+
+        Ok(outputs)
+    }
+
+    /// The estimated memory used by the inputs and outputs of the most recent successful call to
+    /// `infer` (or one of its variants that returns all outputs at once) on this carton or any of
+    /// its clones. Returns `None` if no such inference has completed yet.
+    ///
+    /// This can be useful for sizing batches and detecting unexpectedly large tensors. String
+    /// tensors are estimated (see `Tensor::estimated_bytes`)
+    pub fn last_infer_memory(&self) -> Option<InferMemory> {
+        *self.last_infer_memory.lock().unwrap()
+    }
+
+    /// Fill in defaults for any optional inputs that weren't provided and make sure all required
+    /// inputs are present. This does nothing if the model doesn't specify its inputs or if it was packed
+    /// before optional inputs were supported
+    async fn fill_optional_inputs(
+        &self,
+        mut tensors: HashMap<String, runner_interface_v1::types::Tensor>,
+    ) -> Result<HashMap<String, runner_interface_v1::types::Tensor>> {
+        let specs = match &self.info.info.inputs {
+            Some(specs) if self.info.enforce_required_inputs => specs,
+            _ => return Ok(tensors),
+        };
+
+        let mut missing = Vec::new();
+        for spec in specs {
+            if tensors.contains_key(&spec.name) {
+                continue;
+            }
+
+            if !spec.optional {
+                missing.push(spec.name.clone());
+            } else if let Some(default) = &spec.default {
+                tensors.insert(spec.name.clone(), default.get().await.into());
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(CartonError::MissingInputs(missing));
         }
+
+        Ok(tensors)
     }
 
     /// Infer using a set of inputs. This method has support for intermediate streaming responses
@@ -80,18 +459,180 @@ impl Carton {
         I: IntoIterator<Item = (S, Tensor)> + 'a,
         String: From<S>,
     {
-        match &self.runner {
+        self.streaming_infer_inner(convert_inputs(tensors), None)
+    }
+
+    /// Infer using a set of inputs with a time limit. If the model doesn't finish in time, this returns
+    /// `CartonError::DeadlineExceeded` and the request is cancelled
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn infer_with_deadline<I, S>(
+        &self,
+        tensors: I,
+        timeout: std::time::Duration,
+    ) -> Result<HashMap<String, Tensor>>
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        String: From<S>,
+    {
+        // Tokio's timer may not report an expired deadline until it ticks so check it up front
+        if timeout.is_zero() {
+            return Err(CartonError::DeadlineExceeded(timeout));
+        }
+
+        tokio::time::timeout(timeout, self.infer(tensors))
+            .await
+            .map_err(|_| CartonError::DeadlineExceeded(timeout))?
+    }
+
+    /// Check that the runner is alive and responsive without running inference (e.g. for liveness or
+    /// readiness probes). Returns the round trip time.
+    ///
+    /// The runner responds without waiting for in-flight inference requests to finish. If it doesn't
+    /// respond within `PING_TIMEOUT` (5 seconds), this returns `CartonError::RunnerNotResponding`
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn ping(&self) -> Result<std::time::Duration> {
+        let runner = match self.runner.as_ref() {
+            Runner::V1(runner) => runner,
+        };
+
+        if !runner.supports_ping() {
+            return Err(CartonError::Other(
+                "This runner doesn't support `ping`. It may have been built with an older version of Carton",
+            ));
+        }
+
+        let start = std::time::Instant::now();
+        tokio::time::timeout(PING_TIMEOUT, runner.ping())
+            .await
+            .map_err(|_| CartonError::RunnerNotResponding(PING_TIMEOUT))?
+            .map_err(CartonError::from)?;
+
+        Ok(start.elapsed())
+    }
+
+    /// Get the amount of device (e.g. GPU) memory used by the loaded model. This is useful for capacity
+    /// planning or for detecting leaks across reloads.
+    ///
+    /// Returns `None` if the model is running on the CPU or the runner doesn't report device memory usage.
+    /// What's included depends on the runner. For example, the torch runner currently only reports the size
+    /// of the model's parameters
+    pub async fn device_memory_usage(&self) -> Result<Option<DeviceMemory>> {
+        let runner = match self.runner.as_ref() {
+            Runner::V1(runner) => runner,
+        };
+
+        // Runners built with older versions of Carton don't support this
+        if !runner.supports_device_memory() {
+            return Ok(None);
+        }
+
+        let memory = runner.device_memory_usage().await?;
+
+        Ok(memory.map(Into::into))
+    }
+
+    /// Same as `streaming_infer`, but the stream ends once `timeout` has elapsed. If the model is still running
+    /// at that point, the stream ends cleanly with the items produced so far and `DeadlineStream::truncated`
+    /// returns true. This is useful for latency-bounded generation (e.g. "generate for up to 500ms and return
+    /// what you have").
+    ///
+    /// The time limit is also passed to the runner as the `max_time` runner option (in seconds) so runners
+    /// that support it can stop generating at the deadline (runners built with older versions of Carton don't
+    /// support per-request options so they don't get it). Any request that's still running at the deadline
+    /// is cancelled
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn streaming_infer_with_deadline<'a, I, S>(
+        &'a self,
+        tensors: I,
+        timeout: std::time::Duration,
+    ) -> DeadlineStream<'a>
+    where
+        I: IntoIterator<Item = (S, Tensor)> + 'a,
+        String: From<S>,
+    {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let opts = match self.runner.as_ref() {
+            Runner::V1(runner) if runner.supports_infer_opts() => Some(
+                [(
+                    "max_time".to_owned(),
+                    RunnerOpt::Double(timeout.as_secs_f64()),
+                )]
+                .into(),
+            ),
+            Runner::V1(_) => None,
+        };
+
+        let truncated = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = truncated.clone();
+        let stream = self.streaming_infer_inner(convert_inputs(tensors), opts);
+        let inner = async_stream::stream! {
+            futures::pin_mut!(stream);
+            loop {
+                // Tokio's timer may not report an expired deadline until it ticks so check it before each item
+                if tokio::time::Instant::now() >= deadline {
+                    flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                    break;
+                }
+
+                match tokio::time::timeout_at(deadline, stream.next()).await {
+                    Ok(Some(item)) => yield item,
+                    Ok(None) => break,
+                    Err(_) => {
+                        flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                        break;
+                    }
+                }
+            }
+        };
+
+        DeadlineStream {
+            inner: Box::pin(inner),
+            truncated,
+        }
+    }
+
+    /// Per-request runner options are only supported by runners built with newer versions of Carton
+    fn check_infer_opts(&self, opts: &Option<HashMap<String, RunnerOpt>>) -> Result<()> {
+        let supported = match self.runner.as_ref() {
+            Runner::V1(runner) => runner.supports_infer_opts(),
+        };
+
+        if opts.is_some() && !supported {
+            return Err(CartonError::Other(
+                "This runner doesn't support per-request runner options. It may have been built with an older version of Carton",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn streaming_infer_inner(
+        &self,
+        tensors: HashMap<String, runner_interface_v1::types::Tensor>,
+        opts: Option<HashMap<String, RunnerOpt>>,
+    ) -> impl Stream<Item = Result<HashMap<String, Tensor>>> + '_ {
+        match self.runner.as_ref() {
             Runner::V1(runner) => {
                 async_stream::stream! {
+                    if let Err(e) = self.check_infer_opts(&opts) {
+                        yield Err(e);
+                        return;
+                    }
+
+                    let tensors = match self.fill_optional_inputs(tensors).await {
+                        Ok(tensors) => tensors,
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        }
+                    };
+
+                    // Streaming requests are in flight until the last response
+                    let _permit = self.wait_for_queue().await;
                     for await item in runner
-                        .streaming_infer_with_inputs(
-                            tensors
-                                .into_iter()
-                                .map(|(k, v)| (k.into(), v.into()))
-                                .collect(),
-                        )
+                        .streaming_infer_with_inputs(tensors, convert_opt_map(opts))
                         .await {
-                            yield item.map_err(|e| CartonError::ErrorFromRunner(e))
+                            yield item.map_err(CartonError::from)
                                 .map(|v| convert_map(v))
                         }
                 }
@@ -103,51 +644,111 @@ impl Carton {
     /// This lets carton start processing tensors (e.g. moving them to the correct devices) before
     /// actually running inference and can lead to more efficient pipelines.
     pub async fn seal(&self, tensors: HashMap<String, Tensor>) -> Result<SealHandle> {
-        match &self.runner {
-            Runner::V1(runner) => Ok(SealHandle(
-                runner
-                    .seal(convert_map(tensors))
-                    .await
-                    .map_err(|e| CartonError::ErrorFromRunner(e))?,
-            )),
+        let tensors = self.fill_optional_inputs(convert_map(tensors)).await?;
+        match self.runner.as_ref() {
+            Runner::V1(runner) => Ok(SealHandle(runner.seal(tensors).await?)),
         }
     }
 
     /// Infer using a handle from `seal`.
     /// This approach can make inference pipelines more efficient vs just using `infer`
     pub async fn infer_with_handle(&self, handle: SealHandle) -> Result<HashMap<String, Tensor>> {
-        match &self.runner {
-            Runner::V1(runner) => Ok(convert_map(
-                runner
-                    .infer_with_handle(handle.0)
-                    .await
-                    .map_err(|e| CartonError::ErrorFromRunner(e))?,
-            )),
+        let _permit = self.wait_for_queue().await;
+        match self.runner.as_ref() {
+            Runner::V1(runner) => Ok(convert_map(runner.infer_with_handle(handle.0).await?)),
         }
     }
 
+    /// Run inference on a set of inputs and package the inputs along with the outputs the model produced
+    /// as an `Example`. This is useful when authoring models as the returned value can be added directly to
+    /// the `examples` in `PackOpts`.
+    ///
+    /// All the tensors in the returned `Example` are owned copies so they don't depend on the runner
+    /// and can be serialized later
+    pub async fn capture_example(
+        &self,
+        name: Option<String>,
+        inputs: HashMap<String, Tensor>,
+    ) -> Result<Example> {
+        // `infer` takes ownership of the inputs so we keep a copy around to store in the example
+        // Note: `clone` on a tensor always makes an owned copy of the data
+        let sample_in = inputs.clone();
+
+        // The outputs can be backed by memory from the runner interface so we copy them as well
+        let sample_out: HashMap<_, _> = self
+            .infer(inputs)
+            .await?
+            .into_iter()
+            .map(|(k, v)| (k, v.clone()))
+            .collect();
+
+        let to_example_map = |tensors: HashMap<String, Tensor>| -> HashMap<String, TensorOrMisc> {
+            tensors
+                .into_iter()
+                .map(|(k, v)| (k, TensorOrMisc::Tensor(PossiblyLoaded::from_value(v))))
+                .collect()
+        };
+
+        Ok(Example {
+            name,
+            description: None,
+            inputs: to_example_map(sample_in),
+            sample_out: to_example_map(sample_out),
+        })
+    }
+
     /// Pack a carton given a path and options. Returns the path of the output file
     #[cfg(not(target_family = "wasm"))]
     pub async fn pack<O, P: AsRef<str>>(path: P, opts: O) -> Result<std::path::PathBuf>
     where
         O: Into<PackOpts>,
     {
-        use std::sync::Arc;
+        let mut opts = opts.into();
+        let (_tempdir, model_dir_path) = Self::runner_pack(path.as_ref(), &mut opts).await?;
+
+        log::trace!("About to save the packed model...");
 
+        // Save and package the model
+        crate::format::v1::save(opts, &model_dir_path).await
+    }
+
+    /// Report what `pack` would produce without writing the output file. This includes each file's
+    /// hash and size and whether it would be stored or linked. Note that the runner still processes the model.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn pack_dry_run<O, P: AsRef<str>>(path: P, opts: O) -> Result<crate::info::PackPlan>
+    where
+        O: Into<PackOpts>,
+    {
         let mut opts = opts.into();
+        let (_tempdir, model_dir_path) = Self::runner_pack(path.as_ref(), &mut opts).await?;
+
+        crate::format::v1::plan(opts, &model_dir_path).await
+    }
+
+    /// Launch a runner and ask it to pack the model at `path` into a temp folder. Returns the temp folder
+    /// (which must be kept alive while the output is in use) and the path of the packed model dir
+    #[cfg(not(target_family = "wasm"))]
+    async fn runner_pack(
+        path: &str,
+        opts: &mut PackOpts,
+    ) -> Result<(tempfile::TempDir, std::path::PathBuf)> {
+        use std::sync::Arc;
 
         // Launch a runner
-        let (runner, runner_info) =
-            discover_or_get_runner_and_launch(&opts.info, &crate::types::Device::CPU).await?;
+        let (runner, runner_info) = discover_or_get_runner_and_launch(
+            &opts.info,
+            &crate::types::Device::CPU,
+            true,
+            None,
+            &Default::default(),
+        )
+        .await?;
 
         // Set the runner_compat_version if the user didn't
-        opts.info
-            .runner
-            .runner_compat_version
-            .get_or_insert(runner_info.runner_compat_version);
+        set_runner_compat_version(&mut opts.info, &runner_info)?;
 
         // Create a temp folder
-        // SAFETY: this only needs to last until the end of this method so it's okay if we don't store `tempdir`
+        // This is returned to the caller so it can be kept alive as long as necessary
         let tempdir = tempfile::tempdir()?;
 
         // Convert it to a lunchbox path
@@ -160,19 +761,49 @@ impl Carton {
         log::trace!("Asking runner to pack...");
         let model_dir_path = match runner {
             Runner::V1(runner) => runner
-                .pack(
-                    &localfs,
-                    lunchbox::path::Path::new(path.as_ref()),
-                    temp_folder,
-                )
+                .pack(&localfs, lunchbox::path::Path::new(path), temp_folder)
                 .await
-                .map_err(|e| CartonError::ErrorFromRunner(e))?,
+                .map_err(|e| CartonError::RunnerPackFailed {
+                    reason: e.to_string(),
+                })?,
         };
 
-        log::trace!("About to save the packed model...");
+        Ok((tempdir, model_dir_path.to_string().into()))
+    }
 
-        // Save and package the model
-        crate::format::v1::save(opts, model_dir_path.to_string().as_ref()).await
+    /// Copy a directory from an arbitrary filesystem (e.g. an in-memory filesystem or one backed by object
+    /// storage) to a local temporary directory and pack it with `pack`. Returns the path of the output file
+    ///
+    /// Note: this still needs enough local disk space for a full copy of the model. Runners and `save` only
+    /// work with local directories so packing without a local copy isn't supported yet.
+    ///
+    /// Symlinks that point within the directory are kept as symlinks; other symlinks are replaced with the
+    /// files they point to.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn copy_from_fs_and_pack<F, O, P>(
+        fs: std::sync::Arc<F>,
+        path: P,
+        opts: O,
+    ) -> Result<std::path::PathBuf>
+    where
+        F: lunchbox::ReadableFileSystem + Send + Sync + 'static,
+        F::FileType: lunchbox::types::ReadableFile + Send + Sync + Unpin,
+        O: Into<PackOpts>,
+        P: AsRef<str>,
+    {
+        let local_root = tempfile::tempdir()?;
+        let model_dir_path = copy_dir_to_local(
+            fs.as_ref(),
+            lunchbox::path::Path::new(path.as_ref()),
+            local_root.path(),
+        )
+        .await?;
+
+        let model_dir_path = model_dir_path.to_str().ok_or(CartonError::Other(
+            "The path of the local temp directory is not valid UTF-8",
+        ))?;
+
+        Self::pack(model_dir_path, opts).await
     }
 
     /// Pack a carton given a path and options
@@ -192,15 +823,17 @@ impl Carton {
         let mut pack_opts = pack_opts.into();
 
         // Launch a runner
-        let (runner, runner_info) =
-            discover_or_get_runner_and_launch(&pack_opts.info, &crate::types::Device::CPU).await?;
+        let (runner, runner_info) = discover_or_get_runner_and_launch(
+            &pack_opts.info,
+            &crate::types::Device::CPU,
+            load_opts.auto_install_runner,
+            load_opts.runner_path_override.as_deref(),
+            &load_opts.runner_env,
+        )
+        .await?;
 
         // Set the runner_compat_version if the user didn't
-        pack_opts
-            .info
-            .runner
-            .runner_compat_version
-            .get_or_insert(runner_info.runner_compat_version);
+        set_runner_compat_version(&mut pack_opts.info, &runner_info)?;
 
         // Create a temp folder
         // SAFETY: this tempdir needs to last for the entire time this Carton exists
@@ -221,7 +854,9 @@ impl Carton {
                     temp_folder,
                 )
                 .await
-                .map_err(|e| CartonError::ErrorFromRunner(e))?,
+                .map_err(|e| CartonError::RunnerPackFailed {
+                    reason: e.to_string(),
+                })?,
         };
 
         // Create a localfs with the new root
@@ -236,20 +871,25 @@ impl Carton {
         let info_with_extras = CartonInfoWithExtras {
             info: pack_opts.info,
             manifest_sha256: None,
+            // This model is packed with the current version so its inputs are explicitly marked as optional or not
+            enforce_required_inputs: true,
         };
 
         // Merge in load opts
         let visible_device = load_opts.visible_device.clone();
+        let seed = load_opts.seed;
         let info_with_extras = crate::load::merge_in_load_opts(info_with_extras, load_opts)?;
 
         // TODO: correctly merge `load_opts` into `info_with_extras`
-        crate::load::load_model(&localfs, &runner, &info_with_extras, visible_device).await?;
+        crate::load::load_model(&localfs, &runner, &info_with_extras, visible_device, seed).await?;
 
         // Return a Carton
         Ok(Self {
-            info: info_with_extras,
-            runner,
-            _tempdir: Some(tempdir),
+            info: Arc::new(info_with_extras),
+            runner: Arc::new(runner),
+            queue: None,
+            _tempdir: Some(Arc::new(tempdir)),
+            last_infer_memory: Default::default(),
         })
     }
 
@@ -258,11 +898,59 @@ impl Carton {
         &self.info
     }
 
-    /// Get info for a model
+    /// Get a JSON Schema describing the inputs and outputs of `infer` for this model (e.g. to validate
+    /// requests or generate API docs in a serving layer). See `carton::schema::infer_schema` for details
+    pub fn infer_schema(&self) -> serde_json::Value {
+        crate::schema::infer_schema(&self.info.info)
+    }
+
+    /// Get the inputs of the example at `index` (see `CartonInfo::examples`) with all of their tensors loaded.
+    /// The result can be passed directly to `infer`.
+    ///
+    /// Returns an error if `index` is out of range or if any of the inputs are misc files
+    pub async fn get_example_inputs(&self, index: usize) -> Result<HashMap<String, Tensor>> {
+        let examples = self.info.info.examples.as_deref().unwrap_or_default();
+        let example = examples
+            .get(index)
+            .ok_or(CartonError::ExampleIndexOutOfRange {
+                index,
+                num_examples: examples.len(),
+            })?;
+
+        let mut out = HashMap::new();
+        for (name, value) in &example.inputs {
+            match value {
+                TensorOrMisc::Tensor(tensor) => {
+                    out.insert(name.clone(), tensor.get().await.clone());
+                }
+                TensorOrMisc::Misc(_) => {
+                    return Err(CartonError::ExampleInputIsMiscFile(name.clone()))
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Get info for a model without launching a runner
+    ///
+    /// In addition to packed cartons, this accepts local unpacked model directories (i.e. a directory
+    /// with a `carton.toml` but no `MANIFEST`). `manifest_sha256` is `None` for these
     pub async fn get_model_info<P: AsRef<str>>(url_or_path: P) -> Result<CartonInfoWithExtras> {
         crate::load::get_carton_info(url_or_path.as_ref()).await
     }
 
+    /// Expand a shorthand reference to a model in the registry into a URL. For example,
+    /// `google-research/bert-base-uncased` becomes `https://carton.pub/google-research/bert-base-uncased`
+    /// and `org/model@1.0.0` becomes `https://carton.pub/org/model/1.0.0`. The registry can be
+    /// changed with the `registry_url` config option.
+    ///
+    /// URLs and paths that exist on local disk are returned unchanged. `load` and `get_model_info`
+    /// call this automatically
+    pub async fn resolve(url_or_path: &str) -> String {
+        crate::load::resolve(url_or_path).await
+    }
+
     /// Shrink a packed carton by storing links to files instead of the files themselves when possible.
     /// Takes a path to a packed carton along with a mapping from sha256 to a list of URLs
     /// Returns the path to another packed carton
@@ -274,9 +962,101 @@ impl Carton {
         crate::format::v1::links::create_links(path, urls).await
     }
 
+    /// Check that the links in the `LINKS` file of a packed carton (e.g. one created by `shrink`) are reachable.
+    /// This includes URLs and files in other cartons. If `check_hashes` is true, this also downloads each file
+    /// and makes sure its sha256 matches.
+    ///
+    /// Returns a status for each link. Problems with individual links don't cause this method to fail.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn verify_links(
+        path: std::path::PathBuf,
+        check_hashes: bool,
+    ) -> Result<Vec<crate::info::LinkStatus>> {
+        crate::format::v1::links::verify_links(path, check_hashes).await
+    }
+
+    /// Compare two packed cartons. This compares the files in their MANIFESTs (by sha256), their metadata,
+    /// and their input and output specs. It doesn't read the contents of any other files (e.g. model weights)
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn diff(
+        path_a: std::path::PathBuf,
+        path_b: std::path::PathBuf,
+    ) -> Result<crate::info::CartonDiff> {
+        crate::format::v1::diff::diff(path_a, path_b).await
+    }
+
+    /// Wrap this carton so inference requests that fail with a retryable error (e.g. a transient
+    /// runner error) are retried according to `policy`. See `CartonError::is_retryable` for the errors
+    /// that are retried. Other errors are returned immediately
+    #[cfg(not(target_family = "wasm"))]
+    pub fn with_retries(&self, policy: crate::retry::RetryPolicy) -> crate::retry::RetryingCarton {
+        crate::retry::RetryingCarton::new(self.clone(), policy)
+    }
+
+    /// Get a copy of this carton that sends inference requests through a new queue. At most
+    /// `opts.max_in_flight` requests are sent to the runner at once; the others wait in the queue
+    /// (so callers see backpressure as `infer` taking longer to return).
+    ///
+    /// Clones of the returned `Carton` share the queue, but each clone is a separate caller. With
+    /// `QueuePolicy::Weighted`, a burst of requests from one clone doesn't starve the others.
+    /// See `with_queue_weight` and `queue_stats`.
+    ///
+    /// This applies to `infer` (and its variants), `streaming_infer` and `infer_with_handle`
+    pub fn with_queue(&self, opts: QueueOpts) -> Carton {
+        Carton {
+            info: self.info.clone(),
+            runner: self.runner.clone(),
+            queue: Some(InferenceQueue::new(opts).register(1)),
+            _tempdir: self._tempdir.clone(),
+            last_infer_memory: self.last_infer_memory.clone(),
+        }
+    }
+
+    /// Get a copy of this carton that shares this carton's queue with the specified weight. Weights
+    /// are used by `QueuePolicy::Weighted` and `QueuePolicy::Priority` (see `QueuePolicy` for details).
+    /// Cartons have a weight of 1 by default and a weight of 0 is treated as 1.
+    ///
+    /// If this carton doesn't have a queue, a new one is created with `QueueOpts::default()`
+    pub fn with_queue_weight(&self, weight: u32) -> Carton {
+        let queue = match &self.queue {
+            Some(queue) => queue.with_weight(weight),
+            None => InferenceQueue::new(QueueOpts::default()).register(weight),
+        };
+
+        Carton {
+            info: self.info.clone(),
+            runner: self.runner.clone(),
+            queue: Some(queue),
+            _tempdir: self._tempdir.clone(),
+            last_infer_memory: self.last_infer_memory.clone(),
+        }
+    }
+
+    /// Get the current depth of this carton's queue and the number of requests in flight.
+    /// Returns `None` if this carton doesn't have a queue (see `with_queue`)
+    pub fn queue_stats(&self) -> Option<QueueStats> {
+        self.queue.as_ref().map(|q| q.stats())
+    }
+
+    /// Wait until it's our turn to send a request to the runner (if we have a queue). The request is
+    /// in flight until the returned permit is dropped
+    async fn wait_for_queue(&self) -> Option<QueuePermit> {
+        match &self.queue {
+            Some(queue) => Some(queue.acquire().await),
+            None => None,
+        }
+    }
+
     /// Allocate a tensor
     pub fn alloc_tensor(&self, dtype: DataType, shape: Vec<u64>) -> Result<Tensor> {
-        match &self.runner {
+        // Don't trust the shape (it may overflow)
+        if crate::types::shape_numel(&shape).is_none() {
+            return Err(CartonError::InvalidShape(format!(
+                "The number of elements in shape {shape:?} overflows"
+            )));
+        }
+
+        match self.runner.as_ref() {
             Runner::V1(runner) => {
                 for_each_carton_type! {
                     return match dtype {
@@ -284,7 +1064,7 @@ impl Carton {
                             DataType::$CartonType =>
                                 Ok(runner
                                     .alloc_tensor::<$RustType>(shape)
-                                    .map_err(|e| CartonError::ErrorFromRunner(e))?
+                                    .map_err(|e| CartonError::InvalidShape(e))?
                                     .into()),
                         )*
                     }
@@ -294,6 +1074,91 @@ impl Carton {
     }
 }
 
+/// Convert a set of inputs to the types the runner interface expects
+fn convert_inputs<I, S>(tensors: I) -> HashMap<String, runner_interface_v1::types::Tensor>
+where
+    I: IntoIterator<Item = (S, Tensor)>,
+    String: From<S>,
+{
+    tensors
+        .into_iter()
+        .map(|(k, v)| (k.into(), v.into()))
+        .collect()
+}
+
+/// Set `runner_compat_version` in `info` to the launched runner's compat version if it isn't set already.
+/// We don't know the compat version of runners launched from an override path so it has to be set
+/// explicitly to pack with one of those
+#[cfg(not(target_family = "wasm"))]
+fn set_runner_compat_version(
+    info: &mut crate::info::CartonInfo,
+    runner: &crate::load::LaunchedRunner,
+) -> Result<()> {
+    let runner_config = &mut info.runner;
+    runner_config.runner_compat_version = runner_config
+        .runner_compat_version
+        .or(runner.runner_compat_version());
+
+    if runner_config.runner_compat_version.is_none() {
+        return Err(CartonError::Other(
+            "`runner_compat_version` must be set to use a runner path override with an unpacked model",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Copy the directory at `path` in `fs` to `local_root` and return the local path of the copy.
+/// Relative paths within the directory (including symlinks) are preserved
+#[cfg(not(target_family = "wasm"))]
+async fn copy_dir_to_local<F>(
+    fs: &F,
+    path: &lunchbox::path::Path,
+    local_root: &std::path::Path,
+) -> Result<std::path::PathBuf>
+where
+    F: lunchbox::ReadableFileSystem,
+    F::FileType: lunchbox::types::ReadableFile + Unpin,
+{
+    // `local_root` corresponds to the root of `fs`
+    let model_dir_path = path.to_path(local_root);
+
+    let mut to_process = std::collections::VecDeque::new();
+    to_process.push_back(path.to_owned());
+    while let Some(dir_path) = to_process.pop_front() {
+        tokio::fs::create_dir_all(dir_path.to_path(local_root)).await?;
+
+        let mut dir = fs.read_dir(&dir_path).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            // entry.metadata() doesn't follow symlinks
+            let metadata = entry.metadata().await?;
+            let entry_path = entry.path();
+            let target_path = entry_path.to_path(local_root);
+
+            if metadata.is_dir() {
+                to_process.push_back(entry_path);
+                continue;
+            }
+
+            if metadata.is_symlink() {
+                let symlink_target = fs.read_link(&entry_path).await?;
+                if symlink_target.starts_with(path) {
+                    // Keep symlinks within the directory as symlinks
+                    tokio::fs::symlink(symlink_target.to_path(local_root), target_path).await?;
+                    continue;
+                }
+            }
+
+            // Copy the file (following symlinks that point outside the directory)
+            let mut f = fs.open(&entry_path).await?;
+            let mut target = tokio::fs::File::create(target_path).await?;
+            tokio::io::copy(&mut f, &mut target).await?;
+        }
+    }
+
+    Ok(model_dir_path)
+}
+
 #[cfg(not(target_family = "wasm"))]
 #[cfg(test)]
 mod tests {
@@ -31,6 +31,14 @@ where
     v.into_iter().map(|(k, v)| (k, v.into())).collect()
 }
 
+/// Like `convert_map`, but fails if any of the values can't be converted
+pub fn try_convert_map<A: Hash + Eq, T, U>(v: HashMap<A, T>) -> Result<HashMap<A, U>, U::Error>
+where
+    U: TryFrom<T>,
+{
+    v.into_iter().map(|(k, v)| Ok((k, v.try_into()?))).collect()
+}
+
 pub fn convert_opt_vec<T, U>(v: Option<Vec<T>>) -> Option<Vec<U>>
 where
     U: From<T>,
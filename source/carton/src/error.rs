@@ -35,17 +35,262 @@ pub enum CartonError {
     FetchError(#[from] reqwest::Error),
 
     #[error("IO error: {0}")]
-    IOError(#[from] std::io::Error),
+    IOError(#[source] std::io::Error),
 
     #[error("Error parsing carton metadata: {0}")]
     ConfigParsingError(#[from] toml::de::Error),
 
+    #[error("Invalid carton.toml{}: {message}", format_toml_location(.field, .line, .column))]
+    InvalidCartonToml {
+        message: String,
+
+        /// The path of the offending field (e.g. `input[1].dtype`) if we could figure it out
+        field: Option<String>,
+
+        /// 1-based line and column numbers
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+
     #[error("Runner reported error: {0}")]
     ErrorFromRunner(String),
 
+    #[error("The runner exited or disconnected before it responded. It may have crashed")]
+    RunnerDisconnected,
+
+    #[error("The runner ran out of memory: {0}")]
+    OutOfMemory(String),
+
+    #[error("Inference didn't complete within {0:?}")]
+    DeadlineExceeded(std::time::Duration),
+
+    #[error("The runner didn't respond to a ping within {0:?}")]
+    RunnerNotResponding(std::time::Duration),
+
+    #[error("The runner failed to pack this model: {reason}")]
+    RunnerPackFailed { reason: String },
+
     #[error("Error while parsing version: {0}")]
     SemverParseError(#[from] semver::Error),
 
+    #[error("Invalid shape: {0}")]
+    InvalidShape(String),
+
+    #[error("Mismatched data types: {0}")]
+    DataTypeMismatch(String),
+
+    #[error("Could not quantize or dequantize tensor: {0}")]
+    QuantizationFailed(String),
+
+    #[error("This carton uses spec version {found}, but this version of Carton only supports up to spec version {max_supported}. You may need to upgrade the version of Carton you're using.")]
+    UnsupportedSpecVersion { found: u64, max_supported: u64 },
+
+    #[error("No installed runner matches runner '{name}' with framework version '{version}' and `auto_install_runner` is disabled. To install it, load the model once with `auto_install_runner` set to `true`.")]
+    RunnerNotInstalled { name: String, version: String },
+
+    #[error("Can't use '{}' as the runner: {reason}", .path.display())]
+    InvalidRunnerPath {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    #[error("No installed or installable runner for '{runner_name}' supports the required framework version '{required}'. Available framework versions: {}", format_versions(.available))]
+    NoCompatibleRunner {
+        runner_name: String,
+        required: semver::VersionReq,
+        available: Vec<semver::Version>,
+    },
+
+    #[error("Runner '{runner}' isn't available for this platform ({host_triple}). Runners that support this model are only available for: {}", .available_platforms.join(", "))]
+    RunnerPlatformMismatch {
+        runner: String,
+        host_triple: String,
+        available_platforms: Vec<String>,
+    },
+
+    #[error("This model requires a runner with `runner_compat_version` {carton}, but the available runner(s) only support {runner}. The compat version changes when a runner changes the format of the model directory it expects. {}", compat_version_advice(.carton, .runner))]
+    IncompatibleRunnerCompatVersion { carton: u64, runner: u64 },
+
+    #[error("Example {index} doesn't exist. This model has {num_examples} example(s)")]
+    ExampleIndexOutOfRange { index: usize, num_examples: usize },
+
+    #[error("Input '{0}' of this example is a misc file (e.g. an image) instead of a tensor so it can't be passed to `infer` directly. Read it from the example's `inputs` and convert it to the tensor the model expects instead.")]
+    ExampleInputIsMiscFile(String),
+
+    #[error("Missing required input(s): {}. Only inputs marked as `optional` can be omitted", .0.join(", "))]
+    MissingInputs(Vec<String>),
+
+    #[error("Some examples or self tests don't match the declared inputs and outputs:\n{}\nSet `allow_invalid_examples` in `PackOpts` to pack anyway", .0.join("\n"))]
+    InvalidExamples(Vec<String>),
+
+    #[error("This carton appears to be malicious or corrupted: {0}")]
+    MaliciousArchive(String),
+
+    #[error("{0}. This may be a decompression bomb. If you trust this carton, increase the limits in `LoadOpts` to load it")]
+    ArchiveTooLarge(String),
+
+    #[error("Could not fetch '{path}' from any of the configured IPFS gateways: {}", .errors.join(", "))]
+    IpfsFetchFailed { path: String, errors: Vec<String> },
+
+    #[error("Expected a carton with a manifest sha256 of '{expected}', but got {}", format_manifest_sha256(.actual))]
+    ManifestHashMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("Expected '{path}' in the carton at '{carton_url}' to have a sha256 of '{expected}', but {}", format_linked_sha256(.actual))]
+    LinkedCartonFileMismatch {
+        carton_url: String,
+        path: String,
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error(
+        "Could not open the carton at '{carton_url}' (files in this carton link to it): {error}"
+    )]
+    LinkedCartonOpenFailed { carton_url: String, error: String },
+
+    #[error("Signature verification failed: {0}")]
+    SignatureVerificationFailed(String),
+
+    #[error("Expected '{path}' to have a sha256 of '{expected}' (from the MANIFEST), but got '{actual}'")]
+    FileHashMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("This isn't a carton or an unpacked model directory: it doesn't have a MANIFEST or a carton.toml")]
+    MissingCartonToml,
+
+    #[error("No model named '{name}' in this bundle. Available models: {}", format_names(.available))]
+    ModelNotInBundle {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("'{0}' is compressed in this bundle. Models in a bundle must be stored uncompressed so they can be read without extracting the bundle")]
+    CompressedBundleEntry(String),
+
+    #[error("This is an unpacked model directory (it has a carton.toml, but no MANIFEST). `get_model_info` can read its metadata, but it needs to be packed before it can be loaded")]
+    UnpackedModel,
+
+    #[cfg(feature = "arrow")]
+    #[error("Can't convert to an Arrow RecordBatch: {0}")]
+    ArrowConversionFailed(String),
+
     #[error("Error: {0}")]
     Other(&'static str),
 }
+
+impl CartonError {
+    /// Whether an inference request that failed with this error may succeed if it's retried (see
+    /// `Carton::with_retries`). The retryable errors are:
+    /// - `RunnerDisconnected`: the runner exited (e.g. it crashed). Set `RetryPolicy::reload_from` to
+    ///   start a new runner before retrying
+    /// - `RunnerNotResponding`: the runner didn't respond to a ping in time
+    /// - `OutOfMemory`: the runner ran out of (e.g. GPU) memory. This may succeed once other requests finish
+    /// - `IOError`s caused by a broken or interrupted connection (e.g. `BrokenPipe` or `TimedOut`)
+    ///
+    /// Other errors fail the same way every time. This includes `ErrorFromRunner` because we can't tell
+    /// whether an error reported by the model (e.g. an invalid input) is transient
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RunnerDisconnected | Self::RunnerNotResponding(_) | Self::OutOfMemory(_) => true,
+            Self::IOError(e) => is_transient_io_error(e.kind()),
+            _ => false,
+        }
+    }
+}
+
+impl From<std::io::Error> for CartonError {
+    fn from(e: std::io::Error) -> Self {
+        // The size limits on untrusted containers are enforced as files are read (see `SizeLimitFS`) so
+        // they show up as IO errors
+        match e
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<crate::limitfs::SizeLimitExceeded>())
+        {
+            Some(inner) => Self::ArchiveTooLarge(inner.0.clone()),
+            None => Self::IOError(e),
+        }
+    }
+}
+
+/// IO errors that are caused by a broken or interrupted connection rather than the request itself
+fn is_transient_io_error(kind: std::io::ErrorKind) -> bool {
+    use std::io::ErrorKind;
+
+    matches!(
+        kind,
+        ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::UnexpectedEof
+            | ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+    )
+}
+
+fn format_toml_location(
+    field: &Option<String>,
+    line: &Option<usize>,
+    column: &Option<usize>,
+) -> String {
+    let mut out = String::new();
+    if let Some(field) = field {
+        out += &format!(" (field `{field}`)");
+    }
+
+    match (line, column) {
+        (Some(line), Some(column)) => out += &format!(" at line {line} column {column}"),
+        (Some(line), None) => out += &format!(" at line {line}"),
+        _ => {}
+    }
+
+    out
+}
+
+fn format_manifest_sha256(sha256: &Option<String>) -> String {
+    match sha256 {
+        Some(v) => format!("'{v}'"),
+        None => "a carton without a MANIFEST".into(),
+    }
+}
+
+fn format_linked_sha256(sha256: &Option<String>) -> String {
+    match sha256 {
+        Some(v) => format!("it has a sha256 of '{v}'"),
+        None => "that carton doesn't contain it".into(),
+    }
+}
+
+fn compat_version_advice(carton: &u64, runner: &u64) -> &'static str {
+    if carton > runner {
+        "The model was packed for a newer version of the runner so update the runner (or Carton) to load it."
+    } else {
+        "The model was packed for an older version of the runner so repack it with a current version of Carton (or install an older runner) to load it."
+    }
+}
+
+fn format_names(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".into()
+    } else {
+        names.join(", ")
+    }
+}
+
+fn format_versions(versions: &[semver::Version]) -> String {
+    if versions.is_empty() {
+        "none".into()
+    } else {
+        versions
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
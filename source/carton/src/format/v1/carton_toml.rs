@@ -118,6 +118,9 @@ pub enum RunnerOpt {
 pub struct SelfTest {
     pub(crate) name: Option<String>,
     pub(crate) description: Option<String>,
+
+    // Can be empty (e.g. for models that don't take any inputs)
+    #[serde(default)]
     pub(crate) inputs: HashMap<String, TensorReference>,
 
     // Can be empty
@@ -195,7 +198,11 @@ impl From<String> for TensorReference {
 pub struct Example {
     pub(crate) name: Option<String>,
     pub(crate) description: Option<String>,
+
+    // These can be empty (e.g. for models that don't take any inputs or don't produce any outputs)
+    #[serde(default)]
     pub(crate) inputs: HashMap<String, TensorOrMiscReference>,
+    #[serde(default)]
     pub(crate) sample_out: HashMap<String, TensorOrMiscReference>,
 }
 
@@ -254,6 +261,14 @@ pub struct TensorSpec {
 
     /// Optional internal name
     pub(crate) internal_name: Option<String>,
+
+    /// Whether this input can be omitted when running inference. This is `None` in cartons packed
+    /// before optional inputs were supported (where any input could be omitted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) optional: Option<bool>,
+
+    /// The value to use for an optional input if it isn't provided
+    pub(crate) default: Option<TensorReference>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -273,6 +288,9 @@ pub enum DataType {
     Uint16,
     Uint32,
     Uint64,
+
+    Complex64,
+    Complex128,
 }
 
 #[derive(Debug, PartialEq)]
@@ -348,13 +366,54 @@ impl Serialize for Shape {
     }
 }
 
-/// A dimension can be either a fixed value, a symbol, or any value
+/// A dimension can be either a fixed value, a symbol, a bounded range, or any value
 #[derive(Debug, PartialEq)]
 #[non_exhaustive]
 pub enum Dimension {
     Value(u64),
     Symbol(String),
     Any,
+
+    /// An inclusive range of values. Stored as a string of the form `"min..=max"`, `"min.."` or `"..=max"`
+    /// (i.e. similar to a rust range)
+    Range {
+        min: Option<u64>,
+        max: Option<u64>,
+    },
+}
+
+/// Attempts to parse a range of the form `min..=max`, `min..`, or `..=max`
+/// Returns `None` if the input doesn't look like a range (e.g. it's a symbol)
+fn parse_range(v: &str) -> Option<Result<(Option<u64>, Option<u64>), String>> {
+    let (lower, upper) = v.split_once("..")?;
+
+    let parse_bound = |bound: &str| -> Option<Option<u64>> {
+        if bound.is_empty() {
+            Some(None)
+        } else {
+            bound.parse().ok().map(Some)
+        }
+    };
+
+    // If either side isn't empty or a number, this isn't a range
+    let min = parse_bound(lower)?;
+    let (max, inclusive) = match upper.strip_prefix('=') {
+        Some(upper) => (parse_bound(upper)?, true),
+        None => (parse_bound(upper)?, false),
+    };
+
+    Some(match (min, max) {
+        (_, Some(_)) if !inclusive => Err(format!(
+            "Dimension ranges must be inclusive (e.g. `1..=64`), but got `{v}`"
+        )),
+        (None, None) => Err(format!(
+            "Dimension ranges must have at least one bound, but got `{v}`. Use \"*\" to allow any value"
+        )),
+        (Some(min), Some(max)) if min > max => Err(format!(
+            "The lower bound of a dimension range must not be larger than the upper bound, but got `{v}`"
+        )),
+        bounds => Ok(bounds),
+    })
 }
 
 struct DimensionVisitor;
@@ -381,6 +440,9 @@ impl<'de> Visitor<'de> for DimensionVisitor {
     {
         if v == "*" {
             Ok(Dimension::Any)
+        } else if let Some(range) = parse_range(v) {
+            let (min, max) = range.map_err(serde::de::Error::custom)?;
+            Ok(Dimension::Range { min, max })
         } else {
             Ok(Dimension::Symbol(v.to_owned()))
         }
@@ -405,21 +467,298 @@ impl Serialize for Dimension {
             Dimension::Any => serializer.serialize_str("*"),
             Dimension::Symbol(s) => serializer.serialize_str(s.as_str()),
             Dimension::Value(v) => v.serialize(serializer),
+            Dimension::Range { min, max } => {
+                let min = min.map(|v| v.to_string()).unwrap_or_default();
+                let max = max.map(|v| format!("={v}")).unwrap_or_default();
+                serializer.serialize_str(&format!("{min}..{max}"))
+            }
         }
     }
 }
 
+/// The latest carton spec version this library can load
+pub(crate) const MAX_SUPPORTED_SPEC_VERSION: u64 = 1;
+
+/// Used to read the spec version before parsing the rest of the file
+#[derive(Deserialize)]
+struct SpecVersion {
+    spec_version: u64,
+}
+
 pub(crate) async fn parse(data: &[u8]) -> crate::error::Result<CartonToml> {
-    Ok(toml::from_slice(data)?)
+    // Check the spec version first so cartons from newer versions of the spec fail with a clear
+    // error instead of an arbitrary parsing error
+    let version: SpecVersion = toml::from_slice(data).map_err(|e| describe_error(data, e))?;
+    if version.spec_version > MAX_SUPPORTED_SPEC_VERSION {
+        return Err(crate::error::CartonError::UnsupportedSpecVersion {
+            found: version.spec_version,
+            max_supported: MAX_SUPPORTED_SPEC_VERSION,
+        });
+    }
+
+    let config: CartonToml = toml::from_slice(data).map_err(|e| describe_error(data, e))?;
+
+    // Unknown fields are ignored (see the spec), but they may be optional fields added in a newer
+    // version of carton so we warn about them
+    let unknown = find_unknown_fields(data, &config);
+    if !unknown.is_empty() {
+        log::warn!(
+            "Ignoring unknown fields in carton.toml: {}. You may need to upgrade the version of Carton you're using.",
+            unknown.join(", ")
+        );
+    }
+
+    Ok(config)
+}
+
+/// Replace `${VAR}` and `${VAR:-default}` in string runner opts with the value of the environment
+/// variable `VAR`. This lets a carton use values that differ between machines (e.g. a path) without
+/// repacking it. Referencing an undefined variable without a default is an error.
+///
+/// This is only applied to the copy of the opts that is passed to the runner when loading a model.
+/// `CartonInfo` keeps the original values so environment values don't show up in `get_model_info`
+/// and aren't written out if the carton is packed again
+pub(crate) fn interpolate_runner_opts(
+    opts: &mut HashMap<String, crate::info::RunnerOpt>,
+) -> crate::error::Result<()> {
+    for (key, value) in opts.iter_mut() {
+        if let crate::info::RunnerOpt::String(s) = value {
+            *s = interpolate_env(s, |name| std::env::var(name).ok()).map_err(|message| {
+                crate::error::CartonError::InvalidCartonToml {
+                    message,
+                    field: Some(format!("runner.opts.{key}")),
+                    line: None,
+                    column: None,
+                }
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Interpolate environment variables into `value`. Supports `${VAR}`, `${VAR:-default}` (uses
+/// `default` if `VAR` is unset or empty) and `$${` for a literal `${`. A `$` that isn't followed by
+/// `{` is left as is
+fn interpolate_env(value: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        if let Some(stripped) = rest.strip_prefix("$${") {
+            // Escaped
+            out.push_str("${");
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix("${") {
+            let end = match stripped.find('}') {
+                Some(end) => end,
+                None => return Err(format!("unterminated `${{` in '{value}'")),
+            };
+
+            let (name, default) = match stripped[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&stripped[..end], None),
+            };
+
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!(
+                    "invalid environment variable name '{name}' in '{value}'"
+                ));
+            }
+
+            match (lookup(name), default) {
+                (Some(v), Some(default)) if v.is_empty() => out.push_str(default),
+                (Some(v), _) => out.push_str(&v),
+                (None, Some(default)) => out.push_str(default),
+                (None, None) => {
+                    return Err(format!(
+                        "environment variable '{name}' isn't set. Set it or use `${{{name}:-default}}` to provide a default"
+                    ))
+                }
+            }
+
+            rest = &stripped[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Convert a toml parsing error into an error that points at the offending field (when possible)
+/// so it's easier to fix hand-edited tomls
+fn describe_error(data: &[u8], e: toml::de::Error) -> crate::error::CartonError {
+    let data = String::from_utf8_lossy(data);
+
+    // The message from `toml` ends with the location, but we report that separately
+    let mut message = e.to_string();
+    let line_col = e.line_col().map(|(line, col)| {
+        if let Some(stripped) =
+            message.strip_suffix(&format!(" at line {} column {}", line + 1, col + 1))
+        {
+            message = stripped.to_owned();
+        }
+
+        match key_line_in_table(&data, line, &message) {
+            Some(key_line) => (key_line, 0),
+            None => (line, col),
+        }
+    });
+
+    crate::error::CartonError::InvalidCartonToml {
+        message,
+        field: line_col.and_then(|(line, _)| field_at_line(&data, line)),
+        line: line_col.map(|(line, _)| line + 1),
+        column: line_col.map(|(_, col)| col + 1),
+    }
+}
+
+/// `toml` reports invalid values in a table at the line of the table's header. If the error message
+/// names the key (e.g. "for key `input.dtype`"), find the (0-based) line in that table that sets it
+fn key_line_in_table(data: &str, header_line: usize, message: &str) -> Option<usize> {
+    let (_, key) = message.rsplit_once("for key `")?;
+    let (key, _) = key.split_once('`')?;
+    let key = key.rsplit('.').next()?;
+
+    let mut lines = data.lines().enumerate().skip(header_line);
+    if !lines.next()?.1.trim_start().starts_with('[') {
+        return None;
+    }
+
+    for (idx, text) in lines {
+        let text = text.trim();
+        if text.starts_with('[') {
+            // The start of the next table
+            break;
+        }
+
+        if let Some((k, _)) = text.split_once('=') {
+            if k.trim().trim_matches('"') == key {
+                return Some(idx);
+            }
+        }
+    }
+
+    None
+}
+
+/// Figure out the path of the field defined on a (0-based) line of a toml document (e.g. `input[1].dtype`).
+/// This only understands the kinds of tables used in carton.toml files (tables, arrays of tables and
+/// `key = value` pairs). Returns `None` if the line doesn't define a field
+fn field_at_line(data: &str, line: usize) -> Option<String> {
+    // The number of times we've seen each array of tables so far
+    let mut array_counts: HashMap<String, usize> = HashMap::new();
+    let mut table = String::new();
+
+    for (idx, text) in data.lines().enumerate().take(line + 1) {
+        let text = text.trim();
+        let is_current = idx == line;
+
+        if let Some(name) = text.strip_prefix("[[").and_then(|v| v.split_once("]]")) {
+            // An array of tables (e.g. `[[input]]`)
+            let name = name.0.trim().to_owned();
+            let count = array_counts.entry(name.clone()).or_default();
+            table = format!("{name}[{count}]");
+            *count += 1;
+        } else if let Some(name) = text.strip_prefix('[').and_then(|v| v.split_once(']')) {
+            // A table (e.g. `[runner]` or `[self_test.inputs]`). Tables nested in an array of tables
+            // belong to the most recent item in the array
+            let name = name.0.trim();
+            table = match name.split_once('.') {
+                Some((parent, rest)) if array_counts.contains_key(parent.trim()) => {
+                    format!(
+                        "{}[{}].{}",
+                        parent.trim(),
+                        array_counts[parent.trim()] - 1,
+                        rest.trim()
+                    )
+                }
+                _ => name.to_owned(),
+            };
+        } else if is_current {
+            let (key, _) = text.split_once('=')?;
+            let key = key.trim().trim_matches('"');
+            if key.is_empty() {
+                return None;
+            }
+
+            return Some(if table.is_empty() {
+                key.to_owned()
+            } else {
+                format!("{table}.{key}")
+            });
+        } else {
+            continue;
+        }
+
+        if is_current {
+            return Some(table).filter(|v| !v.is_empty());
+        }
+    }
+
+    None
+}
+
+/// Returns the paths of all the fields in `data` that were not used when parsing `config`
+fn find_unknown_fields(data: &[u8], config: &CartonToml) -> Vec<String> {
+    let mut out = Vec::new();
+
+    // Any field that we understood will still be present after a round trip through `CartonToml`
+    if let (Ok(original), Ok(known)) = (
+        toml::from_slice::<toml::Value>(data),
+        toml::Value::try_from(config),
+    ) {
+        collect_unknown_fields(&original, &known, "", &mut out);
+    }
+
+    out
+}
+
+fn collect_unknown_fields(
+    original: &toml::Value,
+    known: &toml::Value,
+    prefix: &str,
+    out: &mut Vec<String>,
+) {
+    match (original, known) {
+        (toml::Value::Table(original), toml::Value::Table(known)) => {
+            for (k, v) in original {
+                let path = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{prefix}.{k}")
+                };
+
+                match known.get(k) {
+                    Some(known_v) => collect_unknown_fields(v, known_v, &path, out),
+                    None => out.push(path),
+                }
+            }
+        }
+        (toml::Value::Array(original), toml::Value::Array(known)) => {
+            for (idx, (v, known_v)) in original.iter().zip(known).enumerate() {
+                collect_unknown_fields(v, known_v, &format!("{prefix}[{idx}]"), out);
+            }
+        }
+        _ => {}
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{path::PathBuf, str::FromStr};
+    use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
     use target_lexicon::Triple;
 
-    use crate::format::v1::carton_toml::CartonToml;
+    use crate::{
+        format::v1::carton_toml::{find_unknown_fields, CartonToml},
+        info::RunnerOpt,
+    };
 
     fn get_test_data_dir() -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/format/v1/test_data")
@@ -471,6 +810,14 @@ mod tests {
                 let config: CartonToml = toml::from_slice(&data).unwrap();
                 // println!("{:#?}", config);
 
+                // All the fields in our test data should be known
+                assert_eq!(
+                    find_unknown_fields(&data, &config),
+                    Vec::<String>::new(),
+                    "Unexpected unknown fields in {:#?}",
+                    p
+                );
+
                 // Serializing and deserializing should give us back the original config
                 let serialized = toml::to_string_pretty(&config).unwrap();
                 let config2: CartonToml = toml::from_str(&serialized).unwrap();
@@ -479,4 +826,201 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_unsupported_spec_version() {
+        let data = r#"
+            spec_version = 2
+            some_new_required_field = "value"
+
+            [runner]
+            runner_name = "torchscript"
+            required_framework_version = "=1.12.1"
+        "#;
+
+        match super::parse(data.as_bytes()).await {
+            Err(crate::error::CartonError::UnsupportedSpecVersion {
+                found,
+                max_supported,
+            }) => {
+                assert_eq!(found, 2);
+                assert_eq!(max_supported, super::MAX_SUPPORTED_SPEC_VERSION);
+            }
+            other => panic!("Expected an UnsupportedSpecVersion error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        let lookup = |name: &str| match name {
+            "MODEL_DIR" => Some("/data/models".to_owned()),
+            "EMPTY" => Some("".to_owned()),
+            _ => None,
+        };
+
+        let cases = [
+            ("no variables", "no variables"),
+            ("${MODEL_DIR}/vocab.txt", "/data/models/vocab.txt"),
+            ("${UNDEFINED:-/tmp}/vocab.txt", "/tmp/vocab.txt"),
+            ("${MODEL_DIR:-/tmp}", "/data/models"),
+            ("${EMPTY:-fallback}", "fallback"),
+            ("${EMPTY}", ""),
+            ("${UNDEFINED:-}", ""),
+            ("$${MODEL_DIR}", "${MODEL_DIR}"),
+            ("costs $5", "costs $5"),
+            ("${MODEL_DIR}${MODEL_DIR}", "/data/models/data/models"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(super::interpolate_env(input, lookup).unwrap(), expected);
+        }
+
+        for input in ["${UNDEFINED}", "${MODEL_DIR", "${}", "${NOT-VALID}"] {
+            assert!(
+                super::interpolate_env(input, lookup).is_err(),
+                "Expected an error for {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpolate_runner_opts() {
+        let mut opts = HashMap::from([
+            ("num_threads".to_owned(), RunnerOpt::Integer(4)),
+            (
+                "vocab".to_owned(),
+                RunnerOpt::String("${CARTON_TEST_INTERPOLATE_UNDEFINED:-/tmp}/vocab.txt".into()),
+            ),
+        ]);
+        super::interpolate_runner_opts(&mut opts).unwrap();
+
+        assert!(matches!(opts["num_threads"], RunnerOpt::Integer(4)));
+        assert!(matches!(&opts["vocab"], RunnerOpt::String(s) if s == "/tmp/vocab.txt"));
+
+        // Undefined variables without a default should point at the opt
+        let mut opts = HashMap::from([(
+            "vocab".to_owned(),
+            RunnerOpt::String("${CARTON_TEST_INTERPOLATE_UNDEFINED}/vocab.txt".into()),
+        )]);
+        match super::interpolate_runner_opts(&mut opts) {
+            Err(crate::error::CartonError::InvalidCartonToml { message, field, .. }) => {
+                assert_eq!(field.as_deref(), Some("runner.opts.vocab"));
+                assert!(message.contains("CARTON_TEST_INTERPOLATE_UNDEFINED"));
+            }
+            other => panic!("Expected an InvalidCartonToml error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields() {
+        let data = r#"
+            spec_version = 1
+            some_new_optional_field = "value"
+
+            [runner]
+            runner_name = "torchscript"
+            required_framework_version = "=1.12.1"
+            runner_compat_version = 1
+            another_new_field = 5
+
+            [[input]]
+            name = "x"
+            dtype = "float32"
+            shape = [1, 2]
+            yet_another_field = true
+        "#;
+
+        // Unknown fields should not cause parsing to fail
+        let config = super::parse(data.as_bytes()).await.unwrap();
+
+        let mut unknown = find_unknown_fields(data.as_bytes(), &config);
+        unknown.sort();
+        assert_eq!(
+            unknown,
+            vec![
+                "input[0].yet_another_field",
+                "runner.another_new_field",
+                "some_new_optional_field"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_location() {
+        let data = r#"spec_version = 1
+
+[runner]
+runner_name = "torchscript"
+required_framework_version = "=1.12.1"
+runner_compat_version = 1
+
+[[input]]
+name = "x"
+dtype = "float32"
+shape = [1, 2]
+
+[[input]]
+name = "y"
+dtype = "flaot32"
+shape = [1, 2]
+"#;
+
+        match super::parse(data.as_bytes()).await {
+            Err(crate::error::CartonError::InvalidCartonToml {
+                message,
+                field,
+                line,
+                ..
+            }) => {
+                assert!(message.contains("flaot32"), "{message}");
+                assert_eq!(field.as_deref(), Some("input[1].dtype"));
+                assert_eq!(line, Some(15));
+            }
+            other => panic!("Expected an InvalidCartonToml error, got {:?}", other),
+        }
+
+        assert_eq!(
+            super::field_at_line("[[self_test]]\n[self_test.inputs]\nx = 1", 2).as_deref(),
+            Some("self_test[0].inputs.x")
+        );
+        assert_eq!(
+            super::field_at_line("[runner]\n", 0).as_deref(),
+            Some("runner")
+        );
+        assert_eq!(super::field_at_line("a = 1", 5), None);
+    }
+
+    /// Parse many corrupted versions of the test tomls and make sure we never panic
+    #[tokio::test]
+    async fn fuzz_parse() {
+        let paths = get_test_data_dir()
+            .read_dir()
+            .unwrap()
+            .map(|item| item.unwrap().path())
+            .filter(|p| p.to_str().unwrap().ends_with(".toml"));
+
+        for p in paths {
+            let data = std::fs::read(p).unwrap();
+
+            // Truncate the file at every position
+            for len in 0..data.len() {
+                let _ = super::parse(&data[..len]).await;
+            }
+
+            // Replace every byte with characters that are likely to confuse the parser
+            for idx in 0..data.len() {
+                for c in [b'"', b'[', b']', b'=', b'-', b'.', b'\n', 0xff] {
+                    let mut corrupted = data.clone();
+                    corrupted[idx] = c;
+                    if let Err(crate::error::CartonError::InvalidCartonToml {
+                        line: Some(line),
+                        ..
+                    }) = super::parse(&corrupted).await
+                    {
+                        assert!(line <= corrupted.split(|v| *v == b'\n').count());
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,304 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compare two packed cartons using their metadata and MANIFEST files. This doesn't read any of the other
+//! files in the cartons (e.g. model weights) so it's cheap even for large models
+
+use std::collections::BTreeMap;
+
+use lunchbox::types::{MaybeSend, MaybeSync, ReadableFile};
+use lunchbox::ReadableFileSystem;
+use zipfs::ZipFS;
+
+use super::carton_toml::{CartonToml, TensorSpec};
+use super::manifest::parse_manifest;
+use crate::error::Result;
+use crate::info::{CartonDiff, DiffEntry};
+
+/// Compare two packed cartons. See `Carton::diff`
+pub(crate) async fn diff(a: std::path::PathBuf, b: std::path::PathBuf) -> Result<CartonDiff> {
+    let a = ZipFS::new(a).await;
+    let b = ZipFS::new(b).await;
+
+    let files = diff_maps(read_manifest(&a).await?, read_manifest(&b).await?);
+
+    let config_a = super::carton_toml::parse(&a.read("/carton.toml").await?).await?;
+    let config_b = super::carton_toml::parse(&b.read("/carton.toml").await?).await?;
+
+    Ok(CartonDiff {
+        files,
+        ..diff_configs(config_a, config_b)
+    })
+}
+
+/// Read a MANIFEST into a map from file path to sha256
+async fn read_manifest<T>(fs: &T) -> Result<BTreeMap<String, String>>
+where
+    T: ReadableFileSystem + MaybeSend + MaybeSync,
+    T::FileType: ReadableFile + MaybeSend + MaybeSync + Unpin,
+{
+    let manifest = fs.read_to_string("/MANIFEST").await?;
+    Ok(parse_manifest(&manifest)?
+        .into_iter()
+        .map(|(file_path, sha256)| (file_path.to_owned(), sha256.to_owned()))
+        .collect())
+}
+
+/// Compare the metadata and tensor specs of two cartons
+fn diff_configs(mut a: CartonToml, mut b: CartonToml) -> CartonDiff {
+    // Inputs and outputs are compared separately so we take them out before comparing the rest of the metadata
+    let inputs = diff_maps(specs_by_name(a.input.take()), specs_by_name(b.input.take()));
+    let outputs = diff_maps(
+        specs_by_name(a.output.take()),
+        specs_by_name(b.output.take()),
+    );
+
+    let metadata = diff_maps(flatten_config(&a), flatten_config(&b));
+
+    CartonDiff {
+        files: Vec::new(),
+        metadata,
+        inputs,
+        outputs,
+    }
+}
+
+/// Map from tensor name to the spec serialized as toml
+fn specs_by_name(specs: Option<Vec<TensorSpec>>) -> BTreeMap<String, String> {
+    specs
+        .unwrap_or_default()
+        .into_iter()
+        .map(|spec| (spec.name.clone(), to_toml_string(&spec)))
+        .collect()
+}
+
+/// Flatten a config into a map from dotted field path (e.g. `runner.opts.num_threads`) to the
+/// value serialized as toml. Arrays are treated as single values
+fn flatten_config(config: &CartonToml) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    if let Ok(toml::Value::Table(table)) = toml::Value::try_from(config) {
+        flatten_table(table, "", &mut out);
+    }
+
+    out
+}
+
+fn flatten_table(table: toml::value::Table, prefix: &str, out: &mut BTreeMap<String, String>) {
+    for (k, v) in table {
+        let path = if prefix.is_empty() {
+            k
+        } else {
+            format!("{prefix}.{k}")
+        };
+
+        match v {
+            toml::Value::Table(inner) => flatten_table(inner, &path, out),
+            other => {
+                out.insert(path, other.to_string());
+            }
+        }
+    }
+}
+
+fn to_toml_string<T: serde::Serialize>(value: &T) -> String {
+    toml::Value::try_from(value)
+        .map(|v| v.to_string())
+        .unwrap_or_default()
+}
+
+/// Compare two maps and return an entry for every key that was added, removed, or changed (sorted by key)
+fn diff_maps(a: BTreeMap<String, String>, mut b: BTreeMap<String, String>) -> Vec<DiffEntry> {
+    let mut out = Vec::new();
+    for (key, old) in a {
+        match b.remove(&key) {
+            Some(new) if new == old => {}
+            new => out.push(DiffEntry {
+                key,
+                old: Some(old),
+                new,
+            }),
+        }
+    }
+
+    // Anything left in `b` was added
+    out.extend(b.into_iter().map(|(key, new)| DiffEntry {
+        key,
+        old: None,
+        new: Some(new),
+    }));
+
+    out.sort_by(|x, y| x.key.cmp(&y.key));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::info::DiffEntry;
+
+    fn entry(key: &str, old: Option<&str>, new: Option<&str>) -> DiffEntry {
+        DiffEntry {
+            key: key.to_owned(),
+            old: old.map(|v| v.to_owned()),
+            new: new.map(|v| v.to_owned()),
+        }
+    }
+
+    /// Create a packed carton that only contains a carton.toml and a MANIFEST
+    fn create_carton(carton_toml: &str, manifest: &str) -> std::path::PathBuf {
+        let (file, path) = tempfile::NamedTempFile::new().unwrap().keep().unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        for (name, data) in [("carton.toml", carton_toml), ("MANIFEST", manifest)] {
+            writer
+                .start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        path
+    }
+
+    #[tokio::test]
+    async fn test_diff() {
+        let a = create_carton(
+            r#"
+spec_version = 1
+model_name = "model"
+license = "MIT"
+
+[runner]
+runner_name = "torchscript"
+required_framework_version = "=1.12.1"
+runner_compat_version = 1
+
+[runner.opts]
+num_threads = 1
+
+[[input]]
+name = "x"
+dtype = "float32"
+shape = [1, 2]
+
+[[input]]
+name = "y"
+dtype = "float32"
+shape = "*"
+
+[[output]]
+name = "out"
+dtype = "float32"
+shape = "*"
+"#,
+            "carton.toml=aaa\nmodel/weights.bin=111\nmodel/old.txt=222",
+        );
+
+        let b = create_carton(
+            r#"
+spec_version = 1
+model_name = "model"
+license = "Apache-2.0"
+
+[runner]
+runner_name = "torchscript"
+required_framework_version = "=1.12.1"
+runner_compat_version = 1
+
+[runner.opts]
+num_threads = 4
+
+[[input]]
+name = "x"
+dtype = "float64"
+shape = [1, 2]
+
+[[output]]
+name = "out"
+dtype = "float32"
+shape = "*"
+
+[[output]]
+name = "extra"
+dtype = "string"
+shape = "*"
+"#,
+            "carton.toml=bbb\nmodel/weights.bin=333\nmodel/new.txt=444",
+        );
+
+        let diff = super::diff(a, b).await.unwrap();
+
+        assert_eq!(
+            diff.files,
+            vec![
+                entry("carton.toml", Some("aaa"), Some("bbb")),
+                entry("model/new.txt", None, Some("444")),
+                entry("model/old.txt", Some("222"), None),
+                entry("model/weights.bin", Some("111"), Some("333")),
+            ]
+        );
+
+        assert_eq!(
+            diff.metadata,
+            vec![
+                entry("license", Some("\"MIT\""), Some("\"Apache-2.0\"")),
+                entry("runner.opts.num_threads", Some("1"), Some("4")),
+            ]
+        );
+
+        assert_eq!(
+            diff.inputs
+                .iter()
+                .map(|e| e.key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["x", "y"]
+        );
+        assert!(diff.inputs[0].old.as_ref().unwrap().contains("float32"));
+        assert!(diff.inputs[0].new.as_ref().unwrap().contains("float64"));
+        assert!(diff.inputs[1].new.is_none());
+
+        assert_eq!(
+            diff.outputs
+                .iter()
+                .map(|e| e.key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["extra"]
+        );
+        assert!(diff.outputs[0].old.is_none());
+
+        assert!(!diff.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_diff_identical() {
+        let carton_toml = r#"
+spec_version = 1
+
+[runner]
+runner_name = "torchscript"
+required_framework_version = "=1.12.1"
+runner_compat_version = 1
+"#;
+        let manifest = "carton.toml=aaa\nmodel/weights.bin=111";
+
+        let diff = super::diff(
+            create_carton(carton_toml, manifest),
+            create_carton(carton_toml, manifest),
+        )
+        .await
+        .unwrap();
+
+        assert!(diff.is_empty());
+    }
+}
@@ -12,16 +12,37 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
+use async_trait::async_trait;
 use lunchbox::{
     path::{LunchboxPathUtils, PathBuf},
+    types::{HasFileType, MaybeSend, MaybeSync, Metadata, PathType, ReadDir, ReadableFile},
     ReadableFileSystem,
 };
+use pin_project::pin_project;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, ReadBuf};
 use zipfs::ZipFS;
 
-use crate::error::CartonError;
+use super::manifest::parse_manifest;
+#[cfg(not(target_family = "wasm"))]
+use super::mmap::TENSOR_DATA_ALIGNMENT;
+#[cfg(not(target_family = "wasm"))]
+use crate::info::{LinkCheck, LinkStatus};
+use crate::{
+    error::CartonError, httpfs::HttpReadDirPoller, limitfs::SizeLimitFS, load::protocol::HttpURL,
+};
+
+/// A packed carton fetched over http. Like local cartons, it's untrusted so we enforce size limits on the
+/// data read from it (see `LoadOpts::max_archive_size`)
+pub(crate) type RemoteCarton = SizeLimitFS<ZipFS<HttpURL>>;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub(crate) struct Links {
@@ -30,15 +51,370 @@ pub(crate) struct Links {
 
     /// Map from a sha256 to a vec of URLs
     pub(crate) urls: HashMap<String, Vec<String>>,
+
+    /// Map from a sha256 to a vec of files in other cartons. These are only used for files that don't
+    /// have any `urls`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) carton_files: HashMap<String, Vec<CartonFile>>,
+}
+
+/// A file in another carton
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct CartonFile {
+    /// The URL of the carton
+    pub(crate) carton_url: String,
+
+    /// The path of the file in the carton
+    pub(crate) path: String,
+}
+
+impl Links {
+    /// Whether a file with this sha256 is stored as a link (instead of in the carton)
+    pub(crate) fn contains(&self, sha256: &str) -> bool {
+        self.urls.contains_key(sha256) || self.carton_files.contains_key(sha256)
+    }
 }
 
 impl From<Vec<crate::info::LinkedFile>> for Links {
     fn from(value: Vec<crate::info::LinkedFile>) -> Self {
         let mut urls = HashMap::new();
+        let mut carton_files = HashMap::new();
         for item in value {
-            urls.insert(item.sha256, item.urls);
+            if !item.carton_files.is_empty() {
+                carton_files.insert(
+                    item.sha256.clone(),
+                    item.carton_files
+                        .into_iter()
+                        .map(|v| CartonFile {
+                            carton_url: v.carton_url,
+                            path: v.path,
+                        })
+                        .collect(),
+                );
+            }
+
+            if !item.urls.is_empty() || !carton_files.contains_key(&item.sha256) {
+                urls.insert(item.sha256, item.urls);
+            }
+        }
+        Links {
+            version: 1,
+            urls,
+            carton_files,
+        }
+    }
+}
+
+/// Resolve the files in a carton that are links to files in other cartons (see `Links::carton_files`).
+/// `contents` is a map from file path to sha256 (i.e. the MANIFEST). Files with `urls` are skipped
+/// because they're handled by `HttpFS`.
+///
+/// This checks that each linked file has the expected sha256 in the MANIFEST of the carton that contains
+/// it. If a file is in several cartons, they're tried in order until one of them passes this check.
+/// The files themselves are fetched with range requests when they're read and their contents are checked
+/// against the expected sha256 as they're read (see `VerifiedFile`).
+///
+/// `max_total_size` and `max_file_size` are the size limits for each of the other cartons
+pub(crate) async fn resolve_carton_files(
+    contents: &HashMap<&str, &str>,
+    links: &Links,
+    max_total_size: u64,
+    max_file_size: u64,
+) -> crate::error::Result<CartonFileFS<RemoteCarton>> {
+    // Each carton is only opened once even if several files link to it
+    let mut cartons = HashMap::new();
+
+    let mut files = HashMap::new();
+    for (&path, &sha256) in contents {
+        if links
+            .urls
+            .get(sha256)
+            .map_or(false, |urls| !urls.is_empty())
+        {
+            continue;
+        }
+
+        let candidates = match links.carton_files.get(sha256) {
+            Some(candidates) => candidates,
+            None => continue,
+        };
+
+        let mut last_error = None;
+        for link in candidates {
+            match open_carton_file(&mut cartons, link, sha256, max_total_size, max_file_size).await
+            {
+                Ok(fs) => {
+                    files.insert(
+                        path.into(),
+                        LinkedCartonFile {
+                            fs,
+                            path: link.path.as_str().into(),
+                            sha256: sha256.to_owned(),
+                        },
+                    );
+                    last_error = None;
+                    break;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Can't use '{}' in the carton at '{}' for '{path}': {e}",
+                        link.path,
+                        link.carton_url
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(e) = last_error {
+            return Err(e);
+        }
+    }
+
+    Ok(CartonFileFS { files })
+}
+
+/// The cartons opened by `resolve_carton_files` along with their parsed MANIFESTs (keyed by URL)
+type OpenedCartons<'a> = HashMap<&'a str, (Arc<RemoteCarton>, HashMap<String, String>)>;
+
+/// Open the carton containing `link` (if it isn't already open) and check that its MANIFEST lists
+/// `link.path` with the expected sha256
+async fn open_carton_file<'a>(
+    cartons: &mut OpenedCartons<'a>,
+    link: &'a CartonFile,
+    sha256: &str,
+    max_total_size: u64,
+    max_file_size: u64,
+) -> crate::error::Result<Arc<RemoteCarton>> {
+    if !cartons.contains_key(link.carton_url.as_str()) {
+        let opened = open_carton(&link.carton_url, max_total_size, max_file_size)
+            .await
+            .map_err(|e| CartonError::LinkedCartonOpenFailed {
+                carton_url: link.carton_url.clone(),
+                error: e.to_string(),
+            })?;
+
+        cartons.insert(&link.carton_url, opened);
+    }
+
+    let (fs, manifest) = &cartons[link.carton_url.as_str()];
+    match manifest.get(&link.path) {
+        Some(actual) if actual == sha256 => Ok(fs.clone()),
+        actual => Err(CartonError::LinkedCartonFileMismatch {
+            carton_url: link.carton_url.clone(),
+            path: link.path.clone(),
+            expected: sha256.to_owned(),
+            actual: actual.cloned(),
+        }),
+    }
+}
+
+/// Open a packed carton at `carton_url` and read its MANIFEST
+async fn open_carton(
+    carton_url: &str,
+    max_total_size: u64,
+    max_file_size: u64,
+) -> crate::error::Result<(Arc<RemoteCarton>, HashMap<String, String>)> {
+    // `ZipFS` panics if it can't fetch the URL so make sure we can before opening it
+    url::Url::parse(carton_url).map_err(|_| CartonError::Other("invalid URL"))?;
+    crate::http::HTTPFile::new(crate::load::CLIENT.clone(), carton_url.to_owned(), true).await?;
+
+    let zip = Arc::new(ZipFS::new(HttpURL(carton_url.to_owned())).await);
+    let fs = SizeLimitFS::new(zip, max_total_size, max_file_size);
+    let manifest = parse_manifest(&fs.read_to_string("/MANIFEST").await?)?
+        .into_iter()
+        .map(|(file_path, sha256)| (file_path.to_owned(), sha256.to_owned()))
+        .collect();
+    Ok((Arc::new(fs), manifest))
+}
+
+/// A filesystem containing files that live in other cartons. See `resolve_carton_files`
+pub(crate) struct CartonFileFS<F> {
+    /// Map from a path in this carton to the file in another carton
+    files: HashMap<PathBuf, LinkedCartonFile<F>>,
+}
+
+/// A file in another carton along with the sha256 it's expected to have
+struct LinkedCartonFile<F> {
+    /// The carton containing the file
+    fs: Arc<F>,
+
+    /// The path of the file in `fs`
+    path: PathBuf,
+
+    sha256: String,
+}
+
+impl<F> CartonFileFS<F> {
+    fn get(&self, path: impl PathType) -> std::io::Result<&LinkedCartonFile<F>> {
+        self.files
+            .get(path.as_ref())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"))
+    }
+}
+
+/// Check that data read from another carton has the expected sha256 (the same check `cached_download`
+/// does for files with `urls`)
+fn check_sha256(path: &PathBuf, expected: &str, actual: &str) -> std::io::Result<()> {
+    if actual != expected {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Expected the linked file '{path}' to have a sha256 of '{expected}', but got '{actual}'"),
+        ));
+    }
+
+    Ok(())
+}
+
+impl<F> LinkedCartonFile<F>
+where
+    F: ReadableFileSystem + MaybeSend + MaybeSync,
+    F::FileType: ReadableFile + MaybeSend + MaybeSync,
+{
+    async fn read(&self) -> std::io::Result<Vec<u8>> {
+        let data = self.fs.read(&self.path).await?;
+        check_sha256(
+            &self.path,
+            &self.sha256,
+            &format!("{:x}", Sha256::digest(&data)),
+        )?;
+        Ok(data)
+    }
+}
+
+/// A file from another carton. Its contents are hashed as they're read and reading fails at the end of
+/// the file if they don't match the expected sha256
+#[pin_project]
+pub(crate) struct VerifiedFile<T> {
+    #[pin]
+    inner: T,
+    path: PathBuf,
+    expected: String,
+    hasher: Sha256,
+    done: bool,
+}
+
+impl<T: AsyncRead> AsyncRead for VerifiedFile<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.project();
+        let start = buf.filled().len();
+        let had_space = buf.remaining() > 0;
+        match this.inner.poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+
+        let data = &buf.filled()[start..];
+        if !data.is_empty() {
+            this.hasher.update(data);
+        } else if had_space && !*this.done {
+            // We're at the end of the file
+            *this.done = true;
+            let actual = format!("{:x}", this.hasher.finalize_reset());
+            return Poll::Ready(check_sha256(this.path, this.expected, &actual));
         }
-        Links { version: 1, urls }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg_attr(target_family = "wasm", async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait)]
+impl<T> ReadableFile for VerifiedFile<T>
+where
+    T: ReadableFile + MaybeSend + MaybeSync,
+{
+    async fn metadata(&self) -> std::io::Result<Metadata> {
+        self.inner.metadata().await
+    }
+
+    async fn try_clone(&self) -> std::io::Result<Self> {
+        // A clone would share the read position so we can't hash it independently
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Files linked from other cartons can't be cloned",
+        ))
+    }
+}
+
+impl<F: HasFileType> HasFileType for CartonFileFS<F> {
+    type FileType = VerifiedFile<F::FileType>;
+}
+
+#[cfg_attr(target_family = "wasm", async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait)]
+impl<F> ReadableFileSystem for CartonFileFS<F>
+where
+    F: ReadableFileSystem + MaybeSend + MaybeSync,
+    F::FileType: ReadableFile + MaybeSend + MaybeSync,
+{
+    async fn open(&self, path: impl PathType) -> std::io::Result<Self::FileType>
+    where
+        Self::FileType: ReadableFile,
+    {
+        let file = self.get(path)?;
+        Ok(VerifiedFile {
+            inner: file.fs.open(&file.path).await?,
+            path: file.path.clone(),
+            expected: file.sha256.clone(),
+            hasher: Sha256::new(),
+            done: false,
+        })
+    }
+
+    async fn canonicalize(&self, path: impl PathType) -> std::io::Result<PathBuf> {
+        // Normalize the path and make sure it exists
+        let normalized: PathBuf = path_clean::clean(path.as_ref().as_str()).into();
+        self.get(&normalized)?;
+        Ok(normalized)
+    }
+
+    async fn metadata(&self, path: impl PathType) -> std::io::Result<Metadata> {
+        let file = self.get(path)?;
+        file.fs.metadata(&file.path).await
+    }
+
+    async fn read(&self, path: impl PathType) -> std::io::Result<Vec<u8>> {
+        self.get(path)?.read().await
+    }
+
+    type ReadDirPollerType = HttpReadDirPoller;
+
+    async fn read_dir(
+        &self,
+        path: impl PathType,
+    ) -> std::io::Result<ReadDir<Self::ReadDirPollerType, Self>> {
+        let p = path.as_ref();
+        let poller = HttpReadDirPoller::new(
+            self.files
+                .keys()
+                .filter(|k| k.starts_with(p))
+                .cloned()
+                .collect(),
+        );
+
+        Ok(ReadDir::new(poller, self))
+    }
+
+    async fn read_link(&self, _path: impl PathType) -> std::io::Result<PathBuf> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "File not a symlink",
+        ))
+    }
+
+    async fn read_to_string(&self, path: impl PathType) -> std::io::Result<String> {
+        String::from_utf8(self.get(path)?.read().await?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    async fn symlink_metadata(&self, path: impl PathType) -> std::io::Result<Metadata> {
+        // We don't support symlinks so these are the same
+        self.metadata(path).await
     }
 }
 
@@ -51,6 +427,19 @@ pub(crate) async fn create_links(
 ) -> crate::error::Result<std::path::PathBuf> {
     use std::io::Write;
 
+    // Keep the compression method of every entry (see `PackOpts::compress_manifest` and
+    // `PackOpts::store_uncompressed`)
+    let compression_methods = {
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || get_compression_methods(&path))
+            .await
+            .unwrap()?
+    };
+    let manifest_compression = compression_methods
+        .get("MANIFEST")
+        .copied()
+        .unwrap_or(zip::CompressionMethod::Stored);
+
     let fs = ZipFS::new(path).await;
 
     let has_links = PathBuf::from("/LINKS").exists(&fs).await;
@@ -63,6 +452,7 @@ pub(crate) async fn create_links(
         Links {
             version: 1,
             urls: HashMap::new(),
+            carton_files: HashMap::new(),
         }
     };
 
@@ -78,52 +468,74 @@ pub(crate) async fn create_links(
 
     // For each file in the manifest
     let manifest = fs.read_to_string("/MANIFEST").await?;
-    for line in manifest.lines() {
-        if let Some((file_path, sha256)) = line.rsplit_once("=") {
-            if !links.urls.contains_key(sha256) {
-                // Only files that aren't contained in LINKS
-                let data = fs.read(file_path).await?;
-                let file_path = file_path.to_owned();
-                writer = tokio::task::spawn_blocking(move || {
+    for (file_path, sha256) in parse_manifest(&manifest)? {
+        if !links.contains(sha256) {
+            // Only files that aren't contained in LINKS
+            let data = fs.read(file_path).await?;
+            let compression = compression_methods
+                .get(file_path)
+                .copied()
+                .unwrap_or(zip::CompressionMethod::Zstd);
+            let file_path = file_path.to_owned();
+            writer = tokio::task::spawn_blocking(move || {
+                let options = zip::write::FileOptions::default()
+                    .compression_method(compression)
+                    .large_file(data.len() >= 4 * 1024 * 1024 * 1024);
+
+                // Uncompressed tensor_data stays aligned so the loader can still memory map tensors in place
+                if compression == zip::CompressionMethod::Stored
+                    && file_path.starts_with("tensor_data/")
+                {
                     writer
-                        .start_file(
-                            file_path,
-                            zip::write::FileOptions::default()
-                                .compression_method(zip::CompressionMethod::Zstd)
-                                .large_file(data.len() >= 4 * 1024 * 1024 * 1024),
-                        )
+                        .start_file_aligned(file_path, options, TENSOR_DATA_ALIGNMENT)
                         .unwrap();
-                    writer.write_all(&data).unwrap();
-                    writer
-                })
-                .await
-                .unwrap();
-            }
-        } else {
-            return Err(CartonError::Other(
-                "MANIFEST was not in the form {path}={sha256}",
-            ));
+                } else {
+                    writer.start_file(file_path, options).unwrap();
+                }
+                writer.write_all(&data).unwrap();
+                writer
+            })
+            .await
+            .unwrap();
         }
     }
 
     let manifest_data = fs.read("/MANIFEST").await?;
+
+    // The MANIFEST doesn't change so any signature is still valid
+    let signature = if PathBuf::from("/SIGNATURE").exists(&fs).await {
+        Some(fs.read("/SIGNATURE").await?)
+    } else {
+        None
+    };
+
     tokio::task::spawn_blocking(move || {
         // Add MANIFEST
         writer
             .start_file(
                 "MANIFEST",
-                zip::write::FileOptions::default()
-                    .compression_method(zip::CompressionMethod::Stored),
+                zip::write::FileOptions::default().compression_method(manifest_compression),
             )
             .unwrap();
         writer.write_all(&manifest_data).unwrap();
 
+        // Add SIGNATURE (if any)
+        if let Some(signature) = signature {
+            writer
+                .start_file(
+                    "SIGNATURE",
+                    zip::write::FileOptions::default()
+                        .compression_method(zip::CompressionMethod::Stored),
+                )
+                .unwrap();
+            writer.write_all(&signature).unwrap();
+        }
+
         // Add LINKS
         writer
             .start_file(
                 "LINKS",
-                zip::write::FileOptions::default()
-                    .compression_method(zip::CompressionMethod::Stored),
+                zip::write::FileOptions::default().compression_method(manifest_compression),
             )
             .unwrap();
         let data = toml::to_vec(&links).unwrap();
@@ -141,9 +553,203 @@ pub(crate) async fn create_links(
     Ok(output_zip_path)
 }
 
+/// The compression method of every entry in a packed carton
+#[cfg(not(target_family = "wasm"))]
+fn get_compression_methods(
+    path: &std::path::Path,
+) -> crate::error::Result<HashMap<String, zip::CompressionMethod>> {
+    let mut archive =
+        zip::ZipArchive::new(std::fs::File::open(path)?).map_err(std::io::Error::from)?;
+    let mut out = HashMap::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(std::io::Error::from)?;
+        out.insert(entry.name().to_owned(), entry.compression());
+    }
+
+    Ok(out)
+}
+
+/// Check that every link in the `LINKS` file of a packed carton is reachable. This includes URLs and files in
+/// other cartons. If `check_hashes` is true, this also downloads each file to make sure its sha256 matches.
+///
+/// This returns a status for every link instead of failing on the first problem
+#[cfg(not(target_family = "wasm"))]
+pub(crate) async fn verify_links(
+    path: std::path::PathBuf,
+    check_hashes: bool,
+) -> crate::error::Result<Vec<LinkStatus>> {
+    let fs = ZipFS::new(path).await;
+
+    // Cartons without a LINKS file don't have anything to check
+    if !PathBuf::from("/LINKS").exists(&fs).await {
+        return Ok(Vec::new());
+    }
+
+    let links: Links = toml::from_str(&fs.read_to_string("/LINKS").await?)?;
+
+    let client = carton_utils::download::client_builder().build()?;
+    let checks = links.urls.into_iter().flat_map(|(sha256, urls)| {
+        let client = client.clone();
+        urls.into_iter().map(move |url| {
+            let client = client.clone();
+            let sha256 = sha256.clone();
+            async move {
+                let status = if check_hashes {
+                    check_hash(&client, &url, &sha256).await
+                } else {
+                    check_reachable(&client, &url).await
+                };
+
+                LinkStatus {
+                    sha256,
+                    url,
+                    path: None,
+                    status,
+                }
+            }
+        })
+    });
+
+    let mut statuses = futures::future::join_all(checks).await;
+
+    // Each carton is only opened once even if several files link to it
+    let mut cartons = HashMap::new();
+    for (sha256, candidates) in &links.carton_files {
+        for link in candidates {
+            let status = check_carton_file(&mut cartons, link, sha256, check_hashes).await;
+            statuses.push(LinkStatus {
+                sha256: sha256.clone(),
+                url: link.carton_url.clone(),
+                path: Some(link.path.clone()),
+                status,
+            });
+        }
+    }
+
+    Ok(statuses)
+}
+
+/// Check that a file in another carton is listed in that carton's MANIFEST with the expected sha256 and that
+/// it can be read. If `check_hashes` is true, this also reads the whole file to make sure its sha256 matches
+#[cfg(not(target_family = "wasm"))]
+async fn check_carton_file<'a>(
+    cartons: &mut OpenedCartons<'a>,
+    link: &'a CartonFile,
+    expected: &str,
+    check_hashes: bool,
+) -> LinkCheck {
+    use carton_utils::archive::{DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_TOTAL_SIZE};
+    use tokio::io::AsyncReadExt;
+
+    let unreachable = |reason: String| LinkCheck::Unreachable { reason };
+
+    let fs = match open_carton_file(
+        cartons,
+        link,
+        expected,
+        DEFAULT_MAX_TOTAL_SIZE,
+        DEFAULT_MAX_FILE_SIZE,
+    )
+    .await
+    {
+        Ok(fs) => fs,
+        Err(CartonError::LinkedCartonFileMismatch {
+            actual: Some(actual),
+            ..
+        }) => return LinkCheck::HashMismatch { actual },
+        Err(e) => return unreachable(e.to_string()),
+    };
+
+    // The file may be listed in the MANIFEST without being stored in the carton (e.g. if it's a link too)
+    let mut f = match fs.open(&link.path).await {
+        Ok(f) => f,
+        Err(e) => return unreachable(e.to_string()),
+    };
+
+    if !check_hashes {
+        return LinkCheck::Ok;
+    }
+
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1024 * 1024];
+    loop {
+        match f.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) => return unreachable(e.to_string()),
+        }
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual == expected {
+        LinkCheck::Ok
+    } else {
+        LinkCheck::HashMismatch { actual }
+    }
+}
+
+/// Check if a URL is reachable without downloading the whole file
+#[cfg(not(target_family = "wasm"))]
+async fn check_reachable(client: &reqwest::Client, url: &str) -> LinkCheck {
+    // Try a HEAD request first
+    if let Ok(res) = client.head(url).send().await {
+        if res.status().is_success() {
+            return LinkCheck::Ok;
+        }
+    }
+
+    // Some servers don't support HEAD so fall back to requesting a single byte
+    match client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await
+    {
+        Ok(res) if res.status().is_success() => LinkCheck::Ok,
+        Ok(res) => LinkCheck::Unreachable {
+            reason: format!("Got status {}", res.status()),
+        },
+        Err(e) => LinkCheck::Unreachable {
+            reason: e.to_string(),
+        },
+    }
+}
+
+/// Download a URL and check that its sha256 matches `expected`
+#[cfg(not(target_family = "wasm"))]
+async fn check_hash(client: &reqwest::Client, url: &str, expected: &str) -> LinkCheck {
+    use sha2::{Digest, Sha256};
+
+    let unreachable = |reason: String| LinkCheck::Unreachable { reason };
+
+    let mut res = match client.get(url).send().await {
+        Ok(res) if res.status().is_success() => res,
+        Ok(res) => return unreachable(format!("Got status {}", res.status())),
+        Err(e) => return unreachable(e.to_string()),
+    };
+
+    let mut hasher = Sha256::new();
+    loop {
+        match res.chunk().await {
+            Ok(Some(chunk)) => hasher.update(&chunk),
+            Ok(None) => break,
+            Err(e) => return unreachable(e.to_string()),
+        }
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual == expected {
+        LinkCheck::Ok
+    } else {
+        LinkCheck::HashMismatch { actual }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Links;
+    use std::collections::HashMap;
+
+    use super::{CartonFile, Links};
 
     #[test]
     fn test_deserialize_links() {
@@ -163,8 +769,241 @@ e550f6224a5133f597d823ab4590f369e0b20e3c6446488225fc6f7a372b9fe2 = [\"https://ex
             )]
             .into_iter()
             .collect(),
+            carton_files: HashMap::new(),
         };
 
         assert_eq!(deserialized, target);
     }
+
+    #[test]
+    fn test_carton_files() {
+        let serialized = "
+version = 1
+
+[urls]
+
+[[carton_files.e550f6224a5133f597d823ab4590f369e0b20e3c6446488225fc6f7a372b9fe2]]
+carton_url = \"https://example.com/base.carton\"
+path = \"model/weights.bin\"
+";
+
+        let deserialized: Links = toml::from_str(serialized).unwrap();
+        let sha256 = "e550f6224a5133f597d823ab4590f369e0b20e3c6446488225fc6f7a372b9fe2";
+        assert!(deserialized.urls.is_empty());
+        assert_eq!(
+            deserialized.carton_files[sha256],
+            vec![CartonFile {
+                carton_url: "https://example.com/base.carton".into(),
+                path: "model/weights.bin".into(),
+            }]
+        );
+        assert!(deserialized.contains(sha256));
+
+        // Files that only link to other cartons shouldn't get an entry in `urls`
+        let links: Links = vec![crate::info::LinkedFile::new(Vec::new(), sha256.into())
+            .with_carton_files(vec![crate::info::LinkedCartonFile {
+                carton_url: "https://example.com/base.carton".into(),
+                path: "model/weights.bin".into(),
+            }])]
+        .into();
+        assert_eq!(links, deserialized);
+
+        // Roundtrip
+        let roundtripped: Links = toml::from_slice(&toml::to_vec(&links).unwrap()).unwrap();
+        assert_eq!(roundtripped, links);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_verify_unreachable_links() {
+        use std::io::Write;
+
+        use crate::info::LinkCheck;
+
+        let sha256 = "e550f6224a5133f597d823ab4590f369e0b20e3c6446488225fc6f7a372b9fe2";
+        let links = Links {
+            version: 1,
+            urls: [(
+                sha256.to_owned(),
+                vec!["http://127.0.0.1:1/file".to_owned()],
+            )]
+            .into(),
+            carton_files: [(
+                sha256.to_owned(),
+                vec![CartonFile {
+                    carton_url: "http://127.0.0.1:1/base.carton".into(),
+                    path: "model/weights.bin".into(),
+                }],
+            )]
+            .into(),
+        };
+
+        // Create a carton that only contains a LINKS file
+        let (file, path) = tempfile::NamedTempFile::new().unwrap().keep().unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        writer
+            .start_file("LINKS", zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(&toml::to_vec(&links).unwrap()).unwrap();
+        writer.finish().unwrap();
+
+        for check_hashes in [false, true] {
+            let statuses = super::verify_links(path.clone(), check_hashes)
+                .await
+                .unwrap();
+
+            // One status for the URL and one for the file in another carton
+            assert_eq!(statuses.len(), 2);
+            for status in &statuses {
+                assert_eq!(status.sha256, sha256);
+                assert!(matches!(status.status, LinkCheck::Unreachable { .. }));
+            }
+
+            assert_eq!(statuses[0].url, "http://127.0.0.1:1/file");
+            assert_eq!(statuses[0].path, None);
+            assert_eq!(statuses[1].url, "http://127.0.0.1:1/base.carton");
+            assert_eq!(statuses[1].path.as_deref(), Some("model/weights.bin"));
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_shrink_store_uncompressed() {
+        use sha2::{Digest, Sha256};
+
+        use crate::{
+            format::v1::mmap::{StoredEntries, TENSOR_DATA_ALIGNMENT},
+            info::{DataType, Dimension, PossiblyLoaded, Shape, TensorSpec},
+            types::{PackOpts, Tensor},
+        };
+
+        let weights = vec![0u8; 1024 * 1024];
+        let model_dir = tempfile::tempdir().unwrap();
+        std::fs::write(model_dir.path().join("weights.bin"), &weights).unwrap();
+
+        // The default value is stored in `tensor_data/`
+        let mut opts = PackOpts {
+            store_uncompressed: true,
+            ..Default::default()
+        };
+        opts.info.runner.runner_name = "noop".into();
+        opts.info.runner.runner_compat_version = Some(1);
+        opts.info.inputs = Some(vec![TensorSpec {
+            name: "x".into(),
+            dtype: DataType::Float,
+            shape: Shape::Shape(vec![Dimension::Value(2), Dimension::Value(2)]),
+            description: None,
+            internal_name: None,
+            optional: true,
+            default: Some(PossiblyLoaded::from_value(Tensor::new(
+                ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1f32, 2., 3., 4.]).unwrap(),
+            ))),
+        }]);
+
+        let packed = crate::format::v1::save(opts, model_dir.path())
+            .await
+            .unwrap();
+        let shrunk = super::create_links(
+            packed.clone(),
+            [(
+                format!("{:x}", Sha256::digest(&weights)),
+                vec!["https://example.com/weights.bin".to_owned()],
+            )]
+            .into(),
+        )
+        .await
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&shrunk).unwrap()).unwrap();
+        assert!(archive.by_name("model/weights.bin").is_err());
+        let tensors: Vec<_> = archive
+            .file_names()
+            .filter(|name| name.starts_with("tensor_data/"))
+            .map(|name| name.to_owned())
+            .collect();
+        assert!(!tensors.is_empty());
+
+        // The tensor data is still stored uncompressed and aligned so it can be memory mapped
+        for name in tensors {
+            let entry = archive.by_name(&name).unwrap();
+            assert_eq!(entry.compression(), zip::CompressionMethod::Stored);
+            assert_eq!(entry.data_start() % TENSOR_DATA_ALIGNMENT as u64, 0);
+        }
+
+        let stored = StoredEntries::open(&shrunk).unwrap();
+        assert!(
+            unsafe { stored.map_tensor::<f32>("tensor_data/tensor_0.bin", vec![2, 2]) }.is_some()
+        );
+
+        std::fs::remove_file(packed).unwrap();
+        std::fs::remove_file(shrunk).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_verified_file() {
+        use sha2::{Digest, Sha256};
+        use tokio::io::AsyncReadExt;
+
+        use super::VerifiedFile;
+
+        let data = b"some data".as_slice();
+        let file = |expected: String| VerifiedFile {
+            inner: data,
+            path: "model/weights.bin".into(),
+            expected,
+            hasher: Sha256::new(),
+            done: false,
+        };
+
+        let mut out = Vec::new();
+        file(format!("{:x}", Sha256::digest(data)))
+            .read_to_end(&mut out)
+            .await
+            .unwrap();
+        assert_eq!(out, data);
+
+        let err = file("0".repeat(64))
+            .read_to_end(&mut Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_unreachable_carton_files() {
+        use crate::error::CartonError;
+
+        let sha256 = "e550f6224a5133f597d823ab4590f369e0b20e3c6446488225fc6f7a372b9fe2";
+        let links = Links {
+            version: 1,
+            urls: HashMap::new(),
+            carton_files: [(
+                sha256.to_owned(),
+                vec![
+                    CartonFile {
+                        carton_url: "http://127.0.0.1:1/a.carton".into(),
+                        path: "model/weights.bin".into(),
+                    },
+                    CartonFile {
+                        carton_url: "http://127.0.0.1:1/b.carton".into(),
+                        path: "model/weights.bin".into(),
+                    },
+                ],
+            )]
+            .into(),
+        };
+
+        // Both cartons are tried and we get an error instead of a panic
+        let contents = [("model/weights.bin", sha256)].into();
+        match super::resolve_carton_files(&contents, &links, u64::MAX, u64::MAX).await {
+            Err(CartonError::LinkedCartonOpenFailed { carton_url, .. }) => {
+                assert_eq!(carton_url, "http://127.0.0.1:1/b.carton")
+            }
+            Err(e) => panic!("Expected a LinkedCartonOpenFailed error, got {e}"),
+            Ok(_) => panic!("Expected a LinkedCartonOpenFailed error"),
+        }
+    }
 }
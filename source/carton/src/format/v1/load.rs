@@ -16,13 +16,15 @@
 //! This module does a lot of type conversions to map from the types in the toml file to the ones in
 //! crate::types and crate::info
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use lunchbox::path::{LunchboxPathUtils, PathBuf};
 use lunchbox::types::{MaybeSend, MaybeSync, ReadableFile};
 use lunchbox::ReadableFileSystem;
 use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
 
 use crate::conversion_utils::{
     convert_opt_map, convert_opt_vec, convert_vec, ConvertFromWithContext, ConvertIntoWithContext,
@@ -31,6 +33,9 @@ use crate::error::{CartonError, Result};
 use crate::info::{CartonInfoWithExtras, PossiblyLoaded};
 use crate::types::CartonInfo;
 
+use super::manifest::parse_manifest;
+use super::mmap::StoredEntries;
+
 struct MiscFileLoader<T> {
     fs: Arc<T>,
     path: String,
@@ -48,7 +53,10 @@ where
     }
 }
 
-pub(crate) async fn load<T>(fs: &Arc<T>) -> Result<CartonInfoWithExtras>
+pub(crate) async fn load<T>(
+    fs: &Arc<T>,
+    stored: Option<Arc<StoredEntries>>,
+) -> Result<CartonInfoWithExtras>
 where
     T: ReadableFileSystem + MaybeSend + MaybeSync + 'static,
     T::FileType: ReadableFile + MaybeSend + MaybeSync + Unpin + 'static,
@@ -57,22 +65,36 @@ where
     let toml = fs.read("/carton.toml").await?;
     let config = crate::format::v1::carton_toml::parse(&toml).await?;
 
+    // Unpacked models (e.g. a work-in-progress model directory) don't have a MANIFEST
+    let manifest = if PathBuf::from("/MANIFEST").exists(fs.as_ref()).await {
+        Some(fs.read_to_string("/MANIFEST").await?)
+    } else {
+        None
+    };
+
     // Check for misc files
-    let manifest = fs.read_to_string("/MANIFEST").await?;
     let mut misc_file_paths = Vec::new();
+    let mut listed = HashSet::new();
 
     // Filter the manifest to files in `misc/`
     // Note: not using `filter` so we can return errors easily
-    for line in manifest.lines() {
-        if let Some((file_path, _sha256)) = line.rsplit_once("=") {
-            if file_path.starts_with("misc/") {
-                misc_file_paths.push(file_path);
-            }
-        } else {
-            return Err(CartonError::Other(
-                "MANIFEST was not in the form {path}={sha256}",
-            ));
+    for (file_path, _sha256) in parse_manifest(manifest.as_deref().unwrap_or_default())? {
+        if file_path.starts_with("misc/") {
+            misc_file_paths.push(file_path.to_owned());
         }
+
+        listed.insert(normalize_path(file_path));
+    }
+
+    // Don't trust symlinks in the carton (it may be malicious)
+    if manifest.is_some() {
+        let symlinks = find_symlinks(fs.as_ref(), &listed).await?;
+        validate_symlinks(&symlinks)?;
+    }
+
+    // Without a MANIFEST, list the files in `misc/` instead
+    if manifest.is_none() && PathBuf::from("/misc").exists(fs.as_ref()).await {
+        misc_file_paths = list_files(fs.as_ref(), "misc").await?;
     }
 
     // Create the loaders for all the misc files
@@ -85,7 +107,7 @@ where
                 .map(|path| {
                     let mfl = MiscFileLoader {
                         fs: fs.clone(),
-                        path: path.to_owned(),
+                        path: path.clone(),
                     };
 
                     let mfl: crate::info::ArcMiscFileLoader = Arc::new(mfl);
@@ -96,10 +118,22 @@ where
         )
     };
 
-    let tensors =
-        super::tensor::load_tensors(fs, lunchbox::path::Path::new("tensor_data/")).await?;
+    // Unpacked models may not have any tensor data
+    let tensors = if manifest.is_some() || PathBuf::from("/tensor_data").exists(fs.as_ref()).await {
+        super::tensor::load_tensors(fs, lunchbox::path::Path::new("tensor_data/"), stored).await?
+    } else {
+        HashMap::new()
+    };
     let load_context = LoadContext { fs, tensors };
 
+    // Cartons packed before optional inputs were supported don't mark any inputs as `optional` or not.
+    // Callers may rely on omitting inputs for these so we don't require anything
+    let enforce_required_inputs = config
+        .input
+        .iter()
+        .flatten()
+        .any(|spec| spec.optional.is_some());
+
     // Create a CartonInfo struct
     let info = CartonInfo {
         model_name: config.model_name,
@@ -109,8 +143,8 @@ where
         repository: config.repository,
         homepage: config.homepage,
         required_platforms: convert_opt_vec(config.required_platforms),
-        inputs: convert_opt_vec(config.input),
-        outputs: convert_opt_vec(config.output),
+        inputs: config.input.convert_into_with_context(&load_context),
+        outputs: config.output.convert_into_with_context(&load_context),
         self_tests: config.self_test.convert_into_with_context(&load_context),
         // TODO: reuse the misc files from above when loading examples
         examples: config.example.convert_into_with_context(&load_context),
@@ -119,17 +153,201 @@ where
     };
 
     // Compute the manifest sha256
-    let manifest = fs.read("/MANIFEST").await?;
-    let mut hasher = Sha256::new();
-    hasher.update(manifest);
-    let manifest_sha256 = Some(format!("{:x}", hasher.finalize()));
+    let manifest_sha256 = manifest.map(|manifest| {
+        let mut hasher = Sha256::new();
+        hasher.update(manifest);
+        format!("{:x}", hasher.finalize())
+    });
 
     Ok(CartonInfoWithExtras {
         info,
         manifest_sha256,
+        enforce_required_inputs,
     })
 }
 
+/// Recursively list all the files in `dir` (relative to the root of `fs`)
+async fn list_files<T>(fs: &T, dir: &str) -> Result<Vec<String>>
+where
+    T: ReadableFileSystem,
+    T::FileType: ReadableFile,
+{
+    let mut out = Vec::new();
+    let mut to_process = VecDeque::new();
+    to_process.push_back(PathBuf::from(dir));
+    while let Some(dir_path) = to_process.pop_front() {
+        let mut dir = fs.read_dir(&dir_path).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let entry_path = entry.path();
+            if entry.metadata().await?.is_dir() {
+                to_process.push_back(entry_path);
+            } else {
+                out.push(entry_path.to_string());
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// List all the files in `model/` (relative to the root of `fs`). Zip files don't necessarily contain entries
+/// for directories so this doesn't check if `model/` exists first
+async fn list_model_files<T>(fs: &T) -> Result<Vec<String>>
+where
+    T: ReadableFileSystem,
+    T::FileType: ReadableFile,
+{
+    match list_files(fs, "model").await {
+        Err(CartonError::IOError(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        res => res,
+    }
+}
+
+/// Find the symlinks in a packed carton. Returns a map from the (normalized) path of each symlink to its
+/// (normalized) target. Both paths are relative to the root of the carton.
+///
+/// This checks everything listed in `MANIFEST` (`listed`) along with everything in `model/` because the runner
+/// can see all of it. Symlinks that aren't listed in `MANIFEST` are rejected
+async fn find_symlinks<T>(fs: &T, listed: &HashSet<String>) -> Result<HashMap<String, String>>
+where
+    T: ReadableFileSystem + MaybeSync,
+    T::FileType: ReadableFile,
+{
+    let mut paths = listed.clone();
+    paths.extend(
+        list_model_files(fs)
+            .await?
+            .iter()
+            .map(|path| normalize_path(path)),
+    );
+
+    let mut symlinks = HashMap::new();
+    for path in paths {
+        // Missing files are handled elsewhere
+        let Ok(metadata) = fs.symlink_metadata(&path).await else {
+            continue;
+        };
+
+        if !metadata.is_symlink() {
+            continue;
+        }
+
+        if !listed.contains(&path) {
+            return Err(CartonError::MaliciousArchive(format!(
+                "the symlink at '{path}' is not listed in the MANIFEST"
+            )));
+        }
+
+        // Note: `read_link` returns the target relative to the root of `fs`. Some filesystems refuse to read
+        // symlinks they don't support (e.g. ones with absolute targets)
+        let target = fs.read_link(&path).await.map_err(|e| {
+            CartonError::MaliciousArchive(format!("couldn't read the symlink at '{path}': {e}"))
+        })?;
+
+        symlinks.insert(path, normalize_path(target.as_str()));
+    }
+
+    Ok(symlinks)
+}
+
+/// Check the contents of every file in the carton against the sha256 listed for it in `MANIFEST`.
+/// A signature or an expected manifest sha256 only covers the bytes of `MANIFEST` so this is what
+/// extends them to the rest of the carton. Symlinks are checked by making sure their target is listed
+/// with the same sha256
+pub(crate) async fn verify_file_hashes<T>(fs: &T) -> Result<()>
+where
+    T: ReadableFileSystem + MaybeSend + MaybeSync,
+    T::FileType: ReadableFile + MaybeSend + MaybeSync + Unpin,
+{
+    let manifest = fs.read_to_string("/MANIFEST").await?;
+    let expected: HashMap<_, _> = parse_manifest(&manifest)?
+        .into_iter()
+        .map(|(file_path, sha256)| (normalize_path(file_path), sha256.to_owned()))
+        .collect();
+
+    // The runner can see everything in `model/` so make sure there aren't any files that MANIFEST doesn't list
+    for path in list_model_files(fs).await? {
+        let path = normalize_path(&path);
+        if !expected.contains_key(&path) {
+            return Err(CartonError::MaliciousArchive(format!(
+                "'{path}' is not listed in the MANIFEST"
+            )));
+        }
+    }
+
+    for (path, sha256) in &expected {
+        if let Ok(target) = fs.read_link(path).await {
+            let target = normalize_path(target.as_str());
+            if expected.get(&target) != Some(sha256) {
+                return Err(CartonError::MaliciousArchive(format!(
+                    "the symlink at '{path}' points to '{target}', which doesn't match the sha256 in the MANIFEST"
+                )));
+            }
+
+            continue;
+        }
+
+        let mut f = fs.open(path).await?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 1024 * 1024];
+        loop {
+            let n = f.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+
+            hasher.update(&buf[..n]);
+        }
+
+        let actual = format!("{:x}", hasher.finalize());
+        if !actual.eq_ignore_ascii_case(sha256) {
+            return Err(CartonError::FileHashMismatch {
+                path: path.clone(),
+                expected: sha256.clone(),
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Normalize a path relative to the root of a carton
+fn normalize_path(path: &str) -> String {
+    path_clean::clean(path).trim_start_matches('/').to_owned()
+}
+
+/// Make sure all symlinks point to files within the `model` directory and that there are no symlink loops.
+/// `symlinks` is a map from the (normalized) path of each symlink to its (normalized) target. Both paths are
+/// relative to the root of the carton
+fn validate_symlinks(symlinks: &HashMap<String, String>) -> Result<()> {
+    for (path, target) in symlinks {
+        // Note: `target` is normalized so it can only contain `..` at the beginning
+        if target.starts_with("..") || !target.starts_with("model/") {
+            return Err(CartonError::MaliciousArchive(format!(
+                "the symlink at '{path}' points to '{target}', which is outside the model directory"
+            )));
+        }
+    }
+
+    // Follow each symlink chain and make sure it ends
+    for path in symlinks.keys() {
+        let mut visited = HashSet::new();
+        let mut current = path;
+        while let Some(target) = symlinks.get(current) {
+            if !visited.insert(current) {
+                return Err(CartonError::MaliciousArchive(format!(
+                    "found a symlink loop starting at '{path}'"
+                )));
+            }
+
+            current = target;
+        }
+    }
+
+    Ok(())
+}
+
 struct LoadContext<'a, F> {
     fs: &'a Arc<F>,
     tensors: HashMap<String, PossiblyLoaded<crate::types::Tensor>>,
@@ -221,14 +439,21 @@ impl From<super::carton_toml::Triple> for target_lexicon::Triple {
     }
 }
 
-impl From<super::carton_toml::TensorSpec> for crate::info::TensorSpec {
-    fn from(value: super::carton_toml::TensorSpec) -> Self {
+impl<C> ConvertFromWithContext<super::carton_toml::TensorSpec, C> for crate::info::TensorSpec
+where
+    C: Copy,
+    PossiblyLoaded<crate::types::Tensor>:
+        ConvertFromWithContext<super::carton_toml::TensorReference, C>,
+{
+    fn from(value: super::carton_toml::TensorSpec, context: C) -> Self {
         Self {
             name: value.name,
             dtype: value.dtype.into(),
             shape: value.shape.into(),
             description: value.description,
             internal_name: value.internal_name,
+            optional: value.optional.unwrap_or(false),
+            default: value.default.convert_into_with_context(context),
         }
     }
 }
@@ -260,6 +485,7 @@ impl From<super::carton_toml::Dimension> for crate::info::Dimension {
             super::carton_toml::Dimension::Value(v) => Self::Value(v),
             super::carton_toml::Dimension::Symbol(v) => Self::Symbol(v),
             super::carton_toml::Dimension::Any => Self::Any,
+            super::carton_toml::Dimension::Range { min, max } => Self::Range { min, max },
         }
     }
 }
@@ -278,6 +504,8 @@ impl From<super::carton_toml::DataType> for crate::info::DataType {
             super::carton_toml::DataType::Uint16 => Self::U16,
             super::carton_toml::DataType::Uint32 => Self::U32,
             super::carton_toml::DataType::Uint64 => Self::U64,
+            super::carton_toml::DataType::Complex64 => Self::Complex64,
+            super::carton_toml::DataType::Complex128 => Self::Complex128,
         }
     }
 }
@@ -292,3 +520,63 @@ impl From<super::carton_toml::RunnerOpt> for crate::info::RunnerOpt {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::error::CartonError;
+
+    use super::{normalize_path, validate_symlinks};
+
+    fn symlinks(items: &[(&str, &str)]) -> HashMap<String, String> {
+        items
+            .iter()
+            .map(|(path, target)| (normalize_path(path), normalize_path(target)))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_symlinks() {
+        validate_symlinks(&symlinks(&[
+            ("model/a", "model/b"),
+            ("model/b", "model/sub/../c"),
+            ("model/sub/d", "model/c"),
+            ("/model/e", "/model/c"),
+        ]))
+        .unwrap();
+    }
+
+    #[test]
+    fn test_symlink_escapes_model_dir() {
+        for target in [
+            "../etc/passwd",
+            "/etc/passwd",
+            "misc/a",
+            "model",
+            "model/..",
+        ] {
+            assert!(matches!(
+                validate_symlinks(&symlinks(&[("model/a", target)])),
+                Err(CartonError::MaliciousArchive(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_symlink_loop() {
+        assert!(matches!(
+            validate_symlinks(&symlinks(&[
+                ("model/a", "model/b"),
+                ("model/b", "model/c"),
+                ("model/c", "model/a"),
+            ])),
+            Err(CartonError::MaliciousArchive(_))
+        ));
+
+        assert!(matches!(
+            validate_symlinks(&symlinks(&[("model/a", "model/./a")])),
+            Err(CartonError::MaliciousArchive(_))
+        ));
+    }
+}
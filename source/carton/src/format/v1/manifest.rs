@@ -0,0 +1,46 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing for the `MANIFEST` file.
+//! See the `MANIFEST` section of `docs/specification/format.md` for more details
+use crate::error::{CartonError, Result};
+
+/// Parse the contents of a MANIFEST into a list of (file path, sha256) pairs in the order they're listed
+pub(crate) fn parse_manifest(manifest: &str) -> Result<Vec<(&str, &str)>> {
+    manifest
+        .lines()
+        .map(|line| {
+            line.rsplit_once("=").ok_or(CartonError::Other(
+                "MANIFEST was not in the form {path}={sha256}",
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_manifest;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = "carton.toml=abc\nmodel/a=b=def\n";
+        assert_eq!(
+            parse_manifest(manifest).unwrap(),
+            [("carton.toml", "abc"), ("model/a=b", "def")]
+        );
+
+        assert!(parse_manifest("").unwrap().is_empty());
+        assert!(parse_manifest("carton.toml=abc\nmodel/a").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory mapping of uncompressed tensors in packed cartons on local disk.
+//! See `PackOpts::store_uncompressed` for more details
+
+/// The alignment (in bytes) of the data of uncompressed `tensor_data` entries in a packed carton.
+/// This is enough for any of the tensor types we support
+#[cfg(not(target_family = "wasm"))]
+pub(crate) const TENSOR_DATA_ALIGNMENT: u16 = 64;
+
+/// The uncompressed (i.e. `Stored`) entries of a packed carton on local disk. These can be memory
+/// mapped instead of being read (and copied) through the zip filesystem
+#[cfg(not(target_family = "wasm"))]
+pub(crate) struct StoredEntries {
+    file: std::fs::File,
+
+    /// Map from the path of an entry to the offset of its data in `file` and its length
+    entries: std::collections::HashMap<String, (u64, u64)>,
+}
+
+/// Memory mapping isn't supported on wasm so this can't be constructed
+#[cfg(target_family = "wasm")]
+pub(crate) enum StoredEntries {}
+
+#[cfg(not(target_family = "wasm"))]
+impl StoredEntries {
+    /// Find the uncompressed entries of the zip file at `path`. Returns `None` if there aren't any
+    /// (or if `path` isn't a valid zip file; loading will fail with a more useful error later)
+    pub(crate) fn open(path: &std::path::Path) -> Option<Self> {
+        let file = std::fs::File::open(path).ok()?;
+
+        let mut entries = std::collections::HashMap::new();
+        {
+            let mut archive = zip::ZipArchive::new(&file).ok()?;
+            for i in 0..archive.len() {
+                let entry = archive.by_index_raw(i).ok()?;
+                if entry.is_file() && entry.compression() == zip::CompressionMethod::Stored {
+                    entries.insert(entry.name().to_owned(), (entry.data_start(), entry.size()));
+                }
+            }
+        }
+
+        if entries.is_empty() {
+            None
+        } else {
+            Some(Self { file, entries })
+        }
+    }
+
+    /// Memory map the tensor at `path` if it's stored uncompressed and correctly aligned.
+    /// The mapping is copy-on-write so modifying the tensor doesn't modify the file.
+    ///
+    /// SAFETY: every bit pattern must be a valid `T` (i.e. `T` must be one of the numeric tensor types)
+    pub(crate) unsafe fn map_tensor<T>(
+        &self,
+        path: &str,
+        shape: Vec<usize>,
+    ) -> Option<MappedTensor<T>> {
+        let (offset, len) = *self.entries.get(path.trim_start_matches('/'))?;
+
+        // The shape comes from the carton so it may be malicious. Don't let the size wrap around to match `len`
+        let size = shape
+            .iter()
+            .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))?
+            .checked_mul(std::mem::size_of::<T>())?;
+
+        // Empty tensors can't be mapped (and there's nothing to gain from it)
+        if len == 0 || len != size as u64 {
+            return None;
+        }
+
+        let mmap = memmap2::MmapOptions::new()
+            .offset(offset)
+            .len(len as usize)
+            .map_copy(&self.file)
+            .ok()?;
+
+        // Cartons packed by older versions of carton don't align their entries
+        if mmap.as_ptr() as usize % std::mem::align_of::<T>() != 0 {
+            return None;
+        }
+
+        Some(MappedTensor {
+            mmap,
+            shape,
+            _marker: std::marker::PhantomData,
+        })
+    }
+}
+
+/// Tensor storage backed by a (copy-on-write) memory mapped region of a packed carton
+#[cfg(not(target_family = "wasm"))]
+pub(crate) struct MappedTensor<T> {
+    mmap: memmap2::MmapMut,
+    shape: Vec<usize>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl<T> crate::types::TypedStorage<T> for MappedTensor<T> {
+    fn view(&self) -> ndarray::ArrayViewD<T> {
+        // SAFETY: `map_tensor` checked the length and alignment of the mapping
+        unsafe { ndarray::ArrayViewD::from_shape_ptr(self.shape.clone(), self.mmap.as_ptr() as _) }
+    }
+
+    fn view_mut(&mut self) -> ndarray::ArrayViewMutD<T> {
+        // SAFETY: `map_tensor` checked the length and alignment of the mapping
+        unsafe {
+            ndarray::ArrayViewMutD::from_shape_ptr(self.shape.clone(), self.mmap.as_mut_ptr() as _)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{StoredEntries, TENSOR_DATA_ALIGNMENT};
+    use crate::types::TypedStorage;
+
+    #[test]
+    fn test_map_tensor() {
+        let data: Vec<u8> = [1f32, 2., 3., 4.]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+
+        // Write a zip file with one compressed and one uncompressed copy of `data`
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        writer
+            .start_file(
+                "tensor_data/compressed",
+                zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Zstd),
+            )
+            .unwrap();
+        writer.write_all(&data).unwrap();
+        writer
+            .start_file_aligned(
+                "tensor_data/stored",
+                zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Stored),
+                TENSOR_DATA_ALIGNMENT,
+            )
+            .unwrap();
+        writer.write_all(&data).unwrap();
+        writer.finish().unwrap();
+
+        let stored = StoredEntries::open(&path).unwrap();
+        let mapped = unsafe { stored.map_tensor::<f32>("tensor_data/stored", vec![2, 2]) }.unwrap();
+        assert_eq!(
+            mapped.view(),
+            ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1f32, 2., 3., 4.]).unwrap()
+        );
+
+        // Compressed entries and shape mismatches aren't mapped
+        assert!(unsafe { stored.map_tensor::<f32>("tensor_data/compressed", vec![4]) }.is_none());
+        assert!(unsafe { stored.map_tensor::<f32>("tensor_data/stored", vec![5]) }.is_none());
+
+        // Shapes where the size overflows aren't mapped (without the checks, this one would wrap around to 16 bytes)
+        assert!(
+            unsafe { stored.map_tensor::<f32>("tensor_data/stored", vec![(1 << 62) + 4]) }
+                .is_none()
+        );
+        assert!(
+            unsafe { stored.map_tensor::<f32>("tensor_data/stored", vec![usize::MAX, 2]) }
+                .is_none()
+        );
+    }
+}
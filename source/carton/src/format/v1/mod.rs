@@ -15,13 +15,19 @@
 //! This module implements v1 of the Carton file format spec
 //! See `docs/specification/format.md` for more details
 mod carton_toml;
+#[cfg(not(target_family = "wasm"))]
+pub(crate) mod diff;
 pub(crate) mod links;
 mod load;
+pub(crate) mod manifest;
+pub(crate) mod mmap;
+pub(crate) mod signature;
 mod tensor;
-pub(crate) use load::load;
+pub(crate) use carton_toml::interpolate_runner_opts;
+pub(crate) use load::{load, verify_file_hashes};
 
 #[cfg(not(target_family = "wasm"))]
 mod save;
 
 #[cfg(not(target_family = "wasm"))]
-pub(crate) use save::save;
+pub(crate) use save::{plan, save};
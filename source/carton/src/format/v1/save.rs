@@ -25,9 +25,11 @@ use walkdir::WalkDir;
 use crate::conversion_utils::{convert_opt_map, convert_opt_vec, convert_vec};
 use crate::error::{CartonError, Result};
 use crate::format::v1::links::Links;
-use crate::types::PackOpts;
+use crate::info::{CartonInfo, SymbolValues, TensorOrMisc, TensorSpec, THUMBNAIL_NAMES};
+use crate::types::{PackOpts, Tensor};
 
 use super::carton_toml::{CartonToml, TensorOrMiscReference};
+use super::mmap::TENSOR_DATA_ALIGNMENT;
 
 // Util to save a misc file
 async fn save_misc_file<'a>(
@@ -44,6 +46,120 @@ async fn save_misc_file<'a>(
     Ok(())
 }
 
+/// Get the image format of a file from its magic bytes
+fn image_format(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else {
+        None
+    }
+}
+
+/// Warn if a thumbnail isn't an image in the format its name says it is
+async fn check_thumbnail(path: &std::path::Path, name: &str) -> Result<()> {
+    use tokio::io::AsyncReadExt;
+
+    let mut header = Vec::with_capacity(8);
+    tokio::fs::File::open(path)
+        .await?
+        .take(8)
+        .read_to_end(&mut header)
+        .await?;
+
+    let expected = match name.rsplit_once('.') {
+        Some((_, "jpg")) => "jpeg",
+        Some((_, ext)) => ext,
+        None => "",
+    };
+
+    match image_format(&header) {
+        Some(format) if format == expected => {}
+        Some(format) => log::warn!(
+            "The thumbnail `{name}` is a {format} image, which doesn't match its extension"
+        ),
+        None => log::warn!(
+            "The thumbnail `{name}` doesn't appear to be a png or jpeg image so it may not be displayed"
+        ),
+    }
+
+    Ok(())
+}
+
+/// Check a tensor from an example or self test against the declared specs (if any)
+fn check_tensor(
+    specs: &Option<Vec<TensorSpec>>,
+    kind: &str,
+    name: &str,
+    tensor: &Tensor,
+    symbols: &mut SymbolValues,
+) -> Option<String> {
+    // If there are no specs, there's nothing to check against
+    let specs = specs.as_ref()?;
+    match specs.iter().find(|spec| spec.name == name) {
+        None => Some(format!(
+            "{kind} `{name}` is not one of the declared {kind}s"
+        )),
+        Some(spec) => spec
+            .check(tensor, symbols)
+            .err()
+            .map(|e| format!("{kind} `{name}`: {e}")),
+    }
+}
+
+/// Check that the tensors in examples and self tests match the declared inputs and outputs.
+/// Returns a description of each mismatch
+async fn check_examples(info: &CartonInfo) -> Vec<String> {
+    let mut out = Vec::new();
+    let describe = |kind: &str, index: usize, name: &Option<String>| match name {
+        Some(name) => format!("{kind} `{name}`"),
+        None => format!("{kind} {index}"),
+    };
+
+    for (index, item) in info.self_tests.iter().flatten().enumerate() {
+        let label = describe("Self test", index, &item.name);
+        let mut symbols = SymbolValues::default();
+
+        // Sort so symbols are bound in a consistent order
+        let inputs: BTreeMap<_, _> = item.inputs.iter().collect();
+        for (k, v) in inputs {
+            if let Some(e) = check_tensor(&info.inputs, "input", k, v.get().await, &mut symbols) {
+                out.push(format!("{label}: {e}"));
+            }
+        }
+
+        let expected_out: BTreeMap<_, _> = item.expected_out.iter().flatten().collect();
+        for (k, v) in expected_out {
+            if let Some(e) = check_tensor(&info.outputs, "output", k, v.get().await, &mut symbols) {
+                out.push(format!("{label}: {e}"));
+            }
+        }
+    }
+
+    for (index, item) in info.examples.iter().flatten().enumerate() {
+        let label = describe("Example", index, &item.name);
+        let mut symbols = SymbolValues::default();
+
+        // Misc files (e.g. images) aren't passed to the model directly so we only check tensors
+        for (specs, kind, values) in [
+            (&info.inputs, "input", &item.inputs),
+            (&info.outputs, "output", &item.sample_out),
+        ] {
+            let values: BTreeMap<_, _> = values.iter().collect();
+            for (k, v) in values {
+                if let TensorOrMisc::Tensor(t) = v {
+                    if let Some(e) = check_tensor(specs, kind, k, t.get().await, &mut symbols) {
+                        out.push(format!("{label}: {e}"));
+                    }
+                }
+            }
+        }
+    }
+
+    out
+}
+
 /// Given a path to a filled `model` dir, this function creates a complete carton by saving all the additonal
 /// info. Returns a path to the saved file
 pub(crate) async fn save(
@@ -52,6 +168,14 @@ pub(crate) async fn save(
 ) -> Result<std::path::PathBuf> {
     // Extract the model info from pack opts
     let info = pack_opts.info;
+    let allow_invalid_examples = pack_opts.allow_invalid_examples;
+    let signing_key = pack_opts.signing_key;
+    let store_uncompressed = pack_opts.store_uncompressed;
+    let manifest_compression = if pack_opts.compress_manifest {
+        zip::CompressionMethod::Zstd
+    } else {
+        zip::CompressionMethod::Stored
+    };
 
     // Extract info about linked files if any
     let linked_files: Option<Links> = pack_opts.linked_files.map(|v| v.into());
@@ -71,6 +195,18 @@ pub(crate) async fn save(
         }
     }
 
+    // Check that examples and self tests match the declared inputs and outputs
+    let mismatches = check_examples(&info).await;
+    if !mismatches.is_empty() {
+        if allow_invalid_examples {
+            for item in &mismatches {
+                log::warn!("{item}");
+            }
+        } else {
+            return Err(CartonError::InvalidExamples(mismatches));
+        }
+    }
+
     // Create the carton.toml we're going to write out
     let mut config = CartonToml {
         spec_version: 1, // Format V1
@@ -81,7 +217,7 @@ pub(crate) async fn save(
         repository: info.repository,
         homepage: info.homepage,
         required_platforms: convert_opt_vec(info.required_platforms),
-        input: convert_opt_vec(info.inputs),
+        input: None,
         output: convert_opt_vec(info.outputs),
         self_test: None,
         example: None,
@@ -99,15 +235,38 @@ pub(crate) async fn save(
     if let Some(misc_files) = info.misc_files {
         for (name, item) in misc_files {
             save_misc_file(&misc_dir, &name, item).await.unwrap();
+
+            if THUMBNAIL_NAMES.contains(&name.as_str()) {
+                check_thumbnail(&misc_dir.join(&name), &name).await?;
+            }
         }
     }
 
     // 2. Save all the tensors
-    log::trace!("Processing examples and self tests...");
+    log::trace!("Processing input defaults, examples and self tests...");
     let mut tensors_to_save = HashMap::new();
     let mut counter = 0;
 
-    // TODO: Future optimization: if we see the same tensor multiple times, write it out once
+    // Note: `save_tensors` only writes out identical tensors once
+    if let Some(inputs) = info.inputs {
+        let mut out_inputs = Vec::new();
+        for mut spec in inputs {
+            // Save the default value (if any)
+            let default = spec.default.take().map(|v| {
+                let save_key = format!("@tensor_data/_tensor_{counter}");
+                tensors_to_save.insert(save_key.clone(), v);
+                counter += 1;
+                save_key.into()
+            });
+
+            let mut out_spec: super::carton_toml::TensorSpec = spec.into();
+            out_spec.default = default;
+            out_inputs.push(out_spec);
+        }
+
+        config.input = Some(out_inputs);
+    }
+
     if let Some(self_tests) = info.self_tests {
         let mut out_self_tests = Vec::new();
         for item in self_tests {
@@ -261,14 +420,28 @@ pub(crate) async fn save(
         manifest_contents.insert(relative_path.clone(), Some(sha256));
 
         // Add the entry to the zip file
+        // Uncompressed tensor_data is aligned so the loader can memory map tensors in place
+        let store_aligned = store_uncompressed && relative_path.starts_with("tensor_data/");
         writer = tokio::task::spawn_blocking(move || {
-            writer
-                .start_file(
-                    relative_path,
-                    zip::write::FileOptions::default()
-                        .compression_method(zip::CompressionMethod::Zstd),
-                )
-                .unwrap();
+            if store_aligned {
+                writer
+                    .start_file_aligned(
+                        relative_path,
+                        zip::write::FileOptions::default()
+                            .compression_method(zip::CompressionMethod::Stored)
+                            .large_file(data.len() >= 4 * 1024 * 1024 * 1024),
+                        TENSOR_DATA_ALIGNMENT,
+                    )
+                    .unwrap();
+            } else {
+                writer
+                    .start_file(
+                        relative_path,
+                        zip::write::FileOptions::default()
+                            .compression_method(zip::CompressionMethod::Zstd),
+                    )
+                    .unwrap();
+            }
             writer.write_all(&data).unwrap();
             writer
         })
@@ -278,62 +451,16 @@ pub(crate) async fn save(
 
     // Add the model dir
     log::trace!("Packing model dir");
-    for entry in WalkDir::new(&model_dir_path).follow_links(true) {
-        let entry = entry.unwrap();
-        if entry.file_type().is_dir() {
-            continue;
-        }
-
-        let relative_path = Path::new("model")
-            .join(entry.path().strip_prefix(&model_dir_path).unwrap())
-            .to_str()
-            .unwrap()
-            .to_owned();
+    for entry in walk_model_dir(model_dir_path).await {
+        let relative_path = entry.relative_path;
 
         log::trace!("About to pack {}", &relative_path);
         let mut sl = slowlog(format!("Packaging file '{}'", &relative_path), 5)
             .await
             .without_progress();
 
-        // Should we store this file as a symlink?
-        let symlink_target = if entry.path_is_symlink() {
-            let absolute_file_path = entry.path();
-            assert!(absolute_file_path.is_absolute());
-
-            // Get the target
-            let symlink_target = tokio::fs::read_link(absolute_file_path).await.unwrap();
-
-            // Make the target absolute
-            let symlink_target = if symlink_target.is_relative() {
-                absolute_file_path.parent().unwrap().join(symlink_target)
-            } else {
-                symlink_target
-            };
-
-            // Normalize the path
-            let symlink_target = symlink_target.clean();
-
-            // Decide what to do
-            if symlink_target.starts_with(&model_dir_path) {
-                // Store as a relative symlink
-                Some(
-                    pathdiff::diff_paths(symlink_target, absolute_file_path.parent().unwrap())
-                        .unwrap(),
-                )
-            } else {
-                // The symlink points outside the model dir; store as a file
-                None
-            }
-        } else {
-            // Not a symlink
-            None
-        };
-
         // Handle symlinks
-        if let Some(symlink_target) = symlink_target {
-            // Turn it into a string
-            let symlink_target = symlink_target.to_str().unwrap().to_owned();
-
+        if let Some(symlink_target) = entry.symlink_target {
             // Store an empty sha256 for now and we'll update it after all the files have been added
             manifest_contents.insert(relative_path.clone(), None);
 
@@ -350,7 +477,7 @@ pub(crate) async fn save(
         } else {
             // Load the data and compute the sha256
             let mut hasher = Sha256::new();
-            let data = tokio::fs::read(entry.path()).await.unwrap();
+            let data = tokio::fs::read(&entry.path).await.unwrap();
 
             log::trace!("Done reading file {}", &relative_path);
 
@@ -364,10 +491,7 @@ pub(crate) async fn save(
             log::trace!("Computed sha256 of {}", &relative_path);
 
             // Only store the file in the zip if (1) we don't have any linked files or (2) the linked files don't include this sha256
-            if linked_files
-                .as_ref()
-                .map_or(true, |v| !v.urls.contains_key(&sha256))
-            {
+            if linked_files.as_ref().map_or(true, |v| !v.contains(&sha256)) {
                 // Add the entry to the zip file
                 let relative_path = relative_path.clone();
                 writer = tokio::task::spawn_blocking(move || {
@@ -375,7 +499,11 @@ pub(crate) async fn save(
                         .start_file(
                             relative_path,
                             zip::write::FileOptions::default()
-                                .compression_method(zip::CompressionMethod::Zstd)
+                                .compression_method(if store_uncompressed {
+                                    zip::CompressionMethod::Stored
+                                } else {
+                                    zip::CompressionMethod::Zstd
+                                })
                                 .large_file(data.len() >= 4 * 1024 * 1024 * 1024),
                         )
                         .unwrap();
@@ -395,7 +523,147 @@ pub(crate) async fn save(
     }
 
     // Get sha256 values for all the symlinks
-    let manifest_contents = manifest_contents
+    let manifest_contents = resolve_symlink_hashes(&manifest_contents, &symlink_targets);
+
+    // 5. Write the manifest to the zip file in alphabetical order (we're using a BTreeMap for manifest_contents)
+    log::trace!("Writing manifest");
+    let mut manifest_str = String::new();
+    for (k, v) in manifest_contents {
+        manifest_str += &format!("{k}={v}\n");
+    }
+
+    // Sign the manifest if we have a key
+    let signature = signing_key
+        .map(|key| super::signature::sign_manifest(&key, manifest_str.as_bytes()))
+        .transpose()?;
+
+    tokio::task::spawn_blocking(move || {
+        writer
+            .start_file(
+                "MANIFEST",
+                zip::write::FileOptions::default().compression_method(manifest_compression),
+            )
+            .unwrap();
+        writer.write_all(manifest_str.as_bytes()).unwrap();
+
+        if let Some(signature) = signature {
+            writer
+                .start_file(
+                    "SIGNATURE",
+                    zip::write::FileOptions::default()
+                        .compression_method(zip::CompressionMethod::Stored),
+                )
+                .unwrap();
+            writer.write_all(signature.as_bytes()).unwrap();
+        }
+
+        // 6. Add links (if any)
+        if let Some(linked_files) = linked_files {
+            // Add LINKS
+            writer
+                .start_file(
+                    "LINKS",
+                    zip::write::FileOptions::default().compression_method(manifest_compression),
+                )
+                .unwrap();
+            let data = toml::to_vec(&linked_files).unwrap();
+            writer.write_all(&data).unwrap();
+        }
+
+        // Finish writing the zip file
+        log::trace!("Closing zip file writer");
+        let mut f = writer.finish().unwrap();
+        f.flush().unwrap();
+    })
+    .await
+    .unwrap();
+
+    // Return the output path
+    Ok(output_zip_path)
+}
+
+/// A file in the model dir that should be packed
+struct ModelDirEntry {
+    /// The path of the file relative to the root of the carton (i.e. starting with `model/`)
+    relative_path: String,
+
+    /// The absolute path of the file on disk
+    path: PathBuf,
+
+    /// If this file should be stored as a symlink, the relative path it points to
+    symlink_target: Option<String>,
+}
+
+/// Walk a model dir and decide how each file should be packed. Symlinks that point to files within
+/// the model dir are stored as relative symlinks. Everything else is stored as a file
+async fn walk_model_dir(model_dir_path: &Path) -> Vec<ModelDirEntry> {
+    let mut out = Vec::new();
+    for entry in WalkDir::new(&model_dir_path).follow_links(true) {
+        let entry = entry.unwrap();
+        if entry.file_type().is_dir() {
+            continue;
+        }
+
+        let relative_path = Path::new("model")
+            .join(entry.path().strip_prefix(&model_dir_path).unwrap())
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        // Should we store this file as a symlink?
+        let symlink_target = if entry.path_is_symlink() {
+            let absolute_file_path = entry.path();
+            assert!(absolute_file_path.is_absolute());
+
+            // Get the target
+            let symlink_target = tokio::fs::read_link(absolute_file_path).await.unwrap();
+
+            // Make the target absolute
+            let symlink_target = if symlink_target.is_relative() {
+                absolute_file_path.parent().unwrap().join(symlink_target)
+            } else {
+                symlink_target
+            };
+
+            // Normalize the path
+            let symlink_target = symlink_target.clean();
+
+            // Decide what to do
+            if symlink_target.starts_with(&model_dir_path) {
+                // Store as a relative symlink
+                Some(
+                    pathdiff::diff_paths(symlink_target, absolute_file_path.parent().unwrap())
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_owned(),
+                )
+            } else {
+                // The symlink points outside the model dir; store as a file
+                None
+            }
+        } else {
+            // Not a symlink
+            None
+        };
+
+        out.push(ModelDirEntry {
+            relative_path,
+            path: entry.path().to_owned(),
+            symlink_target,
+        });
+    }
+
+    out
+}
+
+/// Given a map from path to sha256 (or None for symlinks) and a map from symlink path to relative target,
+/// get the sha256 of every path by following symlinks
+fn resolve_symlink_hashes(
+    manifest_contents: &BTreeMap<String, Option<String>>,
+    symlink_targets: &HashMap<String, String>,
+) -> BTreeMap<String, String> {
+    manifest_contents
         .iter()
         .map(|(k, v)| {
             if v.is_none() {
@@ -428,57 +696,74 @@ pub(crate) async fn save(
 
                         Some(sha) => {
                             // Got the target
-                            return (k, sha.clone());
+                            return (k.clone(), sha.clone());
                         }
                     }
                 }
             }
 
-            (k, v.as_ref().unwrap().clone())
+            (k.clone(), v.as_ref().unwrap().clone())
         })
-        .collect::<BTreeMap<_, _>>();
+        .collect()
+}
 
-    // 5. Write the manifest to the zip file in alphabetical order (we're using a BTreeMap for manifest_contents)
-    log::trace!("Writing manifest");
-    let mut manifest_str = String::new();
-    for (k, v) in manifest_contents {
-        manifest_str += &format!("{k}={v}\n");
+/// Given a path to a filled `model` dir, figure out what `save` would do with each file without
+/// writing anything
+pub(crate) async fn plan(
+    pack_opts: PackOpts,
+    model_dir_path: &std::path::Path,
+) -> Result<crate::info::PackPlan> {
+    let linked_files: Option<Links> = pack_opts.linked_files.map(|v| v.into());
+
+    let mut manifest_contents = BTreeMap::new();
+    let mut symlink_targets = HashMap::new();
+    let mut sizes = HashMap::new();
+    for entry in walk_model_dir(model_dir_path).await {
+        if let Some(symlink_target) = entry.symlink_target {
+            manifest_contents.insert(entry.relative_path.clone(), None);
+            symlink_targets.insert(entry.relative_path, symlink_target);
+        } else {
+            // Compute the size and sha256 without loading the whole file into memory
+            let (size, sha256) = tokio::task::spawn_blocking(move || -> Result<_> {
+                let mut file = std::fs::File::open(entry.path)?;
+                let mut hasher = Sha256::new();
+                let size = std::io::copy(&mut file, &mut hasher)?;
+                Ok((size, format!("{:x}", hasher.finalize())))
+            })
+            .await
+            .unwrap()?;
+
+            sizes.insert(entry.relative_path.clone(), size);
+            manifest_contents.insert(entry.relative_path, Some(sha256));
+        }
     }
 
-    tokio::task::spawn_blocking(move || {
-        writer
-            .start_file(
-                "MANIFEST",
-                zip::write::FileOptions::default()
-                    .compression_method(zip::CompressionMethod::Stored),
-            )
-            .unwrap();
-        writer.write_all(manifest_str.as_bytes()).unwrap();
+    let hashes = resolve_symlink_hashes(&manifest_contents, &symlink_targets);
 
-        // 6. Add links (if any)
-        if let Some(linked_files) = linked_files {
-            // Add LINKS
-            writer
-                .start_file(
-                    "LINKS",
-                    zip::write::FileOptions::default()
-                        .compression_method(zip::CompressionMethod::Stored),
-                )
-                .unwrap();
-            let data = toml::to_vec(&linked_files).unwrap();
-            writer.write_all(&data).unwrap();
+    let mut out = crate::info::PackPlan::default();
+    for (path, sha256) in hashes {
+        let (storage, size) = if let Some(target) = symlink_targets.remove(&path) {
+            (crate::info::FileStorage::Symlink { target }, 0)
+        } else if linked_files.as_ref().map_or(false, |v| v.contains(&sha256)) {
+            (crate::info::FileStorage::Linked, sizes[&path])
+        } else {
+            (crate::info::FileStorage::Stored, sizes[&path])
+        };
+
+        out.total_size += size;
+        if matches!(storage, crate::info::FileStorage::Stored) {
+            out.stored_size += size;
         }
 
-        // Finish writing the zip file
-        log::trace!("Closing zip file writer");
-        let mut f = writer.finish().unwrap();
-        f.flush().unwrap();
-    })
-    .await
-    .unwrap();
+        out.files.push(crate::info::PlannedFile {
+            path,
+            sha256,
+            size,
+            storage,
+        });
+    }
 
-    // Return the output path
-    Ok(output_zip_path)
+    Ok(out)
 }
 
 impl From<target_lexicon::Triple> for super::carton_toml::Triple {
@@ -495,6 +780,9 @@ impl From<crate::info::TensorSpec> for super::carton_toml::TensorSpec {
             shape: value.shape.into(),
             description: value.description,
             internal_name: value.internal_name,
+            optional: Some(value.optional),
+            // Defaults are saved to `tensor_data` separately (see `save`)
+            default: None,
         }
     }
 }
@@ -513,6 +801,8 @@ impl From<crate::info::DataType> for super::carton_toml::DataType {
             crate::info::DataType::U16 => Self::Uint16,
             crate::info::DataType::U32 => Self::Uint32,
             crate::info::DataType::U64 => Self::Uint64,
+            crate::info::DataType::Complex64 => Self::Complex64,
+            crate::info::DataType::Complex128 => Self::Complex128,
         }
     }
 }
@@ -533,6 +823,7 @@ impl From<crate::info::Dimension> for super::carton_toml::Dimension {
             crate::info::Dimension::Value(v) => Self::Value(v),
             crate::info::Dimension::Symbol(v) => Self::Symbol(v),
             crate::info::Dimension::Any => Self::Any,
+            crate::info::Dimension::Range { min, max } => Self::Range { min, max },
         }
     }
 }
@@ -0,0 +1,93 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detached ed25519 signatures over the MANIFEST of a carton.
+//! The signature only covers the bytes of the MANIFEST. Loaders extend it to the rest of the carton by
+//! checking each file against the sha256 the MANIFEST lists for it (see `verify_file_hashes`).
+//! See the `SIGNATURE` section of `docs/specification/format.md` for more details
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use lunchbox::{
+    path::{LunchboxPathUtils, PathBuf},
+    types::{MaybeSend, MaybeSync, ReadableFile},
+    ReadableFileSystem,
+};
+
+use crate::error::{CartonError, Result};
+
+/// Decode a hex string into exactly `N` bytes
+fn decode_hex<const N: usize>(value: &str) -> Option<[u8; N]> {
+    hex::decode(value.trim()).ok()?.try_into().ok()
+}
+
+/// Sign `manifest` with a hex-encoded ed25519 secret key. Returns the contents of the `SIGNATURE` file
+/// (the hex-encoded signature)
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn sign_manifest(signing_key: &str, manifest: &[u8]) -> Result<String> {
+    let signing_key = decode_hex(signing_key)
+        .map(|v| SigningKey::from_bytes(&v))
+        .ok_or(CartonError::Other(
+            "`signing_key` must be a hex-encoded 32 byte ed25519 secret key",
+        ))?;
+
+    Ok(hex::encode(signing_key.sign(manifest).to_bytes()))
+}
+
+/// Verify the `SIGNATURE` of the carton in `fs` against a hex-encoded ed25519 public key
+pub(crate) async fn verify_manifest<T>(fs: &T, public_key: &str) -> Result<()>
+where
+    T: ReadableFileSystem + MaybeSend + MaybeSync,
+    T::FileType: ReadableFile + MaybeSend + MaybeSync + Unpin,
+{
+    let failed = |reason: &str| CartonError::SignatureVerificationFailed(reason.to_owned());
+
+    let public_key = decode_hex(public_key)
+        .and_then(|v| VerifyingKey::from_bytes(&v).ok())
+        .ok_or_else(|| failed("the provided public key is not a valid hex-encoded ed25519 key"))?;
+
+    if !PathBuf::from("/SIGNATURE").exists(fs).await {
+        return Err(failed("the carton is not signed"));
+    }
+
+    let signature = decode_hex(&fs.read_to_string("/SIGNATURE").await?)
+        .map(|v| Signature::from_bytes(&v))
+        .ok_or_else(|| failed("the SIGNATURE file is not a hex-encoded ed25519 signature"))?;
+
+    let manifest = fs.read("/MANIFEST").await?;
+    public_key
+        .verify(&manifest, &signature)
+        .map_err(|_| failed("the signature does not match the provided public key"))
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = hex::encode(signing_key.verifying_key().to_bytes());
+
+        let signature = sign_manifest(&hex::encode(signing_key.to_bytes()), b"a=b\n").unwrap();
+        let signature = Signature::from_bytes(&decode_hex(&signature).unwrap());
+
+        let public_key = VerifyingKey::from_bytes(&decode_hex(&public_key).unwrap()).unwrap();
+        assert!(public_key.verify(b"a=b\n", &signature).is_ok());
+        assert!(public_key.verify(b"a=c\n", &signature).is_err());
+
+        // Keys must be 32 bytes
+        assert!(sign_manifest("abcd", b"a=b\n").is_err());
+    }
+}
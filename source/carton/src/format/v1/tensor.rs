@@ -16,13 +16,15 @@
 
 use std::{collections::HashMap, sync::Arc};
 
-use carton_macros::for_each_numeric_carton_type;
+use carton_macros::for_each_numeric_or_complex_carton_type;
 use lunchbox::{
     types::{MaybeSend, MaybeSync, ReadableFile},
     ReadableFileSystem,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use super::mmap::StoredEntries;
 use crate::{info::PossiblyLoaded, types::Tensor};
 
 #[derive(Default, Serialize, Deserialize)]
@@ -42,6 +44,9 @@ struct TensorInfo {
 
     /// For nested tensors
     inner: Vec<String>,
+
+    /// For labeled nested tensors (one label per item in `inner`)
+    labels: Option<Vec<String>>,
 }
 
 /// The data for a string tensor
@@ -59,45 +64,66 @@ pub(crate) fn save_tensors(
     // First, split out all nested tensors
     let (nested, mut unnested) = tensors
         .into_iter()
-        .partition::<HashMap<_, _>, _>(|(_, v)| matches!(v, Tensor::NestedTensor(_)));
+        .partition::<HashMap<_, _>, _>(|(_, v)| v.is_nested());
 
     // Add all the inner tensors of the nested tensors into unnested
     for (k, v) in nested {
-        if let Tensor::NestedTensor(items) = v {
-            // Create a nested tensor to write out
-            let mut nt = TensorInfo {
-                name: k.strip_prefix("@tensor_data/").unwrap().to_owned(),
-                dtype: "nested".into(),
-                ..Default::default()
-            };
-
-            // Loop through
-            for (idx, t) in items.into_iter().enumerate() {
-                let inner_name = format!("_carton_nested_inner_{k}_{idx}");
-
-                // Confirm that the inner tensor is not a nested tensor
-                if matches!(t, Tensor::NestedTensor(_)) {
-                    panic!("NestedTensors cannot contain NestedTensors");
-                }
+        let (items, labels) = match v {
+            Tensor::NestedTensor(items) => (items, None),
+            Tensor::LabeledNestedTensor { tensors, labels } => (tensors, Some(labels.clone())),
+            _ => unreachable!("This shouldn't happen because we partitioned above"),
+        };
+
+        // Create a nested tensor to write out
+        let mut nt = TensorInfo {
+            name: k.strip_prefix("@tensor_data/").unwrap().to_owned(),
+            dtype: "nested".into(),
+            labels,
+            ..Default::default()
+        };
+
+        // Loop through
+        for (idx, t) in items.into_iter().enumerate() {
+            let inner_name = format!("_carton_nested_inner_{k}_{idx}");
+
+            // Confirm that the inner tensor is not a nested tensor
+            if t.is_nested() {
+                panic!("NestedTensors cannot contain NestedTensors");
+            }
 
-                // Store the inner tensor name
-                nt.inner.push(inner_name.clone());
+            // Store the inner tensor name
+            nt.inner.push(inner_name.clone());
 
-                // Add the inner tensor to our map of unnested tensors to serialize
-                if unnested.insert(inner_name, t).is_some() {
-                    panic!("Tensor names starting with `_carton_nested_inner_` are reserved.")
-                }
+            // Add the inner tensor to our map of unnested tensors to serialize
+            if unnested.insert(inner_name, t).is_some() {
+                panic!("Tensor names starting with `_carton_nested_inner_` are reserved.")
             }
-
-            // Add it to our toml file
-            index_toml.tensor.push(nt);
-        } else {
-            unreachable!("This shouldn't happen because we partitioned above")
         }
+
+        // Add it to our toml file
+        index_toml.tensor.push(nt);
     }
 
+    // Identical tensors (e.g. an input shared by several examples) are only written out once.
+    // This maps the sha256 of a file's contents to its name
+    let mut written_files = HashMap::new();
+
+    // Write `data` to a file (unless we've already written the same data) and return the file name
+    let mut write_file = |data: &[u8], extension: &str| {
+        let sha256 = format!("{:x}", Sha256::digest(data));
+        let next_idx = written_files.len();
+        written_files
+            .entry((sha256, extension.to_owned()))
+            .or_insert_with(|| {
+                let fname = format!("tensor_{next_idx}.{extension}");
+                std::fs::write(tensor_data_path.join(&fname), data).unwrap();
+                fname
+            })
+            .clone()
+    };
+
     // Serialize all the inner tensors
-    for (tensor_idx, (k, v)) in unnested.iter().enumerate() {
+    for (k, v) in unnested.iter() {
         if let Tensor::String(t) = v {
             // String tensor
             let string_tensor = StringsToml {
@@ -105,25 +131,23 @@ pub(crate) fn save_tensors(
                 data: t.view().as_standard_layout().into_iter().collect(),
             };
 
-            let fname = format!("tensor_{tensor_idx}.toml");
+            // Write out the data
+            let serialized = toml::to_string_pretty(&string_tensor).unwrap();
+            let fname = write_file(serialized.as_bytes(), "toml");
 
             // Add it to the index
             index_toml.tensor.push(TensorInfo {
                 name: k.strip_prefix("@tensor_data/").unwrap().to_owned(),
                 dtype: "string".into(),
                 shape: Some(t.view().shape().into_iter().map(|v| *v as u64).collect()),
-                file: Some(fname.clone()),
+                file: Some(fname),
                 ..Default::default()
             });
-
-            // Write out the data
-            let serialized = toml::to_string_pretty(&string_tensor).unwrap();
-            std::fs::write(tensor_data_path.join(fname), serialized).unwrap();
         } else {
             // Numeric tensor
-            for_each_numeric_carton_type! {
+            for_each_numeric_or_complex_carton_type! {
                 match v {
-                    Tensor::NestedTensor(_) => {
+                    Tensor::NestedTensor(_) | Tensor::LabeledNestedTensor { .. } => {
                         unreachable!("This shouldn't happen because we partitioned above")
                     }
                     Tensor::String(_) => unreachable!(
@@ -143,19 +167,17 @@ pub(crate) fn save_tensors(
 
                             let data = unsafe { std::slice::from_raw_parts(array.as_ptr() as *const u8, total_bytes) };
 
-                            let fname = format!("tensor_{tensor_idx}.bin");
+                            // Write the file out
+                            let fname = write_file(data, "bin");
 
                             // Add it to the index
                             index_toml.tensor.push(TensorInfo {
                                 name: k.strip_prefix("@tensor_data/").unwrap().to_owned(),
                                 dtype: $TypeStr.into(),
                                 shape: Some(array.shape().into_iter().map(|v| *v as u64).collect()),
-                                file: Some(fname.clone()),
+                                file: Some(fname),
                                 ..Default::default()
                             });
-
-                            // Write the file out
-                            std::fs::write(tensor_data_path.join(fname), data).unwrap();
                         }
                     )*
                 };
@@ -175,9 +197,11 @@ fn bytes_per_elem<T>(_array: &ndarray::ArrayViewD<T>) -> usize {
 }
 
 /// Loads tensors
+/// Numeric tensors that are in `stored` (if any) are memory mapped instead of being read from `fs`
 pub(crate) async fn load_tensors<T>(
     fs: &Arc<T>,
     tensor_data_path: &lunchbox::path::Path,
+    stored: Option<Arc<StoredEntries>>,
 ) -> crate::error::Result<HashMap<String, PossiblyLoaded<Tensor>>>
 where
     T: ReadableFileSystem + MaybeSend + MaybeSync + 'static,
@@ -190,7 +214,7 @@ where
     // Create loaders for all the unnested tensors
     let mut unnested: HashMap<String, PossiblyLoaded<Tensor>> = HashMap::new();
     for t in &index_toml.tensor {
-        for_each_numeric_carton_type! {
+        for_each_numeric_or_complex_carton_type! {
             let loader = match t.dtype.as_str() {
                 "nested" => {
                     // Skip
@@ -213,7 +237,17 @@ where
                         let fname = t.file.clone().unwrap();
                         let fs = fs.clone();
                         let path = tensor_data_path.join(fname);
+                        let stored = stored.clone();
                         PossiblyLoaded::from_loader(Box::pin(async move {
+                            // Memory map the tensor if it's stored uncompressed
+                            #[cfg(not(target_family = "wasm"))]
+                            {
+                                // SAFETY: all the numeric types are valid for any bit pattern
+                                if let Some(mapped) = stored.as_ref().and_then(|stored| unsafe { stored.map_tensor::<$RustType>(path.as_str(), shape.clone()) }) {
+                                    return Tensor::new(mapped);
+                                }
+                            }
+
                             let data = fs.read(path).await.unwrap();
 
                             #[cfg(not(target_endian = "little"))]
@@ -222,7 +256,10 @@ where
                             let bytes_per_elem = std::mem::size_of::<$RustType>();
                             let numel = data.len() / bytes_per_elem;
 
-                            let typed_data = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const $RustType, numel) }.to_vec();
+                            // `data` is not guaranteed to be aligned for `$RustType` (e.g. when it's empty) so we
+                            // copy the bytes instead of reinterpreting the buffer in place
+                            let mut typed_data = vec![<$RustType>::default(); numel];
+                            unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), typed_data.as_mut_ptr() as *mut u8, numel * bytes_per_elem) };
 
                             Tensor::$CartonType(ndarray::ArrayD::<$RustType>::from_shape_vec(shape, typed_data).unwrap().into())
                         }))
@@ -246,6 +283,7 @@ where
                     .into_iter()
                     .map(|name| unnested.remove(&name).unwrap())
                     .collect();
+                let labels = item.labels;
                 Some((
                     item.name,
                     PossiblyLoaded::from_loader(Box::pin(async move {
@@ -256,7 +294,10 @@ where
                         }
 
                         // Return a nested tensor
-                        Tensor::NestedTensor(tensors)
+                        match labels {
+                            Some(labels) => Tensor::labeled_nested(tensors, labels).unwrap(),
+                            None => Tensor::NestedTensor(tensors),
+                        }
                     })),
                 ))
             } else {
@@ -270,3 +311,69 @@ where
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::{load_tensors, save_tensors};
+    use crate::types::Tensor;
+
+    #[tokio::test]
+    async fn test_dedup_tensors() {
+        let a = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1f32, 2., 3., 4.]).unwrap(),
+        );
+        let a_copy = a.clone();
+
+        // Same data as `a`, but a different shape
+        let b =
+            Tensor::new(ndarray::ArrayD::from_shape_vec(vec![4], vec![1f32, 2., 3., 4.]).unwrap());
+        let c = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![2], vec![5f32, 6.]).unwrap());
+        let s = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![1], vec!["hello".to_owned()]).unwrap(),
+        );
+        let s_copy = s.clone();
+
+        let tensors = [
+            ("a", &a),
+            ("a_copy", &a_copy),
+            ("b", &b),
+            ("c", &c),
+            ("s", &s),
+            ("s_copy", &s_copy),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let tensor_data_path = dir.path().join("tensor_data");
+        std::fs::create_dir(&tensor_data_path).unwrap();
+        save_tensors(
+            &tensor_data_path,
+            tensors
+                .iter()
+                .map(|(k, v)| (format!("@tensor_data/{k}"), *v))
+                .collect(),
+        )
+        .unwrap();
+
+        // `a`, `a_copy` and `b` share a file as do `s` and `s_copy`
+        // There's also the index
+        assert_eq!(std::fs::read_dir(&tensor_data_path).unwrap().count(), 4);
+
+        // Make sure they all load correctly
+        let fs = Arc::new(
+            lunchbox::LocalFS::with_base_dir(dir.path().to_owned())
+                .await
+                .unwrap(),
+        );
+        let loaded: HashMap<_, _> =
+            load_tensors(&fs, lunchbox::path::Path::new("tensor_data/"), None)
+                .await
+                .unwrap();
+
+        assert_eq!(loaded.len(), tensors.len());
+        for (k, v) in tensors {
+            assert_eq!(loaded[k].get().await, v);
+        }
+    }
+}
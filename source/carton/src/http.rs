@@ -17,7 +17,7 @@ use dashmap::DashMap;
 use futures::TryStreamExt;
 use lazy_static::lazy_static;
 use std::{pin::Pin, sync::Arc, task::Poll};
-use tokio::io::{AsyncRead, AsyncSeek};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
 use url::Url;
 
@@ -37,6 +37,9 @@ pub struct HTTPFile {
 
     state: RequestState,
 
+    // The number of consecutive times reading the current response failed and was retried
+    read_retries: u32,
+
     cached_data: Arc<CachedData>,
 }
 
@@ -45,10 +48,12 @@ enum RequestState {
 
     /// The current request we're waiting on (if any)
     #[cfg(target_family = "wasm")]
-    Request(Pin<Box<dyn std::future::Future<Output = FetchReturnType>>>),
+    Request(Pin<Box<dyn std::future::Future<Output = std::io::Result<FetchReturnType>>>>),
 
     #[cfg(not(target_family = "wasm"))]
-    Request(Pin<Box<dyn std::future::Future<Output = FetchReturnType> + Send + Sync>>),
+    Request(
+        Pin<Box<dyn std::future::Future<Output = std::io::Result<FetchReturnType>> + Send + Sync>>,
+    ),
 
     /// The current streaming response
     #[cfg(target_family = "wasm")]
@@ -98,7 +103,7 @@ impl HTTPFile {
                     u.clone()
                 } else {
                     // Not cached, make a request
-                    let res = client.head(&url).send().await?;
+                    let res = send(&url, || client.head(&url)).await?;
                     let u = match res.headers().get("x-carton-dl-url") {
                         Some(v) => v.to_str().unwrap(),
                         None => {
@@ -124,7 +129,7 @@ impl HTTPFile {
                 // Reuse the head response if we made a request earlier
                 let res = match head_res {
                     Some(v) => v,
-                    None => client.head(&url).send().await?,
+                    None => send(&url, || client.head(&url)).await?,
                 };
 
                 // TODO: maybe lazily fetch this
@@ -154,8 +159,7 @@ impl HTTPFile {
                         file_len.saturating_sub(NUM_END_BYTES),
                         NUM_END_BYTES,
                     )
-                    .await
-                    .into(),
+                    .await?,
                 });
 
                 FILE_INFO_CACHE.insert(url.clone(), cached_data.clone());
@@ -170,6 +174,7 @@ impl HTTPFile {
             file_len: cached_data.file_len,
             seek_pos: 0,
             state: RequestState::None,
+            read_retries: 0,
             cached_data,
         })
     }
@@ -262,7 +267,11 @@ impl AsyncRead for HTTPFile {
                     }));
                 }
                 RequestState::Request(v) => match v.as_mut().poll(cx) {
-                    Poll::Ready(res) => self.state = RequestState::Response(Box::pin(res)),
+                    Poll::Ready(Ok(res)) => self.state = RequestState::Response(Box::pin(res)),
+                    Poll::Ready(Err(e)) => {
+                        self.state = RequestState::None;
+                        return Poll::Ready(Err(e));
+                    }
                     Poll::Pending => return Poll::Pending,
                 },
                 RequestState::Response(res) => {
@@ -273,6 +282,25 @@ impl AsyncRead for HTTPFile {
                     // Update the seek pos
                     self.seek_pos += (num_bytes_orig - num_bytes_end) as u64;
 
+                    if let Poll::Ready(Err(e)) = &out {
+                        // The connection failed or timed out partway through the response.
+                        // Because we make range requests, we can resume from the current seek
+                        // position with a new request
+                        if self.read_retries < max_read_retries() {
+                            log::warn!(
+                                "Reading {} failed: {e}. Retrying from byte {}",
+                                self.url,
+                                self.seek_pos
+                            );
+                            self.read_retries += 1;
+                            self.state = RequestState::None;
+                            continue;
+                        }
+                    } else if num_bytes_orig != num_bytes_end {
+                        // We got data so reset the retry count
+                        self.read_retries = 0;
+                    }
+
                     return out;
                 }
             }
@@ -286,47 +314,99 @@ async fn fetch_range(
     url: &str,
     range_start: u64,
     num_bytes: u64,
-) -> bytes::Bytes {
+) -> std::io::Result<Vec<u8>> {
     log::trace!("Request: {url} {range_start} {num_bytes}");
     let range_end = range_start + num_bytes - 1;
-    let res = client
-        .get(url)
-        .header(
+    let res = send(url, || {
+        client.get(url).header(
             reqwest::header::RANGE,
             format!("bytes={range_start}-{range_end}"),
         )
-        .send()
-        .await
-        .unwrap();
+    })
+    .await?;
 
-    if !res.status().is_success() {
-        // TODO: return an error instead of panic
-        panic!("Error fetching URL {}: {}", url, res.status());
-    }
-
-    res.bytes().await.unwrap()
+    let mut out = Vec::new();
+    into_async_read(url, res).read_to_end(&mut out).await?;
+    Ok(out)
 }
 
 #[cfg(not(target_family = "wasm"))]
-type FetchReturnType = Box<dyn AsyncRead + Unpin + Send + Sync>;
+pub(crate) type FetchReturnType = Box<dyn AsyncRead + Unpin + Send + Sync>;
 
 #[cfg(target_family = "wasm")]
-type FetchReturnType = Box<dyn AsyncRead + Unpin>;
+pub(crate) type FetchReturnType = Box<dyn AsyncRead + Unpin>;
 
-async fn fetch(client: &reqwest::Client, url: &str, range_start: u64) -> FetchReturnType {
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    range_start: u64,
+) -> std::io::Result<FetchReturnType> {
     log::trace!("Request: {url} {range_start}");
-    let res = client
-        .get(url)
-        .header(reqwest::header::RANGE, format!("bytes={range_start}-"))
+    let res = send(url, || {
+        client
+            .get(url)
+            .header(reqwest::header::RANGE, format!("bytes={range_start}-"))
+    })
+    .await?;
+
+    Ok(into_async_read(url, res))
+}
+
+/// Send a request and return an error if it wasn't successful.
+/// On non-wasm platforms, transient failures are retried and hung requests time out (see `carton_utils::config`)
+#[cfg(not(target_family = "wasm"))]
+pub(crate) async fn send(
+    url: &str,
+    make_request: impl Fn() -> reqwest::RequestBuilder,
+) -> std::io::Result<reqwest::Response> {
+    let res = carton_utils::download::send_with_retries(url, make_request).await?;
+    check_status(url, res)
+}
+
+/// Send a request and return an error if it wasn't successful
+#[cfg(target_family = "wasm")]
+pub(crate) async fn send(
+    url: &str,
+    make_request: impl Fn() -> reqwest::RequestBuilder,
+) -> std::io::Result<reqwest::Response> {
+    let res = make_request()
         .send()
         .await
-        .unwrap();
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
 
-    if !res.status().is_success() {
-        // TODO: return an error instead of panic
-        panic!("Error fetching URL {}: {}", url, res.status());
+    check_status(url, res)
+}
+
+fn check_status(url: &str, res: reqwest::Response) -> std::io::Result<reqwest::Response> {
+    if res.status().is_success() {
+        Ok(res)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Error fetching URL {}: {}", url, res.status()),
+        ))
     }
+}
+
+/// Convert a response body into an [`AsyncRead`].
+/// On non-wasm platforms, reads fail with a [`std::io::ErrorKind::TimedOut`] error if no data arrives
+/// within the configured timeout
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn into_async_read(url: &str, res: reqwest::Response) -> FetchReturnType {
+    // Convert from a stream into futures::io::AsyncRead
+    let stream = Box::pin(carton_utils::download::body_stream(url.to_owned(), res))
+        .map_err(std::io::Error::from)
+        .into_async_read();
+
+    // To tokio::io::AsyncRead
+    let stream = stream.compat();
 
+    Box::new(stream)
+}
+
+/// Convert a response body into an [`AsyncRead`]
+#[cfg(target_family = "wasm")]
+pub(crate) fn into_async_read(_url: &str, res: reqwest::Response) -> FetchReturnType {
     // Convert from a stream into futures::io::AsyncRead
     let stream = res
         .bytes_stream()
@@ -338,3 +418,14 @@ async fn fetch(client: &reqwest::Client, url: &str, range_start: u64) -> FetchRe
 
     Box::new(stream)
 }
+
+/// How many times to resume a response that failed partway through
+#[cfg(not(target_family = "wasm"))]
+fn max_read_retries() -> u32 {
+    carton_utils::config::CONFIG.http_retries
+}
+
+#[cfg(target_family = "wasm")]
+fn max_read_retries() -> u32 {
+    0
+}
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::error::{CartonError, Result};
+use crate::{
+    error::{CartonError, Result},
+    http::{into_async_read, send, FetchReturnType},
+};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use futures::{StreamExt, TryStreamExt};
@@ -30,7 +33,10 @@ use std::{
     pin::Pin,
     task::Poll,
 };
-use tokio::{io::AsyncRead, sync::mpsc};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::mpsc,
+};
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::compat::FuturesAsyncReadCompatExt;
 
@@ -48,17 +54,11 @@ enum RequestState {
 
     /// The current request we're waiting on (if any)
     #[cfg(target_family = "wasm")]
-    Request(Pin<Box<dyn std::future::Future<Output = Box<dyn AsyncRead + Unpin>>>>),
+    Request(Pin<Box<dyn std::future::Future<Output = std::io::Result<FetchReturnType>>>>),
 
     #[cfg(not(target_family = "wasm"))]
     Request(
-        Pin<
-            Box<
-                dyn std::future::Future<Output = Box<dyn AsyncRead + Unpin + Send + Sync>>
-                    + Send
-                    + Sync,
-            >,
-        >,
+        Pin<Box<dyn std::future::Future<Output = std::io::Result<FetchReturnType>> + Send + Sync>>,
     ),
 
     /// The current streaming response
@@ -76,6 +76,17 @@ lazy_static! {
     static ref FILE_INFO_CACHE: DashMap<String, CachedData> = DashMap::new();
 }
 
+/// A background download started by `HttpFS::prefetch`. Resolves once the file is in the cache
+#[cfg(not(target_family = "wasm"))]
+type Prefetch =
+    futures::future::Shared<futures::future::BoxFuture<'static, std::result::Result<(), String>>>;
+
+#[cfg(not(target_family = "wasm"))]
+lazy_static! {
+    /// In-progress and completed prefetches keyed by sha256
+    static ref PREFETCHES: DashMap<String, Prefetch> = DashMap::new();
+}
+
 struct CachedData {
     file_len: u64,
 }
@@ -88,7 +99,7 @@ impl HTTPFile {
             None => {
                 // TODO: maybe lazily fetch this
                 // TODO: include the URL in the error messages below
-                let res = client.head(&info.url).send().await?;
+                let res = send(&info.url, || client.head(&info.url)).await?;
                 let file_len = res
                     .headers()
                     .get(reqwest::header::CONTENT_LENGTH)
@@ -141,7 +152,11 @@ impl AsyncRead for HTTPFile {
                         ));
                 }
                 RequestState::Request(v) => match v.as_mut().poll(cx) {
-                    Poll::Ready(res) => self.state = RequestState::Response(Box::pin(res)),
+                    Poll::Ready(Ok(res)) => self.state = RequestState::Response(Box::pin(res)),
+                    Poll::Ready(Err(e)) => {
+                        self.state = RequestState::None;
+                        return Poll::Ready(Err(e));
+                    }
                     Poll::Pending => return Poll::Pending,
                 },
                 RequestState::Response(res) => return res.as_mut().poll_read(cx, buf),
@@ -151,19 +166,29 @@ impl AsyncRead for HTTPFile {
 }
 
 #[cfg(not(target_family = "wasm"))]
-type FetchReturnType = Box<dyn AsyncRead + Unpin + Send + Sync>;
-
-#[cfg(target_family = "wasm")]
-type FetchReturnType = Box<dyn AsyncRead + Unpin>;
-
-#[cfg(not(target_family = "wasm"))]
-async fn fetch(_client: reqwest::Client, url: String, sha256: String) -> FetchReturnType {
+async fn fetch(
+    _client: reqwest::Client,
+    url: String,
+    sha256: String,
+) -> std::io::Result<FetchReturnType> {
     // Note: on non-wasm platforms, we aren't using `client`; we use `cached_download` instead
+    // (which handles timeouts and retries)
     log::trace!("Starting fetch: {url}");
+
+    // If this file is being prefetched, wait for it to finish. `cached_download` below will then
+    // read it from the cache instead of downloading it again
+    let prefetch = PREFETCHES.get(&sha256).map(|v| v.clone());
+    if let Some(prefetch) = prefetch {
+        if let Err(e) = prefetch.await {
+            log::warn!("Prefetching {url} failed: {e}. Downloading it again");
+            PREFETCHES.remove(&sha256);
+        }
+    }
+
     let (tx, rx) = mpsc::channel(16);
 
     // Spawn a task to download and send chunks to our queue
-    tokio::spawn(async move {
+    let handle = tokio::spawn(async move {
         carton_utils::download::cached_download::<String>(
             &url,
             &sha256,
@@ -173,43 +198,41 @@ async fn fetch(_client: reqwest::Client, url: String, sha256: String) -> FetchRe
             |_| {},
         )
         .await
-        .unwrap();
     });
 
-    // Turn it into a stream
-    let stream = ReceiverStream::new(rx);
+    // Turn it into a stream. Once the download finishes (and drops `tx`), check if it failed so
+    // the reader gets an error instead of a truncated file
+    let download_result = futures::stream::once(Box::pin(async move {
+        match handle.await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(Err(e.into())),
+            Err(e) => Some(Err(std::io::Error::new(std::io::ErrorKind::Other, e))),
+        }
+    }))
+    .filter_map(futures::future::ready);
+
+    let stream = ReceiverStream::new(rx).map(Ok).chain(download_result);
 
     // Convert from a stream into futures::io::AsyncRead
-    let stream = stream.map(|v| Ok(v)).into_async_read();
+    let stream = stream.into_async_read();
 
     // To tokio::io::AsyncRead
     let stream = stream.compat();
 
-    Box::new(stream)
+    Ok(Box::new(stream))
 }
 
 #[cfg(target_family = "wasm")]
-async fn fetch(client: reqwest::Client, url: String, _sha256: String) -> FetchReturnType {
+async fn fetch(
+    client: reqwest::Client,
+    url: String,
+    _sha256: String,
+) -> std::io::Result<FetchReturnType> {
     // Note: on WASM, we don't verify the sha256
     // TODO: fix this
     log::trace!("Starting fetch: {url}");
-    let res = client.get(&url).send().await.unwrap();
-
-    if !res.status().is_success() {
-        // TODO: return an error instead of panic
-        panic!("Error fetching URL {}: {}", url, res.status());
-    }
-
-    // Convert from a stream into futures::io::AsyncRead
-    let stream = res
-        .bytes_stream()
-        .map_err(|e| futures::io::Error::new(futures::io::ErrorKind::Other, e))
-        .into_async_read();
-
-    // To tokio::io::AsyncRead
-    let stream = stream.compat();
-
-    Box::new(stream)
+    let res = send(&url, || client.get(&url)).await?;
+    Ok(into_async_read(&url, res))
 }
 
 #[cfg_attr(target_family = "wasm", async_trait(?Send))]
@@ -272,6 +295,41 @@ impl HttpFS {
     pub fn new(client: reqwest::Client, files: HashMap<PathBuf, FileInfo>) -> Self {
         Self { files, client }
     }
+
+    /// Start downloading all the files in this filesystem into the cache in the background.
+    /// Files that are already in the cache (or are being prefetched) aren't downloaded again
+    #[cfg(not(target_family = "wasm"))]
+    pub fn prefetch(&self) {
+        use futures::FutureExt;
+
+        for info in self.files.values() {
+            PREFETCHES.entry(info.sha256.clone()).or_insert_with(|| {
+                let FileInfo { url, sha256 } = info.clone();
+                let handle = tokio::spawn(async move {
+                    log::trace!("Prefetching {url}");
+                    carton_utils::download::cached_download::<String>(
+                        &url,
+                        &sha256,
+                        None,
+                        None,
+                        |_| {},
+                        |_| {},
+                    )
+                    .await
+                    .map_err(|e| e.to_string())
+                });
+
+                async move {
+                    match handle.await {
+                        Ok(res) => res,
+                        Err(e) => Err(e.to_string()),
+                    }
+                }
+                .boxed()
+                .shared()
+            });
+        }
+    }
 }
 
 #[cfg_attr(target_family = "wasm", async_trait(?Send))]
@@ -284,9 +342,12 @@ impl ReadableFileSystem for HttpFS {
     {
         let p = path.as_ref();
         match self.files.get(p) {
-            Some(info) => Ok(HTTPFile::new(self.client.clone(), info.clone())
+            Some(info) => HTTPFile::new(self.client.clone(), info.clone())
                 .await
-                .unwrap()),
+                .map_err(|e| match e {
+                    CartonError::IOError(e) => e,
+                    e => std::io::Error::new(std::io::ErrorKind::Other, e),
+                }),
             None => Err(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 "File not found",
@@ -316,16 +377,14 @@ impl ReadableFileSystem for HttpFS {
     async fn read(&self, path: impl PathType) -> std::io::Result<Vec<u8>> {
         let p = path.as_ref();
         match self.files.get(p) {
-            Some(info) => Ok(self
-                .client
-                .get(&info.url)
-                .send()
-                .await
-                .unwrap()
-                .bytes()
-                .await
-                .unwrap()
-                .to_vec()),
+            Some(info) => {
+                let res = send(&info.url, || self.client.get(&info.url)).await?;
+                let mut out = Vec::new();
+                into_async_read(&info.url, res)
+                    .read_to_end(&mut out)
+                    .await?;
+                Ok(out)
+            }
             None => Err(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 "File not found",
@@ -367,15 +426,14 @@ impl ReadableFileSystem for HttpFS {
     async fn read_to_string(&self, path: impl PathType) -> std::io::Result<String> {
         let p = path.as_ref();
         match self.files.get(p) {
-            Some(info) => Ok(self
-                .client
-                .get(&info.url)
-                .send()
-                .await
-                .unwrap()
-                .text()
-                .await
-                .unwrap()),
+            Some(info) => {
+                let res = send(&info.url, || self.client.get(&info.url)).await?;
+                let mut out = String::new();
+                into_async_read(&info.url, res)
+                    .read_to_string(&mut out)
+                    .await?;
+                Ok(out)
+            }
             None => Err(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 "File not found",
@@ -393,6 +451,12 @@ pub(crate) struct HttpReadDirPoller {
     files: VecDeque<PathBuf>,
 }
 
+impl HttpReadDirPoller {
+    pub(crate) fn new(files: VecDeque<PathBuf>) -> Self {
+        Self { files }
+    }
+}
+
 impl<F> ReadDirPoller<F> for HttpReadDirPoller
 where
     F: ReadableFileSystem,
@@ -0,0 +1,196 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for packing models that link to files on the Hugging Face Hub instead of embedding them
+
+use carton_utils::config::CONFIG;
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{CartonError, Result},
+    info::LinkedFile,
+};
+
+const HUGGINGFACE_URL: &str = "https://huggingface.co";
+
+lazy_static! {
+    // Doesn't follow redirects so we can read the headers of the Hub's response instead of the CDN's
+    static ref HEAD_CLIENT: reqwest::Client = carton_utils::download::client_builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    static ref CLIENT: reqwest::Client = carton_utils::download::client_builder().build().unwrap();
+}
+
+/// Create a `LinkedFile` for a file in a Hugging Face Hub repo (e.g. `facebook/bart-large-mnli`).
+/// The returned value can be added to `linked_files` in `PackOpts` so a copy of the file in the model
+/// directory isn't stored in the packed carton.
+///
+/// `revision` should be a commit hash so the file can't change after the model is packed. The sha256 of
+/// files stored with Git LFS is read from the Hub without downloading them; other files are downloaded
+/// and hashed. Lookups for commit hashes are cached in `CONFIG.cache_dir`.
+pub async fn link_huggingface_file(repo: &str, revision: &str, path: &str) -> Result<LinkedFile> {
+    let url = resolve_url(repo, revision, path);
+
+    // Only cache lookups for immutable revisions
+    let cache_path = if is_commit_hash(revision) {
+        let key = format!("{:x}", Sha256::digest(url.as_bytes()));
+        Some(CONFIG.cache_dir.join("huggingface").join(key))
+    } else {
+        None
+    };
+
+    if let Some(cache_path) = &cache_path {
+        if let Ok(sha256) = tokio::fs::read_to_string(cache_path).await {
+            return Ok(LinkedFile::new(vec![url], sha256));
+        }
+    }
+
+    let sha256 = match lfs_sha256(&url).await? {
+        Some(sha256) => sha256,
+        None => download_sha256(&url).await?,
+    };
+
+    if let Some(cache_path) = &cache_path {
+        tokio::fs::create_dir_all(cache_path.parent().unwrap()).await?;
+        tokio::fs::write(cache_path, &sha256).await?;
+    }
+
+    Ok(LinkedFile::new(vec![url], sha256))
+}
+
+/// The URL the Hub serves a file from
+fn resolve_url(repo: &str, revision: &str, path: &str) -> String {
+    format!(
+        "{HUGGINGFACE_URL}/{}/resolve/{revision}/{}",
+        repo.trim_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+fn is_commit_hash(revision: &str) -> bool {
+    revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Files stored with Git LFS redirect to a CDN and the Hub includes their sha256 in the
+/// `X-Linked-Etag` header. Returns `None` for other files
+async fn lfs_sha256(url: &str) -> Result<Option<String>> {
+    let res = carton_utils::download::send_with_retries(url, || HEAD_CLIENT.head(url))
+        .await
+        .map_err(|e| CartonError::IOError(e.into()))?;
+
+    linked_etag_sha256(url, res.status(), res.headers())
+}
+
+/// Read the sha256 from the `X-Linked-Etag` header of a response to a HEAD request.
+/// `HEAD_CLIENT` doesn't follow redirects so LFS files are a redirect (not an error) here
+fn linked_etag_sha256(
+    url: &str,
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+) -> Result<Option<String>> {
+    if !status.is_success() && !status.is_redirection() {
+        return Err(CartonError::IOError(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Error fetching URL {}: {}", url, status),
+        )));
+    }
+
+    Ok(headers
+        .get("x-linked-etag")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start_matches("W/").trim_matches('"'))
+        .filter(|v| v.len() == 64 && v.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|v| v.to_lowercase()))
+}
+
+/// Download a file and compute its sha256
+async fn download_sha256(url: &str) -> Result<String> {
+    let mut res = crate::http::send(url, || CLIENT.get(url)).await?;
+
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = carton_utils::download::next_chunk(url, &mut res)
+        .await
+        .map_err(|e| CartonError::IOError(e.into()))?
+    {
+        hasher.update(&chunk);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{header::HeaderMap, StatusCode};
+
+    use super::{is_commit_hash, linked_etag_sha256, resolve_url};
+
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(
+            resolve_url(
+                "facebook/bart-large-mnli",
+                "9fc9c4e1808b5613968646fa771fc43fb03995f2",
+                "rust_model.ot"
+            ),
+            "https://huggingface.co/facebook/bart-large-mnli/resolve/9fc9c4e1808b5613968646fa771fc43fb03995f2/rust_model.ot"
+        );
+
+        assert_eq!(
+            resolve_url("/gpt2/", "main", "/onnx/model.onnx"),
+            "https://huggingface.co/gpt2/resolve/main/onnx/model.onnx"
+        );
+
+        assert!(is_commit_hash("9fc9c4e1808b5613968646fa771fc43fb03995f2"));
+        assert!(!is_commit_hash("main"));
+        assert!(!is_commit_hash("v1.0"));
+    }
+
+    #[test]
+    fn test_linked_etag_sha256() {
+        let url = "https://huggingface.co/gpt2/resolve/main/model.safetensors";
+        let sha256 = "248DFC3911869EC493C76E65BF2FCF7F615828B0254C12B473182F0F81D3A707";
+
+        // LFS files are a redirect to the CDN with the sha256 in a (possibly weak) etag
+        let mut headers = HeaderMap::new();
+        headers.insert("x-linked-etag", format!("W/\"{sha256}\"").parse().unwrap());
+        assert_eq!(
+            linked_etag_sha256(url, StatusCode::FOUND, &headers).unwrap(),
+            Some(sha256.to_lowercase())
+        );
+
+        headers.insert("x-linked-etag", format!("\"{sha256}\"").parse().unwrap());
+        assert_eq!(
+            linked_etag_sha256(url, StatusCode::TEMPORARY_REDIRECT, &headers).unwrap(),
+            Some(sha256.to_lowercase())
+        );
+
+        // Files that aren't stored with LFS don't have a sha256 etag
+        let mut headers = HeaderMap::new();
+        headers.insert("x-linked-etag", "\"a1b2c3\"".parse().unwrap());
+        assert_eq!(
+            linked_etag_sha256(url, StatusCode::OK, &headers).unwrap(),
+            None
+        );
+        assert_eq!(
+            linked_etag_sha256(url, StatusCode::OK, &HeaderMap::new()).unwrap(),
+            None
+        );
+
+        // Errors are still errors
+        assert!(linked_etag_sha256(url, StatusCode::NOT_FOUND, &HeaderMap::new()).is_err());
+        assert!(linked_etag_sha256(url, StatusCode::UNAUTHORIZED, &headers).is_err());
+    }
+}
@@ -33,20 +33,185 @@ use crate::{
 };
 
 /// Options that can be specified when packing a model
+#[derive(Default)]
 pub struct PackOpts {
     pub info: CartonInfo,
 
     /// Any files to include in the carton as links (instead of the originals)
     pub linked_files: Option<Vec<LinkedFile>>,
+
+    /// By default, packing fails if any tensors in `examples` or `self_tests` don't match the
+    /// declared `inputs` and `outputs`. If this is `true`, mismatches are logged as warnings instead
+    pub allow_invalid_examples: bool,
+
+    /// A hex-encoded ed25519 secret key. If this is set, a detached signature over the MANIFEST is
+    /// written to a `SIGNATURE` file in the packed carton. It can be checked at load time with
+    /// `LoadOpts::signature_public_key`
+    pub signing_key: Option<String>,
+
+    /// Store `tensor_data` and the model dir without compression. This makes the carton larger, but
+    /// lets Carton memory map tensors directly from the packed file at load time instead of
+    /// decompressing them into memory. Useful for large models. Defaults to false (zstd compression)
+    pub store_uncompressed: bool,
+
+    /// Compress `MANIFEST` and `LINKS` with zstd instead of storing them uncompressed. This makes cartons
+    /// with many files smaller. Carton can load both, but other tools that read `MANIFEST` directly from
+    /// the zip file may expect it to be uncompressed. Defaults to false
+    pub compress_manifest: bool,
 }
 
-/// Info about files we want to include in the carton as links
+/// Info about files we want to include in the carton as links. Create one with `LinkedFile::new`
+#[non_exhaustive]
 pub struct LinkedFile {
     pub urls: Vec<String>,
     pub sha256: String,
+
+    /// Files with this sha256 in other published cartons. This lets a family of models share large
+    /// files (e.g. weights) without storing them in every carton. They're only used if `urls` is empty
+    pub carton_files: Vec<LinkedCartonFile>,
+}
+
+impl LinkedFile {
+    /// A file with the given sha256 that can be downloaded from any of `urls`
+    pub fn new(urls: Vec<String>, sha256: String) -> Self {
+        Self {
+            urls,
+            sha256,
+            carton_files: Vec::new(),
+        }
+    }
+
+    /// Also (or only, if `urls` is empty) fetch this file from other cartons
+    pub fn with_carton_files(mut self, carton_files: Vec<LinkedCartonFile>) -> Self {
+        self.carton_files = carton_files;
+        self
+    }
+}
+
+/// A file in another packed carton. See `LinkedFile::carton_files`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedCartonFile {
+    /// An http(s) URL of the carton that contains the file
+    pub carton_url: String,
+
+    /// The path of the file within that carton (e.g. `model/weights.bin`)
+    pub path: String,
+}
+
+/// The result of checking a single link in the `LINKS` file of a carton. See `Carton::verify_links`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkStatus {
+    /// The sha256 of the linked file
+    pub sha256: String,
+
+    /// The URL of the file or, for files in other cartons, the URL of the carton
+    pub url: String,
+
+    /// The path of the file within the carton at `url` if this is a file in another carton
+    /// (see `LinkedFile::carton_files`)
+    pub path: Option<String>,
+
+    pub status: LinkCheck,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkCheck {
+    /// The URL is reachable (and its contents match the sha256 if hashes were checked)
+    Ok,
+
+    /// The URL could not be fetched (or the file isn't in the carton at that URL)
+    Unreachable { reason: String },
+
+    /// The contents of the URL don't match the expected sha256. For files in other cartons, this is also
+    /// returned if that carton's MANIFEST lists the file with a different sha256
+    HashMismatch { actual: String },
+}
+
+/// The differences between two packed cartons. See `Carton::diff`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CartonDiff {
+    /// Files listed in the MANIFEST. The values are sha256 hashes
+    pub files: Vec<DiffEntry>,
+
+    /// Fields in `carton.toml` other than the input and output specs (e.g. `license` or `runner.opts.num_threads`).
+    /// The values are serialized as toml
+    pub metadata: Vec<DiffEntry>,
+
+    /// Input tensor specs keyed by tensor name. The values are serialized as toml
+    pub inputs: Vec<DiffEntry>,
+
+    /// Output tensor specs keyed by tensor name. The values are serialized as toml
+    pub outputs: Vec<DiffEntry>,
+}
+
+impl CartonDiff {
+    /// Returns true if there are no differences
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.metadata.is_empty()
+            && self.inputs.is_empty()
+            && self.outputs.is_empty()
+    }
+}
+
+/// A single item that differs between two cartons
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    /// A file path, a field name, or a tensor name depending on where this entry is in `CartonDiff`
+    pub key: String,
+
+    /// The value in the first carton (None if it was added)
+    pub old: Option<String>,
+
+    /// The value in the second carton (None if it was removed)
+    pub new: Option<String>,
+}
+
+/// What packing a model would produce. See `Carton::pack_dry_run`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PackPlan {
+    /// The files in the `model` directory of the carton sorted by path
+    pub files: Vec<PlannedFile>,
+
+    /// The total size (in bytes) of all the files in `files`
+    pub total_size: u64,
+
+    /// The total size (in bytes) of the files that would be stored in the carton (i.e. not linked).
+    /// This is before compression
+    pub stored_size: u64,
+}
+
+/// A file that would be included in a carton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    /// The path of the file relative to the root of the carton (e.g. `model/weights.bin`)
+    pub path: String,
+
+    /// The sha256 of the file (or of the file it points to for symlinks)
+    pub sha256: String,
+
+    /// The size of the file in bytes (0 for symlinks)
+    pub size: u64,
+
+    /// How the file would be stored
+    pub storage: FileStorage,
+}
+
+/// How a file would be stored in a carton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStorage {
+    /// The file is stored in the carton
+    Stored,
+
+    /// The file is not stored in the carton because it's available at one of the URLs in `linked_files`
+    Linked,
+
+    /// The file is stored as a symlink to another file in the model directory
+    Symlink { target: String },
 }
 
 // Info about a carton
+#[derive(Default)]
 pub struct CartonInfo {
     /// The name of the model
     pub model_name: Option<String>,
@@ -115,23 +280,185 @@ impl Clone for CartonInfo {
     }
 }
 
+/// Misc files with one of these names are treated as the thumbnail of the model. If there's more than
+/// one, the first one in this list is used
+pub const THUMBNAIL_NAMES: &[&str] = &["thumbnail.png", "thumbnail.jpg", "thumbnail.jpeg"];
+
+/// A reference to a misc file in `model_description`
+#[derive(Clone)]
+pub struct MiscFileReference {
+    /// The reference as it appears in the description (e.g. `@misc/file.png`)
+    pub reference: String,
+
+    /// The key of the referenced file in `misc_files`
+    pub path: String,
+
+    pub loader: ArcMiscFileLoader,
+}
+
+/// The misc file references in `model_description`. See `CartonInfo::resolve_misc_file_references`
+#[derive(Clone, Default)]
+pub struct MiscFileReferences {
+    /// References that match a misc file (in the order they first appear in the description)
+    pub resolved: Vec<MiscFileReference>,
+
+    /// References to misc files that don't exist (in the order they first appear in the description)
+    pub missing: Vec<String>,
+
+    /// Misc files that aren't referenced by the description (sorted by path)
+    pub unreferenced: Vec<String>,
+}
+
+impl CartonInfo {
+    /// The canonical order of this model's inputs (i.e. the order they're declared in `inputs`).
+    /// Returns `None` if the model doesn't declare its inputs.
+    ///
+    /// This is passed to runners when loading the model so runners that take positional arguments
+    /// don't need their own ordering file (see `carton_runner_interface::input_ordering`)
+    pub fn input_ordering(&self) -> Option<Vec<String>> {
+        self.inputs
+            .as_ref()
+            .map(|specs| specs.iter().map(|spec| spec.name.clone()).collect())
+    }
+
+    /// The canonical order of this model's outputs (i.e. the order they're declared in `outputs`).
+    /// Returns `None` if the model doesn't declare its outputs
+    pub fn output_ordering(&self) -> Option<Vec<String>> {
+        self.outputs
+            .as_ref()
+            .map(|specs| specs.iter().map(|spec| spec.name.clone()).collect())
+    }
+
+    /// Get the loader for the thumbnail of this model (e.g. a preview image shown on a model card).
+    /// This is a misc file named `thumbnail.png` (or one of the other `THUMBNAIL_NAMES`)
+    pub fn thumbnail(&self) -> Option<&ArcMiscFileLoader> {
+        let misc_files = self.misc_files.as_ref()?;
+        THUMBNAIL_NAMES
+            .iter()
+            .find_map(|name| misc_files.get(*name))
+    }
+
+    /// Find the markdown links and images in `model_description` that reference misc files (e.g.
+    /// `![architecture](@misc/model_architecture.png)`) and match them up with the loaders in `misc_files`.
+    /// This is useful when rendering the description (e.g. in a model card).
+    ///
+    /// Other links (e.g. https URLs) are ignored.
+    pub fn resolve_misc_file_references(&self) -> MiscFileReferences {
+        let mut out = MiscFileReferences::default();
+        let empty = HashMap::new();
+        let misc_files = self.misc_files.as_ref().unwrap_or(&empty);
+
+        let mut seen = std::collections::HashSet::new();
+        let mut referenced = std::collections::HashSet::new();
+        let description = self.model_description.as_deref().unwrap_or_default();
+        for reference in markdown_link_targets(description) {
+            let path = match reference.strip_prefix("@misc/") {
+                Some(path) => path_clean::clean(path),
+                None => continue,
+            };
+
+            if !seen.insert(reference) {
+                continue;
+            }
+
+            match misc_files.get(&path) {
+                Some(loader) => {
+                    referenced.insert(path.clone());
+                    out.resolved.push(MiscFileReference {
+                        reference: reference.to_owned(),
+                        path,
+                        loader: loader.clone(),
+                    })
+                }
+                None => out.missing.push(reference.to_owned()),
+            }
+        }
+
+        out.unreferenced = misc_files
+            .keys()
+            .filter(|k| !referenced.contains(*k))
+            .cloned()
+            .collect();
+        out.unreferenced.sort();
+
+        out
+    }
+}
+
+/// Get the targets of inline links and images (`[text](target)` and `![alt](target "title")`) and
+/// link reference definitions (`[label]: target`) in a markdown string.
+/// This isn't a complete markdown parser, but it handles the cases we care about.
+fn markdown_link_targets(markdown: &str) -> Vec<&str> {
+    // Strip the optional surrounding `<>` or trailing title from a link destination
+    fn destination(s: &str) -> Option<&str> {
+        let s = s.trim_start();
+        let out = match s.strip_prefix('<') {
+            Some(rest) => rest.split('>').next(),
+            None => s.split_whitespace().next(),
+        };
+
+        out.filter(|v| !v.is_empty())
+    }
+
+    let mut out = Vec::new();
+    for line in markdown.lines() {
+        // Link reference definitions
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            if let Some((_, rest)) = trimmed.split_once("]:") {
+                out.extend(destination(rest));
+                continue;
+            }
+        }
+
+        // Inline links and images
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            rest = &rest[start + 2..];
+            let end = rest.find(')').unwrap_or(rest.len());
+            out.extend(destination(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    out
+}
+
+impl PackOpts {
+    /// Set the thumbnail for this model (e.g. a preview image for a model registry). This is stored as a misc
+    /// file named `thumbnail.png` and replaces any existing thumbnail. See `CartonInfo::thumbnail`
+    pub fn set_thumbnail(&mut self, thumbnail: ArcMiscFileLoader) {
+        let misc_files = self.info.misc_files.get_or_insert_with(HashMap::new);
+        for name in THUMBNAIL_NAMES {
+            misc_files.remove(*name);
+        }
+
+        misc_files.insert(THUMBNAIL_NAMES[0].to_owned(), thumbnail);
+    }
+}
+
 impl From<CartonInfo> for PackOpts {
     fn from(value: CartonInfo) -> Self {
         Self {
             info: value,
-            linked_files: None,
+            ..Default::default()
         }
     }
 }
 
 /// A struct used when loading models. It contains extra things like the
 /// manifest hash
+#[derive(Clone)]
 pub struct CartonInfoWithExtras {
     pub info: CartonInfo,
 
     /// The sha256 of the MANIFEST file (if available)
     /// This should always be available unless we're running an unpacked model
     pub manifest_sha256: Option<String>,
+
+    /// Whether `infer` should fail if required inputs are missing. This is only set for cartons that
+    /// explicitly mark their inputs as `optional` or not (i.e. ones packed after optional inputs were supported)
+    pub(crate) enforce_required_inputs: bool,
 }
 
 #[cfg(target_family = "wasm")]
@@ -350,7 +677,7 @@ impl Clone for TensorOrMisc {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct RunnerInfo {
     /// The name of the runner to use
     pub runner_name: String,
@@ -376,19 +703,8 @@ pub struct RunnerInfo {
 impl From<RunnerInfo> for PackOpts {
     fn from(value: RunnerInfo) -> Self {
         let info = CartonInfo {
-            model_name: None,
-            short_description: None,
-            model_description: None,
-            license: None,
-            repository: None,
-            homepage: None,
-            required_platforms: None,
-            inputs: None,
-            outputs: None,
-            self_tests: None,
-            examples: None,
             runner: value,
-            misc_files: None,
+            ..Default::default()
         };
 
         info.into()
@@ -420,6 +736,15 @@ pub struct TensorSpec {
 
     /// Optional internal name
     pub internal_name: Option<String>,
+
+    /// Whether this input can be omitted when running inference. Ignored for outputs
+    #[serde(default)]
+    pub optional: bool,
+
+    /// A value to use for this input if it's optional and not provided to `infer`. If this is
+    /// `None`, optional inputs that aren't provided are not passed to the runner at all
+    #[serde(skip)]
+    pub default: Option<PossiblyLoaded<Tensor>>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -436,17 +761,112 @@ pub enum Shape {
     Shape(Vec<Dimension>),
 }
 
-/// A dimension can be either a fixed value, a symbol, or any value
+/// A dimension can be either a fixed value, a symbol, a bounded range, or any value
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Dimension {
     Value(u64),
     Symbol(String),
     Any,
+
+    /// An inclusive range of allowed values. If a bound is `None`, the range is unbounded on that side
+    Range {
+        min: Option<u64>,
+        max: Option<u64>,
+    },
+}
+
+/// The values bound to shape and dimension symbols while checking a set of related tensors (e.g. all
+/// the tensors in an example) against their specs
+#[derive(Default)]
+pub(crate) struct SymbolValues {
+    shapes: HashMap<String, Vec<usize>>,
+    dims: HashMap<String, usize>,
+}
+
+impl TensorSpec {
+    /// Check that `tensor` has the dtype and shape described by this spec. Symbols must have the same
+    /// value across all the tensors checked with the same `symbols`.
+    /// Returns a description of the mismatch if the tensor doesn't match
+    pub(crate) fn check(
+        &self,
+        tensor: &Tensor,
+        symbols: &mut SymbolValues,
+    ) -> std::result::Result<(), String> {
+        // Nested tensors can't be described by a spec so there's nothing to check
+        let (dtype, shape) = match (tensor.dtype(), tensor.shape()) {
+            (Some(dtype), Some(shape)) => (dtype, shape),
+            _ => return Ok(()),
+        };
+
+        if dtype != self.dtype {
+            return Err(format!(
+                "expected dtype `{}`, but got `{}`",
+                self.dtype.to_str(),
+                dtype.to_str()
+            ));
+        }
+
+        let dims = match &self.shape {
+            Shape::Any => return Ok(()),
+            Shape::Symbol(symbol) => {
+                let expected = symbols
+                    .shapes
+                    .entry(symbol.clone())
+                    .or_insert_with(|| shape.clone());
+                if *expected != shape {
+                    return Err(format!(
+                        "expected shape `{symbol}` to be {expected:?} to match other tensors, but got {shape:?}"
+                    ));
+                }
+
+                return Ok(());
+            }
+            Shape::Shape(dims) => dims,
+        };
+
+        if dims.len() != shape.len() {
+            return Err(format!(
+                "expected {} dimension(s), but got shape {shape:?}",
+                dims.len()
+            ));
+        }
+
+        for (i, (dim, &actual)) in dims.iter().zip(&shape).enumerate() {
+            match dim {
+                Dimension::Value(v) if *v != actual as u64 => {
+                    return Err(format!(
+                        "expected dimension {i} to be {v}, but got shape {shape:?}"
+                    ));
+                }
+                Dimension::Range { min: Some(min), .. } if (actual as u64) < *min => {
+                    return Err(format!(
+                        "expected dimension {i} to be at least {min}, but got shape {shape:?}"
+                    ));
+                }
+                Dimension::Range { max: Some(max), .. } if (actual as u64) > *max => {
+                    return Err(format!(
+                        "expected dimension {i} to be at most {max}, but got shape {shape:?}"
+                    ));
+                }
+                Dimension::Symbol(symbol) => {
+                    let expected = *symbols.dims.entry(symbol.clone()).or_insert(actual);
+                    if expected != actual {
+                        return Err(format!(
+                            "expected dimension {i} (`{symbol}`) to be {expected} to match other tensors, but got shape {shape:?}"
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
 }
 
 for_each_carton_type! {
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum DataType {
         $($CartonType,)*
     }
@@ -563,3 +983,122 @@ where
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use async_trait::async_trait;
+
+    use super::{
+        ArcMiscFileLoader, DataType, MiscFile, MiscFileLoader, PackOpts, RunnerInfo, Shape,
+        TensorSpec,
+    };
+
+    struct EmptyLoader;
+
+    #[async_trait]
+    impl MiscFileLoader for EmptyLoader {
+        async fn get(&self) -> MiscFile {
+            Box::new(tokio::io::empty())
+        }
+    }
+
+    #[test]
+    fn test_resolve_misc_file_references() {
+        let loader: ArcMiscFileLoader = Arc::new(EmptyLoader);
+        let misc_files: HashMap<_, _> = ["arch.png", "images/sample.jpg", "unused.txt"]
+            .into_iter()
+            .map(|k| (k.to_owned(), loader.clone()))
+            .collect();
+
+        let description = r#"
+# Some model
+
+![architecture](@misc/arch.png "The architecture")
+See [the paper](https://example.com/paper.pdf) and [a sample](<@misc/images/../images/sample.jpg>).
+![missing](@misc/missing.png) ![again](@misc/arch.png)
+
+[ref]: @misc/other.png
+"#;
+
+        let mut info = PackOpts::from(RunnerInfo {
+            runner_name: "noop".into(),
+            required_framework_version: semver::VersionReq::parse("*").unwrap(),
+            runner_compat_version: None,
+            opts: None,
+        })
+        .info;
+        info.model_description = Some(description.into());
+        info.misc_files = Some(misc_files);
+
+        let refs = info.resolve_misc_file_references();
+        assert_eq!(
+            refs.resolved
+                .iter()
+                .map(|r| (r.reference.as_str(), r.path.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("@misc/arch.png", "arch.png"),
+                ("@misc/images/../images/sample.jpg", "images/sample.jpg")
+            ]
+        );
+        assert_eq!(refs.missing, vec!["@misc/missing.png", "@misc/other.png"]);
+        assert_eq!(refs.unreferenced, vec!["unused.txt"]);
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        let loader: ArcMiscFileLoader = Arc::new(EmptyLoader);
+        let mut opts = PackOpts::from(RunnerInfo {
+            runner_name: "noop".into(),
+            required_framework_version: semver::VersionReq::parse("*").unwrap(),
+            runner_compat_version: None,
+            opts: None,
+        });
+        assert!(opts.info.thumbnail().is_none());
+
+        // Misc files with a thumbnail name are recognized
+        opts.info.misc_files = Some([("thumbnail.jpg".to_owned(), loader.clone())].into());
+        assert!(opts.info.thumbnail().is_some());
+
+        // Setting the thumbnail replaces the existing one
+        opts.set_thumbnail(loader);
+        let misc_files = opts.info.misc_files.as_ref().unwrap();
+        assert_eq!(misc_files.len(), 1);
+        assert!(misc_files.contains_key("thumbnail.png"));
+        assert!(opts.info.thumbnail().is_some());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let spec = |name: &str| TensorSpec {
+            name: name.into(),
+            dtype: DataType::Float,
+            shape: Shape::Any,
+            description: None,
+            internal_name: None,
+            optional: false,
+            default: None,
+        };
+
+        let mut info = PackOpts::from(RunnerInfo {
+            runner_name: "noop".into(),
+            required_framework_version: semver::VersionReq::parse("*").unwrap(),
+            runner_compat_version: None,
+            opts: None,
+        })
+        .info;
+        assert_eq!(info.input_ordering(), None);
+        assert_eq!(info.output_ordering(), None);
+
+        // The ordering matches the order the specs are declared in
+        info.inputs = Some(vec![spec("b"), spec("a"), spec("c")]);
+        info.outputs = Some(vec![]);
+        assert_eq!(
+            info.input_ordering(),
+            Some(vec!["b".to_owned(), "a".to_owned(), "c".to_owned()])
+        );
+        assert_eq!(info.output_ordering(), Some(vec![]));
+    }
+}
@@ -12,18 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(not(target_family = "wasm"))]
+mod bundle;
 pub mod carton;
 pub mod conversion_utils;
 pub mod error;
 mod format;
 mod http;
 mod httpfs;
+#[cfg(not(target_family = "wasm"))]
+pub mod huggingface;
 pub mod info;
+mod limitfs;
 mod load;
 mod overlayfs;
+#[cfg(not(target_family = "wasm"))]
+mod preload;
+pub mod queue;
+#[cfg(not(target_family = "wasm"))]
+pub mod retry;
 mod runner_interface;
+pub mod schema;
 pub mod types;
 pub use crate::carton::Carton;
+#[cfg(not(target_family = "wasm"))]
+pub use crate::preload::preload;
 
 #[cfg(not(target_family = "wasm"))]
 mod cuda;
@@ -0,0 +1,299 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use lunchbox::{
+    path::PathBuf,
+    types::{
+        DirEntry, HasFileType, MaybeSend, MaybeSync, Metadata, PathType, ReadDir, ReadDirPoller,
+        ReadableFile,
+    },
+    ReadableFileSystem,
+};
+use pin_project::pin_project;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// SizeLimitFS wraps a filesystem and enforces limits on the number of bytes actually read from it.
+/// This is used with untrusted containers where the sizes in the headers may not match the
+/// decompressed data (e.g. a decompression bomb).
+///
+/// Bytes are tracked per path so reading the same file more than once (e.g. to verify it and then
+/// to load it) only counts it once towards the total.
+pub(crate) struct SizeLimitFS<F> {
+    inner: Arc<F>,
+    limits: Arc<SizeLimits>,
+}
+
+struct SizeLimits {
+    max_total_size: u64,
+    max_file_size: u64,
+
+    /// The max number of bytes read from each path
+    read_per_path: DashMap<PathBuf, u64>,
+
+    /// The sum of the values in `read_per_path`
+    total_read: AtomicU64,
+}
+
+/// The error (wrapped in an `std::io::Error`) returned when reading goes over one of the limits.
+/// This is converted to `CartonError::ArchiveTooLarge`
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub(crate) struct SizeLimitExceeded(pub(crate) String);
+
+impl SizeLimitExceeded {
+    fn io_error(message: String) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::Other, Self(message))
+    }
+}
+
+impl SizeLimits {
+    /// Record that `read` bytes have been read from `path` by a single handle
+    fn record(&self, path: &PathBuf, read: u64) -> std::io::Result<()> {
+        if read > self.max_file_size {
+            return Err(SizeLimitExceeded::io_error(format!(
+                "File '{path}' is larger than the max allowed size of {} bytes",
+                self.max_file_size
+            )));
+        }
+
+        let mut entry = self.read_per_path.entry(path.clone()).or_insert(0);
+        if read > *entry {
+            let total = self.total_read.fetch_add(read - *entry, Ordering::SeqCst) + read - *entry;
+            *entry = read;
+
+            if total > self.max_total_size {
+                return Err(SizeLimitExceeded::io_error(format!(
+                    "The files in this carton are larger than the max allowed total size of {} bytes",
+                    self.max_total_size
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<F> SizeLimitFS<F> {
+    pub fn new(inner: Arc<F>, max_total_size: u64, max_file_size: u64) -> Self {
+        Self {
+            inner,
+            limits: Arc::new(SizeLimits {
+                max_total_size,
+                max_file_size,
+                read_per_path: DashMap::new(),
+                total_read: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+/// The filetype for SizeLimitFS
+#[pin_project]
+pub(crate) struct SizeLimitFile<T> {
+    #[pin]
+    inner: T,
+    path: PathBuf,
+    limits: Arc<SizeLimits>,
+
+    /// The number of bytes read using this handle
+    read: u64,
+}
+
+impl<T: AsyncRead> AsyncRead for SizeLimitFile<T> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.project();
+        let before = buf.filled().len();
+        match this.inner.poll_read(cx, buf) {
+            std::task::Poll::Ready(Ok(())) => {
+                *this.read += (buf.filled().len() - before) as u64;
+                let res = this.limits.record(this.path, *this.read);
+                if res.is_err() {
+                    // Callers don't expect any data to be read along with an error
+                    buf.set_filled(before);
+                }
+
+                std::task::Poll::Ready(res)
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg_attr(target_family = "wasm", async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait)]
+impl<T> ReadableFile for SizeLimitFile<T>
+where
+    T: ReadableFile + MaybeSend + MaybeSync,
+{
+    async fn metadata(&self) -> std::io::Result<Metadata> {
+        self.inner.metadata().await
+    }
+
+    async fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(Self {
+            inner: self.inner.try_clone().await?,
+            path: self.path.clone(),
+            limits: self.limits.clone(),
+            read: 0,
+        })
+    }
+}
+
+impl<F: HasFileType> HasFileType for SizeLimitFS<F> {
+    type FileType = SizeLimitFile<F::FileType>;
+}
+
+#[cfg_attr(target_family = "wasm", async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait)]
+impl<F> ReadableFileSystem for SizeLimitFS<F>
+where
+    F: ReadableFileSystem + MaybeSend + MaybeSync,
+    F::FileType: ReadableFile + MaybeSend + MaybeSync + Unpin,
+    F::ReadDirPollerType: MaybeSend,
+{
+    async fn open(&self, path: impl PathType) -> std::io::Result<Self::FileType>
+    where
+        Self::FileType: ReadableFile,
+    {
+        let path = path.as_ref().to_owned();
+        let inner = self.inner.open(&path).await?;
+        Ok(SizeLimitFile {
+            inner,
+            path,
+            limits: self.limits.clone(),
+            read: 0,
+        })
+    }
+
+    async fn canonicalize(&self, path: impl PathType) -> std::io::Result<PathBuf> {
+        self.inner.canonicalize(path).await
+    }
+
+    async fn metadata(&self, path: impl PathType) -> std::io::Result<Metadata> {
+        self.inner.metadata(path).await
+    }
+
+    async fn read(&self, path: impl PathType) -> std::io::Result<Vec<u8>> {
+        // Go through `open` so the limits are checked as we read instead of after the whole file is in memory
+        let mut f = self.open(path).await?;
+        let mut out = Vec::new();
+        f.read_to_end(&mut out).await?;
+        Ok(out)
+    }
+
+    type ReadDirPollerType = SizeLimitReadDirPoller;
+
+    async fn read_dir(
+        &self,
+        path: impl PathType,
+    ) -> std::io::Result<ReadDir<Self::ReadDirPollerType, Self>> {
+        let mut entries = VecDeque::new();
+        let mut dir = self.inner.read_dir(path).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            entries.push_back((entry.file_name(), entry.path()));
+        }
+
+        Ok(ReadDir::new(SizeLimitReadDirPoller { entries }, self))
+    }
+
+    async fn read_link(&self, path: impl PathType) -> std::io::Result<PathBuf> {
+        self.inner.read_link(path).await
+    }
+
+    async fn read_to_string(&self, path: impl PathType) -> std::io::Result<String> {
+        let mut f = self.open(path).await?;
+        let mut out = String::new();
+        f.read_to_string(&mut out).await?;
+        Ok(out)
+    }
+
+    async fn symlink_metadata(&self, path: impl PathType) -> std::io::Result<Metadata> {
+        self.inner.symlink_metadata(path).await
+    }
+}
+
+pub(crate) struct SizeLimitReadDirPoller {
+    // (file_name, path)
+    entries: VecDeque<(String, PathBuf)>,
+}
+
+impl<F> ReadDirPoller<F> for SizeLimitReadDirPoller
+where
+    F: ReadableFileSystem,
+    F::FileType: ReadableFile,
+{
+    fn poll_next_entry<'a>(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+        fs: &'a F,
+    ) -> std::task::Poll<std::io::Result<Option<DirEntry<'a, F>>>> {
+        std::task::Poll::Ready(Ok(self
+            .entries
+            .pop_front()
+            .map(|(file_name, path)| DirEntry::new(fs, file_name, path))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use lunchbox::ReadableFileSystem;
+
+    use super::SizeLimitFS;
+
+    #[tokio::test]
+    async fn test_size_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("b"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("c"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("big"), vec![0u8; 200]).unwrap();
+
+        let inner = Arc::new(
+            lunchbox::LocalFS::with_base_dir(dir.path().to_owned())
+                .await
+                .unwrap(),
+        );
+        // A single file over the limit fails
+        let fs = SizeLimitFS::new(inner.clone(), 250, 150);
+        assert!(fs.read("big").await.is_err());
+
+        // The bytes read before that failure count towards the total so use a new filesystem
+        let fs = SizeLimitFS::new(inner, 250, 150);
+
+        // Reading the same file multiple times only counts it once
+        assert_eq!(fs.read("a").await.unwrap().len(), 100);
+        assert_eq!(fs.read("a").await.unwrap().len(), 100);
+        assert_eq!(fs.read_to_string("a").await.unwrap().len(), 100);
+        assert_eq!(fs.read("b").await.unwrap().len(), 100);
+
+        // ...but reading a third file goes over the total limit
+        assert!(fs.read("c").await.is_err());
+    }
+}
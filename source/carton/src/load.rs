@@ -27,11 +27,16 @@ use semver::VersionReq;
 use url::{ParseError, Url};
 use zipfs::{GetReader, ZipFS};
 
+#[cfg(not(target_family = "wasm"))]
+use carton_utils::archive::{DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_TOTAL_SIZE};
+
 use crate::{
     error::CartonError,
+    format::v1::{manifest::parse_manifest, mmap::StoredEntries},
     http::HTTPFile,
     httpfs::{FileInfo, HttpFS},
     info::CartonInfoWithExtras,
+    limitfs::SizeLimitFS,
     overlayfs::OverlayFS,
     types::{CartonInfo, Device, LoadOpts},
 };
@@ -72,12 +77,18 @@ pub(crate) enum Runner {
 /// The maximum version of the runner interface supported by this build of carton
 const MAX_SUPPORTED_INTERFACE_VERSION: u64 = 1;
 
+/// Where to fetch installable runners from
+// TODO: make this configurable
+#[cfg(not(target_family = "wasm"))]
+const RUNNER_INDEX_URL: &str = "https://nightly.carton.run/v1/runners";
+
 /// Step 1: Fetch the file or directory (and call into step 2)
 /// If `url` points to a dir on disk, load a local lunchbox filesystem and
 /// call directly into step 3
 /// If `skip_runner` is true, a runner will not be launched. Only CartonInfo will be returned.
 async fn fetch(url: &str, opts: LoadOpts, skip_runner: bool) -> ReturnType {
-    let url = parse_protocol(url);
+    let url = resolve(url).await;
+    let url = parse_protocol(&url);
     match url {
         #[cfg(not(target_family = "wasm"))]
         LocatorWithProtocol::LocalFilePath(path) => {
@@ -88,33 +99,223 @@ async fn fetch(url: &str, opts: LoadOpts, skip_runner: bool) -> ReturnType {
                     &Arc::new(lunchbox::LocalFS::with_base_dir(path.0).await.unwrap()),
                     opts,
                     skip_runner,
+                    None,
                 )
                 .await
             } else {
                 // This is a file (or a symlink to one)
-                unwrap_container(path, opts, skip_runner).await
+                // Find any uncompressed entries so we can memory map them
+                let local_path = std::path::PathBuf::from(&path.0);
+                let stored = tokio::task::spawn_blocking(move || StoredEntries::open(&local_path))
+                    .await
+                    .unwrap()
+                    .map(Arc::new);
+
+                unwrap_container(path, opts, skip_runner, stored).await
             }
         }
         #[cfg(target_family = "wasm")]
         LocatorWithProtocol::LocalFilePath(_) => panic!("Local file paths not supported on wasm!"),
-        LocatorWithProtocol::HttpURL(url) => unwrap_container(url, opts, skip_runner).await,
+        LocatorWithProtocol::HttpURL(url) => unwrap_container(url, opts, skip_runner, None).await,
+        LocatorWithProtocol::IpfsURL(url) => {
+            let url = resolve_ipfs_gateway(&url).await?;
+            unwrap_container(url, opts, skip_runner, None).await
+        }
+    }
+}
+
+/// Expand a shorthand reference to a model in the registry (`org/model` or `org/model@version`) into a
+/// URL. Anything else (including paths that exist on local disk) is returned unchanged.
+/// See `Carton::resolve` for more details
+pub(crate) async fn resolve(url_or_path: &str) -> String {
+    // Local paths take precedence over registry references
+    #[cfg(not(target_family = "wasm"))]
+    if tokio::fs::metadata(url_or_path).await.is_ok() {
+        return url_or_path.to_owned();
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    let registry = carton_utils::config::CONFIG.registry_url.clone();
+
+    // This matches the default in `carton_utils::config`
+    #[cfg(target_family = "wasm")]
+    let registry = "https://carton.pub".to_owned();
+
+    match parse_registry_ref(url_or_path) {
+        Some((name, None)) => format!("{}/{name}", registry.trim_end_matches('/')),
+        Some((name, Some(version))) => {
+            format!("{}/{name}/{version}", registry.trim_end_matches('/'))
+        }
+        None => url_or_path.to_owned(),
+    }
+}
+
+/// Parse `org/model` or `org/model@version` into `(org/model, version)`.
+/// Returns `None` if `input` isn't of that form
+fn parse_registry_ref(input: &str) -> Option<(&str, Option<&str>)> {
+    // URLs have a scheme
+    if !matches!(Url::parse(input), Err(ParseError::RelativeUrlWithoutBase)) {
+        return None;
+    }
+
+    let (name, version) = match input.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (input, None),
+    };
+
+    // Names are `{org}/{model}` and can't look like relative paths (e.g. `./model` or `a/model.carton`)
+    let valid_segment = |segment: &str| {
+        segment
+            .chars()
+            .next()
+            .map_or(false, |c| c.is_ascii_alphanumeric())
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+
+    let (org, model) = name.split_once('/')?;
+    if !valid_segment(org) || !valid_segment(model) || model.ends_with(".carton") {
+        return None;
+    }
+
+    // Versions can include semver build metadata (e.g. `1.0.0+abc`)
+    if let Some(version) = version {
+        if !valid_segment(&version.replace('+', "-")) {
+            return None;
+        }
     }
+
+    Some((name, version))
+}
+
+/// Find an IPFS gateway that can serve `url`. Gateways are tried in the order they're configured
+///
+/// Note: gateways aren't trusted to return the right content. Use `expected_manifest_sha256` in
+/// `LoadOpts` to verify the carton (this checks every file against the pinned MANIFEST)
+async fn resolve_ipfs_gateway(url: &protocol::IpfsURL) -> crate::error::Result<protocol::HttpURL> {
+    #[cfg(not(target_family = "wasm"))]
+    let gateways = carton_utils::config::CONFIG.ipfs_gateways.clone();
+
+    // This matches the default in `carton_utils::config`
+    #[cfg(target_family = "wasm")]
+    let gateways = vec![
+        "https://ipfs.io/ipfs/".to_owned(),
+        "https://dweb.link/ipfs/".to_owned(),
+    ];
+
+    let mut errors = Vec::new();
+    for gateway in gateways {
+        let candidate = format!("{}/{}", gateway.trim_end_matches('/'), url.0);
+        match crate::http::send(&candidate, || CLIENT.head(&candidate)).await {
+            Ok(_) => return Ok(protocol::HttpURL(candidate)),
+            Err(e) => {
+                log::warn!("Failed to fetch '{candidate}' from IPFS gateway. Trying the next one (if any): {e}");
+                errors.push(format!("{gateway}: {e}"));
+            }
+        }
+    }
+
+    Err(CartonError::IpfsFetchFailed {
+        path: url.0.clone(),
+        errors,
+    })
 }
 
 /// Optional Step 2: Unwrap a container (e.g. zip) (and call into step 3)
-async fn unwrap_container<T>(item: T, opts: LoadOpts, skip_runner: bool) -> ReturnType
+/// `stored` contains the uncompressed entries of the container if it's a file on local disk
+pub(crate) async fn unwrap_container<T>(
+    item: T,
+    opts: LoadOpts,
+    skip_runner: bool,
+    stored: Option<Arc<StoredEntries>>,
+) -> ReturnType
 where
     T: GetReader + 'static + MaybeSync + MaybeSend,
     T::R: MaybeSync + MaybeSend,
 {
     // We currently only support zip so there isn't a whole lot to do here
-    let zip = ZipFS::new(item).await;
+    let zip = Arc::new(ZipFS::new(item).await);
+
+    // Don't trust the container (it may be a decompression bomb)
+    let (max_total_size, max_file_size) = archive_size_limits(&opts);
 
-    maybe_resolve_links(&Arc::new(zip), opts, skip_runner).await
+    // The headers may not match the actual data so enforce the limits on the bytes we actually read.
+    // This includes reading the MANIFEST below
+    let zip = Arc::new(SizeLimitFS::new(zip, max_total_size, max_file_size));
+
+    // Fail early if the sizes in the container headers are too large
+    check_archive_size(&zip, max_total_size, max_file_size).await?;
+
+    maybe_resolve_links(&zip, opts, skip_runner, stored).await
+}
+
+/// The max total size and max file size of the data read from an untrusted container
+fn archive_size_limits(opts: &LoadOpts) -> (u64, u64) {
+    (
+        opts.max_archive_size.unwrap_or(DEFAULT_MAX_TOTAL_SIZE),
+        opts.max_archive_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+    )
+}
+
+// These match the defaults in `carton_utils::archive`
+#[cfg(target_family = "wasm")]
+const DEFAULT_MAX_TOTAL_SIZE: u64 = 128 * 1024 * 1024 * 1024;
+#[cfg(target_family = "wasm")]
+const DEFAULT_MAX_FILE_SIZE: u64 = 32 * 1024 * 1024 * 1024;
+
+/// Check the uncompressed sizes of the files in the MANIFEST of a container against the limits.
+/// Files that aren't in the container (e.g. linked files) are skipped
+async fn check_archive_size<T>(
+    fs: &Arc<T>,
+    max_total_size: u64,
+    max_file_size: u64,
+) -> crate::error::Result<()>
+where
+    T: lunchbox::ReadableFileSystem + MaybeSend + MaybeSync + 'static,
+{
+    let manifest = match fs.read_to_string("/MANIFEST").await {
+        Ok(manifest) => manifest,
+        Err(e) => match CartonError::from(e) {
+            // The MANIFEST itself is over the limits
+            e @ CartonError::ArchiveTooLarge(_) => return Err(e),
+            // If there isn't a manifest, loading will fail with a more useful error later
+            _ => return Ok(()),
+        },
+    };
+
+    let mut total_size = 0u64;
+    for (file_path, _sha256) in parse_manifest(&manifest)? {
+        let size = match fs.symlink_metadata(file_path).await {
+            Ok(metadata) => metadata.len(),
+            // This file isn't in the container
+            Err(_) => continue,
+        };
+
+        if size > max_file_size {
+            return Err(CartonError::ArchiveTooLarge(format!(
+                "File '{file_path}' is {size} bytes, which is larger than the max allowed size of {max_file_size} bytes"
+            )));
+        }
+
+        total_size += size;
+        if total_size > max_total_size {
+            return Err(CartonError::ArchiveTooLarge(format!(
+                "The files in this carton are larger than the max allowed total size of {max_total_size} bytes"
+            )));
+        }
+    }
+
+    Ok(())
 }
 
 /// Step 3: Resolve links (and call into step 4)
-async fn maybe_resolve_links<T>(fs: &Arc<T>, opts: LoadOpts, skip_runner: bool) -> ReturnType
+async fn maybe_resolve_links<T>(
+    fs: &Arc<T>,
+    opts: LoadOpts,
+    skip_runner: bool,
+    stored: Option<Arc<StoredEntries>>,
+) -> ReturnType
 where
     T: lunchbox::ReadableFileSystem + MaybeSend + MaybeSync + 'static,
     T::FileType: lunchbox::types::ReadableFile + MaybeSend + MaybeSync + Unpin,
@@ -126,14 +327,22 @@ where
     let has_links = PathBuf::from("/LINKS").exists(fs.as_ref()).await;
 
     if !has_manifest {
-        // Not a valid carton
-        // Return an error
-        todo!()
+        // This may be an unpacked model directory (i.e. a carton.toml and a `model` dir). We can read
+        // its metadata, but we can't run it without packing it first
+        if !PathBuf::from("/carton.toml").exists(fs.as_ref()).await {
+            return Err(CartonError::MissingCartonToml);
+        }
+
+        if !skip_runner {
+            return Err(CartonError::UnpackedModel);
+        }
+
+        return load_carton(fs, opts, skip_runner, stored).await;
     }
 
     if !has_links {
         // No links to resolve so just pass through
-        load_carton(fs, opts, skip_runner).await
+        load_carton(fs, opts, skip_runner, stored).await
     } else {
         // Resolve links and then make an overlayfs and
         // pass through to load_carton
@@ -141,24 +350,25 @@ where
         // TODO: technically this should be in format::v1 because it's specific to the format
 
         // Map from file path to sha256
-        let mut contents = HashMap::new();
-
-        // Note: not using `filter` so we can return errors easily
         let manifest = fs.read_to_string("/MANIFEST").await?;
-        for line in manifest.lines() {
-            if let Some((file_path, sha256)) = line.rsplit_once("=") {
-                contents.insert(file_path, sha256);
-            } else {
-                return Err(CartonError::Other(
-                    "MANIFEST was not in the form {path}={sha256}",
-                ));
-            }
-        }
+        let contents: HashMap<_, _> = parse_manifest(&manifest)?.into_iter().collect();
 
         // Load links
         let links = fs.read_to_string("/LINKS").await?;
         let links: crate::format::v1::links::Links = toml::from_str(&links)?;
 
+        // Open the other cartons that any files link to. These are untrusted too
+        let (max_total_size, max_file_size) = archive_size_limits(&opts);
+        let carton_files = Arc::new(
+            crate::format::v1::links::resolve_carton_files(
+                &contents,
+                &links,
+                max_total_size,
+                max_file_size,
+            )
+            .await?,
+        );
+
         // Generate a mapping from file path to url
         let file_mapping = contents
             .into_iter()
@@ -184,16 +394,32 @@ where
         // Create an HttpFS to handle fetching links
         let httpfs = Arc::new(HttpFS::new(CLIENT.clone(), file_mapping));
 
-        // Create an overlay filesystem that does URL fetching for the files in links
-        let overlay = Arc::new(OverlayFS::new(httpfs, fs.clone()));
+        // Start downloading the linked files in the background so the download overlaps with launching
+        // the runner (see `LoadOpts::prefetch_linked_files`)
+        #[cfg(not(target_family = "wasm"))]
+        if opts.prefetch_linked_files && !skip_runner {
+            httpfs.prefetch();
+        }
+
+        // Create an overlay filesystem that does URL fetching for the files in links and falls back
+        // to reading from other cartons
+        let overlay = Arc::new(OverlayFS::new(
+            Arc::new(OverlayFS::new(carton_files, httpfs)),
+            fs.clone(),
+        ));
 
         // Continue loading the carton
-        load_carton(&overlay, opts, skip_runner).await
+        load_carton(&overlay, opts, skip_runner, stored).await
     }
 }
 
 /// Step 4: Load carton info from the resolved fs (and call into step 5 and then call into step 6)
-async fn load_carton<T>(fs: &Arc<T>, opts: LoadOpts, skip_runner: bool) -> ReturnType
+async fn load_carton<T>(
+    fs: &Arc<T>,
+    opts: LoadOpts,
+    skip_runner: bool,
+    stored: Option<Arc<StoredEntries>>,
+) -> ReturnType
 where
     T: lunchbox::ReadableFileSystem + MaybeSend + MaybeSync + 'static,
     T::FileType: lunchbox::types::ReadableFile + MaybeSend + MaybeSync + Unpin,
@@ -201,42 +427,141 @@ where
 {
     // First, figure out which format version this is
     // Currently, there's only one so we always pass through to it
-    let info_with_extras = crate::format::v1::load(fs).await?;
+    let info_with_extras = crate::format::v1::load(fs, stored).await?;
+
+    // Make sure this is the carton we expected before doing anything else with it
+    if let Some(expected) = &opts.expected_manifest_sha256 {
+        match &info_with_extras.manifest_sha256 {
+            Some(actual) if actual.eq_ignore_ascii_case(expected) => {}
+            actual => {
+                return Err(CartonError::ManifestHashMismatch {
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                })
+            }
+        }
+    }
+
+    // Check the signature (if requested)
+    if let Some(public_key) = &opts.signature_public_key {
+        crate::format::v1::signature::verify_manifest(fs.as_ref(), public_key).await?;
+    }
+
+    // The manifest hash and the signature only cover `MANIFEST` so we also need to check the contents of
+    // every file against it before the runner sees them
+    if opts.expected_manifest_sha256.is_some() || opts.signature_public_key.is_some() {
+        crate::format::v1::verify_file_hashes(fs.as_ref()).await?;
+    }
+
+    // Keep track of the framework versions the carton was packed for if we're overriding them
+    let declared_framework_version = opts.override_required_framework_version.as_ref().map(|_| {
+        info_with_extras
+            .info
+            .runner
+            .required_framework_version
+            .clone()
+    });
 
     // Merge in load opts
     let visible_device = opts.visible_device.clone();
+    let streaming_buffer_size = opts.streaming_buffer_size;
+    let auto_install_runner = opts.auto_install_runner;
+    let runner_path_override = opts.runner_path_override.clone();
+    let runner_env = opts.runner_env.clone();
+    let seed = opts.seed;
     let info_with_extras = merge_in_load_opts(info_with_extras, opts)?;
 
     if skip_runner {
         Ok((info_with_extras, None))
     } else {
         // Launch a runner
-        let (runner, _) =
-            discover_or_get_runner_and_launch(&info_with_extras.info, &visible_device).await?;
+        let (mut runner, runner_info) = discover_or_get_runner_and_launch(
+            &info_with_extras.info,
+            &visible_device,
+            auto_install_runner,
+            runner_path_override.as_deref(),
+            &runner_env,
+        )
+        .await?;
+
+        if let Some(declared) = &declared_framework_version {
+            warn_if_untested_framework_version(declared, &runner_info);
+        }
+
+        if let Some(size) = streaming_buffer_size {
+            match &mut runner {
+                Runner::V1(runner) => runner.set_streaming_buffer_size(size),
+            }
+        }
 
         // We need to pass in the `model` subdirectory as the filesystem root instead of
         // fs directly.
         let wrapped = Arc::new(ChrootFS::new(fs.clone(), "model".into()));
 
         // Load the model
-        load_model(&wrapped, &runner, &info_with_extras, visible_device).await?;
+        load_model(&wrapped, &runner, &info_with_extras, visible_device, seed).await?;
 
         Ok((info_with_extras, Some(runner)))
     }
 }
 
+/// The runner launched by `discover_or_get_runner_and_launch`
+#[cfg(not(target_family = "wasm"))]
+pub(crate) enum LaunchedRunner {
+    /// An installed runner along with the info from its `runner.toml`
+    Installed(carton_runner_packager::discovery::RunnerInfo),
+
+    /// A runner binary passed in with `runner_path_override` (or the env). We don't have a `runner.toml`
+    /// for it so its framework version, compat version and release date are unknown
+    Override,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl LaunchedRunner {
+    /// The `runner_compat_version` of the runner or `None` if it's unknown
+    pub(crate) fn runner_compat_version(&self) -> Option<u64> {
+        match self {
+            LaunchedRunner::Installed(info) => Some(info.runner_compat_version),
+            LaunchedRunner::Override => None,
+        }
+    }
+}
+
 // Step 5: Figure out what runner to use (or get it if necessary) and launch the runner
+// If `auto_install_runner` is false, this returns an error instead of installing a missing runner
+// If the runner path is overridden (via `runner_path_override` or the env), that runner is launched directly
 #[cfg(not(target_family = "wasm"))]
 pub(crate) async fn discover_or_get_runner_and_launch(
     info: &CartonInfo,
     visible_device: &Device,
-) -> crate::error::Result<(Runner, carton_runner_packager::discovery::RunnerInfo)> {
+    auto_install_runner: bool,
+    runner_path_override: Option<&std::path::Path>,
+    runner_env: &HashMap<String, String>,
+) -> crate::error::Result<(Runner, LaunchedRunner)> {
     use carton_runner_packager::{
-        discovery::RunnerFilterConstraints,
-        fetch::{get_or_install_runner, RunnerInstallConstraints},
+        discovery::{
+            get_installed_compat_versions, get_matching_installed_runner, RunnerFilterConstraints,
+        },
+        fetch::{
+            get_available_compat_versions, get_available_framework_versions,
+            get_available_platforms, get_or_install_runner, RunnerInstallConstraints,
+        },
     };
     use runner_interface_v1::slowlog::slowlog;
 
+    if let Some(runner_path) =
+        get_runner_path_override(&info.runner.runner_name, runner_path_override)?
+    {
+        log::info!(
+            "Using runner '{}' for '{}' instead of an installed runner",
+            runner_path.display(),
+            info.runner.runner_name
+        );
+
+        let runner = launch_runner(&runner_path, visible_device, runner_env).await?;
+        return Ok((Runner::V1(runner), LaunchedRunner::Override));
+    }
+
     // Filter the runners to ones that match our requirements
     let filters = RunnerFilterConstraints {
         runner_name: Some(info.runner.runner_name.clone()),
@@ -246,54 +571,277 @@ pub(crate) async fn discover_or_get_runner_and_launch(
         platform: target_lexicon::HOST.to_string(),
     };
 
-    let mut sl = slowlog(
-        format!(
-            "Fetching runner for '{}' version '{}'",
-            filters.runner_name.as_ref().unwrap(),
-            filters.framework_version_range.as_ref().unwrap()
+    let candidate = match get_matching_installed_runner(&filters, &None).await {
+        // We already have a matching runner installed
+        Some(candidate) => candidate,
+        None if !auto_install_runner => {
+            // If there's an installed runner that only differs in compat version, explain that instead
+            if let Some(err) = incompatible_compat_version(
+                info.runner.runner_compat_version,
+                get_installed_compat_versions(&filters).await,
+            ) {
+                return Err(err);
+            }
+
+            return Err(CartonError::RunnerNotInstalled {
+                name: info.runner.runner_name.clone(),
+                version: info.runner.required_framework_version.to_string(),
+            });
+        }
+        None => {
+            log::info!(
+                "No installed runner found for '{}' version '{}'. Fetching and installing one...",
+                info.runner.runner_name,
+                info.runner.required_framework_version
+            );
+
+            let mut sl = slowlog(
+                format!(
+                    "Fetching runner for '{}' version '{}'",
+                    info.runner.runner_name, info.runner.required_framework_version
+                ),
+                5,
+            )
+            .await
+            .without_progress();
+
+            let constraints = RunnerInstallConstraints { id: None, filters };
+            let candidate = get_or_install_runner(RUNNER_INDEX_URL, &constraints, false).await;
+
+            sl.done();
+
+            match candidate {
+                Ok(candidate) => candidate,
+                Err(e) => {
+                    log::debug!("Failed to get a runner: {e}");
+
+                    // If there are matching runners for other platforms, the runner just isn't built
+                    // for this one
+                    let available_platforms =
+                        get_available_platforms(RUNNER_INDEX_URL, &constraints.filters).await;
+                    if !available_platforms.is_empty() {
+                        return Err(CartonError::RunnerPlatformMismatch {
+                            runner: info.runner.runner_name.clone(),
+                            host_triple: constraints.filters.platform.clone(),
+                            available_platforms,
+                        });
+                    }
+
+                    // If there are matching runners with a different compat version, either the model or
+                    // the runner is out of date
+                    if let Some(err) = incompatible_compat_version(
+                        info.runner.runner_compat_version,
+                        get_available_compat_versions(RUNNER_INDEX_URL, &constraints.filters).await,
+                    ) {
+                        return Err(err);
+                    }
+
+                    // No matching runners. Explain why by listing the framework versions we do have
+                    return Err(CartonError::NoCompatibleRunner {
+                        runner_name: info.runner.runner_name.clone(),
+                        required: info.runner.required_framework_version.clone(),
+                        available: get_available_framework_versions(
+                            RUNNER_INDEX_URL,
+                            &constraints.filters,
+                        )
+                        .await,
+                    });
+                }
+            }
+        }
+    };
+
+    // We have a runner we can use!
+    match candidate.runner_interface_version {
+        // Find the right interface to use
+        1 => {
+            let runner = launch_runner(
+                &std::path::PathBuf::from(&candidate.runner_path),
+                visible_device,
+                runner_env,
+            )
+            .await?;
+
+            Ok((Runner::V1(runner), LaunchedRunner::Installed(candidate)))
+        }
+        version => unreachable!(
+            "This runner requires a newer interface ({version}) than we have. Shouldn't happen because we filtered above."
         ),
-        5,
+    }
+}
+
+/// Launch the runner binary at `runner_path`
+#[cfg(not(target_family = "wasm"))]
+async fn launch_runner(
+    runner_path: &std::path::Path,
+    visible_device: &Device,
+    runner_env: &HashMap<String, String>,
+) -> crate::error::Result<runner_interface_v1::Runner> {
+    runner_interface_v1::Runner::new_with_env(
+        runner_path,
+        visible_device.clone().into(),
+        runner_env,
     )
     .await
-    .without_progress();
+    .map_err(|reason| CartonError::InvalidRunnerPath {
+        path: runner_path.to_owned(),
+        reason,
+    })
+}
 
-    let candidate = get_or_install_runner(
-        // TODO: make this configurable
-        "https://nightly.carton.run/v1/runners",
-        &RunnerInstallConstraints { id: None, filters },
-        false,
-    )
-    .await;
+/// Warn if the runner we picked (because of `override_required_framework_version`) has a framework
+/// version outside the range the carton was packed for. We still use the runner
+#[cfg(not(target_family = "wasm"))]
+fn warn_if_untested_framework_version(declared: &VersionReq, runner: &LaunchedRunner) {
+    if let Some(runner_info) = untested_runner(declared, runner) {
+        log::warn!(
+            "Running this model with {} version {}, but it was packed for version '{declared}'. This combination is untested and may not work correctly. This is because `override_required_framework_version` was set",
+            runner_info.runner_name,
+            runner_info.framework_version,
+        );
+    }
+}
 
-    sl.done();
+/// Returns the info of `runner` if its framework version doesn't match `declared`
+#[cfg(not(target_family = "wasm"))]
+fn untested_runner<'a>(
+    declared: &VersionReq,
+    runner: &'a LaunchedRunner,
+) -> Option<&'a carton_runner_packager::discovery::RunnerInfo> {
+    match runner {
+        LaunchedRunner::Installed(runner_info) => {
+            (!declared.matches(&runner_info.framework_version)).then_some(runner_info)
+        }
+        // Runners launched from an override path don't have a `runner.toml` so we don't know
+        // their framework version
+        LaunchedRunner::Override => None,
+    }
+}
 
-    match candidate {
-        Ok(candidate) => {
-            // We have a runner we can use!
+#[cfg(target_family = "wasm")]
+fn warn_if_untested_framework_version(_declared: &VersionReq, _runner_info: &()) {}
 
-            match candidate.runner_interface_version {
-                // Find the right interface to use
-                1 => {
-                    let runner = runner_interface_v1::Runner::new(
-                        &std::path::PathBuf::from(&candidate.runner_path),
-                        visible_device.clone().into(),
-                    )
-                    .await
-                    .unwrap();
+/// If runners matching everything except the compat version are available, return an
+/// `IncompatibleRunnerCompatVersion` error that reports the newest one
+#[cfg(not(target_family = "wasm"))]
+fn incompatible_compat_version(carton: Option<u64>, available: Vec<u64>) -> Option<CartonError> {
+    let carton = carton?;
+    if available.contains(&carton) {
+        // The compat version isn't the problem
+        return None;
+    }
 
-                    Ok((Runner::V1(runner), candidate))
-                }
-                version => unreachable!(
-                    "This runner requires a newer interface ({version}) than we have. Shouldn't happen because we filtered above."
-                ),
-            }
+    let runner = available.into_iter().max()?;
+    Some(CartonError::IncompatibleRunnerCompatVersion { carton, runner })
+}
+
+/// Find the binary for `runner_name` in an unpacked runner directory containing a `runner.toml` and the
+/// runner binary (see `Carton::load_with_local_runner_dir`)
+#[cfg(all(feature = "test-utils", not(target_family = "wasm")))]
+pub(crate) async fn find_local_runner(
+    runner_dir: &std::path::Path,
+    runner_name: &str,
+) -> crate::error::Result<std::path::PathBuf> {
+    let invalid = |reason: String| CartonError::InvalidRunnerPath {
+        path: runner_dir.to_owned(),
+        reason,
+    };
+
+    let runners =
+        carton_runner_packager::discovery::read_runner_toml(&runner_dir.join("runner.toml"))
+            .await
+            .map_err(|e| invalid(format!("couldn't read `runner.toml`: {e}")))?;
+
+    let runner = match runners.iter().find(|r| r.runner_name == runner_name) {
+        Some(runner) => runner,
+        None => {
+            let names: Vec<_> = runners.iter().map(|r| r.runner_name.as_str()).collect();
+            return Err(invalid(format!(
+                "`runner.toml` doesn't contain a runner named '{runner_name}'. It contains: [{}]",
+                names.join(", ")
+            )));
         }
-        Err(e) => {
-            // No matching runners
-            // TODO: return an error instead of panicking
-            panic!("No matching runner: {e}")
+    };
+
+    if runner.runner_interface_version > MAX_SUPPORTED_INTERFACE_VERSION {
+        return Err(invalid(format!(
+            "the runner uses runner interface version {}, but this version of carton only supports up to version {MAX_SUPPORTED_INTERFACE_VERSION}",
+            runner.runner_interface_version
+        )));
+    }
+
+    if runner.platform != target_lexicon::HOST.to_string() {
+        return Err(invalid(format!(
+            "the runner was built for '{}', but this platform is '{}'",
+            runner.platform,
+            target_lexicon::HOST
+        )));
+    }
+
+    // Whether the binary is executable is checked when it's launched (see `get_runner_path_override`)
+    let runner_path = std::path::PathBuf::from(&runner.runner_path);
+    if !runner_path.is_file() {
+        return Err(invalid(format!(
+            "the runner binary '{}' doesn't exist",
+            runner_path.display()
+        )));
+    }
+
+    Ok(runner_path)
+}
+
+/// Get the runner binary to launch instead of an installed runner (if any).
+/// `runner_path_override` takes precedence over the `CARTON_RUNNER_OVERRIDE_{RUNNER_NAME}` env var
+#[cfg(not(target_family = "wasm"))]
+fn get_runner_path_override(
+    runner_name: &str,
+    runner_path_override: Option<&std::path::Path>,
+) -> crate::error::Result<Option<std::path::PathBuf>> {
+    let path: std::path::PathBuf = match runner_path_override {
+        Some(path) => path.to_owned(),
+        None => match std::env::var_os(runner_override_env_var(runner_name)) {
+            Some(path) => path.into(),
+            None => return Ok(None),
+        },
+    };
+
+    let invalid = |reason: String| CartonError::InvalidRunnerPath {
+        path: path.clone(),
+        reason,
+    };
+
+    let metadata = std::fs::metadata(&path).map_err(|e| invalid(e.to_string()))?;
+    if !metadata.is_file() {
+        return Err(invalid("not a file".into()));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(invalid("not executable".into()));
         }
     }
+
+    Ok(Some(path))
+}
+
+/// The env var that overrides the runner binary for `runner_name`.
+/// For example, `CARTON_RUNNER_OVERRIDE_TORCHSCRIPT` for the `torchscript` runner
+#[cfg(not(target_family = "wasm"))]
+fn runner_override_env_var(runner_name: &str) -> String {
+    let name: String = runner_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("CARTON_RUNNER_OVERRIDE_{name}")
 }
 
 // No discovery for wasm - just launch a runner and return
@@ -301,6 +849,9 @@ pub(crate) async fn discover_or_get_runner_and_launch(
 pub(crate) async fn discover_or_get_runner_and_launch(
     c: &CartonInfo,
     visible_device: &Device,
+    auto_install_runner: bool,
+    runner_path_override: Option<&std::path::Path>,
+    runner_env: &HashMap<String, String>,
 ) -> crate::error::Result<(Runner, ())> {
     todo!()
 }
@@ -311,6 +862,7 @@ pub(crate) async fn load_model<T>(
     runner: &Runner,
     c: &CartonInfoWithExtras,
     visible_device: Device,
+    seed: Option<u64>,
 ) -> crate::error::Result<()>
 where
     T: lunchbox::ReadableFileSystem + MaybeSend + MaybeSync + 'static,
@@ -319,22 +871,53 @@ where
 {
     match runner {
         Runner::V1(runner) => {
+            // Resolve `${ENV_VAR}` references in runner opts using the environment we're loading in.
+            // This is only done on the copy we pass to the runner (see `interpolate_runner_opts`)
+            let mut runner_opts = c.info.runner.opts.clone();
+            if let Some(opts) = runner_opts.as_mut() {
+                crate::format::v1::interpolate_runner_opts(opts)?;
+            }
+
+            let mut runner_opts: Option<HashMap<_, _>> =
+                runner_opts.map(|item| item.into_iter().map(|(k, v)| (k, v.into())).collect());
+
+            // Let runners that take positional arguments know the canonical order of the inputs and outputs
+            for (key, ordering) in [
+                (
+                    runner_interface_v1::INPUT_ORDERING_OPT,
+                    c.info.input_ordering(),
+                ),
+                (
+                    runner_interface_v1::OUTPUT_ORDERING_OPT,
+                    c.info.output_ordering(),
+                ),
+            ] {
+                if let Some(ordering) = ordering {
+                    runner_opts.get_or_insert_with(Default::default).insert(
+                        key.to_owned(),
+                        runner_interface_v1::types::RunnerOpt::String(ordering.join("\n")),
+                    );
+                }
+            }
+
+            if let Some(seed) = seed {
+                runner_opts.get_or_insert_with(Default::default).insert(
+                    runner_interface_v1::SEED_OPT.to_owned(),
+                    runner_interface_v1::types::RunnerOpt::Integer(seed as i64),
+                );
+            }
+
             runner
                 .load(
                     fs,
                     c.info.runner.runner_name.clone(),
                     c.info.runner.required_framework_version.clone(),
                     c.info.runner.runner_compat_version.unwrap(),
-                    c.info
-                        .runner
-                        .opts
-                        .clone()
-                        .map(|item| item.into_iter().map(|(k, v)| (k, v.into())).collect()),
+                    runner_opts,
                     visible_device.into(),
                     c.manifest_sha256.clone(),
                 )
-                .await
-                .map_err(|e| CartonError::ErrorFromRunner(e))?;
+                .await?;
         }
     }
 
@@ -380,6 +963,8 @@ fn parse_protocol(input: &str) -> LocatorWithProtocol {
         Ok(parsed) => match parsed.scheme() {
             "file" => LocatorWithProtocol::LocalFilePath(input.into()),
             "http" | "https" => LocatorWithProtocol::HttpURL(input.into()),
+            // `ipfs://{cid}` or `ipfs://{cid}/path/to/model.carton`
+            "ipfs" => LocatorWithProtocol::IpfsURL((&parsed).into()),
             _other => todo!(),
         },
         // This is a file
@@ -391,12 +976,25 @@ fn parse_protocol(input: &str) -> LocatorWithProtocol {
 enum LocatorWithProtocol {
     LocalFilePath(protocol::LocalFilePath),
     HttpURL(protocol::HttpURL),
+    IpfsURL(protocol::IpfsURL),
 }
 
-mod protocol {
+pub(crate) mod protocol {
     pub struct LocalFilePath(pub String);
     pub struct HttpURL(pub String);
 
+    /// A CID optionally followed by a path (i.e. an `ipfs://` URL without the scheme)
+    pub struct IpfsURL(pub String);
+
+    impl From<&url::Url> for IpfsURL {
+        fn from(value: &url::Url) -> Self {
+            // The CID is the host in `ipfs://{cid}/path`. `ipfs:{cid}/path` doesn't have a host so
+            // the CID is the start of the path
+            let cid_and_path = format!("{}{}", value.host_str().unwrap_or_default(), value.path());
+            Self(cid_and_path.trim_matches('/').to_owned())
+        }
+    }
+
     impl From<&str> for LocalFilePath {
         fn from(value: &str) -> Self {
             Self(value.to_owned())
@@ -421,16 +1019,10 @@ impl GetReader for protocol::LocalFilePath {
 }
 
 lazy_static! {
-    // TODO: for some reason, if we allow HTTP2, requests hang when making
-    // multiple parallel requests (e.g. when loading a model)
-    // This is likely a bug within reqwest or something it uses under the hood
-    static ref CLIENT: reqwest::Client = {
+    pub(crate) static ref CLIENT: reqwest::Client = {
+        // This applies the TLS and proxy settings from the carton config
         #[cfg(not(target_family = "wasm"))]
-        return reqwest::ClientBuilder::new()
-            .http1_only()
-            .use_rustls_tls()
-            .build()
-            .unwrap();
+        return carton_utils::download::client_builder().build().unwrap();
 
         #[cfg(target_family = "wasm")]
         return reqwest::Client::new();
@@ -448,3 +1040,84 @@ impl GetReader for protocol::HttpURL {
             .unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_registry_ref;
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn test_untested_runner() {
+        use super::{untested_runner, LaunchedRunner};
+
+        let installed = |framework_version: &str| {
+            LaunchedRunner::Installed(carton_runner_packager::discovery::RunnerInfo {
+                runner_name: "torchscript".into(),
+                framework_version: semver::Version::parse(framework_version).unwrap(),
+                runner_compat_version: 1,
+                runner_interface_version: 1,
+                runner_release_date: chrono::Utc::now(),
+                runner_path: "/runner".into(),
+                platform: target_lexicon::HOST.to_string(),
+            })
+        };
+
+        let declared = semver::VersionReq::parse("=2.0.1").unwrap();
+        assert!(untested_runner(&declared, &installed("2.0.1")).is_none());
+        assert!(untested_runner(&declared, &installed("2.1.0")).is_some());
+
+        // We don't know the framework version of an override so we can't warn about it
+        let runner = LaunchedRunner::Override;
+        assert!(untested_runner(&declared, &runner).is_none());
+    }
+
+    #[test]
+    fn test_parse_ipfs_url() {
+        use super::{parse_protocol, LocatorWithProtocol};
+
+        for (input, expected) in [
+            ("ipfs://somecid", "somecid"),
+            ("ipfs://somecid/", "somecid"),
+            (
+                "ipfs://QmCaseSensitive/path/to/model.carton",
+                "QmCaseSensitive/path/to/model.carton",
+            ),
+            ("IPFS://somecid/model.carton", "somecid/model.carton"),
+            ("ipfs:somecid/model.carton", "somecid/model.carton"),
+            ("ipfs:x", "x"),
+            ("ipfs:", ""),
+        ] {
+            match parse_protocol(input) {
+                LocatorWithProtocol::IpfsURL(url) => assert_eq!(url.0, expected, "{input}"),
+                _ => panic!("Expected {input} to be parsed as an IPFS URL"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_registry_ref() {
+        assert_eq!(
+            parse_registry_ref("google-research/bert-base-uncased"),
+            Some(("google-research/bert-base-uncased", None))
+        );
+        assert_eq!(
+            parse_registry_ref("org/model@1.0.0+abc"),
+            Some(("org/model", Some("1.0.0+abc")))
+        );
+
+        // URLs and things that look like paths aren't registry references
+        for input in [
+            "https://carton.pub/org/model",
+            "ipfs://somecid",
+            "model.carton",
+            "./org/model",
+            "/org/model",
+            "org/model.carton",
+            "org/model/extra",
+            "org/model@",
+            "org/model@1.0/2",
+        ] {
+            assert_eq!(parse_registry_ref(input), None, "{input}");
+        }
+    }
+}
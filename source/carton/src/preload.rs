@@ -0,0 +1,65 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Load many cartons at once (e.g. to warm up a model server)
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::StreamExt;
+use runner_interface_v1::slowlog::slowlog;
+
+use crate::{error::Result, types::LoadOpts, Carton};
+
+/// Load several cartons concurrently with at most `concurrency` loads in flight at a time.
+///
+/// Returns one result per url (in the same order as `urls`). A failure to load one carton does not
+/// affect the others. Progress across all the loads is logged periodically.
+///
+/// All the loads share the same download cache so runners and files required by multiple cartons
+/// are only downloaded once.
+pub async fn preload(urls: Vec<String>, concurrency: usize, opts: LoadOpts) -> Vec<Result<Carton>> {
+    let total = urls.len();
+    let completed = AtomicUsize::new(0);
+
+    let mut sl = slowlog(format!("Preloading {total} cartons"), 5).await;
+    sl.set_total(Some(total));
+    sl.set_progress(Some(0));
+
+    let out = futures::stream::iter(urls)
+        .map(|url| {
+            let opts = opts.clone();
+            let completed = &completed;
+            let sl = &sl;
+            async move {
+                let res = Carton::load(&url, opts).await;
+                let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                sl.set_progress(Some(done));
+
+                match &res {
+                    Ok(_) => log::info!("Preloaded '{url}' ({done} / {total})"),
+                    Err(e) => log::warn!("Failed to preload '{url}' ({done} / {total}): {e}"),
+                }
+
+                res
+            }
+        })
+        // `buffered` keeps the results in the same order as `urls`
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    sl.done();
+
+    out
+}
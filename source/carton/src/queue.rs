@@ -0,0 +1,421 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A queue that limits the number of inference requests in flight to a runner and decides which
+//! waiting request goes next. See `Carton::with_queue` for more details
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::oneshot;
+
+/// How a queue picks the next request to send to the runner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueuePolicy {
+    /// Requests are sent in the order they arrive regardless of which `Carton` they came from
+    #[default]
+    Fifo,
+
+    /// Each `Carton` gets a share of the runner proportional to its weight (see `Carton::with_queue_weight`)
+    /// so a burst of requests from one `Carton` doesn't delay the others. Requests from the same `Carton`
+    /// are sent in the order they arrive
+    Weighted,
+
+    /// Requests from the `Carton` with the highest weight are always sent first. Requests with the same
+    /// weight are sent in the order they arrive. Note: this can starve `Carton`s with low weights
+    Priority,
+}
+
+/// Options for `Carton::with_queue`
+#[derive(Debug, Clone)]
+pub struct QueueOpts {
+    /// How to pick the next request. Defaults to `QueuePolicy::Fifo`
+    pub policy: QueuePolicy,
+
+    /// The max number of requests that can be in flight to the runner at once. Other requests wait in the
+    /// queue. Defaults to 4
+    pub max_in_flight: usize,
+}
+
+impl Default for QueueOpts {
+    fn default() -> Self {
+        Self {
+            policy: QueuePolicy::default(),
+            max_in_flight: 4,
+        }
+    }
+}
+
+/// A snapshot of the state of a queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueStats {
+    /// The number of requests waiting in the queue (from all `Carton`s that share it)
+    pub queued: usize,
+
+    /// The number of requests waiting in the queue from this `Carton`
+    pub queued_by_this_carton: usize,
+
+    /// The number of requests currently being processed by the runner
+    pub in_flight: usize,
+}
+
+/// The amount of virtual time a request from a caller with weight 1 takes (in `QueuePolicy::Weighted`)
+const STRIDE: u64 = 1 << 20;
+
+struct Caller {
+    weight: u32,
+
+    /// The virtual time at which this caller's next request should go (for `QueuePolicy::Weighted`)
+    pass: u64,
+
+    /// Requests from this caller that are waiting along with their arrival order
+    waiting: VecDeque<(u64, oneshot::Sender<()>)>,
+}
+
+struct State {
+    in_flight: usize,
+    queued: usize,
+
+    /// Used to order requests by arrival
+    next_seq: u64,
+
+    /// The pass of the most recently dispatched request (for `QueuePolicy::Weighted`)
+    virtual_time: u64,
+
+    next_caller_id: u64,
+    callers: HashMap<u64, Caller>,
+}
+
+pub(crate) struct InferenceQueue {
+    opts: QueueOpts,
+    state: Mutex<State>,
+}
+
+impl InferenceQueue {
+    pub(crate) fn new(opts: QueueOpts) -> Arc<Self> {
+        Arc::new(Self {
+            opts: QueueOpts {
+                max_in_flight: opts.max_in_flight.max(1),
+                ..opts
+            },
+            state: Mutex::new(State {
+                in_flight: 0,
+                queued: 0,
+                next_seq: 0,
+                virtual_time: 0,
+                next_caller_id: 0,
+                callers: HashMap::new(),
+            }),
+        })
+    }
+
+    /// Register a new caller. The caller is removed when the returned value is dropped
+    pub(crate) fn register(self: &Arc<Self>, weight: u32) -> QueueCaller {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_caller_id;
+        state.next_caller_id += 1;
+        state.callers.insert(
+            id,
+            Caller {
+                weight: weight.max(1),
+                pass: 0,
+                waiting: VecDeque::new(),
+            },
+        );
+
+        QueueCaller {
+            queue: self.clone(),
+            id,
+        }
+    }
+
+    fn stats(&self, caller: u64) -> QueueStats {
+        let state = self.state.lock().unwrap();
+        QueueStats {
+            queued: state.queued,
+            queued_by_this_carton: state.callers.get(&caller).map_or(0, |c| c.waiting.len()),
+            in_flight: state.in_flight,
+        }
+    }
+
+    /// Send waiting requests until we hit `max_in_flight` or run out of requests
+    fn dispatch(&self, state: &mut State) {
+        while state.in_flight < self.opts.max_in_flight {
+            let caller_id = match self.pick(state) {
+                Some(id) => id,
+                None => return,
+            };
+
+            let caller = state.callers.get_mut(&caller_id).unwrap();
+            let (_, tx) = caller.waiting.pop_front().unwrap();
+            state.virtual_time = state.virtual_time.max(caller.pass);
+            caller.pass += STRIDE / caller.weight as u64;
+            state.queued -= 1;
+
+            // The request may have been cancelled while it was waiting
+            if tx.send(()).is_ok() {
+                state.in_flight += 1;
+            }
+        }
+    }
+
+    /// Pick the caller whose request should go next
+    fn pick(&self, state: &State) -> Option<u64> {
+        let waiting = state
+            .callers
+            .iter()
+            .filter_map(|(id, c)| c.waiting.front().map(|(seq, _)| (*id, c, *seq)));
+
+        match self.opts.policy {
+            QueuePolicy::Fifo => waiting.min_by_key(|(_, _, seq)| *seq),
+            QueuePolicy::Weighted => waiting.min_by_key(|(_, c, seq)| (c.pass, *seq)),
+            QueuePolicy::Priority => {
+                waiting.min_by_key(|(_, c, seq)| (std::cmp::Reverse(c.weight), *seq))
+            }
+        }
+        .map(|(id, _, _)| id)
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight -= 1;
+        self.dispatch(&mut state);
+    }
+}
+
+/// A handle for a caller of a queue (i.e. one `Carton`)
+pub(crate) struct QueueCaller {
+    queue: Arc<InferenceQueue>,
+    id: u64,
+}
+
+impl QueueCaller {
+    /// Register another caller with the same weight on the same queue
+    pub(crate) fn duplicate(&self) -> QueueCaller {
+        let weight = self.queue.state.lock().unwrap().callers[&self.id].weight;
+        self.queue.register(weight)
+    }
+
+    /// Register another caller on the same queue
+    pub(crate) fn with_weight(&self, weight: u32) -> QueueCaller {
+        self.queue.register(weight)
+    }
+
+    pub(crate) fn stats(&self) -> QueueStats {
+        self.queue.stats(self.id)
+    }
+
+    /// Wait for our turn. The request is in flight until the returned permit is dropped
+    pub(crate) async fn acquire(&self) -> QueuePermit {
+        let (seq, rx) = {
+            let mut state = self.queue.state.lock().unwrap();
+
+            // Go straight to the runner if we can
+            if state.queued == 0 && state.in_flight < self.queue.opts.max_in_flight {
+                state.in_flight += 1;
+                return QueuePermit {
+                    queue: self.queue.clone(),
+                };
+            }
+
+            let (tx, rx) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.queued += 1;
+
+            // Callers that were idle don't get to "save up" their share
+            let virtual_time = state.virtual_time;
+            let caller = state.callers.get_mut(&self.id).unwrap();
+            if caller.waiting.is_empty() {
+                caller.pass = caller.pass.max(virtual_time);
+            }
+            caller.waiting.push_back((seq, tx));
+
+            // In case something freed up since the last dispatch
+            self.queue.dispatch(&mut state);
+
+            (seq, rx)
+        };
+
+        let mut waiting = Waiting {
+            rx,
+            seq,
+            caller: self.id,
+            queue: self.queue.clone(),
+        };
+
+        // `dispatch` never drops the sender without sending so this can't fail
+        (&mut waiting.rx).await.unwrap();
+
+        QueuePermit {
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl Drop for QueueCaller {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        if let Some(caller) = state.callers.remove(&self.id) {
+            // Any waiting requests have already been cancelled (they borrow the `Carton` that owns this)
+            state.queued -= caller.waiting.len();
+        }
+    }
+}
+
+/// A request that's waiting in the queue
+struct Waiting {
+    rx: oneshot::Receiver<()>,
+    seq: u64,
+    caller: u64,
+    queue: Arc<InferenceQueue>,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        // `dispatch` only sends while holding the lock so this can't race with it
+        let mut state = self.queue.state.lock().unwrap();
+        match self.rx.try_recv() {
+            // We were cancelled right after being dispatched so give our slot to the next request
+            Ok(()) => {
+                state.in_flight -= 1;
+                self.queue.dispatch(&mut state);
+            }
+
+            // We were cancelled while waiting so leave the queue
+            Err(oneshot::error::TryRecvError::Empty) => {
+                let seq = self.seq;
+                if let Some(caller) = state.callers.get_mut(&self.caller) {
+                    let before = caller.waiting.len();
+                    caller.waiting.retain(|(s, _)| *s != seq);
+                    let removed = before - caller.waiting.len();
+                    state.queued -= removed;
+                }
+            }
+
+            // We already got our permit (or were removed from the queue)
+            Err(oneshot::error::TryRecvError::Closed) => {}
+        }
+    }
+}
+
+/// A request that's in flight to the runner
+pub(crate) struct QueuePermit {
+    queue: Arc<InferenceQueue>,
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::{InferenceQueue, QueueCaller, QueueOpts, QueuePolicy, QueueStats};
+
+    /// Wait for our turn and record it
+    async fn request(order: &Mutex<Vec<&'static str>>, name: &'static str, caller: &QueueCaller) {
+        let _permit = caller.acquire().await;
+        order.lock().unwrap().push(name);
+    }
+
+    /// Queue three requests from `a` and then one from `b` while the queue is full and return the
+    /// order in which they're sent
+    async fn dispatch_order(policy: QueuePolicy, b_weight: u32) -> Vec<&'static str> {
+        let queue = InferenceQueue::new(QueueOpts {
+            policy,
+            max_in_flight: 1,
+        });
+        let a = queue.register(1);
+        let b = queue.register(b_weight);
+
+        let first = a.acquire().await;
+
+        // `join_all` polls the requests in order so they're queued in order
+        let order = Mutex::new(Vec::new());
+        let requests = futures::future::join_all([
+            request(&order, "a", &a),
+            request(&order, "a", &a),
+            request(&order, "a", &a),
+            request(&order, "b", &b),
+        ]);
+
+        tokio::join!(requests, async {
+            tokio::task::yield_now().await;
+            drop(first);
+        });
+
+        order.into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_policies() {
+        assert_eq!(
+            dispatch_order(QueuePolicy::Fifo, 1).await,
+            ["a", "a", "a", "b"]
+        );
+        assert_eq!(
+            dispatch_order(QueuePolicy::Weighted, 1).await,
+            ["a", "b", "a", "a"]
+        );
+        assert_eq!(
+            dispatch_order(QueuePolicy::Priority, 2).await,
+            ["b", "a", "a", "a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_cancellation() {
+        let queue = InferenceQueue::new(QueueOpts {
+            policy: QueuePolicy::Fifo,
+            max_in_flight: 1,
+        });
+        let a = queue.register(1);
+        let b = queue.register(1);
+
+        let first = a.acquire().await;
+
+        let mut waiting = Box::pin(b.acquire());
+        assert!(futures::poll!(&mut waiting).is_pending());
+        assert_eq!(
+            b.stats(),
+            QueueStats {
+                queued: 1,
+                queued_by_this_carton: 1,
+                in_flight: 1,
+            }
+        );
+        assert_eq!(a.stats().queued_by_this_carton, 0);
+
+        // Cancelling a waiting request removes it from the queue
+        drop(waiting);
+        assert_eq!(b.stats().queued, 0);
+
+        // Cancelling a request after it's dispatched frees its slot
+        let mut waiting = Box::pin(b.acquire());
+        assert!(futures::poll!(&mut waiting).is_pending());
+        drop(first);
+        assert_eq!(b.stats().in_flight, 1);
+        drop(waiting);
+        assert_eq!(b.stats().in_flight, 0);
+
+        let _permit = a.acquire().await;
+        assert_eq!(a.stats().in_flight, 1);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retry inference requests that fail with transient errors (see `Carton::with_retries`)
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use crate::{
+    error::Result,
+    types::{LoadOpts, Tensor},
+    Carton,
+};
+
+/// Options for `Carton::with_retries`
+#[derive(Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of times to retry a request after the first attempt fails. Only retryable
+    /// errors are retried (see `CartonError::is_retryable`)
+    pub max_retries: u32,
+
+    /// How long to wait before each retry
+    pub delay: Duration,
+
+    /// If set, the model is reloaded from this url or path (with these options) before each retry.
+    /// This launches a new runner so it can recover from a runner that's in a bad state. Later requests
+    /// use the reloaded model
+    pub reload_from: Option<(String, LoadOpts)>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            delay: Duration::from_millis(100),
+            reload_from: None,
+        }
+    }
+}
+
+/// A wrapper around a `Carton` that retries failed inference requests. See `Carton::with_retries`
+pub struct RetryingCarton {
+    carton: RwLock<Arc<Carton>>,
+    policy: RetryPolicy,
+}
+
+impl RetryingCarton {
+    pub(crate) fn new(carton: Carton, policy: RetryPolicy) -> Self {
+        Self {
+            carton: RwLock::new(Arc::new(carton)),
+            policy,
+        }
+    }
+
+    /// Infer using a set of inputs, retrying according to the policy if the request fails with a
+    /// retryable error. If the policy reloads the model and reloading fails, that counts as a failed
+    /// attempt and is retried as well. Returns the error from the last inference attempt if none of
+    /// them succeed
+    pub async fn infer<I, S>(&self, tensors: I) -> Result<HashMap<String, Tensor>>
+    where
+        I: IntoIterator<Item = (S, Tensor)>,
+        String: From<S>,
+    {
+        // Keep the inputs around so we can send them again
+        let tensors: HashMap<String, Tensor> =
+            tensors.into_iter().map(|(k, v)| (k.into(), v)).collect();
+
+        let mut attempt = 0;
+        let mut last_err = None;
+        loop {
+            if let Some(e) = last_err.take() {
+                tokio::time::sleep(self.policy.delay).await;
+
+                if let Some((url_or_path, opts)) = &self.policy.reload_from {
+                    match Carton::load(url_or_path, opts.clone()).await {
+                        Ok(carton) => *self.carton.write().unwrap() = Arc::new(carton),
+                        Err(load_err) if attempt < self.policy.max_retries => {
+                            attempt += 1;
+                            log::warn!(
+                                "Reloading the model failed. Retrying ({attempt} / {}): {load_err}",
+                                self.policy.max_retries
+                            );
+                            last_err = Some(e);
+                            continue;
+                        }
+                        Err(load_err) => {
+                            log::warn!("Reloading the model failed: {load_err}");
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+
+            let res = self.carton().infer_ref::<_, &String>(&tensors).await;
+            match res {
+                Err(e) if e.is_retryable() && attempt < self.policy.max_retries => {
+                    attempt += 1;
+                    log::warn!(
+                        "Inference failed with a retryable error. Retrying ({attempt} / {}): {e}",
+                        self.policy.max_retries
+                    );
+                    last_err = Some(e);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// The carton requests are currently sent to. This changes when the model is reloaded
+    pub fn carton(&self) -> Arc<Carton> {
+        self.carton.read().unwrap().clone()
+    }
+}
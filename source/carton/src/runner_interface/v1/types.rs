@@ -15,9 +15,10 @@
 //! Implements type conversions between carton::types and carton-runner-interface types for V1 of the runner interface
 
 use crate::conversion_utils::convert_vec;
-use carton_macros::for_each_carton_type;
+use carton_macros::{for_each_carton_type, for_each_numeric_or_complex_carton_type};
 
-use crate::types::{Device, RunnerOpt, Tensor, TypedStorage};
+use crate::error::CartonError;
+use crate::types::{string_bytes, Device, DeviceMemory, RunnerOpt, Tensor, TypedStorage};
 
 impl From<Device> for runner_interface_v1::types::Device {
     fn from(value: Device) -> Self {
@@ -39,15 +40,45 @@ impl From<RunnerOpt> for runner_interface_v1::types::RunnerOpt {
     }
 }
 
+impl From<runner_interface_v1::types::DeviceMemory> for DeviceMemory {
+    fn from(value: runner_interface_v1::types::DeviceMemory) -> Self {
+        Self {
+            allocated_bytes: value.allocated_bytes,
+            reserved_bytes: value.reserved_bytes,
+        }
+    }
+}
+
+impl From<runner_interface_v1::error::RunnerError> for CartonError {
+    fn from(value: runner_interface_v1::error::RunnerError) -> Self {
+        match value {
+            runner_interface_v1::error::RunnerError::Disconnected => Self::RunnerDisconnected,
+            runner_interface_v1::error::RunnerError::Message(e) => Self::ErrorFromRunner(e),
+            runner_interface_v1::error::RunnerError::OutOfMemory(e) => Self::OutOfMemory(e),
+        }
+    }
+}
+
 // Implement conversions between tensor types
 for_each_carton_type! {
     impl From<Tensor> for runner_interface_v1::types::Tensor {
         fn from(value: Tensor) -> Self {
+            (&value).into()
+        }
+    }
+
+    // This copies the data out of the view so we don't need ownership of the tensor
+    impl From<&Tensor> for runner_interface_v1::types::Tensor {
+        fn from(value: &Tensor) -> Self {
             match value {
                 $(
                     Tensor::$CartonType(v) => Self::$CartonType(v.view().into()),
                 )*
-                Tensor::NestedTensor(v) => Self::NestedTensor(convert_vec(v)),
+                Tensor::NestedTensor(v) => Self::NestedTensor(v.iter().map(|t| t.into()).collect()),
+                Tensor::LabeledNestedTensor { tensors, labels } => Self::LabeledNestedTensor {
+                    tensors: tensors.iter().map(|t| t.into()).collect(),
+                    labels: labels.clone(),
+                },
             }
         }
     }
@@ -60,6 +91,26 @@ for_each_carton_type! {
                     runner_interface_v1::types::Tensor::$CartonType(v) => Self::$CartonType(v.into()),
                 )*
                 runner_interface_v1::types::Tensor::NestedTensor(v) => Self::NestedTensor(convert_vec(v)),
+                runner_interface_v1::types::Tensor::LabeledNestedTensor { tensors, labels } => Self::LabeledNestedTensor {
+                    tensors: convert_vec(tensors),
+                    labels,
+                },
+            }
+        }
+    }
+}
+
+for_each_numeric_or_complex_carton_type! {
+    /// The same as `Tensor::estimated_bytes`, but for tensors that have already been converted for the runner
+    pub(crate) fn estimated_bytes(value: &runner_interface_v1::types::Tensor) -> u64 {
+        match value {
+            $(
+                runner_interface_v1::types::Tensor::$CartonType(v) => (v.view().len() * std::mem::size_of::<$RustType>()) as u64,
+            )*
+            runner_interface_v1::types::Tensor::String(v) => string_bytes(v.view().iter()),
+            runner_interface_v1::types::Tensor::NestedTensor(tensors)
+            | runner_interface_v1::types::Tensor::LabeledNestedTensor { tensors, .. } => {
+                tensors.iter().map(estimated_bytes).sum()
             }
         }
     }
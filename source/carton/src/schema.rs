@@ -0,0 +1,288 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export the inputs and outputs of a model as a JSON Schema (e.g. to validate requests or generate
+//! API docs in a serving layer). See `Carton::infer_schema`
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::info::{CartonInfo, DataType, Dimension, Shape, TensorSpec};
+
+/// The JSON Schema version of the generated schemas
+pub const SCHEMA_VERSION: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Build a JSON Schema describing the inputs and outputs of `infer` for a model.
+///
+/// Tensors are represented as nested JSON arrays (with one level of nesting per dimension) of
+/// numbers, integers, or strings. Complex numbers are `[real, imaginary]` pairs. The schema has an
+/// `inputs` and an `outputs` property. If the model doesn't declare its inputs (or outputs), the
+/// corresponding property is an unconstrained object.
+///
+/// JSON Schema can't express that two dimensions must have the same size so symbols (e.g. `batch_size`)
+/// are listed in the `x-carton-parameters` keyword and each dimension that uses one is marked with
+/// `x-carton-symbol`. Tensors with an `Any` (or symbolic) shape are unconstrained arrays.
+/// Every tensor also has an `x-carton-dtype` keyword with its carton dtype (e.g. `float32`)
+pub fn infer_schema(info: &CartonInfo) -> Value {
+    let mut parameters = BTreeMap::new();
+
+    let mut out = Map::new();
+    out.insert("$schema".into(), SCHEMA_VERSION.into());
+    if let Some(name) = &info.model_name {
+        out.insert("title".into(), name.as_str().into());
+    }
+    if let Some(description) = &info.short_description {
+        out.insert("description".into(), description.as_str().into());
+    }
+
+    out.insert("type".into(), "object".into());
+    out.insert(
+        "properties".into(),
+        json!({
+            "inputs": tensors_schema(info.inputs.as_deref(), true, &mut parameters),
+            "outputs": tensors_schema(info.outputs.as_deref(), false, &mut parameters),
+        }),
+    );
+    out.insert("required".into(), json!(["inputs"]));
+
+    if !parameters.is_empty() {
+        out.insert(
+            "x-carton-parameters".into(),
+            parameters
+                .into_iter()
+                .map(|(name, kind)| (name, json!({ "kind": kind })))
+                .collect::<Map<_, _>>()
+                .into(),
+        );
+    }
+
+    out.into()
+}
+
+/// The schema of a map of tensors (i.e. the inputs or outputs of a model)
+fn tensors_schema(
+    specs: Option<&[TensorSpec]>,
+    is_input: bool,
+    parameters: &mut BTreeMap<String, &'static str>,
+) -> Value {
+    let specs = match specs {
+        Some(specs) => specs,
+        None => return json!({ "type": "object" }),
+    };
+
+    let properties: Map<_, _> = specs
+        .iter()
+        .map(|spec| (spec.name.clone(), tensor_schema(spec, parameters)))
+        .collect();
+
+    // Optional inputs can be omitted. Outputs are always returned
+    let required: Vec<_> = specs
+        .iter()
+        .filter(|spec| !(is_input && spec.optional))
+        .map(|spec| spec.name.as_str())
+        .collect();
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn tensor_schema(spec: &TensorSpec, parameters: &mut BTreeMap<String, &'static str>) -> Value {
+    let mut out = match &spec.shape {
+        Shape::Any => json!({ "type": "array" }),
+        Shape::Symbol(symbol) => {
+            parameters.insert(symbol.clone(), "shape");
+            json!({ "type": "array", "x-carton-symbol": symbol })
+        }
+        Shape::Shape(dims) => {
+            // Build the schema from the innermost dimension out
+            let mut schema = element_schema(&spec.dtype);
+            for dim in dims.iter().rev() {
+                let mut array = json!({ "type": "array", "items": schema });
+                match dim {
+                    Dimension::Value(v) => {
+                        array["minItems"] = (*v).into();
+                        array["maxItems"] = (*v).into();
+                    }
+                    Dimension::Range { min, max } => {
+                        if let Some(min) = min {
+                            array["minItems"] = (*min).into();
+                        }
+                        if let Some(max) = max {
+                            array["maxItems"] = (*max).into();
+                        }
+                    }
+                    Dimension::Symbol(symbol) => {
+                        parameters.insert(symbol.clone(), "dimension");
+                        array["x-carton-symbol"] = symbol.as_str().into();
+                    }
+                    Dimension::Any => {}
+                }
+
+                schema = array;
+            }
+
+            schema
+        }
+    };
+
+    out["x-carton-dtype"] = spec.dtype.to_str().into();
+    if let Some(description) = &spec.description {
+        out["description"] = description.as_str().into();
+    }
+
+    out
+}
+
+/// The schema of a single element of a tensor
+fn element_schema(dtype: &DataType) -> Value {
+    match dtype {
+        DataType::Float | DataType::Double => json!({ "type": "number" }),
+        DataType::String => json!({ "type": "string" }),
+        DataType::I8 => integer_schema(i8::MIN as i64, i8::MAX as u64),
+        DataType::I16 => integer_schema(i16::MIN as i64, i16::MAX as u64),
+        DataType::I32 => integer_schema(i32::MIN as i64, i32::MAX as u64),
+        DataType::I64 => integer_schema(i64::MIN, i64::MAX as u64),
+        DataType::U8 => integer_schema(0, u8::MAX as u64),
+        DataType::U16 => integer_schema(0, u16::MAX as u64),
+        DataType::U32 => integer_schema(0, u32::MAX as u64),
+        DataType::U64 => integer_schema(0, u64::MAX),
+        DataType::Complex64 | DataType::Complex128 => json!({
+            "type": "array",
+            "items": { "type": "number" },
+            "minItems": 2,
+            "maxItems": 2,
+        }),
+    }
+}
+
+fn integer_schema(min: i64, max: u64) -> Value {
+    json!({ "type": "integer", "minimum": min, "maximum": max })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::info::{CartonInfo, DataType, Dimension, RunnerInfo, Shape, TensorSpec};
+
+    fn spec(name: &str, dtype: DataType, shape: Shape) -> TensorSpec {
+        TensorSpec {
+            name: name.into(),
+            dtype,
+            shape,
+            description: None,
+            internal_name: None,
+            optional: false,
+            default: None,
+        }
+    }
+
+    #[test]
+    fn test_infer_schema() {
+        let mut x = spec(
+            "x",
+            DataType::Float,
+            Shape::Shape(vec![
+                Dimension::Symbol("batch_size".into()),
+                Dimension::Value(3),
+            ]),
+        );
+        x.description = Some("An input".into());
+
+        let mut mask = spec(
+            "mask",
+            DataType::U8,
+            Shape::Shape(vec![Dimension::Range {
+                min: Some(1),
+                max: None,
+            }]),
+        );
+        mask.optional = true;
+
+        let info = CartonInfo {
+            model_name: Some("test".into()),
+            short_description: None,
+            model_description: None,
+            license: None,
+            repository: None,
+            homepage: None,
+            required_platforms: None,
+            inputs: Some(vec![
+                x,
+                mask,
+                spec("prompt", DataType::String, Shape::Shape(vec![])),
+            ]),
+            outputs: Some(vec![spec("y", DataType::Complex64, Shape::Any)]),
+            self_tests: None,
+            examples: None,
+            runner: RunnerInfo {
+                runner_name: "noop".into(),
+                required_framework_version: semver::VersionReq::parse("*").unwrap(),
+                runner_compat_version: None,
+                opts: None,
+            },
+            misc_files: None,
+        };
+
+        let schema = super::infer_schema(&info);
+        assert_eq!(schema["title"], "test");
+        assert_eq!(
+            schema["x-carton-parameters"],
+            json!({ "batch_size": { "kind": "dimension" } })
+        );
+
+        let inputs = &schema["properties"]["inputs"];
+        assert_eq!(inputs["required"], json!(["x", "prompt"]));
+        assert_eq!(
+            inputs["properties"]["x"],
+            json!({
+                "type": "array",
+                "x-carton-symbol": "batch_size",
+                "items": {
+                    "type": "array",
+                    "minItems": 3,
+                    "maxItems": 3,
+                    "items": { "type": "number" },
+                },
+                "x-carton-dtype": "float32",
+                "description": "An input",
+            })
+        );
+        assert_eq!(
+            inputs["properties"]["mask"],
+            json!({
+                "type": "array",
+                "minItems": 1,
+                "items": { "type": "integer", "minimum": 0, "maximum": 255 },
+                "x-carton-dtype": "uint8",
+            })
+        );
+        assert_eq!(
+            inputs["properties"]["prompt"],
+            json!({ "type": "string", "x-carton-dtype": "string" })
+        );
+
+        let outputs = &schema["properties"]["outputs"];
+        assert_eq!(outputs["required"], json!(["y"]));
+        assert_eq!(
+            outputs["properties"]["y"],
+            json!({ "type": "array", "x-carton-dtype": "complex64" })
+        );
+    }
+}
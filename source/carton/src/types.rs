@@ -12,16 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-pub use carton_macros::{for_each_carton_type, for_each_numeric_carton_type};
+pub use carton_macros::{
+    for_each_carton_type, for_each_numeric_carton_type, for_each_numeric_or_complex_carton_type,
+};
+pub use runner_interface_v1::shape_numel;
 use serde::{de::Visitor, Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::{error::CartonError, info::PossiblyLoaded};
+
 /// An opaque handle returned by `seal`
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct SealHandle(pub(crate) u64);
 
+/// An output returned by `infer_lazy`. It's only fetched from the runner the first time `get` is called
+#[derive(Clone)]
+pub struct LazyTensor {
+    // The error from the runner if fetching the output failed
+    pub(crate) inner: PossiblyLoaded<Result<Tensor, String>>,
+}
+
+impl LazyTensor {
+    /// Get the tensor (fetching it from the runner if necessary). If fetching fails (e.g. because the
+    /// runner crashed), this returns `CartonError::ErrorFromRunner` (as do later calls)
+    pub async fn get(&self) -> crate::error::Result<&Tensor> {
+        self.inner
+            .get()
+            .await
+            .as_ref()
+            .map_err(|e| CartonError::ErrorFromRunner(e.clone()))
+    }
+}
+
 /// Options provided when loading a Carton
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LoadOpts {
     /// Override the runner to use
     /// If not overridden, this is fetched from the carton metadata
@@ -44,6 +68,91 @@ pub struct LoadOpts {
     /// will use that device; it is up to the model to actually use it
     /// (e.g. by moving itself to GPU if it sees one available)
     pub visible_device: Device,
+
+    /// The max number of responses from `streaming_infer` to buffer before pausing the runner.
+    /// If not set, this defaults to 16
+    pub streaming_buffer_size: Option<usize>,
+
+    /// Whether to automatically fetch and install the required runner if a matching one isn't
+    /// already installed. If this is false and no matching runner is installed, loading fails with
+    /// `CartonError::RunnerNotInstalled`. Defaults to true
+    #[serde(default = "default_auto_install_runner")]
+    pub auto_install_runner: bool,
+
+    /// The max total uncompressed size (in bytes) of the files in a packed carton. Loading fails with
+    /// `CartonError::ArchiveTooLarge` if the sizes listed in the archive exceed this. The limit is also
+    /// enforced on the bytes actually decompressed (in case the listed sizes are wrong); reads past it fail
+    /// with an IO error. This protects against decompression bombs when loading untrusted models.
+    /// If not set, this defaults to 128 GiB
+    pub max_archive_size: Option<u64>,
+
+    /// The max uncompressed size (in bytes) of a single file in a packed carton.
+    /// If not set, this defaults to 32 GiB
+    pub max_archive_file_size: Option<u64>,
+
+    /// The expected sha256 of the carton's MANIFEST (as a hex string). If this is set, loading
+    /// fails with `CartonError::ManifestHashMismatch` before launching a runner if the carton
+    /// doesn't match. Every file is also checked against the sha256 listed in the `MANIFEST`
+    /// (`CartonError::FileHashMismatch`) so the whole carton is read before the runner starts.
+    /// Useful when loading from untrusted sources (e.g. public IPFS gateways)
+    pub expected_manifest_sha256: Option<String>,
+
+    /// Launch this runner binary directly instead of finding (or installing) a runner that matches
+    /// the carton. Useful when developing a runner. If this isn't set, the
+    /// `CARTON_RUNNER_OVERRIDE_{RUNNER_NAME}` env var is checked (e.g. `CARTON_RUNNER_OVERRIDE_TORCHSCRIPT`)
+    pub runner_path_override: Option<std::path::PathBuf>,
+
+    /// A hex-encoded ed25519 public key. If this is set, loading fails with
+    /// `CartonError::SignatureVerificationFailed` before launching a runner unless the carton has a
+    /// `SIGNATURE` made with the matching secret key (see `PackOpts::signing_key`).
+    /// The signature covers `MANIFEST` so every file is also checked against the sha256 it lists
+    /// (`CartonError::FileHashMismatch`). This means the whole carton is read before the runner starts
+    pub signature_public_key: Option<String>,
+
+    /// Environment variables to set for the runner process (e.g. `OMP_NUM_THREADS` or `HF_HOME`).
+    /// These are merged with the environment of the current process and take precedence over it.
+    /// `CUDA_VISIBLE_DEVICES` is always set based on `visible_device`
+    #[serde(default)]
+    pub runner_env: HashMap<String, String>,
+
+    /// Start downloading the linked files of a carton (e.g. model weights stored at a URL) in the
+    /// background as soon as its metadata is read. This overlaps the download with finding,
+    /// installing, and launching the runner. Reads of a file that's still downloading wait for the
+    /// download to finish. If the download fails, the file is fetched again when it's read.
+    /// Ignored on wasm. Defaults to false
+    #[serde(default)]
+    pub prefetch_linked_files: bool,
+
+    /// A seed for the random number generators of the runner's framework (e.g. `torch.manual_seed`).
+    /// This makes stochastic models (e.g. generative models) reproducible, which is useful when
+    /// comparing outputs against `expected_out` in self tests. If this isn't set, runners don't seed
+    /// anything. See the docs for the runners that support it
+    pub seed: Option<u64>,
+}
+
+fn default_auto_install_runner() -> bool {
+    true
+}
+
+impl Default for LoadOpts {
+    fn default() -> Self {
+        Self {
+            override_runner_name: None,
+            override_required_framework_version: None,
+            override_runner_opts: None,
+            visible_device: Default::default(),
+            streaming_buffer_size: None,
+            auto_install_runner: default_auto_install_runner(),
+            max_archive_size: None,
+            max_archive_file_size: None,
+            expected_manifest_sha256: None,
+            runner_path_override: None,
+            signature_public_key: None,
+            runner_env: HashMap::new(),
+            prefetch_linked_files: false,
+            seed: None,
+        }
+    }
 }
 
 /// The types of options that can be passed to runners
@@ -61,6 +170,17 @@ pub enum Device {
     },
 }
 
+/// The amount of device (e.g. GPU) memory used by a loaded model. See `Carton::device_memory_usage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceMemory {
+    /// The number of bytes used by tensors (e.g. the weights of the model)
+    pub allocated_bytes: u64,
+
+    /// The number of bytes the framework is holding on to (e.g. in a caching allocator). This includes
+    /// `allocated_bytes`. `None` if the runner can't tell
+    pub reserved_bytes: Option<u64>,
+}
+
 /// Default to the first visible GPU (if any)
 impl Default for Device {
     #[cfg(not(target_family = "wasm"))]
@@ -88,7 +208,6 @@ impl Device {
     pub fn maybe_from_str(s: &str) -> crate::error::Result<Self> {
         // Check if it's an index
 
-        use crate::error::CartonError;
         if let Ok(index) = s.parse::<u32>() {
             return Ok(Self::maybe_from_index(index));
         }
@@ -167,7 +286,7 @@ pub type PackOpts = crate::info::PackOpts;
 
 pub type CartonInfo = crate::info::CartonInfo;
 
-for_each_numeric_carton_type! {
+for_each_numeric_or_complex_carton_type! {
     /// The core tensor type
     #[derive(Clone)]
     pub enum Tensor {
@@ -189,7 +308,16 @@ for_each_numeric_carton_type! {
         /// TensorFlow requires that the number of dimensions and the type of each contained tensor
         /// is the same:
         /// https://www.tensorflow.org/guide/ragged_tensor#what_you_can_store_in_a_ragged_tensor
-        NestedTensor(Vec<Tensor>)
+        NestedTensor(Vec<Tensor>),
+
+        /// A `NestedTensor` with a label for each contained tensor (e.g. the class of each set of
+        /// detections). `labels` has the same length as `tensors`.
+        ///
+        /// Use `Tensor::labeled_nested` to create one of these
+        LabeledNestedTensor {
+            tensors: Vec<Tensor>,
+            labels: Vec<String>,
+        },
     }
 }
 
@@ -217,21 +345,578 @@ impl Tensor {
     {
         GenericTensorStorage::from(item).into()
     }
+
+    /// Create a `LabeledNestedTensor`. There must be exactly one label for each tensor
+    pub fn labeled_nested(tensors: Vec<Tensor>, labels: Vec<String>) -> crate::error::Result<Self> {
+        if tensors.len() != labels.len() {
+            return Err(CartonError::InvalidShape(format!(
+                "Expected one label per tensor in a labeled nested tensor, but got {} tensors and {} labels",
+                tensors.len(),
+                labels.len()
+            )));
+        }
+
+        Ok(Self::LabeledNestedTensor { tensors, labels })
+    }
+
+    /// Returns true if this is a `NestedTensor` or a `LabeledNestedTensor`
+    pub fn is_nested(&self) -> bool {
+        matches!(
+            self,
+            Self::NestedTensor(_) | Self::LabeledNestedTensor { .. }
+        )
+    }
+
+    /// Create a string tensor with shape `shape` from strings in row-major (C) order. The number
+    /// of strings must match the number of elements in `shape`
+    ///
+    /// This accepts anything that can be borrowed as a `str` (e.g. `&str` or `String`)
+    pub fn from_strs<I, S>(shape: Vec<usize>, strs: I) -> crate::error::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let data = collect_strings(strs);
+        if shape.iter().try_fold(1usize, |acc, v| acc.checked_mul(*v)) != Some(data.len()) {
+            return Err(CartonError::InvalidShape(format!(
+                "Cannot create a string tensor with shape {shape:?} from {} strings",
+                data.len()
+            )));
+        }
+
+        Ok(Self::new(
+            ndarray::ArrayD::from_shape_vec(shape, data).unwrap(),
+        ))
+    }
+
+    /// Create a tensor with shape `shape` where every element is `value` (e.g. a padding token or
+    /// `f32::NEG_INFINITY` for a mask). The type of the tensor is the type of `value`
+    pub fn filled<T: Clone + 'static>(shape: Vec<usize>, value: T) -> Self
+    where
+        GenericTensorStorage<T>: From<ndarray::ArrayD<T>>,
+        Tensor: From<GenericTensorStorage<T>>,
+    {
+        Self::new(ndarray::ArrayD::from_elem(shape, value))
+    }
+
+    /// Create a 0-d (scalar) string tensor
+    pub fn string_scalar<S: AsRef<str>>(s: S) -> Self {
+        Self::new(ndarray::arr0(s.as_ref().to_owned()).into_dyn())
+    }
+
+    /// Create a 1-d string tensor with one element per string
+    pub fn string_vec<I, S>(strs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::new(ndarray::Array1::from_vec(collect_strings(strs)).into_dyn())
+    }
+
+    /// A view of the data in a string tensor. Returns `None` for other types of tensors
+    pub fn string_view(&self) -> Option<ndarray::ArrayViewD<String>> {
+        match self {
+            Self::String(item) => Some(item.view()),
+            _ => None,
+        }
+    }
+}
+
+/// Copy a set of borrowed strings into a `Vec` with a single allocation for the `Vec` itself
+fn collect_strings<I, S>(strs: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let iter = strs.into_iter();
+    let mut out = Vec::with_capacity(iter.size_hint().0);
+    out.extend(iter.map(|s| s.as_ref().to_owned()));
+    out
+}
+
+/// Int8 quantization. Quantized values are `clamp(round(x / scale) + zero_point, -128, 127)` and dequantized
+/// values are `(q - zero_point) * scale`
+impl Tensor {
+    /// Quantize a float32 tensor to int8 with a single scale and zero point for the whole tensor.
+    /// Values outside the int8 range are clamped
+    pub fn quantize_int8(&self, scale: f32, zero_point: i8) -> crate::error::Result<Tensor> {
+        self.quantize_int8_inner(&[scale], &[zero_point], None)
+    }
+
+    /// Quantize a float32 tensor to int8 with one scale and zero point per index along `axis`
+    /// (e.g. per output channel of a weight tensor). Values outside the int8 range are clamped
+    pub fn quantize_int8_per_channel(
+        &self,
+        scales: &[f32],
+        zero_points: &[i8],
+        axis: usize,
+    ) -> crate::error::Result<Tensor> {
+        self.quantize_int8_inner(scales, zero_points, Some(axis))
+    }
+
+    /// Dequantize an int8 tensor to float32 with a single scale and zero point for the whole tensor
+    pub fn dequantize_int8(&self, scale: f32, zero_point: i8) -> crate::error::Result<Tensor> {
+        self.dequantize_int8_inner(&[scale], &[zero_point], None)
+    }
+
+    /// Dequantize an int8 tensor to float32 with one scale and zero point per index along `axis`
+    pub fn dequantize_int8_per_channel(
+        &self,
+        scales: &[f32],
+        zero_points: &[i8],
+        axis: usize,
+    ) -> crate::error::Result<Tensor> {
+        self.dequantize_int8_inner(scales, zero_points, Some(axis))
+    }
+
+    fn quantize_int8_inner(
+        &self,
+        scales: &[f32],
+        zero_points: &[i8],
+        axis: Option<usize>,
+    ) -> crate::error::Result<Tensor> {
+        let view = match self {
+            Self::Float(item) => item.view(),
+            _ => {
+                return Err(CartonError::QuantizationFailed(format!(
+                    "Expected a float32 tensor, but got a {} tensor",
+                    self.dtype().map_or("nested", |d| d.to_str())
+                )))
+            }
+        };
+
+        check_quantization_params(view.shape(), scales, zero_points, axis)?;
+        if view.iter().any(|v| v.is_nan()) {
+            return Err(CartonError::QuantizationFailed(
+                "Cannot quantize a tensor that contains NaN".into(),
+            ));
+        }
+
+        let quantize = |x: f32, scale: f32, zero_point: i8| {
+            ((x / scale).round() + zero_point as f32).clamp(i8::MIN as f32, i8::MAX as f32) as i8
+        };
+
+        Ok(Tensor::new(map_per_channel(
+            view,
+            scales,
+            zero_points,
+            axis,
+            quantize,
+        )))
+    }
+
+    fn dequantize_int8_inner(
+        &self,
+        scales: &[f32],
+        zero_points: &[i8],
+        axis: Option<usize>,
+    ) -> crate::error::Result<Tensor> {
+        let view = match self {
+            Self::I8(item) => item.view(),
+            _ => {
+                return Err(CartonError::QuantizationFailed(format!(
+                    "Expected an int8 tensor, but got a {} tensor",
+                    self.dtype().map_or("nested", |d| d.to_str())
+                )))
+            }
+        };
+
+        check_quantization_params(view.shape(), scales, zero_points, axis)?;
+
+        let dequantize =
+            |q: i8, scale: f32, zero_point: i8| (q as i32 - zero_point as i32) as f32 * scale;
+
+        Ok(Tensor::new(map_per_channel(
+            view,
+            scales,
+            zero_points,
+            axis,
+            dequantize,
+        )))
+    }
+}
+
+/// Make sure the scales are valid and that there's one scale and zero point per index along `axis`
+/// (or exactly one of each if `axis` is `None`)
+fn check_quantization_params(
+    shape: &[usize],
+    scales: &[f32],
+    zero_points: &[i8],
+    axis: Option<usize>,
+) -> crate::error::Result<()> {
+    let expected = match axis {
+        None => 1,
+        Some(axis) => *shape.get(axis).ok_or_else(|| {
+            CartonError::QuantizationFailed(format!(
+                "Axis {axis} is out of range for a tensor with shape {shape:?}"
+            ))
+        })?,
+    };
+
+    if scales.len() != expected || zero_points.len() != expected {
+        return Err(CartonError::QuantizationFailed(format!(
+            "Expected {expected} scales and zero points, but got {} scales and {} zero points",
+            scales.len(),
+            zero_points.len()
+        )));
+    }
+
+    if let Some(scale) = scales.iter().find(|s| !(s.is_finite() && **s > 0.0)) {
+        return Err(CartonError::QuantizationFailed(format!(
+            "Scales must be finite and positive, but got {scale}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Apply `f` to every element of `view` with the scale and zero point for its index along `axis`
+/// (or the only scale and zero point if `axis` is `None`)
+fn map_per_channel<A: Copy, B: Default + Clone>(
+    view: ndarray::ArrayViewD<A>,
+    scales: &[f32],
+    zero_points: &[i8],
+    axis: Option<usize>,
+    f: impl Fn(A, f32, i8) -> B,
+) -> ndarray::ArrayD<B> {
+    match axis {
+        None => view.mapv(|v| f(v, scales[0], zero_points[0])),
+        Some(axis) => {
+            let mut out = ndarray::ArrayD::default(view.raw_dim());
+            for (i, (mut out_lane, in_lane)) in out
+                .axis_iter_mut(ndarray::Axis(axis))
+                .zip(view.axis_iter(ndarray::Axis(axis)))
+                .enumerate()
+            {
+                out_lane.zip_mut_with(&in_lane, |o, v| *o = f(*v, scales[i], zero_points[i]));
+            }
+
+            out
+        }
+    }
 }
 
 for_each_carton_type! {
-    impl std::fmt::Debug for Tensor {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// View operations that change the shape of a tensor.
+    /// These share the underlying data with the original tensor when possible
+    impl Tensor {
+        /// Reshape a tensor. The number of elements in `new_shape` must match the number of elements
+        /// in the tensor. Elements are ordered in row-major (C) order.
+        ///
+        /// This only copies if the tensor isn't contiguous in row-major order.
+        pub fn reshape(self, new_shape: Vec<usize>) -> crate::error::Result<Tensor> {
+            match self {
+                $(
+                    Self::$CartonType(item) => Ok(Self::$CartonType(item.reshape(new_shape)?)),
+                )*
+                Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => Err(CartonError::InvalidShape("Nested tensors cannot be reshaped".into())),
+            }
+        }
+
+        /// Remove `axis` from the shape of a tensor. `axis` must have a length of 1
+        pub fn squeeze(self, axis: usize) -> crate::error::Result<Tensor> {
+            match self {
+                $(
+                    Self::$CartonType(item) => Ok(Self::$CartonType(item.squeeze(axis)?)),
+                )*
+                Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => Err(CartonError::InvalidShape("Nested tensors cannot be squeezed".into())),
+            }
+        }
+
+        /// Insert a new axis with a length of 1 at `axis` (e.g. to add a leading batch dimension)
+        pub fn unsqueeze(self, axis: usize) -> crate::error::Result<Tensor> {
+            match self {
+                $(
+                    Self::$CartonType(item) => Ok(Self::$CartonType(item.unsqueeze(axis)?)),
+                )*
+                Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => Err(CartonError::InvalidShape("Nested tensors cannot be unsqueezed".into())),
+            }
+        }
+    }
+}
+
+for_each_carton_type! {
+    /// Operations that combine tensors (e.g. to assemble a batch from individual samples)
+    impl Tensor {
+        /// Join tensors along an existing axis. All the tensors must have the same dtype and the same
+        /// shape except along `axis`. For example, concatenating tensors with shapes `[2, 3]` and
+        /// `[4, 3]` along axis 0 returns a tensor with shape `[6, 3]`.
+        ///
+        /// This always copies the data into a new tensor
+        pub fn concat(tensors: &[Tensor], axis: usize) -> crate::error::Result<Tensor> {
+            Self::combine(tensors, axis, false)
+        }
+
+        /// Join tensors along a new axis. All the tensors must have the same dtype and shape. For
+        /// example, stacking two tensors with shape `[3]` along axis 0 returns a tensor with shape `[2, 3]`.
+        ///
+        /// This always copies the data into a new tensor
+        pub fn stack(tensors: &[Tensor], axis: usize) -> crate::error::Result<Tensor> {
+            Self::combine(tensors, axis, true)
+        }
+
+        fn combine(tensors: &[Tensor], axis: usize, stack: bool) -> crate::error::Result<Tensor> {
+            let op = if stack { "stack" } else { "concatenate" };
+            let first = match tensors.first() {
+                Some(first) => first,
+                None => return Err(CartonError::InvalidShape(format!("Cannot {op} an empty list of tensors"))),
+            };
+
+            match first {
+                $(
+                    Self::$CartonType(_) => {
+                        let views = tensors
+                            .iter()
+                            .map(|t| match t {
+                                Self::$CartonType(item) => Ok(item.view()),
+                                other => Err(CartonError::DataTypeMismatch(format!(
+                                    "Cannot {op} a {} tensor with a {} tensor",
+                                    $TypeStr,
+                                    other.dtype().map_or("nested", |v| v.to_str()),
+                                ))),
+                            })
+                            .collect::<crate::error::Result<Vec<_>>>()?;
+
+                        let out = if stack {
+                            ndarray::stack(ndarray::Axis(axis), &views)
+                        } else {
+                            ndarray::concatenate(ndarray::Axis(axis), &views)
+                        };
+
+                        match out {
+                            Ok(out) => Ok(Self::new(out)),
+                            Err(e) => Err(CartonError::InvalidShape(format!(
+                                "Cannot {op} tensors with shapes {:?} along axis {axis}: {e}",
+                                views.iter().map(|v| v.shape()).collect::<Vec<_>>()
+                            ))),
+                        }
+                    }
+                )*
+                Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => Err(CartonError::InvalidShape(format!("Nested tensors cannot be {op}d"))),
+            }
+        }
+    }
+}
+
+for_each_numeric_or_complex_carton_type! {
+    impl Tensor {
+        /// The data type of this tensor. Returns `None` for nested tensors
+        pub fn dtype(&self) -> Option<DataType> {
+            match self {
+                $(
+                    Self::$CartonType(_) => Some(DataType::$CartonType),
+                )*
+                Self::String(_) => Some(DataType::String),
+                Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => None,
+            }
+        }
+
+        /// The shape of this tensor. Returns `None` for nested tensors
+        pub fn shape(&self) -> Option<Vec<usize>> {
+            match self {
+                $(
+                    Self::$CartonType(item) => Some(item.view().shape().to_vec()),
+                )*
+                Self::String(item) => Some(item.view().shape().to_vec()),
+                Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => None,
+            }
+        }
+    }
+}
+
+for_each_numeric_or_complex_carton_type! {
+    /// Access to the raw data of numeric tensors
+    impl Tensor {
+        /// The size of the data in this tensor in bytes (i.e. the number of elements multiplied by the
+        /// size of each element). This is 0 for string and nested tensors because they don't have a raw
+        /// byte representation
+        pub fn byte_len(&self) -> usize {
+            match self {
+                $(
+                    Self::$CartonType(item) => item.view().len() * std::mem::size_of::<$RustType>(),
+                )*
+                Self::String(_) | Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => 0,
+            }
+        }
+
+        /// The data in this tensor as raw (native-endian) bytes without copying.
+        ///
+        /// Returns `None` for string and nested tensors and for tensors that aren't contiguous in
+        /// standard (row-major) layout. In that case, callers need to make a copy (e.g. with
+        /// `as_standard_layout`).
+        pub fn as_bytes(&self) -> Option<&[u8]> {
+            match self {
+                $(
+                    Self::$CartonType(item) => {
+                        let data = item.view().to_slice()?;
+
+                        // SAFETY: numeric types don't have padding bytes so it's okay to view them as bytes
+                        Some(unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data)) })
+                    }
+                )*
+                Self::String(_) | Self::NestedTensor(_) | Self::LabeledNestedTensor { .. } => None,
+            }
+        }
+    }
+}
+
+for_each_numeric_or_complex_carton_type! {
+    impl Tensor {
+        /// An estimate of the memory used by the data in this tensor in bytes. For numeric tensors, this
+        /// is the same as `byte_len`. For string tensors, this is the total length of the strings plus the
+        /// size of each `String` itself. Nested tensors include all of the tensors they contain
+        pub fn estimated_bytes(&self) -> u64 {
+            match self {
+                $(
+                    Self::$CartonType(_) => self.byte_len() as u64,
+                )*
+                Self::String(item) => string_bytes(item.view().iter()),
+                Self::NestedTensor(tensors) | Self::LabeledNestedTensor { tensors, .. } => {
+                    tensors.iter().map(|t| t.estimated_bytes()).sum()
+                }
+            }
+        }
+    }
+}
+
+/// The estimated memory used by the strings in a string tensor (see `Tensor::estimated_bytes`)
+pub(crate) fn string_bytes<'a, I: Iterator<Item = &'a String>>(strs: I) -> u64 {
+    strs.map(|s| (s.len() + std::mem::size_of::<String>()) as u64)
+        .sum()
+}
+
+/// The estimated total memory used by the data in a set of tensors (e.g. the inputs or outputs of
+/// an inference request). See `Tensor::estimated_bytes` for details
+pub fn tensor_map_bytes(tensors: &HashMap<String, Tensor>) -> u64 {
+    tensors.values().map(|t| t.estimated_bytes()).sum()
+}
+
+/// The max number of elements the `Debug` impl for `Tensor` shows before truncating a tensor (see
+/// `Tensor::display`). This is the same as numpy's default `threshold`
+pub const DEFAULT_DISPLAY_MAX_ELEMENTS: usize = 1000;
+
+/// The number of items shown at the start and end of each dimension when a tensor is truncated. This is
+/// the same as numpy's default `edgeitems`
+const DISPLAY_EDGE_ITEMS: usize = 3;
+
+for_each_numeric_or_complex_carton_type! {
+    impl Tensor {
+        /// A numpy-like string representation of this tensor including its values, shape and dtype. For example:
+        ///
+        /// ```text
+        /// tensor([[0, 1, 2],
+        ///         [3, 4, 5]], shape=[2, 3], dtype=int32)
+        /// ```
+        ///
+        /// If the tensor has more than `max_elements` elements, only the first and last few items of each
+        /// dimension are shown (with `...` in between). The `Debug` impl uses this with
+        /// `DEFAULT_DISPLAY_MAX_ELEMENTS`
+        pub fn display(&self, max_elements: usize) -> String {
             match self {
                 $(
-                    Self::$CartonType(item) => f.debug_tuple(stringify!($CartonType)).field(&item.view()).finish(),
+                    Self::$CartonType(item) => format_tensor(item.view(), DataType::$CartonType, max_elements, |v| v.to_string()),
                 )*
-                Self::NestedTensor(item) => f.debug_tuple("NestedTensor").field(item).finish(),
+                Self::String(item) => format_tensor(item.view(), DataType::String, max_elements, |v| format!("{v:?}")),
+                Self::NestedTensor(tensors) => format!(
+                    "NestedTensor([{}])",
+                    tensors.iter().map(|t| t.display(max_elements)).collect::<Vec<_>>().join(", ")
+                ),
+                Self::LabeledNestedTensor { tensors, labels } => format!(
+                    "LabeledNestedTensor([{}])",
+                    std::iter::zip(labels, tensors)
+                        .map(|(label, t)| format!("{label:?}: {}", t.display(max_elements)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
             }
         }
     }
 }
 
+/// See `Tensor::display`
+fn format_tensor<T>(
+    view: ndarray::ArrayViewD<T>,
+    dtype: DataType,
+    max_elements: usize,
+    format_elem: impl Fn(&T) -> String,
+) -> String {
+    let prefix = "tensor(";
+    let mut out = prefix.to_owned();
+    format_axis(
+        &view,
+        view.len() > max_elements,
+        &format_elem,
+        prefix.len(),
+        &mut out,
+    );
+
+    out += &format!(", shape={:?}, dtype={})", view.shape(), dtype.to_str());
+    out
+}
+
+/// Format the first axis of `view` (and recursively, the rest of them) into `out`. `indent` is the
+/// column the opening bracket of this axis is at
+fn format_axis<T>(
+    view: &ndarray::ArrayViewD<T>,
+    truncate: bool,
+    format_elem: &impl Fn(&T) -> String,
+    indent: usize,
+    out: &mut String,
+) {
+    if view.ndim() == 0 {
+        // Scalars always have exactly one element
+        if let Some(v) = view.first() {
+            out.push_str(&format_elem(v));
+        }
+
+        return;
+    }
+
+    // Like numpy, the innermost dimension is on one line and every other dimension has one line per item
+    let separator = if view.ndim() == 1 {
+        ", ".to_owned()
+    } else {
+        format!(",\n{}", " ".repeat(indent + 1))
+    };
+
+    let len = view.shape()[0];
+    out.push('[');
+    for i in 0..len {
+        if truncate
+            && len > 2 * DISPLAY_EDGE_ITEMS
+            && i >= DISPLAY_EDGE_ITEMS
+            && i < len - DISPLAY_EDGE_ITEMS
+        {
+            if i == DISPLAY_EDGE_ITEMS {
+                out.push_str("...");
+                out.push_str(&separator);
+            }
+
+            continue;
+        }
+
+        format_axis(
+            &view.index_axis(ndarray::Axis(0), i),
+            truncate,
+            format_elem,
+            indent + 1,
+            out,
+        );
+
+        if i + 1 < len {
+            out.push_str(&separator);
+        }
+    }
+
+    out.push(']');
+}
+
+impl std::fmt::Debug for Tensor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.display(DEFAULT_DISPLAY_MAX_ELEMENTS))
+    }
+}
+
 for_each_carton_type! {
     impl PartialEq for Tensor {
         fn eq(&self, other: &Tensor) -> bool {
@@ -240,12 +925,234 @@ for_each_carton_type! {
                     (Self::$CartonType(me), Tensor::$CartonType(other))  => me.view() == other.view(),
                 )*
                 (Self::NestedTensor(me), Tensor::NestedTensor(other)) => std::iter::zip(me, other).map(|(a, b)| a == b).all(|v| v),
+                (
+                    Self::LabeledNestedTensor { tensors: me, labels: my_labels },
+                    Tensor::LabeledNestedTensor { tensors: other, labels: other_labels },
+                ) => my_labels == other_labels && std::iter::zip(me, other).map(|(a, b)| a == b).all(|v| v),
                 _ => false,
             }
         }
     }
 }
 
+/// A self-describing representation of a tensor used by the `Serialize` and `Deserialize` impls below.
+/// Numeric data is stored as little-endian bytes (base64 encoded in human-readable formats like JSON)
+#[cfg(feature = "tensor-serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedTensor<'a> {
+    dtype: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shape: Option<Vec<usize>>,
+
+    /// For numeric tensors
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "raw_data")]
+    data: Option<Vec<u8>>,
+
+    /// For string tensors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    strings: Option<Vec<String>>,
+
+    /// For nested tensors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tensors: Option<std::borrow::Cow<'a, [Tensor]>>,
+
+    /// For labeled nested tensors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    labels: Option<std::borrow::Cow<'a, [String]>>,
+}
+
+/// Conversion to and from the little-endian bytes used for numeric data in `SerializedTensor`
+#[cfg(feature = "tensor-serde")]
+trait LittleEndianBytes: Sized {
+    /// The number of bytes used for each element
+    const SIZE: usize;
+
+    fn write_le_bytes(&self, out: &mut Vec<u8>);
+
+    /// `bytes` must be exactly `SIZE` bytes long
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+#[cfg(feature = "tensor-serde")]
+macro_rules! impl_little_endian_bytes {
+    ($($t:ty),*) => {
+        $(
+            impl LittleEndianBytes for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn write_le_bytes(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes())
+                }
+
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    <$t>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "tensor-serde")]
+impl_little_endian_bytes!(f32, f64, i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// Complex numbers are stored as the real part followed by the imaginary part
+#[cfg(feature = "tensor-serde")]
+impl<T: LittleEndianBytes> LittleEndianBytes for num_complex::Complex<T> {
+    const SIZE: usize = 2 * T::SIZE;
+
+    fn write_le_bytes(&self, out: &mut Vec<u8>) {
+        self.re.write_le_bytes(out);
+        self.im.write_le_bytes(out);
+    }
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        let (re, im) = bytes.split_at(T::SIZE);
+        Self::new(T::from_le_slice(re), T::from_le_slice(im))
+    }
+}
+
+/// Store raw bytes as base64 in human-readable formats and as bytes otherwise
+#[cfg(feature = "tensor-serde")]
+mod raw_data {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S>(data: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match data {
+            Some(data) if serializer.is_human_readable() => {
+                serializer.serialize_some(&base64::engine::general_purpose::STANDARD.encode(data))
+            }
+            Some(data) => serializer.serialize_some(serde_bytes::Bytes::new(data)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            Option::<String>::deserialize(deserializer)?
+                .map(|data| {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(data)
+                        .map_err(serde::de::Error::custom)
+                })
+                .transpose()
+        } else {
+            Ok(Option::<serde_bytes::ByteBuf>::deserialize(deserializer)?.map(|v| v.into_vec()))
+        }
+    }
+}
+
+#[cfg(feature = "tensor-serde")]
+for_each_numeric_or_complex_carton_type! {
+    impl Serialize for Tensor {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let out = match self {
+                $(
+                    Self::$CartonType(item) => {
+                        let view = item.view();
+
+                        // Iterates in logical (row-major) order regardless of the memory layout
+                        let mut data = Vec::with_capacity(view.len() * <$RustType as LittleEndianBytes>::SIZE);
+                        for v in view.iter() {
+                            v.write_le_bytes(&mut data);
+                        }
+
+                        SerializedTensor {
+                            dtype: $TypeStr.into(),
+                            shape: Some(view.shape().to_vec()),
+                            data: Some(data),
+                            strings: None,
+                            tensors: None,
+                            labels: None,
+                        }
+                    }
+                )*
+                Self::String(item) => {
+                    let view = item.view();
+                    SerializedTensor {
+                        dtype: "string".into(),
+                        shape: Some(view.shape().to_vec()),
+                        data: None,
+                        strings: Some(view.iter().cloned().collect()),
+                        tensors: None,
+                        labels: None,
+                    }
+                }
+                Self::NestedTensor(items) => SerializedTensor {
+                    dtype: "nested".into(),
+                    shape: None,
+                    data: None,
+                    strings: None,
+                    tensors: Some(std::borrow::Cow::Borrowed(items)),
+                    labels: None,
+                },
+                Self::LabeledNestedTensor { tensors, labels } => SerializedTensor {
+                    dtype: "nested".into(),
+                    shape: None,
+                    data: None,
+                    strings: None,
+                    tensors: Some(std::borrow::Cow::Borrowed(tensors)),
+                    labels: Some(std::borrow::Cow::Borrowed(labels)),
+                },
+            };
+
+            out.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Tensor {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            use serde::de::Error;
+
+            let value = SerializedTensor::deserialize(deserializer)?;
+            if value.dtype == "nested" {
+                let tensors = value.tensors.ok_or_else(|| D::Error::missing_field("tensors"))?.into_owned();
+                return match value.labels {
+                    Some(labels) => Self::labeled_nested(tensors, labels.into_owned()).map_err(D::Error::custom),
+                    None => Ok(Self::NestedTensor(tensors)),
+                };
+            }
+
+            let shape = value.shape.ok_or_else(|| D::Error::missing_field("shape"))?;
+            match value.dtype.as_str() {
+                $(
+                    $TypeStr => {
+                        let data = value.data.ok_or_else(|| D::Error::missing_field("data"))?;
+                        let bytes_per_elem = <$RustType as LittleEndianBytes>::SIZE;
+                        if data.len() % bytes_per_elem != 0 {
+                            return Err(D::Error::custom(format!("The length of the data for a {} tensor must be a multiple of {bytes_per_elem}", $TypeStr)));
+                        }
+
+                        let typed_data: Vec<$RustType> = data.chunks_exact(bytes_per_elem).map(<$RustType as LittleEndianBytes>::from_le_slice).collect();
+
+                        let arr = ndarray::ArrayD::from_shape_vec(shape, typed_data).map_err(D::Error::custom)?;
+                        Ok(Self::new(arr))
+                    }
+                )*
+                "string" => {
+                    let data = value.strings.ok_or_else(|| D::Error::missing_field("strings"))?;
+                    let arr = ndarray::ArrayD::from_shape_vec(shape, data).map_err(D::Error::custom)?;
+                    Ok(Self::new(arr))
+                }
+                other => Err(D::Error::custom(format!("Unknown tensor dtype: {other}"))),
+            }
+        }
+    }
+}
+
 pub trait TypedStorage<T> {
     // Get a view of this tensor
     fn view(&self) -> ndarray::ArrayViewD<T>;
@@ -306,6 +1213,491 @@ impl<T> GenericTensorStorage<T> {
     }
 }
 
+impl<T: Clone> GenericTensorStorage<T> {
+    fn reshape(self, new_shape: Vec<usize>) -> crate::error::Result<Self> {
+        let numel = shape_numel(&new_shape.iter().map(|v| *v as u64).collect::<Vec<_>>())
+            .ok_or_else(|| {
+                CartonError::InvalidShape(format!(
+                    "The number of elements in shape {new_shape:?} overflows"
+                ))
+            })?;
+
+        if numel != self.view.len() as u64 {
+            return Err(CartonError::InvalidShape(format!(
+                "Cannot reshape a tensor with shape {:?} ({} elements) to {:?} ({} elements)",
+                self.view.shape(),
+                self.view.len(),
+                new_shape,
+                numel
+            )));
+        }
+
+        if !self.view.is_standard_layout() {
+            // We can't change the shape without copying
+            let data = self.view.iter().cloned().collect();
+            return Ok(Self::new(
+                ndarray::ArrayD::from_shape_vec(new_shape, data).unwrap(),
+            ));
+        }
+
+        // Keep the same underlying storage
+        let Self { view, _keepalive } = self;
+        Ok(Self {
+            view: view.into_shape(new_shape).unwrap(),
+            _keepalive,
+        })
+    }
+}
+
+impl<T> GenericTensorStorage<T> {
+    fn squeeze(self, axis: usize) -> crate::error::Result<Self> {
+        match self.view.shape().get(axis) {
+            Some(1) => {}
+            Some(len) => {
+                return Err(CartonError::InvalidShape(format!(
+                    "Cannot squeeze axis {axis} with length {len}. Only axes with a length of 1 can be squeezed"
+                )))
+            }
+            None => {
+                return Err(CartonError::InvalidShape(format!(
+                    "Cannot squeeze axis {axis} of a tensor with {} dims",
+                    self.view.ndim()
+                )))
+            }
+        }
+
+        let Self { view, _keepalive } = self;
+        Ok(Self {
+            view: view.index_axis_move(ndarray::Axis(axis), 0),
+            _keepalive,
+        })
+    }
+
+    fn unsqueeze(self, axis: usize) -> crate::error::Result<Self> {
+        if axis > self.view.ndim() {
+            return Err(CartonError::InvalidShape(format!(
+                "Cannot unsqueeze at axis {axis} of a tensor with {} dims",
+                self.view.ndim()
+            )));
+        }
+
+        let Self { view, _keepalive } = self;
+        Ok(Self {
+            view: view.insert_axis(ndarray::Axis(axis)),
+            _keepalive,
+        })
+    }
+}
+
 // TODO: explain why this is okay
 unsafe impl<T: Send> Send for GenericTensorStorage<T> {}
 unsafe impl<T: Sync> Sync for GenericTensorStorage<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::Tensor;
+    use crate::error::CartonError;
+
+    #[test]
+    fn test_display() {
+        let t = Tensor::new(
+            ndarray::Array::range(0.0f32, 6.0, 1.0)
+                .into_shape(vec![2, 3])
+                .unwrap(),
+        );
+        assert_eq!(
+            t.display(1000),
+            "tensor([[0, 1, 2],\n        [3, 4, 5]], shape=[2, 3], dtype=float32)"
+        );
+
+        // Large tensors are truncated
+        let t = Tensor::new(ndarray::Array::from_iter(0..10i64).into_dyn());
+        assert_eq!(
+            t.display(1000),
+            "tensor([0, 1, 2, 3, 4, 5, 6, 7, 8, 9], shape=[10], dtype=int64)"
+        );
+        assert_eq!(
+            t.display(5),
+            "tensor([0, 1, 2, ..., 7, 8, 9], shape=[10], dtype=int64)"
+        );
+
+        let t = Tensor::new(
+            ndarray::Array::from_iter(0..64u8)
+                .into_shape(vec![8, 8])
+                .unwrap(),
+        );
+        let expected = [
+            "tensor([[0, 1, 2, ..., 5, 6, 7],",
+            "        [8, 9, 10, ..., 13, 14, 15],",
+            "        [16, 17, 18, ..., 21, 22, 23],",
+            "        ...,",
+            "        [40, 41, 42, ..., 45, 46, 47],",
+            "        [48, 49, 50, ..., 53, 54, 55],",
+            "        [56, 57, 58, ..., 61, 62, 63]], shape=[8, 8], dtype=uint8)",
+        ];
+        assert_eq!(t.display(10), expected.join("\n"));
+
+        // Strings, scalars and empty tensors
+        assert_eq!(
+            Tensor::string_scalar("hello").display(1000),
+            "tensor(\"hello\", shape=[], dtype=string)"
+        );
+        assert_eq!(
+            Tensor::new(ndarray::ArrayD::<f64>::zeros(vec![0, 3])).display(1000),
+            "tensor([], shape=[0, 3], dtype=float64)"
+        );
+
+        // Debug uses `display`
+        let t =
+            Tensor::labeled_nested(vec![Tensor::filled(vec![2], 1i32)], vec!["a".into()]).unwrap();
+        assert_eq!(
+            format!("{t:?}"),
+            "LabeledNestedTensor([\"a\": tensor([1, 1], shape=[2], dtype=int32)])"
+        );
+    }
+
+    #[test]
+    fn test_filled() {
+        let t = Tensor::filled(vec![2, 3], -1i64);
+        let expected = Tensor::new(ndarray::ArrayD::from_elem(vec![2, 3], -1i64));
+        assert_eq!(t, expected);
+
+        let t = Tensor::filled(vec![2], String::from("[PAD]"));
+        assert_eq!(
+            t.string_view().unwrap().as_slice().unwrap(),
+            &["[PAD]", "[PAD]"]
+        );
+    }
+
+    #[test]
+    fn test_reshape() {
+        let data: Vec<f32> = (0..6).map(|v| v as f32).collect();
+        let t = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![2, 3], data.clone()).unwrap());
+        let expected = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![3, 2], data).unwrap());
+
+        assert_eq!(t.clone().reshape(vec![3, 2]).unwrap(), expected);
+
+        // The number of elements must match
+        assert!(t.reshape(vec![4, 2]).is_err());
+    }
+
+    #[test]
+    fn test_quantize_int8() {
+        let t = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(
+                vec![2, 3],
+                vec![0.0f32, 0.5, -1.0, 100.0, -100.0, 1.2],
+            )
+            .unwrap(),
+        );
+
+        // Values outside the int8 range are clamped
+        let q = t.quantize_int8(0.1, 2).unwrap();
+        let expected = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2, 3], vec![2i8, 7, -8, 127, -128, 14]).unwrap(),
+        );
+        assert_eq!(q, expected);
+
+        let dq = q.dequantize_int8(0.1, 2).unwrap();
+        let dq = match dq {
+            Tensor::Float(dq) => dq,
+            _ => panic!("Expected a float32 tensor"),
+        };
+        assert!((dq.view()[[0, 1]] - 0.5).abs() < 1e-6);
+        assert!((dq.view()[[1, 0]] - 12.5).abs() < 1e-5);
+
+        // Per channel along axis 0
+        let q = t
+            .quantize_int8_per_channel(&[0.5, 1.0], &[0, -1], 0)
+            .unwrap();
+        let expected = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2, 3], vec![0i8, 1, -2, 99, -101, 0]).unwrap(),
+        );
+        assert_eq!(q, expected);
+
+        // Invalid params
+        assert!(t.quantize_int8(0.0, 0).is_err());
+        assert!(t.quantize_int8_per_channel(&[1.0], &[0], 1).is_err());
+        assert!(t
+            .quantize_int8_per_channel(&[1.0, 1.0], &[0, 0], 2)
+            .is_err());
+        assert!(expected.quantize_int8(1.0, 0).is_err());
+        assert!(t.dequantize_int8(1.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_string_tensors() {
+        // 0-d
+        let t = Tensor::string_scalar("hello");
+        let view = t.string_view().unwrap();
+        assert_eq!(view.shape(), &[] as &[usize]);
+        assert_eq!(view[[]], "hello");
+
+        // 1-d from `&str` and `String`
+        let t = Tensor::string_vec(["a", "b", "c"]);
+        assert_eq!(t.string_view().unwrap().shape(), &[3]);
+        assert_eq!(
+            t,
+            Tensor::string_vec(vec!["a".to_owned(), "b".into(), "c".into()])
+        );
+
+        // N-d
+        let t = Tensor::from_strs(vec![2, 3], ["a", "b", "c", "d", "e", "f"]).unwrap();
+        let view = t.string_view().unwrap();
+        assert_eq!(view.shape(), &[2, 3]);
+        assert_eq!(view[[1, 0]], "d");
+        assert_eq!(
+            t.reshape(vec![6]).unwrap(),
+            Tensor::string_vec(["a", "b", "c", "d", "e", "f"])
+        );
+
+        // The number of strings must match the shape
+        assert!(matches!(
+            Tensor::from_strs(vec![2, 2], ["a", "b", "c"]),
+            Err(crate::error::CartonError::InvalidShape(_))
+        ));
+
+        // Other tensor types don't have a string view
+        let t = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![1], vec![1f32]).unwrap());
+        assert!(t.string_view().is_none());
+    }
+
+    #[test]
+    fn test_shape_numel() {
+        assert_eq!(super::shape_numel(&[]), Some(1));
+        assert_eq!(super::shape_numel(&[2, 3, 4]), Some(24));
+        assert_eq!(super::shape_numel(&[2, 0, u64::MAX]), Some(0));
+        assert_eq!(super::shape_numel(&[u64::MAX, 2]), None);
+        assert_eq!(super::shape_numel(&[1 << 32, 1 << 32]), None);
+
+        // Reshaping to a shape that overflows is an error (instead of wrapping around)
+        let t = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![1], vec![1f32]).unwrap());
+        assert!(matches!(
+            t.reshape(vec![1 << 32, 1 << 32, 1 << 32]),
+            Err(crate::error::CartonError::InvalidShape(_))
+        ));
+    }
+
+    #[test]
+    fn test_reshape_non_contiguous() {
+        let arr = ndarray::ArrayD::from_shape_vec(vec![2, 3], (0..6).collect::<Vec<i32>>())
+            .unwrap()
+            .reversed_axes();
+        let expected = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![6], arr.iter().cloned().collect()).unwrap(),
+        );
+
+        assert_eq!(Tensor::new(arr).reshape(vec![6]).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_squeeze_unsqueeze() {
+        let t = Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![3, 4]));
+
+        let unsqueezed = t.clone().unsqueeze(0).unwrap();
+        assert_eq!(
+            unsqueezed,
+            Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![1, 3, 4]))
+        );
+
+        // Can only squeeze axes with a length of 1
+        assert!(unsqueezed.clone().squeeze(1).is_err());
+        assert!(unsqueezed.clone().squeeze(3).is_err());
+        assert!(unsqueezed.clone().unsqueeze(4).is_err());
+
+        assert_eq!(unsqueezed.squeeze(0).unwrap(), t);
+    }
+
+    #[test]
+    fn test_as_bytes() {
+        let t =
+            Tensor::new(ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1u16, 2, 3, 4]).unwrap());
+        assert_eq!(t.byte_len(), 8);
+
+        let expected: Vec<u8> = [1u16, 2, 3, 4]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        assert_eq!(t.as_bytes().unwrap(), expected.as_slice());
+
+        // Non-contiguous tensors need to be copied by the caller
+        let t = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1u16, 2, 3, 4])
+                .unwrap()
+                .reversed_axes(),
+        );
+        assert_eq!(t.byte_len(), 8);
+        assert!(t.as_bytes().is_none());
+
+        // String tensors don't have a raw byte representation
+        let t = Tensor::new(ndarray::ArrayD::from_elem(vec![2], "a".to_owned()));
+        assert_eq!(t.byte_len(), 0);
+        assert!(t.as_bytes().is_none());
+    }
+
+    #[test]
+    fn test_labeled_nested() {
+        let tensors = vec![
+            Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![1, 4])),
+            Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![3, 4])),
+        ];
+
+        // There must be one label per tensor
+        assert!(Tensor::labeled_nested(tensors.clone(), vec!["cat".to_owned()]).is_err());
+
+        let t = Tensor::labeled_nested(tensors.clone(), vec!["cat".to_owned(), "dog".to_owned()])
+            .unwrap();
+        assert!(t.is_nested());
+
+        // Labels are part of equality
+        assert_ne!(
+            t,
+            Tensor::labeled_nested(tensors, vec!["cat".to_owned(), "bird".to_owned()]).unwrap()
+        );
+    }
+
+    #[cfg(feature = "tensor-serde")]
+    #[test]
+    fn test_tensor_serde() {
+        let tensors = vec![
+            Tensor::new(
+                ndarray::ArrayD::from_shape_vec(vec![2, 3], (0..6).map(|v| v as f32).collect())
+                    .unwrap(),
+            ),
+            // Non-contiguous
+            Tensor::new(
+                ndarray::ArrayD::from_shape_vec(vec![2, 3], (0..6).collect::<Vec<i64>>())
+                    .unwrap()
+                    .reversed_axes(),
+            ),
+            Tensor::new(ndarray::ArrayD::<u8>::zeros(vec![0, 4])),
+            Tensor::new(
+                ndarray::ArrayD::from_shape_vec(
+                    vec![2],
+                    vec![
+                        num_complex::Complex::new(1.0f32, -2.0),
+                        num_complex::Complex::new(0.5, 3.0),
+                    ],
+                )
+                .unwrap(),
+            ),
+            Tensor::new(
+                ndarray::ArrayD::from_shape_vec(vec![2], vec!["a".to_owned(), "b".to_owned()])
+                    .unwrap(),
+            ),
+            Tensor::NestedTensor(vec![
+                Tensor::new(ndarray::ArrayD::<f64>::ones(vec![2])),
+                Tensor::new(ndarray::ArrayD::<f64>::ones(vec![3])),
+            ]),
+            Tensor::labeled_nested(
+                vec![
+                    Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![1, 4])),
+                    Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![3, 4])),
+                ],
+                vec!["cat".to_owned(), "dog".to_owned()],
+            )
+            .unwrap(),
+        ];
+
+        for t in tensors {
+            let serialized = serde_json::to_string(&t).unwrap();
+            let deserialized: Tensor = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, t);
+        }
+
+        // String tensors are serialized as arrays of strings
+        let t = Tensor::new(
+            ndarray::ArrayD::from_shape_vec(vec![1], vec!["hello".to_owned()]).unwrap(),
+        );
+        assert_eq!(
+            serde_json::to_value(&t).unwrap(),
+            serde_json::json!({"dtype": "string", "shape": [1], "strings": ["hello"]})
+        );
+
+        // Numeric data is little-endian regardless of the platform
+        use base64::Engine;
+        let t = Tensor::new(ndarray::ArrayD::from_shape_vec(vec![2], vec![1u16, 0x0203]).unwrap());
+        let data = base64::engine::general_purpose::STANDARD.encode([1u8, 0, 3, 2]);
+        assert_eq!(
+            serde_json::to_value(&t).unwrap(),
+            serde_json::json!({"dtype": "uint16", "shape": [2], "data": data})
+        );
+    }
+
+    #[test]
+    fn test_estimated_bytes() {
+        let numeric = Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![2, 3]));
+        assert_eq!(numeric.estimated_bytes(), 24);
+
+        let strings = Tensor::string_vec(["a", "bcd"]);
+        assert_eq!(
+            strings.estimated_bytes(),
+            4 + 2 * std::mem::size_of::<String>() as u64
+        );
+
+        let nested = Tensor::NestedTensor(vec![
+            Tensor::new(ndarray::ArrayD::<u8>::zeros(vec![5])),
+            Tensor::new(ndarray::ArrayD::<i64>::zeros(vec![2])),
+        ]);
+        assert_eq!(nested.estimated_bytes(), 21);
+
+        let map = [("a".to_owned(), numeric), ("b".to_owned(), nested)]
+            .into_iter()
+            .collect();
+        assert_eq!(super::tensor_map_bytes(&map), 45);
+    }
+
+    #[test]
+    fn test_concat_stack() {
+        let a =
+            Tensor::new(ndarray::ArrayD::from_shape_vec(vec![1, 2], vec![1.0f32, 2.0]).unwrap());
+        let b =
+            Tensor::new(ndarray::ArrayD::from_shape_vec(vec![1, 2], vec![3.0f32, 4.0]).unwrap());
+
+        assert_eq!(
+            Tensor::concat(&[a.clone(), b.clone()], 0).unwrap(),
+            Tensor::new(
+                ndarray::ArrayD::from_shape_vec(vec![2, 2], vec![1.0f32, 2.0, 3.0, 4.0]).unwrap()
+            )
+        );
+        assert_eq!(
+            Tensor::concat(&[a.clone(), b.clone()], 1).unwrap(),
+            Tensor::new(
+                ndarray::ArrayD::from_shape_vec(vec![1, 4], vec![1.0f32, 2.0, 3.0, 4.0]).unwrap()
+            )
+        );
+        assert_eq!(
+            Tensor::stack(&[a.clone(), b.clone()], 0).unwrap().shape(),
+            Some(vec![2, 1, 2])
+        );
+
+        // String tensors
+        assert_eq!(
+            Tensor::stack(
+                &[
+                    Tensor::string_vec(["a", "b"]),
+                    Tensor::string_vec(["c", "d"])
+                ],
+                0
+            )
+            .unwrap(),
+            Tensor::from_strs(vec![2, 2], ["a", "b", "c", "d"]).unwrap()
+        );
+
+        // Mismatched dtypes, shapes and axes
+        let c = Tensor::new(ndarray::ArrayD::<f64>::zeros(vec![1, 2]));
+        assert!(matches!(
+            Tensor::concat(&[a.clone(), c], 0),
+            Err(CartonError::DataTypeMismatch(_))
+        ));
+
+        let d = Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![1, 3]));
+        assert!(matches!(
+            Tensor::concat(&[a.clone(), d.clone()], 0),
+            Err(CartonError::InvalidShape(_))
+        ));
+        assert!(Tensor::concat(&[a.clone(), d], 1).is_ok());
+        assert!(Tensor::stack(&[a.clone(), b], 3).is_err());
+        assert!(Tensor::concat(&[], 0).is_err());
+    }
+}
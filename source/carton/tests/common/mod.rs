@@ -0,0 +1,149 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Utilities shared between integration tests
+
+// Not every test uses every helper
+#![allow(dead_code)]
+
+use carton::{
+    info::RunnerInfo,
+    types::{CartonInfo, LoadOpts, PackOpts},
+    Carton,
+};
+use semver::VersionReq;
+
+/// Build the noop runner (if necessary) and return the path of the binary
+pub fn noop_runner_path() -> std::path::PathBuf {
+    escargot::CargoBuild::new()
+        .package("carton-runner-noop")
+        .run()
+        .unwrap()
+        .path()
+        .to_owned()
+}
+
+/// A `runner.toml` containing one noop runner. `runner_path` is relative to the directory containing
+/// `runner.toml` (or absolute)
+pub fn noop_runner_toml(
+    runner_name: &str,
+    runner_compat_version: u64,
+    runner_path: &str,
+) -> String {
+    format!(
+        r#"
+version = 1
+
+[[runner]]
+runner_name = "{runner_name}"
+framework_version = "1.0.0"
+runner_compat_version = {runner_compat_version}
+runner_interface_version = 1
+runner_release_date = "1979-05-27T07:32:00Z"
+runner_path = "{runner_path}"
+platform = "{}"
+"#,
+        target_lexicon::HOST.to_string()
+    )
+}
+
+/// Build the noop runner and point `CARTON_RUNNER_DIR` at a runner dir containing it
+pub fn setup_noop_runner() {
+    let runner_path = noop_runner_path();
+    let runner_toml = noop_runner_toml("noop", 1, &runner_path.display().to_string());
+
+    // The config is only read once per process so this dir is kept around (instead of being deleted on drop)
+    // in case another test in this process loads a runner from it later
+    let runner_dir = tempfile::tempdir().unwrap().into_path();
+    std::fs::write(runner_dir.join("runner.toml"), runner_toml).unwrap();
+    std::env::set_var("CARTON_RUNNER_DIR", &runner_dir);
+}
+
+/// A `CartonInfo` for a model that uses the noop runner
+pub fn noop_carton_info() -> CartonInfo {
+    CartonInfo {
+        model_name: None,
+        short_description: None,
+        model_description: None,
+        license: None,
+        repository: None,
+        homepage: None,
+        required_platforms: None,
+        inputs: None,
+        outputs: None,
+        self_tests: None,
+        examples: None,
+        runner: RunnerInfo {
+            runner_name: "noop".into(),
+            required_framework_version: VersionReq::parse("*").unwrap(),
+            runner_compat_version: None,
+            opts: None,
+        },
+        misc_files: None,
+    }
+}
+
+/// Load a model with the noop runner (which echoes its inputs)
+pub async fn load_noop_carton() -> Carton {
+    setup_noop_runner();
+
+    let model_dir = tempfile::tempdir().unwrap().into_path();
+    Carton::load_unpacked(
+        model_dir.to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+        LoadOpts::default(),
+    )
+    .await
+    .unwrap()
+}
+
+/// Copy the packed carton at `path`, replacing the contents of `file` with `data`. Everything else
+/// (including `MANIFEST`) is kept as-is so the copy no longer matches its `MANIFEST`
+pub fn replace_file_in_carton(
+    path: &std::path::Path,
+    file: &str,
+    data: &[u8],
+) -> std::path::PathBuf {
+    use std::io::{Read, Write};
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+    let out_path = tempfile::tempdir()
+        .unwrap()
+        .into_path()
+        .join("modified.carton");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&out_path).unwrap());
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).unwrap();
+        let name = entry.name().to_owned();
+        if entry.is_dir() {
+            writer.add_directory(name, Default::default()).unwrap();
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).unwrap();
+        if name == file {
+            contents = data.to_vec();
+        }
+
+        writer.start_file(name, Default::default()).unwrap();
+        writer.write_all(&contents).unwrap();
+    }
+
+    writer.finish().unwrap();
+    out_path
+}
@@ -0,0 +1,138 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure loading fails if a packed carton exceeds the size limits in `LoadOpts`
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+#[tokio::test]
+async fn test_archive_limits() {
+    setup_noop_runner();
+
+    // Pack a model with two files
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::write(model_dir.path().join("a.bin"), vec![0u8; 1024]).unwrap();
+    std::fs::write(model_dir.path().join("b.bin"), vec![0u8; 1024]).unwrap();
+
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let packed_path = packed_path.to_str().unwrap();
+
+    // A file is larger than the per-file limit
+    let res = Carton::load(
+        packed_path,
+        LoadOpts {
+            max_archive_file_size: Some(512),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(res, Err(CartonError::ArchiveTooLarge(_))));
+
+    // Each file is under the per-file limit, but the total is too large
+    let res = Carton::load(
+        packed_path,
+        LoadOpts {
+            max_archive_size: Some(1536),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(res, Err(CartonError::ArchiveTooLarge(_))));
+
+    // The default limits are large enough
+    Carton::load(packed_path, LoadOpts::default())
+        .await
+        .unwrap();
+}
+
+/// Set the uncompressed size of every entry in the zip headers to 1 byte without changing the data
+fn understate_sizes(path: &str) {
+    let mut data = std::fs::read(path).unwrap();
+    let read_u16 =
+        |data: &[u8], pos: usize| u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+    let read_u32 = |data: &[u8], pos: usize| {
+        u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize
+    };
+
+    // Find the end of central directory record
+    let eocd = (0..data.len() - 3)
+        .rev()
+        .find(|&pos| data[pos..pos + 4] == [0x50, 0x4b, 0x05, 0x06])
+        .unwrap();
+    let num_entries = read_u16(&data, eocd + 10);
+    let mut pos = read_u32(&data, eocd + 16);
+
+    for _ in 0..num_entries {
+        // Update the central directory header and the local header it points to
+        let local = read_u32(&data, pos + 42);
+        data[pos + 24..pos + 28].copy_from_slice(&1u32.to_le_bytes());
+        data[local + 22..local + 26].copy_from_slice(&1u32.to_le_bytes());
+
+        pos +=
+            46 + read_u16(&data, pos + 28) + read_u16(&data, pos + 30) + read_u16(&data, pos + 32);
+    }
+
+    std::fs::write(path, data).unwrap();
+}
+
+#[tokio::test]
+async fn test_understated_sizes() {
+    setup_noop_runner();
+
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::write(model_dir.path().join("a.bin"), [0u8; 4]).unwrap();
+
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            compress_manifest: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let packed_path = packed_path.to_str().unwrap();
+    understate_sizes(packed_path);
+
+    // Every file is under the limit according to the headers, but the actual data (starting with the
+    // MANIFEST) is larger
+    let res = Carton::load(
+        packed_path,
+        LoadOpts {
+            max_archive_file_size: Some(16),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(
+        matches!(res, Err(CartonError::ArchiveTooLarge(_))),
+        "Expected an `ArchiveTooLarge` error, but got {:?}",
+        res.err()
+    );
+}
@@ -0,0 +1,97 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test loading models from a bundle of several packed models
+use std::io::Write;
+
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+async fn pack(model_name: &str) -> Vec<u8> {
+    let mut info = noop_carton_info();
+    info.model_name = Some(model_name.into());
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    std::fs::read(path).unwrap()
+}
+
+#[tokio::test]
+async fn test_bundle() {
+    setup_noop_runner();
+
+    // Make a zip bundle with two models stored uncompressed and one compressed model
+    let dir = tempfile::tempdir().unwrap();
+    let bundle_path = dir.path().join("bundle.zip");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&bundle_path).unwrap());
+    for (name, method) in [
+        ("small.carton", zip::CompressionMethod::Stored),
+        ("models/large.carton", zip::CompressionMethod::Stored),
+        ("compressed.carton", zip::CompressionMethod::Deflated),
+    ] {
+        writer
+            .start_file(
+                name,
+                zip::write::FileOptions::default().compression_method(method),
+            )
+            .unwrap();
+        writer
+            .write_all(&pack(name.trim_end_matches(".carton")).await)
+            .unwrap();
+    }
+    writer.finish().unwrap();
+
+    // Models can be referred to with or without the extension
+    for (name, expected) in [
+        ("small", "small"),
+        ("small.carton", "small"),
+        ("models/large", "models/large"),
+    ] {
+        let carton = Carton::load_from_bundle(&bundle_path, name, LoadOpts::default())
+            .await
+            .unwrap();
+        assert_eq!(carton.get_info().info.model_name.as_deref(), Some(expected));
+    }
+
+    match Carton::load_from_bundle(&bundle_path, "compressed", LoadOpts::default()).await {
+        Err(CartonError::CompressedBundleEntry(path)) => assert_eq!(path, "compressed.carton"),
+        other => panic!(
+            "Expected a `CompressedBundleEntry` error, got {:?}",
+            other.err()
+        ),
+    }
+
+    match Carton::load_from_bundle(&bundle_path, "missing", LoadOpts::default()).await {
+        Err(CartonError::ModelNotInBundle { name, available }) => {
+            assert_eq!(name, "missing");
+            assert_eq!(available, ["compressed", "models/large", "small"]);
+        }
+        other => panic!("Expected a `ModelNotInBundle` error, got {:?}", other.err()),
+    }
+}
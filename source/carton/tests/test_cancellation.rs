@@ -0,0 +1,44 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure dropping inference futures (i.e. cancelling requests) doesn't affect other requests
+use carton::types::Tensor;
+use futures::{FutureExt, StreamExt};
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cancellation() {
+    let carton = load_noop_carton().await;
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+
+    // Start a bunch of requests and drop them before they complete
+    for _ in 0..32 {
+        let _ = carton.infer([("x", x.clone())]).now_or_never();
+    }
+
+    // Drop a streaming request after the first response
+    {
+        let stream = carton.streaming_infer([("x", x.clone())]).await;
+        futures::pin_mut!(stream);
+        let _ = stream.next().await;
+    }
+
+    // Other requests still work
+    for _ in 0..4 {
+        let res = carton.infer([("x", x.clone())]).await.unwrap();
+        assert_eq!(res["x"], x);
+    }
+}
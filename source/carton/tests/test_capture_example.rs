@@ -0,0 +1,63 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! This test makes sure examples captured from a loaded model can be packed into a carton
+use carton::{
+    types::{PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{load_noop_carton, noop_carton_info};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_capture_example() {
+    let model = load_noop_carton().await;
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2, 3], 1.0f32));
+
+    let example = model
+        .capture_example(Some("ones".into()), [("x".to_owned(), x.clone())].into())
+        .await
+        .unwrap();
+
+    // The noop runner echoes its inputs
+    assert_eq!(example.name.as_deref(), Some("ones"));
+    assert_eq!(example.get_input_tensor("x").await, Some(&x));
+    assert_eq!(example.get_output_tensor("x").await, Some(&x));
+
+    // The captured tensors don't depend on the model so they can still be packed after it's unloaded
+    drop(model);
+
+    let mut info = noop_carton_info();
+    info.examples = Some(vec![example]);
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let info = Carton::get_model_info(path.to_str().unwrap())
+        .await
+        .unwrap();
+    let examples = info.info.examples.unwrap();
+    assert_eq!(examples.len(), 1);
+    assert_eq!(examples[0].name.as_deref(), Some("ones"));
+    assert_eq!(examples[0].get_input_tensor("x").await, Some(&x));
+    assert_eq!(examples[0].get_output_tensor("x").await, Some(&x));
+}
@@ -0,0 +1,81 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure cartons packed with `compress_manifest` load the same way as ones with a stored MANIFEST
+use carton::{
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+async fn pack(compress_manifest: bool) -> std::path::PathBuf {
+    let model_dir = tempfile::tempdir().unwrap();
+    for i in 0..100 {
+        std::fs::write(model_dir.path().join(format!("{i}.bin")), [i as u8]).unwrap();
+    }
+
+    Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            compress_manifest,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn manifest_compression(path: &std::path::Path) -> zip::CompressionMethod {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+    let manifest = archive.by_name("MANIFEST").unwrap();
+    manifest.compression()
+}
+
+#[tokio::test]
+async fn test_compress_manifest() {
+    setup_noop_runner();
+
+    let stored = pack(false).await;
+    let compressed = pack(true).await;
+
+    assert_eq!(
+        manifest_compression(&stored),
+        zip::CompressionMethod::Stored
+    );
+    assert_eq!(
+        manifest_compression(&compressed),
+        zip::CompressionMethod::Zstd
+    );
+
+    // The model hash is of the uncompressed MANIFEST so it's the same either way
+    let mut hashes = Vec::new();
+    for path in [&stored, &compressed] {
+        let carton = Carton::load(path.to_str().unwrap(), LoadOpts::default())
+            .await
+            .unwrap();
+
+        hashes.push(carton.get_info().manifest_sha256.clone().unwrap());
+    }
+
+    assert_eq!(hashes[0], hashes[1]);
+
+    // Shrinking a carton keeps the MANIFEST compressed
+    let shrunk = Carton::shrink(compressed, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(manifest_compression(&shrunk), zip::CompressionMethod::Zstd);
+}
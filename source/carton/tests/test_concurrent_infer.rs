@@ -0,0 +1,50 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure concurrent requests from clones of a `Carton` get the correct responses
+use std::collections::HashMap;
+
+use carton::types::Tensor;
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_concurrent_infer() {
+    let carton = load_noop_carton().await;
+
+    let handles: Vec<_> = (0..64)
+        .map(|i| {
+            let carton = carton.clone();
+            tokio::spawn(async move {
+                // Each task sends a different tensor. The noop runner echoes its inputs so the response
+                // should contain the same tensor
+                let tensors: HashMap<String, Tensor> = [(
+                    "x".to_owned(),
+                    Tensor::new(ndarray::ArrayD::<i64>::from_elem(vec![i + 1], i as i64)),
+                )]
+                .into();
+
+                for _ in 0..10 {
+                    let out = carton.infer(tensors.clone()).await.unwrap();
+                    assert_eq!(out, tensors);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
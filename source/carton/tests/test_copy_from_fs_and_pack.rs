@@ -0,0 +1,73 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! This test makes sure models can be copied from a filesystem other than the local one and packed
+use std::{io::Read, sync::Arc};
+
+use carton::{
+    types::{LoadOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_copy_from_fs_and_pack() {
+    setup_noop_runner();
+
+    // The root of the filesystem is a temp dir so paths in it don't match local paths
+    let root = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(root.path().join("model/sub")).unwrap();
+    std::fs::write(root.path().join("model/weights.bin"), "weights").unwrap();
+    std::fs::write(root.path().join("model/sub/config.json"), "{}").unwrap();
+    let fs = Arc::new(
+        lunchbox::LocalFS::with_base_dir(root.path().to_owned())
+            .await
+            .unwrap(),
+    );
+
+    let path = Carton::copy_from_fs_and_pack(fs, "model", noop_carton_info())
+        .await
+        .unwrap();
+
+    // The files in the model dir are packed
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+    for (name, expected) in [
+        ("model/weights.bin", "weights"),
+        ("model/sub/config.json", "{}"),
+    ] {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, expected);
+    }
+
+    // Nothing is written to the source filesystem
+    let entries: Vec<_> = std::fs::read_dir(root.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(entries, ["model"]);
+
+    // The packed carton can be loaded and used
+    let model = Carton::load(path.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    let out = model.infer([("x", x.clone())]).await.unwrap();
+    assert_eq!(out["x"], x);
+}
@@ -0,0 +1,68 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test inference with deadlines
+use std::time::Duration;
+
+use carton::{error::CartonError, types::Tensor};
+use futures::StreamExt;
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deadline() {
+    let carton = load_noop_carton().await;
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+
+    // Requests that finish in time return normally
+    let res = carton
+        .infer_with_deadline([("x", x.clone())], Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(res["x"], x);
+
+    let mut stream = carton
+        .streaming_infer_with_deadline([("x", x.clone())], Duration::from_secs(60))
+        .await;
+    let items: Vec<_> = (&mut stream).collect().await;
+    assert!(!items.is_empty());
+    assert!(!stream.truncated());
+    for item in items {
+        assert_eq!(item.unwrap()["x"], x);
+    }
+
+    // Non-streaming requests that don't finish in time fail
+    match carton
+        .infer_with_deadline([("x", x.clone())], Duration::ZERO)
+        .await
+    {
+        Err(CartonError::DeadlineExceeded(_)) => {}
+        _ => panic!("Expected the request to time out"),
+    }
+
+    // Streaming requests that don't finish in time end cleanly with the items before the deadline
+    let mut stream = carton
+        .streaming_infer_with_deadline([("x", x.clone())], Duration::ZERO)
+        .await;
+    let items = (&mut stream).collect::<Vec<_>>().await;
+    assert!(stream.truncated());
+    for item in items {
+        assert_eq!(item.unwrap()["x"], x);
+    }
+
+    // Other requests still work
+    let res = carton.infer([("x", x.clone())]).await.unwrap();
+    assert_eq!(res["x"], x);
+}
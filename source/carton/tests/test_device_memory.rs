@@ -0,0 +1,25 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `device_memory_usage` works with runners that only use the CPU
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_device_memory_usage() {
+    let carton = load_noop_carton().await;
+
+    // The noop runner doesn't use any devices
+    assert_eq!(carton.device_memory_usage().await.unwrap(), None);
+}
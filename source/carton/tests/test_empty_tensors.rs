@@ -0,0 +1,64 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! These tests make sure zero-element tensors (and empty sets of tensors) make it through a runner and back
+use std::collections::HashMap;
+
+use carton::types::Tensor;
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test]
+async fn test_empty_tensors() {
+    let carton = load_noop_carton().await;
+
+    let tensors: HashMap<String, Tensor> = [
+        (
+            "a".to_owned(),
+            Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![0])),
+        ),
+        (
+            "b".to_owned(),
+            Tensor::new(ndarray::ArrayD::<i64>::zeros(vec![0, 5])),
+        ),
+        (
+            "c".to_owned(),
+            Tensor::new(ndarray::ArrayD::<String>::default(vec![3, 0])),
+        ),
+    ]
+    .into();
+
+    // The noop runner echoes its inputs so we should get back tensors with the same (empty) shapes
+    let out = carton.infer(tensors.clone()).await.unwrap();
+    assert_eq!(out, tensors);
+}
+
+#[tokio::test]
+async fn test_no_inputs_or_outputs() {
+    let carton = load_noop_carton().await;
+
+    // Models may not take any inputs. Because the noop runner echoes its inputs, this also tests
+    // a model that doesn't produce any outputs
+    let out = carton
+        .infer(HashMap::<String, Tensor>::new())
+        .await
+        .unwrap();
+    assert!(out.is_empty());
+
+    // Same thing with a sealed set of inputs
+    let handle = carton.seal(HashMap::new()).await.unwrap();
+    let out = carton.infer_with_handle(handle).await.unwrap();
+    assert!(out.is_empty());
+}
@@ -0,0 +1,72 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure the inputs of an example can be fetched and used for inference
+use std::collections::HashMap;
+
+use carton::{
+    error::CartonError,
+    info::{Example, PossiblyLoaded, TensorOrMisc},
+    types::{LoadOpts, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+#[tokio::test]
+async fn test_get_example_inputs() {
+    setup_noop_runner();
+
+    let x = Tensor::new(ndarray::ArrayD::<f32>::ones(vec![2, 3]));
+
+    let mut info = noop_carton_info();
+    info.examples = Some(vec![Example {
+        name: Some("ones".into()),
+        description: None,
+        inputs: [(
+            "x".to_owned(),
+            TensorOrMisc::Tensor(PossiblyLoaded::from_value(x.clone())),
+        )]
+        .into(),
+        sample_out: HashMap::new(),
+    }]);
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let carton = Carton::load_unpacked(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+        LoadOpts::default(),
+    )
+    .await
+    .unwrap();
+
+    let inputs = carton.get_example_inputs(0).await.unwrap();
+    assert_eq!(inputs, [("x".to_owned(), x.clone())].into());
+
+    // The inputs can be passed directly to `infer` (the noop runner echoes its inputs)
+    let out = carton.infer(inputs).await.unwrap();
+    assert_eq!(out["x"], x);
+
+    assert!(matches!(
+        carton.get_example_inputs(1).await,
+        Err(CartonError::ExampleIndexOutOfRange {
+            index: 1,
+            num_examples: 1
+        })
+    ));
+}
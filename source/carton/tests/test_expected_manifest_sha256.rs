@@ -0,0 +1,99 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure loading fails if a carton doesn't match `expected_manifest_sha256`
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, replace_file_in_carton, setup_noop_runner};
+
+#[tokio::test]
+async fn test_expected_manifest_sha256() {
+    setup_noop_runner();
+
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::write(model_dir.path().join("a.bin"), vec![0u8; 1024]).unwrap();
+
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let packed_path = packed_path.to_str().unwrap();
+
+    let manifest_sha256 = Carton::load(packed_path, LoadOpts::default())
+        .await
+        .unwrap()
+        .get_info()
+        .manifest_sha256
+        .clone()
+        .unwrap();
+
+    // The correct hash loads (case insensitive)
+    Carton::load(
+        packed_path,
+        LoadOpts {
+            expected_manifest_sha256: Some(manifest_sha256.to_uppercase()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // A different hash fails
+    let res = Carton::load(
+        packed_path,
+        LoadOpts {
+            expected_manifest_sha256: Some("0".repeat(64)),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    match res {
+        Err(CartonError::ManifestHashMismatch { actual, .. }) => {
+            assert_eq!(actual, Some(manifest_sha256.clone()))
+        }
+        Err(e) => panic!("Expected a ManifestHashMismatch error, got {e}"),
+        Ok(_) => panic!("Expected a ManifestHashMismatch error"),
+    }
+
+    // Files that don't match the pinned MANIFEST fail even though the MANIFEST itself matches
+    let modified = replace_file_in_carton(
+        std::path::Path::new(packed_path),
+        "model/a.bin",
+        &[1u8; 1024],
+    );
+    let res = Carton::load(
+        modified.to_str().unwrap(),
+        LoadOpts {
+            expected_manifest_sha256: Some(manifest_sha256),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(matches!(
+        res,
+        Err(CartonError::FileHashMismatch { path, .. }) if path == "model/a.bin"
+    ));
+}
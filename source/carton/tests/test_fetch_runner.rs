@@ -95,7 +95,7 @@ def get_model():
         model_dir.path().to_str().unwrap().to_owned(),
         PackOpts {
             info,
-            linked_files: None,
+            ..Default::default()
         },
         LoadOpts::default(),
     )
@@ -0,0 +1,45 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test fetching outputs lazily
+use carton::types::Tensor;
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_infer_lazy() {
+    let carton = load_noop_carton().await;
+    let a = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    let b = Tensor::new(ndarray::ArrayD::from_elem(vec![3], 2i64));
+
+    // The noop runner echoes its inputs
+    let out = carton
+        .infer_lazy([("a", a.clone()), ("b", b.clone())])
+        .await
+        .unwrap();
+
+    assert_eq!(out.len(), 2);
+
+    // Only fetch one output and drop the other one
+    assert_eq!(out["a"].get().await.unwrap(), &a);
+
+    // Getting the same output again doesn't refetch it
+    assert_eq!(out["a"].clone().get().await.unwrap(), &a);
+    drop(out);
+
+    // Other requests still work
+    let res = carton.infer([("b", b.clone())]).await.unwrap();
+    assert_eq!(res["b"], b);
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test reporting the memory used by inference requests
+use carton::types::{tensor_map_bytes, Tensor};
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_last_infer_memory() {
+    let carton = load_noop_carton().await;
+    assert_eq!(carton.last_infer_memory(), None);
+
+    let inputs = [
+        (
+            "x".to_owned(),
+            Tensor::new(ndarray::ArrayD::<f32>::zeros(vec![4, 8])),
+        ),
+        ("s".to_owned(), Tensor::string_vec(["hello", "world"])),
+    ]
+    .into_iter()
+    .collect();
+
+    let expected = tensor_map_bytes(&inputs);
+    let outputs = carton.infer(inputs).await.unwrap();
+
+    // The noop runner returns its inputs
+    let memory = carton.last_infer_memory().unwrap();
+    assert_eq!(memory.input_bytes, expected);
+    assert_eq!(memory.output_bytes, tensor_map_bytes(&outputs));
+    assert_eq!(memory.total_bytes(), 2 * expected);
+
+    // Clones share the most recent value
+    assert_eq!(carton.clone().last_infer_memory(), Some(memory));
+}
@@ -0,0 +1,49 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `infer_ordered` returns outputs in a stable order
+use carton::types::Tensor;
+
+mod common;
+use common::load_noop_carton;
+
+fn scalar(v: f32) -> Tensor {
+    Tensor::new(ndarray::ArrayD::from_elem(vec![], v))
+}
+
+#[tokio::test]
+async fn test_infer_ordered() {
+    let carton = load_noop_carton().await;
+
+    // The noop carton doesn't have output specs so the outputs should be sorted by name
+    let out = carton
+        .infer_ordered([("c", scalar(3.0)), ("a", scalar(1.0)), ("b", scalar(2.0))])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        out,
+        vec![
+            ("a".to_owned(), scalar(1.0)),
+            ("b".to_owned(), scalar(2.0)),
+            ("c".to_owned(), scalar(3.0)),
+        ]
+    );
+
+    // Input names must be unique
+    assert!(carton
+        .infer_ordered([("a", scalar(1.0)), ("a", scalar(2.0))])
+        .await
+        .is_err());
+}
@@ -0,0 +1,118 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure packing checks self tests and examples against the declared inputs and outputs
+use std::collections::HashMap;
+
+use carton::{
+    error::CartonError,
+    info::{DataType, Dimension, PossiblyLoaded, SelfTest, Shape, TensorSpec},
+    types::{CartonInfo, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+fn spec(name: &str) -> TensorSpec {
+    TensorSpec {
+        name: name.into(),
+        dtype: DataType::Float,
+        shape: Shape::Shape(vec![Dimension::Symbol("N".into()), Dimension::Value(2)]),
+        description: None,
+        internal_name: None,
+        optional: false,
+        default: None,
+    }
+}
+
+fn self_test(x: Tensor, y: Tensor) -> SelfTest {
+    SelfTest {
+        name: None,
+        description: None,
+        inputs: [("x".to_owned(), PossiblyLoaded::from_value(x))].into(),
+        expected_out: Some([("y".to_owned(), PossiblyLoaded::from_value(y))].into()),
+    }
+}
+
+fn info_with_self_tests(self_tests: Vec<SelfTest>) -> CartonInfo {
+    let mut info = noop_carton_info();
+    info.inputs = Some(vec![spec("x")]);
+    info.outputs = Some(vec![spec("y")]);
+    info.self_tests = Some(self_tests);
+    info
+}
+
+async fn pack(info: CartonInfo, allow_invalid_examples: bool) -> carton::error::Result<()> {
+    let model_dir = tempfile::tempdir().unwrap();
+    Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            allow_invalid_examples,
+            ..Default::default()
+        },
+    )
+    .await
+    .map(|_| ())
+}
+
+#[tokio::test]
+async fn test_invalid_examples() {
+    setup_noop_runner();
+
+    let floats = |shape: &[usize]| Tensor::new(ndarray::ArrayD::<f32>::zeros(shape));
+
+    // Matching tensors pack successfully
+    let info = info_with_self_tests(vec![self_test(floats(&[3, 2]), floats(&[3, 2]))]);
+    pack(info, false).await.unwrap();
+
+    // A dtype mismatch, an inconsistent symbol and a fixed dimension mismatch
+    let info = info_with_self_tests(vec![
+        self_test(
+            Tensor::new(ndarray::ArrayD::<i32>::zeros(vec![3, 2])),
+            floats(&[3, 2]),
+        ),
+        self_test(floats(&[3, 2]), floats(&[4, 2])),
+        self_test(floats(&[3, 5]), floats(&[3, 2])),
+    ]);
+    match pack(info, false).await {
+        Err(CartonError::InvalidExamples(mismatches)) => {
+            assert_eq!(mismatches.len(), 3, "{mismatches:#?}");
+            assert_eq!(
+                mismatches[0],
+                "Self test 0: input `x`: expected dtype `float32`, but got `int32`"
+            );
+            assert!(mismatches[1].starts_with("Self test 1: output `y`"));
+            assert!(mismatches[2].starts_with("Self test 2: input `x`"));
+        }
+        Err(e) => panic!("Expected an InvalidExamples error, got {e}"),
+        Ok(_) => panic!("Expected an InvalidExamples error"),
+    }
+
+    // Undeclared tensors are reported
+    let mut st = self_test(floats(&[3, 2]), floats(&[3, 2]));
+    st.inputs
+        .insert("z".to_owned(), PossiblyLoaded::from_value(floats(&[3, 2])));
+    st.expected_out = Some(HashMap::new());
+    let info = info_with_self_tests(vec![st]);
+    assert!(matches!(
+        pack(info, false).await,
+        Err(CartonError::InvalidExamples(mismatches)) if mismatches == vec!["Self test 0: input `z` is not one of the declared inputs".to_owned()]
+    ));
+
+    // Mismatches are only warnings if `allow_invalid_examples` is set
+    let info = info_with_self_tests(vec![self_test(floats(&[3, 2]), floats(&[4, 2]))]);
+    pack(info, true).await.unwrap();
+}
@@ -0,0 +1,93 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test loading a model with a runner from an unpacked runner directory
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, noop_runner_path, noop_runner_toml, setup_noop_runner};
+
+/// Make a runner dir containing a copy of the noop runner named `runner_name`
+fn make_runner_dir(runner_name: &str) -> tempfile::TempDir {
+    let runner_dir = tempfile::tempdir().unwrap();
+    std::fs::copy(noop_runner_path(), runner_dir.path().join("runner")).unwrap();
+
+    // `runner_path` is relative to the directory containing `runner.toml`
+    std::fs::write(
+        runner_dir.path().join("runner.toml"),
+        noop_runner_toml(runner_name, 1, "runner"),
+    )
+    .unwrap();
+    runner_dir
+}
+
+#[tokio::test]
+async fn test_local_runner_dir() {
+    // Packing needs an installed runner
+    setup_noop_runner();
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let carton_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let carton_path = carton_path.to_str().unwrap();
+
+    // There's no installed runner named "noop-dev" so this has to use the runner dir
+    let runner_dir = make_runner_dir("noop-dev");
+    let opts = || LoadOpts {
+        override_runner_name: Some("noop-dev".into()),
+        ..Default::default()
+    };
+
+    // The noop runner echoes its inputs
+    let carton = Carton::load_with_local_runner_dir(carton_path, runner_dir.path(), opts())
+        .await
+        .unwrap();
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    let out = carton.infer([("x", x.clone())]).await.unwrap();
+    assert_eq!(out["x"], x);
+
+    // The runner dir must have a runner with a matching name
+    let err =
+        Carton::load_with_local_runner_dir(carton_path, runner_dir.path(), LoadOpts::default())
+            .await
+            .err()
+            .unwrap();
+    assert!(matches!(err, CartonError::InvalidRunnerPath { .. }));
+    assert!(err.to_string().contains("noop-dev"), "{err}");
+
+    // The runner binary must exist
+    std::fs::remove_file(runner_dir.path().join("runner")).unwrap();
+    assert!(matches!(
+        Carton::load_with_local_runner_dir(carton_path, runner_dir.path(), opts()).await,
+        Err(CartonError::InvalidRunnerPath { .. })
+    ));
+
+    // And so must `runner.toml`
+    let empty_dir = tempfile::tempdir().unwrap();
+    assert!(matches!(
+        Carton::load_with_local_runner_dir(carton_path, empty_dir.path(), opts()).await,
+        Err(CartonError::InvalidRunnerPath { .. })
+    ));
+}
@@ -0,0 +1,143 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `infer` fills in defaults for optional inputs and requires all other inputs
+//! (unless the carton was packed before optional inputs were supported)
+use carton::{
+    error::CartonError,
+    info::{DataType, PossiblyLoaded, Shape, TensorSpec},
+    types::{LoadOpts, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+fn scalar(v: f32) -> Tensor {
+    Tensor::new(ndarray::ArrayD::from_elem(vec![], v))
+}
+
+fn spec(name: &str, optional: bool, default: Option<Tensor>) -> TensorSpec {
+    TensorSpec {
+        name: name.into(),
+        dtype: DataType::Float,
+        shape: Shape::Shape(vec![]),
+        description: None,
+        internal_name: None,
+        optional,
+        default: default.map(PossiblyLoaded::from_value),
+    }
+}
+
+#[tokio::test]
+async fn test_optional_inputs() {
+    setup_noop_runner();
+
+    let mut info = noop_carton_info();
+    info.inputs = Some(vec![
+        spec("a", false, None),
+        spec("b", true, Some(scalar(2.0))),
+        spec("c", true, None),
+    ]);
+
+    // Pack and load the model so the default goes through `tensor_data`
+    let model_dir = tempfile::tempdir().unwrap();
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let carton = Carton::load(packed_path.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+
+    // The noop runner echoes its inputs. `b` should be filled in with its default and `c` should
+    // not be passed at all
+    let out = carton.infer([("a", scalar(1.0))]).await.unwrap();
+    assert_eq!(out.len(), 2);
+    assert_eq!(out["a"], scalar(1.0));
+    assert_eq!(out["b"], scalar(2.0));
+
+    // Provided values take precedence over defaults
+    let out = carton
+        .infer([("a", scalar(1.0)), ("b", scalar(5.0)), ("c", scalar(6.0))])
+        .await
+        .unwrap();
+    assert_eq!(out["b"], scalar(5.0));
+    assert_eq!(out["c"], scalar(6.0));
+
+    // `a` is required
+    match carton.infer([("b", scalar(5.0))]).await {
+        Err(CartonError::MissingInputs(missing)) => assert_eq!(missing, vec!["a".to_owned()]),
+        Err(e) => panic!("Expected a MissingInputs error, got {e}"),
+        Ok(_) => panic!("Expected a MissingInputs error"),
+    }
+}
+
+#[tokio::test]
+async fn test_inputs_without_optional_flag() {
+    use std::io::Read;
+
+    setup_noop_runner();
+
+    let mut info = noop_carton_info();
+    info.inputs = Some(vec![spec("a", false, None), spec("b", false, None)]);
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // Remove the `optional` flags to match a carton packed before optional inputs were supported
+    let mut carton_toml = String::new();
+    zip::ZipArchive::new(std::fs::File::open(&packed_path).unwrap())
+        .unwrap()
+        .by_name("carton.toml")
+        .unwrap()
+        .read_to_string(&mut carton_toml)
+        .unwrap();
+    assert!(carton_toml.contains("optional = false"));
+    let carton_toml = carton_toml.replace("optional = false", "");
+    let old_path =
+        common::replace_file_in_carton(&packed_path, "carton.toml", carton_toml.as_bytes());
+
+    // Existing callers may omit inputs so they should be passed through as-is
+    let carton = Carton::load(old_path.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+    let out = carton.infer([("a", scalar(1.0))]).await.unwrap();
+    assert_eq!(out.len(), 1);
+    assert_eq!(out["a"], scalar(1.0));
+
+    // The same carton with the flags requires `b`
+    let carton = Carton::load(packed_path.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+    match carton.infer([("a", scalar(1.0))]).await {
+        Err(CartonError::MissingInputs(missing)) => assert_eq!(missing, vec!["b".to_owned()]),
+        Err(e) => panic!("Expected a MissingInputs error, got {e}"),
+        Ok(_) => panic!("Expected a MissingInputs error"),
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `Carton::pack_dry_run` reports the files that would be packed
+use carton::{
+    info::{FileStorage, LinkedFile, PackPlan, PlannedFile},
+    types::PackOpts,
+    Carton,
+};
+use sha2::{Digest, Sha256};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+fn sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+#[tokio::test]
+async fn test_pack_dry_run() {
+    setup_noop_runner();
+
+    // Create a model dir with a regular file, a file we'll link and a symlink
+    let model_dir = tempfile::tempdir().unwrap();
+    let weights = vec![0u8; 1024];
+    std::fs::write(model_dir.path().join("a.txt"), "hello").unwrap();
+    std::fs::write(model_dir.path().join("weights.bin"), &weights).unwrap();
+    std::os::unix::fs::symlink("a.txt", model_dir.path().join("b.txt")).unwrap();
+
+    let plan = Carton::pack_dry_run(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            linked_files: Some(vec![LinkedFile::new(
+                vec!["https://example.com/weights.bin".into()],
+                sha256(&weights),
+            )]),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        plan,
+        PackPlan {
+            files: vec![
+                PlannedFile {
+                    path: "model/a.txt".into(),
+                    sha256: sha256(b"hello"),
+                    size: 5,
+                    storage: FileStorage::Stored,
+                },
+                PlannedFile {
+                    path: "model/b.txt".into(),
+                    sha256: sha256(b"hello"),
+                    size: 0,
+                    storage: FileStorage::Symlink {
+                        target: "a.txt".into()
+                    },
+                },
+                PlannedFile {
+                    path: "model/weights.bin".into(),
+                    sha256: sha256(&weights),
+                    size: 1024,
+                    storage: FileStorage::Linked,
+                },
+            ],
+            total_size: 1029,
+            stored_size: 5,
+        }
+    );
+
+    // A dry run shouldn't modify the model dir
+    assert_eq!(std::fs::read_dir(model_dir.path()).unwrap().count(), 3);
+}
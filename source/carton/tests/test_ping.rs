@@ -0,0 +1,34 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `ping` gets a response from the runner
+use carton::carton::PING_TIMEOUT;
+use carton::types::Tensor;
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ping() {
+    let carton = load_noop_carton().await;
+
+    let rtt = carton.ping().await.unwrap();
+    assert!(rtt < PING_TIMEOUT);
+
+    // Pinging doesn't interfere with inference
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    let (res, ping) = tokio::join!(carton.infer([("x", x.clone())]), carton.ping());
+    assert_eq!(res.unwrap()["x"], x);
+    ping.unwrap();
+}
@@ -0,0 +1,62 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `preload` loads every carton and doesn't stop at failures
+use carton::{
+    types::{LoadOpts, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+#[tokio::test]
+async fn test_preload() {
+    setup_noop_runner();
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let packed_path = packed_path.to_str().unwrap().to_owned();
+
+    let tempdir = tempfile::tempdir().unwrap();
+    let missing = tempdir.path().join("missing.carton").display().to_string();
+
+    let urls = vec![
+        packed_path.clone(),
+        missing,
+        packed_path.clone(),
+        packed_path,
+    ];
+    let out = carton::preload(urls, 2, LoadOpts::default()).await;
+
+    // Results are in the same order as the inputs
+    assert_eq!(out.len(), 4);
+    assert!(out[1].is_err());
+
+    // The other cartons still load
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    for i in [0, 2, 3] {
+        let carton = out[i].as_ref().unwrap();
+        let res = carton.infer([("x", x.clone())]).await.unwrap();
+        assert_eq!(res["x"], x);
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test retrying inference requests
+use std::time::Duration;
+
+use carton::{error::CartonError, retry::RetryPolicy, types::Tensor};
+
+mod common;
+use common::load_noop_carton;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_retry() {
+    let carton = load_noop_carton().await.with_retries(RetryPolicy {
+        max_retries: 3,
+        delay: Duration::ZERO,
+        reload_from: None,
+    });
+
+    // Requests that succeed are returned as-is
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    let out = carton.infer([("x", x.clone())]).await.unwrap();
+    assert_eq!(out["x"], x);
+
+    // Only transient errors are retried
+    assert!(CartonError::RunnerDisconnected.is_retryable());
+    assert!(CartonError::RunnerNotResponding(Duration::from_secs(5)).is_retryable());
+    assert!(CartonError::OutOfMemory("CUDA out of memory".into()).is_retryable());
+    assert!(!CartonError::MissingInputs(vec!["x".into()]).is_retryable());
+    assert!(!CartonError::InvalidShape("bad shape".into()).is_retryable());
+
+    // We can't tell whether an error reported by the model is transient so those aren't retried
+    assert!(!CartonError::ErrorFromRunner("Invalid input".into()).is_retryable());
+}
+
+#[test]
+fn test_retryable_io_errors() {
+    use std::io::{Error, ErrorKind};
+
+    // Errors from a broken or interrupted connection are retried
+    for kind in [
+        ErrorKind::BrokenPipe,
+        ErrorKind::ConnectionReset,
+        ErrorKind::ConnectionAborted,
+        ErrorKind::NotConnected,
+        ErrorKind::UnexpectedEof,
+        ErrorKind::TimedOut,
+        ErrorKind::Interrupted,
+    ] {
+        assert!(
+            CartonError::IOError(Error::new(kind, "failed")).is_retryable(),
+            "{kind:?} should be retryable"
+        );
+    }
+
+    // Other IO errors fail the same way every time
+    for kind in [
+        ErrorKind::NotFound,
+        ErrorKind::PermissionDenied,
+        ErrorKind::InvalidData,
+    ] {
+        assert!(
+            !CartonError::IOError(Error::new(kind, "failed")).is_retryable(),
+            "{kind:?} shouldn't be retryable"
+        );
+    }
+}
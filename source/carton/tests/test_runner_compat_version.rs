@@ -0,0 +1,77 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure loading a carton whose `runner_compat_version` isn't supported by the installed
+//! runner fails with `IncompatibleRunnerCompatVersion`
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, noop_runner_path, noop_runner_toml};
+
+#[tokio::test]
+async fn test_runner_compat_version() {
+    // The only installed runner has compat version 2
+    let runner_path = noop_runner_path();
+    let runner_dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        runner_dir.path().join("runner.toml"),
+        noop_runner_toml("noop", 2, &runner_path.display().to_string()),
+    )
+    .unwrap();
+    std::env::set_var("CARTON_RUNNER_DIR", runner_dir.path());
+
+    // Pack a model for compat version 1 (using the runner directly)
+    let mut info = noop_carton_info();
+    info.runner.runner_compat_version = Some(1);
+
+    std::env::set_var("CARTON_RUNNER_OVERRIDE_NOOP", &runner_path);
+    let model_dir = tempfile::tempdir().unwrap();
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    std::env::remove_var("CARTON_RUNNER_OVERRIDE_NOOP");
+
+    // Loading it with the installed runner should explain the mismatch
+    let res = Carton::load(
+        packed_path.to_str().unwrap(),
+        LoadOpts {
+            auto_install_runner: false,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    match res {
+        Err(
+            e @ CartonError::IncompatibleRunnerCompatVersion {
+                carton: 1,
+                runner: 2,
+            },
+        ) => {
+            assert!(e.to_string().contains("repack"), "{e}")
+        }
+        Err(e) => panic!("Expected an IncompatibleRunnerCompatVersion error, got {e}"),
+        Ok(_) => panic!("Expected an IncompatibleRunnerCompatVersion error"),
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure environment variables in runner opts are only resolved for the runner
+//! and not in the model info
+use std::collections::HashMap;
+
+use carton::{
+    info::RunnerOpt,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+#[tokio::test]
+async fn test_runner_opts_env() {
+    setup_noop_runner();
+
+    let raw = "${CARTON_TEST_RUNNER_OPTS_ENV_UNDEFINED:-/tmp}/vocab.txt";
+    let mut info = noop_carton_info();
+    info.runner.opts = Some(HashMap::from([(
+        "vocab".to_owned(),
+        RunnerOpt::String(raw.to_owned()),
+    )]));
+
+    let model_dir = tempfile::tempdir().unwrap();
+    let packed_path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // The model info should contain the original value
+    let check_info =
+        |info: &carton::info::CartonInfo| match &info.runner.opts.as_ref().unwrap()["vocab"] {
+            RunnerOpt::String(s) => assert_eq!(s, raw),
+            _ => panic!("Expected a string opt"),
+        };
+
+    let info = Carton::get_model_info(packed_path.to_str().unwrap())
+        .await
+        .unwrap();
+    check_info(&info.info);
+
+    // Even after loading the model
+    let carton = Carton::load(packed_path.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+    check_info(&carton.get_info().info);
+}
@@ -0,0 +1,95 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure `runner_path_override` launches a runner binary directly instead of an installed runner
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, noop_runner_path};
+
+async fn load_with_override(runner_path: std::path::PathBuf) -> carton::error::Result<Carton> {
+    load_with_override_and_compat_version(runner_path, Some(1)).await
+}
+
+async fn load_with_override_and_compat_version(
+    runner_path: std::path::PathBuf,
+    runner_compat_version: Option<u64>,
+) -> carton::error::Result<Carton> {
+    // There's no installed runner with this name
+    let mut info = noop_carton_info();
+    info.runner.runner_name = "noop-dev".into();
+
+    // We don't have a `runner.toml` for the override so we don't know its compat version
+    info.runner.runner_compat_version = runner_compat_version;
+
+    let model_dir = tempfile::tempdir().unwrap();
+    Carton::load_unpacked(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            ..Default::default()
+        },
+        LoadOpts {
+            runner_path_override: Some(runner_path),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_runner_path_override() {
+    // The noop runner echoes its inputs
+    let carton = load_with_override(noop_runner_path()).await.unwrap();
+    let x = Tensor::new(ndarray::ArrayD::from_elem(vec![2], 1.0f32));
+    let out = carton.infer([("x", x.clone())]).await.unwrap();
+    assert_eq!(out["x"], x);
+
+    // The compat version of the override has to be set explicitly
+    assert!(matches!(
+        load_with_override_and_compat_version(noop_runner_path(), None).await,
+        Err(CartonError::Other(_))
+    ));
+
+    // Paths that don't exist aren't launched
+    let tempdir = tempfile::tempdir().unwrap();
+    let missing = tempdir.path().join("missing");
+    assert!(matches!(
+        load_with_override(missing.clone()).await,
+        Err(CartonError::InvalidRunnerPath { path, .. }) if path == missing
+    ));
+
+    // Neither are directories
+    assert!(matches!(
+        load_with_override(tempdir.path().to_owned()).await,
+        Err(CartonError::InvalidRunnerPath { .. })
+    ));
+
+    // Or executables that fail to start
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let not_a_binary = tempdir.path().join("not_a_binary");
+        std::fs::write(&not_a_binary, "").unwrap();
+        std::fs::set_permissions(&not_a_binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(matches!(
+            load_with_override(not_a_binary.clone()).await,
+            Err(CartonError::InvalidRunnerPath { path, .. }) if path == not_a_binary
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure signed cartons can be verified when loading
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+use ed25519_dalek::SigningKey;
+
+mod common;
+use common::{noop_carton_info, replace_file_in_carton, setup_noop_runner};
+
+async fn pack(signing_key: Option<&SigningKey>) -> std::path::PathBuf {
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::write(model_dir.path().join("a.bin"), vec![0u8; 1024]).unwrap();
+
+    Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            signing_key: signing_key.map(|key| hex::encode(key.to_bytes())),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+async fn load(path: &std::path::Path, public_key: &SigningKey) -> carton::error::Result<Carton> {
+    Carton::load(
+        path.to_str().unwrap(),
+        LoadOpts {
+            signature_public_key: Some(hex::encode(public_key.verifying_key().to_bytes())),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_signature() {
+    setup_noop_runner();
+
+    let key = SigningKey::from_bytes(&[1; 32]);
+    let other_key = SigningKey::from_bytes(&[2; 32]);
+
+    // The matching key verifies
+    let signed = pack(Some(&key)).await;
+    load(&signed, &key).await.unwrap();
+
+    // Signatures are opt-in so signed cartons load without a key
+    Carton::load(signed.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+
+    // A different key fails
+    assert!(matches!(
+        load(&signed, &other_key).await,
+        Err(CartonError::SignatureVerificationFailed(_))
+    ));
+
+    // Files that don't match the signed MANIFEST fail
+    let tampered = replace_file_in_carton(&signed, "model/a.bin", &[1u8; 1024]);
+    assert!(matches!(
+        load(&tampered, &key).await,
+        Err(CartonError::FileHashMismatch { path, .. }) if path == "model/a.bin"
+    ));
+
+    // Unsigned cartons fail if a key is provided
+    let unsigned = pack(None).await;
+    assert!(matches!(
+        load(&unsigned, &key).await,
+        Err(CartonError::SignatureVerificationFailed(_))
+    ));
+}
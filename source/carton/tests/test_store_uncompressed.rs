@@ -0,0 +1,80 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This test makes sure cartons packed with `store_uncompressed` load the same tensors as compressed ones
+use carton::{
+    info::{DataType, Dimension, PossiblyLoaded, Shape, TensorSpec},
+    types::{LoadOpts, PackOpts, Tensor},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+fn default_value() -> Tensor {
+    Tensor::new(ndarray::ArrayD::from_shape_fn(vec![256, 4], |idx| {
+        (idx[0] * 4 + idx[1]) as f32
+    }))
+}
+
+async fn pack(store_uncompressed: bool) -> std::path::PathBuf {
+    let mut info = noop_carton_info();
+    info.inputs = Some(vec![TensorSpec {
+        name: "x".into(),
+        dtype: DataType::Float,
+        shape: Shape::Shape(vec![Dimension::Value(256), Dimension::Value(4)]),
+        description: None,
+        internal_name: None,
+        optional: true,
+        default: Some(PossiblyLoaded::from_value(default_value())),
+    }]);
+
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::write(model_dir.path().join("weights.bin"), vec![0u8; 1024 * 1024]).unwrap();
+
+    Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info,
+            store_uncompressed,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_store_uncompressed() {
+    setup_noop_runner();
+
+    let compressed = pack(false).await;
+    let uncompressed = pack(true).await;
+
+    // Storing files uncompressed makes the carton larger
+    assert!(
+        std::fs::metadata(&uncompressed).unwrap().len()
+            > std::fs::metadata(&compressed).unwrap().len() + 1024 * 1024 / 2
+    );
+
+    for path in [compressed, uncompressed] {
+        let carton = Carton::load(path.to_str().unwrap(), LoadOpts::default())
+            .await
+            .unwrap();
+
+        // The noop runner echoes its inputs so this returns the default value of `x`
+        let out = carton.infer::<_, String>([]).await.unwrap();
+        assert_eq!(out["x"], default_value());
+    }
+}
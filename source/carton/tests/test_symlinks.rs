@@ -0,0 +1,95 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! This test makes sure cartons containing relative symlinks can be packed and loaded and that cartons with
+//! malicious symlinks are rejected
+use carton::{
+    error::CartonError,
+    types::{LoadOpts, PackOpts},
+    Carton,
+};
+
+mod common;
+use common::{noop_carton_info, setup_noop_runner};
+
+#[tokio::test]
+async fn test_relative_symlinks() {
+    setup_noop_runner();
+
+    // `save` stores symlink targets relative to the directory containing the link
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(model_dir.path().join("sub")).unwrap();
+    std::fs::write(model_dir.path().join("a.txt"), "hello").unwrap();
+    std::os::unix::fs::symlink("a.txt", model_dir.path().join("b.txt")).unwrap();
+    std::os::unix::fs::symlink("../a.txt", model_dir.path().join("sub/c.txt")).unwrap();
+
+    let path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    Carton::get_model_info(path.to_str().unwrap())
+        .await
+        .unwrap();
+
+    Carton::load(path.to_str().unwrap(), LoadOpts::default())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_unlisted_symlink() {
+    setup_noop_runner();
+
+    let model_dir = tempfile::tempdir().unwrap();
+    std::fs::write(model_dir.path().join("a.txt"), "hello").unwrap();
+
+    let path = Carton::pack(
+        model_dir.path().to_str().unwrap(),
+        PackOpts {
+            info: noop_carton_info(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // Add a symlink that escapes the model dir without listing it in MANIFEST
+    let f = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .unwrap();
+    let mut zip = zip::ZipWriter::new_append(f).unwrap();
+    zip.add_symlink(
+        "model/evil.txt",
+        "../../../etc/passwd",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.finish().unwrap();
+
+    // This is rejected even without a signature or an expected manifest sha256
+    match Carton::load(path.to_str().unwrap(), LoadOpts::default()).await {
+        Err(CartonError::MaliciousArchive(_)) => {}
+        other => panic!(
+            "Expected a `MaliciousArchive` error, but got {:?}",
+            other.err()
+        ),
+    }
+}
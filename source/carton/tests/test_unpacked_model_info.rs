@@ -0,0 +1,60 @@
+// Copyright 2023 Vivek Panyam
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test reading info from an unpacked model directory without a runner
+use carton::{error::CartonError, Carton};
+
+#[tokio::test]
+async fn test_unpacked_model_info() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("model")).unwrap();
+    std::fs::create_dir_all(dir.path().join("misc/images")).unwrap();
+    std::fs::write(dir.path().join("misc/images/cat.png"), "not really a png").unwrap();
+    std::fs::write(
+        dir.path().join("carton.toml"),
+        r#"
+spec_version = 1
+model_name = "work_in_progress"
+
+[runner]
+runner_name = "some_runner_that_isnt_installed"
+required_framework_version = "=1.0.0"
+runner_compat_version = 1
+"#,
+    )
+    .unwrap();
+
+    let path = dir.path().to_str().unwrap();
+    let info = Carton::get_model_info(path).await.unwrap();
+    assert_eq!(info.info.model_name.as_deref(), Some("work_in_progress"));
+    assert_eq!(
+        info.info.runner.runner_name,
+        "some_runner_that_isnt_installed"
+    );
+    assert!(info.manifest_sha256.is_none());
+    assert!(info.info.misc_files.unwrap().contains_key("images/cat.png"));
+
+    // It can't be loaded without packing it first
+    assert!(matches!(
+        Carton::load(path, Default::default()).await,
+        Err(CartonError::UnpackedModel)
+    ));
+
+    // Directories without a carton.toml aren't models
+    std::fs::remove_file(dir.path().join("carton.toml")).unwrap();
+    assert!(matches!(
+        Carton::get_model_info(path).await,
+        Err(CartonError::MissingCartonToml)
+    ));
+}